@@ -3,7 +3,7 @@ use std::os::linux::net::SocketAddrExt;
 #[cfg(target_os = "linux")]
 use std::os::unix::net::SocketAddr;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     io::{BufRead, BufReader, Write},
     net::{Shutdown, TcpStream},
     os::unix::net::UnixStream,
@@ -24,20 +24,28 @@ use crate::{
         ListFiles,
         LsInfo,
         Mounts,
+        Neighbors,
         Playlist,
         Song,
+        Stats,
         Status,
         Update,
         Volume,
+        added_ids::AddedIds,
+        comments::Comments,
+        count::{Count, GroupedCounts},
         decoders::Decoders,
+        fingerprint::Fingerprint,
         list::{MpdGroupedList, MpdList, RawGroupedPairs},
         list_all::ListAll,
         list_playlist::FileList,
         messages::Messages,
         mpd_config::MpdConfig,
         outputs::Outputs,
+        plchanges::{PosIds, QueueDiff, QueueVersion},
+        replay_gain::{ReplayGainMode, ReplayGainStatus},
         status::OnOffOneshot,
-        stickers::{Sticker, Stickers, StickersWithFile},
+        stickers::{Sticker, StickerType, Stickers, StickersWithFile},
     },
     errors::{ErrorCode, MpdError, MpdFailureResponse},
     filter::{Filter, FilterKind, Tag},
@@ -51,7 +59,9 @@ use crate::{
     },
     proto_client::{ProtoClient, SocketClient},
     queue_position::QueuePosition,
+    recording::{Recorder, RecordingReader},
     single_or_range::SingleOrRange,
+    song_range::SongRange,
 };
 
 type MpdResult<T> = Result<T, MpdError>;
@@ -60,16 +70,21 @@ pub const MIN_SUPPORTED_VERSION: Version = Version { major: 0, minor: 23, patch:
 
 pub struct Client<'name> {
     name: &'name str,
-    rx: BufReader<TcpOrUnixStream>,
+    rx: BufReader<RecordingReader<TcpOrUnixStream>>,
     pub stream: TcpOrUnixStream,
     addr: MpdAddress,
     password: Option<MpdPassword>,
     pub version: Version,
     pub config: Option<MpdConfig>,
     pub supported_commands: HashSet<String>,
+    /// Protocol features enabled for this connection, always empty before MPD
+    /// 0.24
+    pub protocol_features: HashSet<String>,
     partition: Option<String>,
     autocreate_partition: bool,
     enable_keepalive: bool,
+    tag_types: Option<Vec<String>>,
+    recorder: Option<Recorder>,
 }
 
 impl std::fmt::Debug for Client<'_> {
@@ -177,7 +192,9 @@ impl<'name> Client<'name> {
         partition: Option<String>,
         autocreate_partition: bool,
         enable_keepalive: bool,
+        recorder: Option<Recorder>,
     ) -> MpdResult<Client<'name>> {
+        let recorder = recorder.map(|recorder| recorder.connection(name));
         let mut stream = match addr {
             MpdAddress::IpAndPort(ref addr) => TcpOrUnixStream::Tcp(TcpStream::connect(addr)?),
             MpdAddress::SocketPath(ref addr) => TcpOrUnixStream::Unix(UnixStream::connect(addr)?),
@@ -196,7 +213,7 @@ impl<'name> Client<'name> {
         .set_keepalive(enable_keepalive)?;
         stream.set_write_timeout(None)?;
         stream.set_read_timeout(None)?;
-        let mut rx = BufReader::new(stream.try_clone()?);
+        let mut rx = BufReader::new(RecordingReader::new(stream.try_clone()?, recorder.clone()));
 
         let mut buf = String::new();
         rx.read_line(&mut buf)?;
@@ -225,6 +242,9 @@ impl<'name> Client<'name> {
             enable_keepalive,
             config: None,
             supported_commands: HashSet::new(),
+            protocol_features: HashSet::new(),
+            tag_types: None,
+            recorder,
         };
 
         if let Some(MpdPassword(ref password)) = client.password.clone() {
@@ -250,6 +270,7 @@ impl<'name> Client<'name> {
         // have any effect
         client.binary_limit(2u64.pow(18))?;
         client.supported_commands = client.commands()?.0.into_iter().collect();
+        client.negotiate_protocol()?;
 
         Ok(client)
     }
@@ -274,7 +295,8 @@ impl<'name> Client<'name> {
         .set_keepalive(self.enable_keepalive)?;
         stream.set_write_timeout(None)?;
         stream.set_read_timeout(None)?;
-        let mut rx = BufReader::new(stream.try_clone()?);
+        let mut rx =
+            BufReader::new(RecordingReader::new(stream.try_clone()?, self.recorder.clone()));
 
         let mut buf = String::new();
         rx.read_line(&mut buf)?;
@@ -317,12 +339,70 @@ impl<'name> Client<'name> {
         }
 
         self.supported_commands = self.commands()?.0.into_iter().collect();
+        self.negotiate_protocol()?;
 
         self.binary_limit(1024 * 1024 * 5)?;
 
+        // Songs still work with all tags, so it should not prevent the reconnect
+        if self.tag_types.is_some()
+            && let Err(err) = self.apply_tag_types()
+        {
+            log::warn!(err:?; "Failed to restore tag types after reconnect");
+        }
+
         Ok(self)
     }
 
+    /// Whether the given protocol feature is enabled for this connection
+    pub fn supports_protocol_feature(&self, feature: &str) -> bool {
+        self.protocol_features.contains(feature)
+    }
+
+    fn negotiate_protocol(&mut self) -> MpdResult<()> {
+        self.protocol_features = if self.version < Version::new(0, 24, 0) {
+            HashSet::new()
+        } else {
+            match self.protocol() {
+                Ok(features) => features.0.into_iter().collect(),
+                // Missing permission should not prevent the connection
+                Err(MpdError::Mpd(_)) => HashSet::new(),
+                Err(err) => return Err(err),
+            }
+        };
+        debug!(name = self.name, features:? = self.protocol_features; "Negotiated protocol features");
+        Ok(())
+    }
+
+    /// Restricts the tags MPD sends with songs to the given ones, or enables
+    /// all of them again when `None`. Tags unknown to the server are skipped.
+    /// The selection is kept and restored after reconnecting.
+    pub fn set_tag_types(&mut self, tags: Option<Vec<String>>) -> MpdResult<()> {
+        self.tag_types = tags;
+        self.apply_tag_types()
+    }
+
+    fn apply_tag_types(&mut self) -> MpdResult<()> {
+        self.tag_types_all()?;
+        let Some(wanted) = self.tag_types.clone() else {
+            return Ok(());
+        };
+
+        let known: HashSet<String> =
+            self.tag_types()?.0.into_iter().map(|tag| tag.to_lowercase()).collect();
+        let tags = wanted
+            .into_iter()
+            .filter(|tag| known.contains(&tag.to_lowercase()))
+            .unique_by(|tag| tag.to_lowercase())
+            .collect_vec();
+        debug!(name = self.name, tags:?; "Negotiated tag types");
+
+        self.tag_types_clear()?;
+        if !tags.is_empty() {
+            self.tag_types_enable(&tags)?;
+        }
+        Ok(())
+    }
+
     pub fn set_read_timeout(
         &mut self,
         timeout: Option<std::time::Duration>,
@@ -339,14 +419,19 @@ impl<'name> Client<'name> {
 
     fn clear_read_buf(&mut self) -> Result<()> {
         log::trace!("Reinitialized read buffer");
-        self.rx = BufReader::new(self.stream.try_clone()?);
+        self.rx =
+            BufReader::new(RecordingReader::new(self.stream.try_clone()?, self.recorder.clone()));
         Ok(())
     }
 }
 
 impl SocketClient for Client<'_> {
     fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
-        Write::write_all(&mut self.stream, bytes)
+        Write::write_all(&mut self.stream, bytes)?;
+        if let Some(recorder) = &self.recorder {
+            recorder.sent(bytes);
+        }
+        Ok(())
     }
 
     fn read(&mut self) -> &mut impl BufRead {
@@ -436,6 +521,22 @@ impl MpdClient for Client<'_> {
         self.send_crossfade(seconds).and_then(|()| self.read_ok())
     }
 
+    fn mixramp_db(&mut self, db: f32) -> MpdResult<()> {
+        self.send_mixramp_db(db).and_then(|()| self.read_ok())
+    }
+
+    fn mixramp_delay(&mut self, seconds: f32) -> MpdResult<()> {
+        self.send_mixramp_delay(seconds).and_then(|()| self.read_ok())
+    }
+
+    fn replay_gain_mode(&mut self, mode: ReplayGainMode) -> MpdResult<()> {
+        self.send_replay_gain_mode(mode).and_then(|()| self.read_ok())
+    }
+
+    fn replay_gain_status(&mut self) -> MpdResult<ReplayGainStatus> {
+        self.send_replay_gain_status().and_then(|()| self.read_response())
+    }
+
     fn get_current_song(&mut self) -> MpdResult<Option<Song>> {
         self.send_get_current_song().and_then(|()| self.read_opt_response())
     }
@@ -444,6 +545,10 @@ impl MpdClient for Client<'_> {
         self.send_get_status().and_then(|()| self.read_response())
     }
 
+    fn stats(&mut self) -> MpdResult<Stats> {
+        self.send_stats().and_then(|()| self.read_response())
+    }
+
     // Playback control
     fn pause_toggle(&mut self) -> MpdResult<()> {
         self.send_pause_toggle().and_then(|()| self.read_ok())
@@ -485,6 +590,14 @@ impl MpdClient for Client<'_> {
         self.send_seek_current(value).and_then(|()| self.read_ok())
     }
 
+    fn seek(&mut self, pos: usize, time: Duration) -> MpdResult<()> {
+        self.send_seek(pos, time).and_then(|()| self.read_ok())
+    }
+
+    fn seek_id(&mut self, id: u32, time: Duration) -> MpdResult<()> {
+        self.send_seek_id(id, time).and_then(|()| self.read_ok())
+    }
+
     fn repeat(&mut self, enabled: bool) -> MpdResult<()> {
         self.send_repeat(enabled).and_then(|()| self.read_ok())
     }
@@ -514,11 +627,27 @@ impl MpdClient for Client<'_> {
         self.send_list_mounts().and_then(|()| self.read_response())
     }
 
+    fn list_neighbors(&mut self) -> MpdResult<Neighbors> {
+        self.send_list_neighbors().and_then(|()| self.read_response())
+    }
+
     // Current queue
     fn add(&mut self, uri: &str, position: Option<QueuePosition>) -> MpdResult<()> {
         self.send_add(uri, position).and_then(|()| self.read_ok())
     }
 
+    fn add_id(&mut self, uri: &str, position: Option<QueuePosition>) -> MpdResult<u32> {
+        let ids: AddedIds = self.send_add_id(uri, position).and_then(|()| self.read_response())?;
+        ids.0
+            .first()
+            .copied()
+            .ok_or_else(|| MpdError::Generic("MPD did not return id of the added song".to_owned()))
+    }
+
+    fn range_id(&mut self, id: u32, range: SongRange) -> MpdResult<()> {
+        self.send_range_id(id, range).and_then(|()| self.read_ok())
+    }
+
     fn clear(&mut self) -> MpdResult<()> {
         self.send_clear().and_then(|()| self.read_ok())
     }
@@ -547,6 +676,46 @@ impl MpdClient for Client<'_> {
         self.send_playlist_id(id).and_then(|()| self.read_opt_response())
     }
 
+    fn plchanges(&mut self, version: u32) -> MpdResult<Vec<Song>> {
+        self.send_plchanges(version).and_then(|()| self.read_response())
+    }
+
+    fn plchanges_posid(&mut self, version: u32) -> MpdResult<PosIds> {
+        self.send_plchanges_posid(version).and_then(|()| self.read_response())
+    }
+
+    fn queue_diff(
+        &mut self,
+        from: &QueueVersion,
+        known_positions: &HashMap<u32, usize>,
+    ) -> MpdResult<Option<QueueDiff>> {
+        let status = self.get_status()?;
+        let Some(to) = status.queue_version().filter(|to| to.partition == from.partition) else {
+            return Ok(None);
+        };
+
+        let changes = self.plchanges_posid(from.version)?.0;
+        // Songs which are new or kept their position but were still reported as changed
+        // (priority, tags of a stream, ...) need their metadata fetched again
+        let needs_metadata = changes
+            .iter()
+            .any(|change| known_positions.get(&change.id).is_none_or(|pos| *pos == change.pos));
+
+        let (songs, moved) = if needs_metadata {
+            (self.plchanges(from.version)?, Vec::new())
+        } else {
+            (Vec::new(), changes)
+        };
+
+        Ok(Some(QueueDiff {
+            from: from.clone(),
+            to,
+            length: status.playlistlength as usize,
+            songs,
+            moved,
+        }))
+    }
+
     /// Search the database for songs matching FILTER
     fn find(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
         self.send_find(filter).and_then(|()| self.read_response())
@@ -569,6 +738,18 @@ impl MpdClient for Client<'_> {
         }
     }
 
+    /// Search the queue for songs matching FILTER (see Filters)
+    fn playlist_find(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
+        self.send_playlist_find(filter).and_then(|()| self.read_response())
+    }
+
+    /// Search the queue for songs matching FILTER (see Filters). Parameters
+    /// have the same meaning as for `playlist_find`, except that search is not
+    /// case sensitive.
+    fn playlist_search(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
+        self.send_playlist_search(filter).and_then(|()| self.read_response())
+    }
+
     fn move_in_queue(&mut self, from: SingleOrRange, to: QueuePosition) -> MpdResult<()> {
         self.send_move_in_queue(from, to).and_then(|()| self.read_ok())
     }
@@ -615,10 +796,42 @@ impl MpdClient for Client<'_> {
         self.read_response::<RawGroupedPairs>().map(|raw| raw.into_grouped_list(tag.as_str()))
     }
 
+    fn count(&mut self, filter: &[Filter<'_>]) -> MpdResult<Count> {
+        self.send_count(Some(filter), None).and_then(|()| self.read_response())
+    }
+
+    fn count_grouped(
+        &mut self,
+        group: Tag,
+        filter: Option<&[Filter<'_>]>,
+    ) -> MpdResult<GroupedCounts> {
+        self.send_count(filter, Some(&group)).and_then(|()| self.read_response())
+    }
+
+    fn search_count(&mut self, filter: &[Filter<'_>]) -> MpdResult<Count> {
+        self.send_search_count(filter, None).and_then(|()| self.read_response())
+    }
+
+    fn search_count_grouped(
+        &mut self,
+        group: Tag,
+        filter: &[Filter<'_>],
+    ) -> MpdResult<GroupedCounts> {
+        self.send_search_count(filter, Some(&group)).and_then(|()| self.read_response())
+    }
+
     fn shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()> {
         self.send_shuffle(range).and_then(|()| self.read_ok())
     }
 
+    fn prio(&mut self, priority: u8, songs: &[SingleOrRange]) -> MpdResult<()> {
+        self.send_prio(priority, songs).and_then(|()| self.read_ok())
+    }
+
+    fn prio_id(&mut self, priority: u8, ids: &[u32]) -> MpdResult<()> {
+        self.send_prio_id(priority, ids).and_then(|()| self.read_ok())
+    }
+
     #[allow(clippy::needless_range_loop)]
     fn add_random_songs(&mut self, count: usize, filter: Option<&[Filter<'_>]>) -> MpdResult<()> {
         let mut result = if let Some(filter) = filter {
@@ -688,6 +901,14 @@ impl MpdClient for Client<'_> {
         self.send_albumart(path).and_then(|cmd| self.read_bin(&cmd))
     }
 
+    fn read_comments(&mut self, path: &str) -> MpdResult<Comments> {
+        self.send_read_comments(path).and_then(|()| self.read_response())
+    }
+
+    fn get_fingerprint(&mut self, path: &str) -> MpdResult<Fingerprint> {
+        self.send_get_fingerprint(path).and_then(|()| self.read_response())
+    }
+
     // Stored playlists
     fn list_playlists(&mut self) -> MpdResult<Vec<Playlist>> {
         self.send_list_playlists().and_then(|()| self.read_response())
@@ -765,6 +986,10 @@ impl MpdClient for Client<'_> {
         self.send_disable_output(id).and_then(|()| self.read_ok())
     }
 
+    fn output_set(&mut self, id: u32, name: &str, value: &str) -> MpdResult<()> {
+        self.send_output_set(id, name, value).and_then(|()| self.read_ok())
+    }
+
     // Decoders
     fn decoders(&mut self) -> MpdResult<Decoders> {
         self.send_decoders().and_then(|()| self.read_response())
@@ -772,30 +997,23 @@ impl MpdClient for Client<'_> {
 
     // Stickers
     fn sticker(&mut self, uri: &str, key: &str) -> MpdResult<Option<Sticker>> {
-        let result: MpdResult<Sticker> =
-            self.send_sticker(uri, key).and_then(|()| self.read_response());
-
-        if let Err(MpdError::Mpd(MpdFailureResponse { code: ErrorCode::NoExist, .. })) = result {
-            return Ok(None);
-        }
-
-        result.map(Some)
+        self.typed_sticker(&StickerType::Song, uri, key)
     }
 
     fn set_sticker(&mut self, uri: &str, key: &str, value: &str) -> MpdResult<()> {
-        self.send_set_sticker(uri, key, value).and_then(|()| self.read_ok())
+        self.set_typed_sticker(&StickerType::Song, uri, key, value)
     }
 
     fn delete_sticker(&mut self, uri: &str, key: &str) -> MpdResult<()> {
-        self.send_delete_sticker(uri, key).and_then(|()| self.read_ok())
+        self.delete_typed_sticker(&StickerType::Song, uri, key)
     }
 
     fn delete_all_stickers(&mut self, uri: &str) -> MpdResult<()> {
-        self.send_delete_all_stickers(uri).and_then(|()| self.read_ok())
+        self.send_delete_all_stickers(&StickerType::Song, uri).and_then(|()| self.read_ok())
     }
 
     fn list_stickers(&mut self, uri: &str) -> MpdResult<Stickers> {
-        self.send_list_stickers(uri).and_then(|()| self.read_response())
+        self.list_typed_stickers(&StickerType::Song, uri)
     }
 
     /// Resulting `Vec` is of the same length as input `uri`s.
@@ -810,7 +1028,7 @@ impl MpdClient for Client<'_> {
             self.send_start_cmd_list_ok()?;
 
             for uri in &uris[i..] {
-                self.send_list_stickers(uri)?;
+                self.send_list_stickers(&StickerType::Song, uri)?;
             }
             self.send_execute_cmd_list()?;
 
@@ -847,7 +1065,67 @@ impl MpdClient for Client<'_> {
         key: &str,
         opts: StickerFindOptions,
     ) -> MpdResult<StickersWithFile> {
-        self.send_find_stickers(uri, key, opts).and_then(|()| self.read_response())
+        self.find_typed_stickers(&StickerType::Song, uri, key, opts)
+    }
+
+    fn typed_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        key: &str,
+    ) -> MpdResult<Option<Sticker>> {
+        let result: MpdResult<Sticker> =
+            self.send_sticker(ty, uri, key).and_then(|()| self.read_response());
+
+        if let Err(MpdError::Mpd(MpdFailureResponse { code: ErrorCode::NoExist, .. })) = result {
+            return Ok(None);
+        }
+
+        result.map(Some)
+    }
+
+    fn set_typed_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        key: &str,
+        value: &str,
+    ) -> MpdResult<()> {
+        self.send_set_sticker(ty, uri, key, value).and_then(|()| self.read_ok())
+    }
+
+    fn delete_typed_sticker(&mut self, ty: &StickerType, uri: &str, key: &str) -> MpdResult<()> {
+        self.send_delete_sticker(ty, uri, key).and_then(|()| self.read_ok())
+    }
+
+    fn list_typed_stickers(&mut self, ty: &StickerType, uri: &str) -> MpdResult<Stickers> {
+        self.send_list_stickers(ty, uri).and_then(|()| self.read_response())
+    }
+
+    fn find_typed_stickers(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        key: &str,
+        opts: StickerFindOptions,
+    ) -> MpdResult<StickersWithFile> {
+        self.send_find_stickers(ty, uri, key, opts).and_then(|()| self.read_response())
+    }
+
+    fn inc_sticker(&mut self, ty: &StickerType, uri: &str, key: &str, value: u32) -> MpdResult<()> {
+        self.send_inc_sticker(ty, uri, key, value).and_then(|()| self.read_ok())
+    }
+
+    fn dec_sticker(&mut self, ty: &StickerType, uri: &str, key: &str, value: u32) -> MpdResult<()> {
+        self.send_dec_sticker(ty, uri, key, value).and_then(|()| self.read_ok())
+    }
+
+    fn sticker_names(&mut self) -> MpdResult<MpdList> {
+        self.send_sticker_names().and_then(|()| self.read_response())
+    }
+
+    fn sticker_types(&mut self) -> MpdResult<MpdList> {
+        self.send_sticker_types().and_then(|()| self.read_response())
     }
 
     fn switch_to_partition(&mut self, name: &str) -> MpdResult<()> {
@@ -911,4 +1189,56 @@ impl MpdClient for Client<'_> {
     fn string_normalization_clear(&mut self) -> MpdResult<()> {
         self.send_string_normalization_clear().and_then(|()| self.read_ok())
     }
+
+    fn tag_types(&mut self) -> MpdResult<MpdList> {
+        self.send_tag_types().and_then(|()| self.read_response())
+    }
+
+    fn tag_types_available(&mut self) -> MpdResult<MpdList> {
+        self.send_tag_types_available().and_then(|()| self.read_response())
+    }
+
+    fn tag_types_enable(&mut self, tags: &[String]) -> MpdResult<()> {
+        self.send_tag_types_enable(tags).and_then(|()| self.read_ok())
+    }
+
+    fn tag_types_disable(&mut self, tags: &[String]) -> MpdResult<()> {
+        self.send_tag_types_disable(tags).and_then(|()| self.read_ok())
+    }
+
+    fn tag_types_all(&mut self) -> MpdResult<()> {
+        self.send_tag_types_all().and_then(|()| self.read_ok())
+    }
+
+    fn tag_types_clear(&mut self) -> MpdResult<()> {
+        self.send_tag_types_clear().and_then(|()| self.read_ok())
+    }
+
+    fn protocol(&mut self) -> MpdResult<MpdList> {
+        self.send_protocol().and_then(|()| self.read_response())
+    }
+
+    fn protocol_available(&mut self) -> MpdResult<MpdList> {
+        self.send_protocol_available().and_then(|()| self.read_response())
+    }
+
+    fn protocol_enable(&mut self, features: &[String]) -> MpdResult<()> {
+        self.send_protocol_enable(features).and_then(|()| self.read_ok())
+    }
+
+    fn protocol_disable(&mut self, features: &[String]) -> MpdResult<()> {
+        self.send_protocol_disable(features).and_then(|()| self.read_ok())
+    }
+
+    fn protocol_all(&mut self) -> MpdResult<()> {
+        self.send_protocol_all().and_then(|()| self.read_ok())
+    }
+
+    fn protocol_clear(&mut self) -> MpdResult<()> {
+        self.send_protocol_clear().and_then(|()| self.read_ok())
+    }
+
+    fn url_handlers(&mut self) -> MpdResult<MpdList> {
+        self.send_url_handlers().and_then(|()| self.read_response())
+    }
 }
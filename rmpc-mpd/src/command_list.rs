@@ -0,0 +1,366 @@
+use std::{any::Any, marker::PhantomData};
+
+use crate::{
+    errors::{MpdError, MpdFailureResponse},
+    from_mpd::FromMpd,
+    mpd_client::MpdCommand,
+    proto_client::ProtoClient,
+};
+
+type MpdResult<T> = Result<T, MpdError>;
+type SendFn<'a, C> = Box<dyn FnOnce(&mut C) -> MpdResult<()> + 'a>;
+type ReadFn<C> = fn(&mut C) -> MpdResult<Box<dyn Any>>;
+
+/// Builder for commands sent together inside `command_list_ok_begin`. Each
+/// queued command returns a [`Handle`] which is used to retrieve its typed
+/// result from [`CommandListResults`] once the list is executed.
+///
+/// ```ignore
+/// let mut list = CommandList::new();
+/// let status = list.push::<Status>(|c| c.send_get_status());
+/// let song = list.push_opt::<Song>(|c| c.send_get_current_song());
+/// let mut results = list.execute(client)?;
+/// let (status, song) = (results.take(status)?, results.take(song)?);
+/// ```
+pub struct CommandList<'a, C> {
+    commands: Vec<(SendFn<'a, C>, ReadFn<C>)>,
+}
+
+impl<C> std::fmt::Debug for CommandList<'_, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CommandList {{ len: {} }}", self.commands.len())
+    }
+}
+
+/// Typed reference to the result of a command queued in a [`CommandList`]
+#[derive(Debug)]
+pub struct Handle<T> {
+    idx: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+// Implemented manually because derive would require `T: Copy`
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> Handle<T> {
+    /// Position of the command in the list
+    #[must_use]
+    pub fn index(&self) -> usize {
+        self.idx
+    }
+}
+
+#[derive(Debug)]
+enum Entry {
+    Done(Box<dyn Any>),
+    Failed(MpdFailureResponse),
+    NotExecuted,
+    Taken,
+}
+
+/// Results of an executed [`CommandList`]. MPD stops executing the list at
+/// the first failed command, every command after it is reported as not
+/// executed.
+#[derive(Debug)]
+pub struct CommandListResults {
+    entries: Vec<Entry>,
+}
+
+impl<C: MpdCommand + ProtoClient> Default for CommandList<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, C: MpdCommand + ProtoClient> CommandList<'a, C> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self { commands: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Queues a command which responds only with `OK`
+    pub fn push_ok(&mut self, send: impl FnOnce(&mut C) -> MpdResult<()> + 'a) -> Handle<()> {
+        self.queue(send, |client| Ok(Box::new(client.read_ok()?)))
+    }
+
+    /// Queues a command whose response is parsed into `T`
+    pub fn push<T>(&mut self, send: impl FnOnce(&mut C) -> MpdResult<()> + 'a) -> Handle<T>
+    where
+        T: FromMpd + Default + 'static,
+    {
+        self.queue(send, |client| Ok(Box::new(client.read_response::<T>()?)))
+    }
+
+    /// Queues a command whose response is parsed into `T`, `None` when MPD
+    /// responded without any values
+    pub fn push_opt<T>(
+        &mut self,
+        send: impl FnOnce(&mut C) -> MpdResult<()> + 'a,
+    ) -> Handle<Option<T>>
+    where
+        T: FromMpd + Default + 'static,
+    {
+        self.queue(send, |client| Ok(Box::new(client.read_opt_response::<T>()?)))
+    }
+
+    fn queue<T>(
+        &mut self,
+        send: impl FnOnce(&mut C) -> MpdResult<()> + 'a,
+        read: ReadFn<C>,
+    ) -> Handle<T> {
+        self.commands.push((Box::new(send), read));
+        Handle { idx: self.commands.len() - 1, _marker: PhantomData }
+    }
+
+    /// Sends all queued commands and reads their responses. Failures of the
+    /// individual commands are reported through the results, only errors
+    /// which leave the connection in an unknown state are returned directly.
+    ///
+    /// When a command fails to be sent the list is still ended so the
+    /// connection does not stay inside of it. MPD executes the commands sent
+    /// up to that point.
+    pub fn execute(self, client: &mut C) -> MpdResult<CommandListResults> {
+        if self.commands.is_empty() {
+            return Ok(CommandListResults { entries: Vec::new() });
+        }
+
+        let len = self.commands.len();
+        let (senders, readers): (Vec<_>, Vec<_>) = self.commands.into_iter().unzip();
+
+        client.send_start_cmd_list_ok()?;
+        for (idx, send) in senders.into_iter().enumerate() {
+            if let Err(err) = send(client) {
+                if let Err(end_err) = Self::end_aborted(client, &readers[..idx]) {
+                    log::error!(err:? = end_err; "Failed to end partially sent command list");
+                }
+                return Err(err);
+            }
+        }
+        client.send_execute_cmd_list()?;
+
+        let mut entries = Vec::with_capacity(len);
+        for read in readers {
+            match read(client) {
+                Ok(value) => entries.push(Entry::Done(value)),
+                Err(MpdError::Mpd(failure)) => {
+                    entries.push(Entry::Failed(failure));
+                    break;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if entries.len() == len && matches!(entries.last(), Some(Entry::Done(_))) {
+            // OK for the whole command list
+            client.read_ok()?;
+        }
+        entries.resize_with(len, || Entry::NotExecuted);
+
+        Ok(CommandListResults { entries })
+    }
+
+    /// Ends the command list after only the commands of the given readers
+    /// were sent and skips their responses
+    fn end_aborted(client: &mut C, readers: &[ReadFn<C>]) -> MpdResult<()> {
+        client.send_execute_cmd_list()?;
+        for read in readers {
+            match read(client) {
+                Ok(_) => {}
+                // MPD stops executing the list at the first failure
+                Err(MpdError::Mpd(_)) => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+
+        client.read_ok()
+    }
+}
+
+impl CommandListResults {
+    /// Takes the result of the command referenced by the handle. Fails with
+    /// the error MPD reported for the command or when it was not executed.
+    pub fn take<T: 'static>(&mut self, handle: Handle<T>) -> MpdResult<T> {
+        let Some(entry) = self.entries.get_mut(handle.idx) else {
+            return Err(MpdError::Generic(format!(
+                "Command list has no command at index {}",
+                handle.idx
+            )));
+        };
+
+        match std::mem::replace(entry, Entry::Taken) {
+            Entry::Done(value) => value.downcast::<T>().map(|value| *value).map_err(|_| {
+                MpdError::Generic(format!(
+                    "Unexpected response type for command at index {}",
+                    handle.idx
+                ))
+            }),
+            Entry::Failed(failure) => Err(MpdError::Mpd(failure)),
+            Entry::NotExecuted => Err(MpdError::Generic(format!(
+                "Command at index {} was not executed because a previous command failed",
+                handle.idx
+            ))),
+            Entry::Taken => Err(MpdError::Generic(format!(
+                "Result of command at index {} was already taken",
+                handle.idx
+            ))),
+        }
+    }
+
+    /// Index and error of the command which failed, if any
+    pub fn failure(&self) -> Option<(usize, &MpdFailureResponse)> {
+        self.entries.iter().enumerate().find_map(|(idx, entry)| match entry {
+            Entry::Failed(failure) => Some((idx, failure)),
+            _ => None,
+        })
+    }
+
+    /// Fails with the error of the failed command, if any
+    pub fn into_result(mut self) -> MpdResult<Self> {
+        match self.entries.iter().position(|entry| matches!(entry, Entry::Failed(_))) {
+            Some(idx) => match std::mem::replace(&mut self.entries[idx], Entry::Taken) {
+                Entry::Failed(failure) => Err(MpdError::Mpd(failure)),
+                _ => unreachable!("entry was matched as failed"),
+            },
+            None => Ok(self),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::{BufReader, Cursor};
+
+    use rmpc_shared::version::Version;
+
+    use super::CommandList;
+    use crate::{
+        commands::{Song, Status, added_ids::AddedIds, status::State},
+        errors::{ErrorCode, MpdError},
+        mpd_client::MpdCommand,
+        proto_client::{ProtoClient, SocketClient},
+    };
+
+    struct TestClient {
+        read: BufReader<Cursor<Vec<u8>>>,
+        written: String,
+    }
+
+    impl TestClient {
+        fn new(buf: &[u8]) -> Self {
+            Self { read: BufReader::new(Cursor::new(buf.to_vec())), written: String::new() }
+        }
+    }
+
+    impl SocketClient for TestClient {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+            self.written.push_str(&String::from_utf8_lossy(bytes));
+            Ok(())
+        }
+
+        fn read(&mut self) -> &mut impl std::io::BufRead {
+            &mut self.read
+        }
+
+        fn clear_read_buf(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn version(&self) -> Version {
+            Version::new(0, 25, 0)
+        }
+    }
+
+    #[test]
+    fn returns_typed_results() {
+        let mut client = TestClient::new(b"state: play\nlist_OK\nlist_OK\nOK\n");
+        let mut list = CommandList::new();
+        let status = list.push::<Status>(|c: &mut TestClient| c.send_get_status());
+        let song = list.push_opt::<Song>(|c: &mut TestClient| c.send_get_current_song());
+
+        let mut results = list.execute(&mut client).unwrap();
+
+        assert_eq!(results.take(status).unwrap().state, State::Play);
+        assert_eq!(results.take(song).unwrap(), None);
+        assert_eq!(
+            client.written,
+            "command_list_ok_begin\nstatus\ncurrentsong\ncommand_list_end\n"
+        );
+    }
+
+    #[test]
+    fn attributes_failure_to_the_failed_command() {
+        let mut client = TestClient::new(b"Id: 5\nlist_OK\nACK [50@1] {addid} No such directory\n");
+        let mut list = CommandList::new();
+        let handles = ["a", "b", "c"]
+            .into_iter()
+            .map(|uri| list.push::<AddedIds>(move |c: &mut TestClient| c.send_add_id(uri, None)))
+            .collect::<Vec<_>>();
+
+        let mut results = list.execute(&mut client).unwrap();
+
+        assert_eq!(
+            results.failure().map(|(idx, failure)| (idx, failure.code == ErrorCode::NoExist)),
+            Some((1, true))
+        );
+        let mut handles = handles.into_iter();
+        assert_eq!(results.take(handles.next().unwrap()).unwrap().0, vec![5]);
+        assert!(matches!(results.take(handles.next().unwrap()), Err(MpdError::Mpd(_))));
+        assert!(matches!(results.take(handles.next().unwrap()), Err(MpdError::Generic(_))));
+    }
+
+    #[test]
+    fn into_result_fails_with_the_failed_command() {
+        let mut client = TestClient::new(
+            b"list_OK
+ACK [50@1] {deleteid} No such song
+",
+        );
+        let mut list = CommandList::new();
+        list.push_ok(|c: &mut TestClient| c.send_delete_id(0));
+        list.push_ok(|c: &mut TestClient| c.send_delete_id(1));
+
+        let result = list.execute(&mut client).unwrap().into_result();
+
+        assert!(matches!(result, Err(MpdError::Mpd(failure)) if failure.command_list_index == 1));
+    }
+
+    #[test]
+    fn ends_list_when_command_fails_to_be_sent() {
+        let mut client = TestClient::new(b"list_OK\nOK\nstate: play\nOK\n");
+        let mut list = CommandList::new();
+        list.push_ok(|c: &mut TestClient| c.send_delete_id(0));
+        list.push_ok(|_: &mut TestClient| Err(MpdError::UnsupportedMpdVersion("test")));
+        list.push_ok(|c: &mut TestClient| c.send_delete_id(1));
+
+        let result = list.execute(&mut client);
+
+        assert!(matches!(result, Err(MpdError::UnsupportedMpdVersion(_))));
+        assert_eq!(client.written, "command_list_ok_begin\ndeleteid 0\ncommand_list_end\n");
+        assert_eq!(client.read_response::<Status>().unwrap().state, State::Play);
+    }
+
+    #[test]
+    fn empty_list_sends_nothing() {
+        let mut client = TestClient::new(b"");
+
+        let results = CommandList::new().execute(&mut client).unwrap();
+
+        assert!(results.failure().is_none());
+        assert!(client.written.is_empty());
+    }
+}
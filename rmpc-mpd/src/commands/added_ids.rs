@@ -0,0 +1,20 @@
+use derive_more::{AsRef, Into, IntoIterator};
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled},
+};
+
+/// Ids of the songs added to the queue by one or more `addid` commands
+#[derive(Debug, Default, Clone, IntoIterator, AsRef, Into)]
+pub struct AddedIds(pub Vec<u32>);
+
+impl FromMpd for AddedIds {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "id" => self.0.push(value.parse()?),
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
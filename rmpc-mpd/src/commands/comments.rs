@@ -0,0 +1,51 @@
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled, split_line},
+};
+
+/// Raw tags of a file as read by `readcomments`, in the order they were
+/// returned by MPD
+#[derive(Debug, Serialize, Default, Clone, IntoIterator, AsRef, AsMut, Into)]
+pub struct Comments(pub Vec<Comment>);
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromMpd for Comments {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        self.0.push(Comment { name: key.to_owned(), value });
+        Ok(LineHandled::Yes)
+    }
+
+    // Names of the comments are kept in their original case unlike keys of
+    // other responses
+    fn next(&mut self, line: String) -> Result<(), MpdError> {
+        let (name, value) = split_line(line)?;
+        self.0.push(Comment { name, value });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{Comment, Comments, FromMpd};
+
+    #[test]
+    fn keeps_case_of_comment_names() {
+        let mut result = Comments::default();
+        result.next("REPLAYGAIN_TRACK_GAIN: -6.50 dB".to_owned()).unwrap();
+        result.next("MusicBrainz_TrackId: abc: def".to_owned()).unwrap();
+
+        assert_eq!(result.0, vec![
+            Comment { name: "REPLAYGAIN_TRACK_GAIN".to_owned(), value: "-6.50 dB".to_owned() },
+            Comment { name: "MusicBrainz_TrackId".to_owned(), value: "abc: def".to_owned() },
+        ]);
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled, ParseErrorExt},
+};
+
+#[derive(Debug, Default, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct Count {
+    pub songs: u64,
+    /// Sum of durations of the counted songs
+    pub playtime: Duration,
+}
+
+impl FromMpd for Count {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "songs" => self.songs = value.parse().logerr(key, &value)?,
+            "playtime" => self.playtime = Duration::from_secs(value.parse().logerr(key, &value)?),
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct GroupedCounts(pub Vec<GroupedCount>);
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq, Eq)]
+pub struct GroupedCount {
+    /// Value of the tag the songs were grouped by, empty for songs without
+    /// the tag
+    pub value: String,
+    pub count: Count,
+}
+
+impl FromMpd for GroupedCounts {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        // Every group starts with the name of the grouped tag as its key
+        if key != "songs" && key != "playtime" {
+            self.0.push(GroupedCount { value, count: Count::default() });
+            return Ok(LineHandled::Yes);
+        }
+
+        self.0
+            .last_mut()
+            .context(anyhow!(
+                "No element in accumulator while parsing GroupedCounts. Key '{key}' Value :'{value}'"
+            ))?
+            .count
+            .next_internal(key, value)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Count, FromMpd, GroupedCount, GroupedCounts};
+
+    #[test]
+    fn can_parse_grouped_count() {
+        let input = "Genre: \nsongs: 2\nplaytime: 300\nGenre: Rock\nsongs: 10\nplaytime: 2400";
+
+        let mut result = GroupedCounts::default();
+        for line in input.lines() {
+            let (key, value) = line.split_once(": ").unwrap();
+            result.next_internal(key.to_lowercase().as_str(), value.to_owned()).unwrap();
+        }
+
+        assert_eq!(result.0, vec![
+            GroupedCount {
+                value: String::new(),
+                count: Count { songs: 2, playtime: Duration::from_secs(300) }
+            },
+            GroupedCount {
+                value: "Rock".to_owned(),
+                count: Count { songs: 10, playtime: Duration::from_secs(2400) }
+            },
+        ]);
+    }
+}
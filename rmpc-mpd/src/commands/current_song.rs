@@ -8,6 +8,7 @@ use super::metadata_tag::MetadataTag;
 use crate::{
     errors::MpdError,
     from_mpd::{FromMpd, LineHandled, ParseErrorExt},
+    song_range::SongRange,
 };
 
 #[derive(Default, Serialize, PartialEq, Eq, Clone)]
@@ -19,9 +20,22 @@ pub struct Song {
     pub last_modified: DateTime<Utc>,
     // Option because it is present from mpd 0.24 onwards
     pub added: Option<DateTime<Utc>>,
+    // Priority of the song in the queue, MPD omits it when it is 0
+    pub prio: u8,
 }
 
 impl Song {
+    /// Position of the song in the queue, only present for songs in the queue
+    pub fn pos(&self) -> Option<usize> {
+        self.metadata.get("pos").and_then(|pos| pos.last().parse().ok())
+    }
+
+    /// Portion of the song which is played, only present for songs in the
+    /// queue with a range set
+    pub fn range(&self) -> Option<SongRange> {
+        self.metadata.get("range").and_then(|range| range.last().parse().ok())
+    }
+
     pub fn samplerate(&self) -> Option<u32> {
         self.metadata.get("format").and_then(|audio| {
             audio.first().split(':').next().and_then(|rate_str| rate_str.parse().ok())
@@ -72,6 +86,7 @@ impl FromMpd for Song {
                 self.added =
                     Some(value.parse().context("Failed to parse date").logerr(key, &value)?);
             }
+            "prio" => self.prio = value.parse().logerr(key, &value)?,
             key => {
                 self.metadata
                     .entry(key.to_owned())
@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled},
+};
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Compressed and base64 encoded chromaprint fingerprint as returned by
+    /// MPD
+    pub chromaprint: String,
+}
+
+impl FromMpd for Fingerprint {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "chromaprint" => self.chromaprint = value,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
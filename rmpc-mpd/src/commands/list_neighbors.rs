@@ -0,0 +1,46 @@
+use anyhow::{Context, anyhow};
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled},
+};
+
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct Neighbors(pub Vec<Neighbor>);
+
+/// Storage discovered by one of the neighbor plugins which can be mounted
+#[derive(Debug, Default, Serialize, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    /// URI of the storage, usable as the path of `mount`
+    pub neighbor: String,
+    /// Human readable name of the storage
+    pub name: String,
+}
+
+impl FromMpd for Neighbors {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        if key == "neighbor" {
+            self.0.push(Neighbor::default());
+        }
+
+        self.0
+            .last_mut()
+            .context(anyhow!(
+                "No element in accumulator while parsing Neighbors. Key '{key}' Value :'{value}'"
+            ))?
+            .next_internal(key, value)
+    }
+}
+
+impl FromMpd for Neighbor {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "neighbor" => self.neighbor = value,
+            "name" => self.name = value,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
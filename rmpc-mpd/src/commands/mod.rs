@@ -1,10 +1,15 @@
+pub mod added_ids;
+pub mod comments;
+pub mod count;
 pub mod current_song;
 pub mod decoders;
+pub mod fingerprint;
 pub mod idle;
 pub mod list;
 pub mod list_all;
 pub mod list_files;
 pub mod list_mounts;
+pub mod list_neighbors;
 pub mod list_playlist;
 pub mod list_playlists;
 pub mod lsinfo;
@@ -13,6 +18,9 @@ pub mod metadata_tag;
 pub mod mpd_config;
 pub mod outputs;
 pub mod playlist_info;
+pub mod plchanges;
+pub mod replay_gain;
+pub mod stats;
 pub mod status;
 pub mod stickers;
 pub mod update;
@@ -24,8 +32,11 @@ pub use self::{
     idle::IdleEvent,
     list_files::ListFiles,
     list_mounts::Mounts,
+    list_neighbors::Neighbors,
     list_playlists::Playlist,
     lsinfo::LsInfo,
+    plchanges::{QueueDiff, QueueVersion},
+    stats::Stats,
     status::{State, Status},
     update::Update,
     volume::Volume,
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use anyhow::{Context, anyhow};
 use derive_more::{AsMut, AsRef, Into, IntoIterator};
 use serde::Serialize;
@@ -16,6 +18,8 @@ pub struct Output {
     pub name: String,
     pub enabled: bool,
     pub plugin: String,
+    /// Runtime attributes of the output which can be changed with `outputset`
+    pub attributes: BTreeMap<String, String>,
 }
 
 impl FromMpd for Outputs {
@@ -44,8 +48,51 @@ impl FromMpd for Output {
                 _ => return Ok(LineHandled::No { value }),
             },
             "plugin" => self.plugin = value,
+            "attribute" => match value.split_once('=') {
+                Some((name, attr_value)) => {
+                    self.attributes.insert(name.to_owned(), attr_value.to_owned());
+                }
+                None => return Ok(LineHandled::No { value }),
+            },
             _ => return Ok(LineHandled::No { value }),
         }
         Ok(LineHandled::Yes)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{FromMpd, Outputs};
+
+    #[test]
+    fn parses_output_attributes() {
+        let mut result = Outputs::default();
+        for line in [
+            "outputid: 0",
+            "outputname: Speakers",
+            "plugin: alsa",
+            "outputenabled: 1",
+            "attribute: allowed_formats=",
+            "attribute: dop=0",
+            "outputid: 1",
+            "outputname: Null",
+            "plugin: null",
+            "outputenabled: 0",
+        ] {
+            result.next(line.to_owned()).unwrap();
+        }
+
+        assert_eq!(result.0.len(), 2);
+        assert_eq!(
+            result.0[0].attributes,
+            BTreeMap::from([
+                ("allowed_formats".to_owned(), String::new()),
+                ("dop".to_owned(), "0".to_owned()),
+            ])
+        );
+        assert!(result.0[1].attributes.is_empty());
+    }
+}
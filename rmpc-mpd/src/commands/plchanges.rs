@@ -0,0 +1,219 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use anyhow::{Context, anyhow};
+use derive_more::{AsMut, AsRef, Into, IntoIterator};
+use serde::Serialize;
+
+use super::{Song, metadata_tag::MetadataTag};
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled, ParseErrorExt},
+};
+
+#[derive(Debug, Serialize, Default, IntoIterator, AsRef, AsMut, Into)]
+pub struct PosIds(pub Vec<PosId>);
+
+#[derive(Debug, Default, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct PosId {
+    pub pos: usize,
+    pub id: u32,
+}
+
+impl FromMpd for PosIds {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        if key == "cpos" {
+            self.0.push(PosId::default());
+        }
+
+        self.0
+            .last_mut()
+            .context(anyhow!(
+                "No element in accumulator while parsing PosIds. Key '{key}' Value :'{value}'"
+            ))?
+            .next_internal(key, value)
+    }
+}
+
+impl FromMpd for PosId {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "cpos" => self.pos = value.parse().logerr(key, &value)?,
+            "id" => self.id = value.parse().logerr(key, &value)?,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+/// Version of the queue of a single partition. Versions of queues in different
+/// partitions are not comparable.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct QueueVersion {
+    pub partition: String,
+    pub version: u32,
+}
+
+/// Changes made to the queue between two of its versions.
+#[derive(Debug, Serialize)]
+pub struct QueueDiff {
+    pub from: QueueVersion,
+    pub to: QueueVersion,
+    /// Length of the queue after the changes are applied
+    pub length: usize,
+    /// Songs which were added or modified, with their new position in the
+    /// `pos` tag
+    pub songs: Vec<Song>,
+    /// Songs which were only moved to a different position
+    pub moved: Vec<PosId>,
+}
+
+impl QueueDiff {
+    /// Applies the changes to `queue` which is expected to be at version
+    /// `self.from`. Returns `false` when the changes do not fit the queue. The
+    /// queue is left in an unspecified state in that case and has to be
+    /// fetched again in full.
+    pub fn apply(self, queue: &mut Vec<Song>) -> bool {
+        let mut changes = Vec::with_capacity(self.songs.len() + self.moved.len());
+        let mut vacated = HashSet::new();
+
+        if !self.moved.is_empty() {
+            let index: HashMap<u32, usize> =
+                queue.iter().enumerate().map(|(idx, song)| (song.id, idx)).collect();
+
+            for PosId { pos, id } in self.moved {
+                let Some(idx) = index.get(&id).copied() else {
+                    return false;
+                };
+                // The old position of a moved song is always either overwritten by another
+                // change or cut off, so the song can be taken out of the queue
+                let mut song = std::mem::take(&mut queue[idx]);
+                song.metadata.insert("pos".to_owned(), MetadataTag::Single(pos.to_string()));
+                vacated.insert(idx);
+                changes.push((pos, song));
+            }
+        }
+
+        for song in self.songs {
+            let Some(pos) = song.pos() else {
+                return false;
+            };
+            changes.push((pos, song));
+        }
+
+        changes.sort_unstable_by_key(|(pos, _)| *pos);
+        queue.truncate(self.length);
+
+        for (pos, song) in changes {
+            match pos.cmp(&queue.len()) {
+                Ordering::Less => queue[pos] = song,
+                Ordering::Equal => queue.push(song),
+                Ordering::Greater => return false,
+            }
+            vacated.remove(&pos);
+        }
+
+        queue.len() == self.length && vacated.iter().all(|idx| *idx >= self.length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(id: u32, pos: usize) -> Song {
+        Song {
+            id,
+            file: format!("song{id}.mp3"),
+            metadata: HashMap::from([("pos".to_owned(), MetadataTag::Single(pos.to_string()))]),
+            ..Default::default()
+        }
+    }
+
+    fn queue(ids: &[u32]) -> Vec<Song> {
+        ids.iter().enumerate().map(|(pos, id)| song(*id, pos)).collect()
+    }
+
+    fn ids(queue: &[Song]) -> Vec<u32> {
+        queue.iter().map(|song| song.id).collect()
+    }
+
+    fn diff(length: usize, songs: Vec<Song>, moved: Vec<PosId>) -> QueueDiff {
+        let version = |version| QueueVersion { partition: "default".to_owned(), version };
+        QueueDiff { from: version(1), to: version(2), length, songs, moved }
+    }
+
+    #[test]
+    fn appends_new_songs() {
+        let mut q = queue(&[1, 2]);
+
+        assert!(diff(4, vec![song(3, 2), song(4, 3)], vec![]).apply(&mut q));
+
+        assert_eq!(ids(&q), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn removes_song_from_the_middle() {
+        let mut q = queue(&[1, 2, 3, 4]);
+
+        let moved = vec![PosId { pos: 1, id: 3 }, PosId { pos: 2, id: 4 }];
+        assert!(diff(3, vec![], moved).apply(&mut q));
+
+        assert_eq!(ids(&q), vec![1, 3, 4]);
+        assert_eq!(q[2].pos(), Some(2));
+    }
+
+    #[test]
+    fn swaps_songs() {
+        let mut q = queue(&[1, 2, 3]);
+
+        let moved = vec![PosId { pos: 0, id: 3 }, PosId { pos: 2, id: 1 }];
+        assert!(diff(3, vec![], moved).apply(&mut q));
+
+        assert_eq!(ids(&q), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn replaces_modified_song() {
+        let mut q = queue(&[1, 2, 3]);
+        let mut modified = song(2, 1);
+        modified.prio = 10;
+
+        assert!(diff(3, vec![modified], vec![]).apply(&mut q));
+
+        assert_eq!(ids(&q), vec![1, 2, 3]);
+        assert_eq!(q[1].prio, 10);
+    }
+
+    #[test]
+    fn clears_queue() {
+        let mut q = queue(&[1, 2, 3]);
+
+        assert!(diff(0, vec![], vec![]).apply(&mut q));
+
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn fails_on_unknown_moved_song() {
+        let mut q = queue(&[1, 2]);
+
+        assert!(!diff(2, vec![], vec![PosId { pos: 0, id: 5 }]).apply(&mut q));
+    }
+
+    #[test]
+    fn fails_on_gap() {
+        let mut q = queue(&[1, 2]);
+
+        assert!(!diff(4, vec![song(4, 3)], vec![]).apply(&mut q));
+    }
+
+    #[test]
+    fn fails_when_moved_song_leaves_hole() {
+        let mut q = queue(&[1, 2, 3]);
+
+        assert!(!diff(3, vec![], vec![PosId { pos: 2, id: 1 }]).apply(&mut q));
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled, ParseErrorExt},
+};
+
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::AsRefStr,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+    /// Album gain when playing songs from a single album in order, track gain
+    /// otherwise
+    Auto,
+}
+
+impl ReplayGainMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            ReplayGainMode::Off => ReplayGainMode::Track,
+            ReplayGainMode::Track => ReplayGainMode::Album,
+            ReplayGainMode::Album => ReplayGainMode::Auto,
+            ReplayGainMode::Auto => ReplayGainMode::Off,
+        }
+    }
+}
+
+impl std::str::FromStr for ReplayGainMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(ReplayGainMode::Off),
+            "track" => Ok(ReplayGainMode::Track),
+            "album" => Ok(ReplayGainMode::Album),
+            "auto" => Ok(ReplayGainMode::Auto),
+            val => Err(anyhow!("Received unknown value for ReplayGainMode '{val}'")),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayGainStatus {
+    pub mode: ReplayGainMode,
+}
+
+impl FromMpd for ReplayGainStatus {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "replay_gain_mode" => self.mode = value.parse().logerr(key, &value)?,
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{FromMpd, ReplayGainMode, ReplayGainStatus};
+
+    #[test]
+    fn parses_replay_gain_status() {
+        let mut result = ReplayGainStatus::default();
+        result.next_internal("replay_gain_mode", "album".to_owned()).unwrap();
+
+        assert_eq!(result.mode, ReplayGainMode::Album);
+    }
+
+    #[test]
+    fn cycles_through_all_modes() {
+        let mut mode = ReplayGainMode::Off;
+        let mut visited = Vec::new();
+        for _ in 0..4 {
+            visited.push(mode.to_string());
+            mode = mode.cycle();
+        }
+
+        assert_eq!(mode, ReplayGainMode::Off);
+        assert_eq!(visited, vec!["off", "track", "album", "auto"]);
+    }
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    errors::MpdError,
+    from_mpd::{FromMpd, LineHandled, ParseErrorExt},
+};
+
+#[derive(Debug, Default, Serialize, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub artists: u64,
+    pub albums: u64,
+    pub songs: u64,
+    /// How long MPD has been running
+    pub uptime: Duration,
+    /// How long MPD has been playing
+    pub playtime: Duration,
+    /// Sum of durations of all songs in the database
+    pub db_playtime: Duration,
+    /// Time of the last database update, `None` when the database was never
+    /// updated
+    pub db_update: Option<DateTime<Utc>>,
+}
+
+impl FromMpd for Stats {
+    fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
+        match key {
+            "artists" => self.artists = value.parse().logerr(key, &value)?,
+            "albums" => self.albums = value.parse().logerr(key, &value)?,
+            "songs" => self.songs = value.parse().logerr(key, &value)?,
+            "uptime" => self.uptime = Duration::from_secs(value.parse().logerr(key, &value)?),
+            "playtime" => self.playtime = Duration::from_secs(value.parse().logerr(key, &value)?),
+            "db_playtime" => {
+                self.db_playtime = Duration::from_secs(value.parse().logerr(key, &value)?);
+            }
+            "db_update" => {
+                let timestamp: i64 = value.parse().logerr(key, &value)?;
+                self.db_update = (timestamp > 0)
+                    .then(|| {
+                        DateTime::from_timestamp(timestamp, 0)
+                            .with_context(|| format!("Invalid db_update timestamp '{value}'"))
+                    })
+                    .transpose()?;
+            }
+            _ => return Ok(LineHandled::No { value }),
+        }
+        Ok(LineHandled::Yes)
+    }
+}
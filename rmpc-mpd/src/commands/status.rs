@@ -1,9 +1,9 @@
 use std::time::Duration;
 
 use anyhow::anyhow;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use super::Volume;
+use super::{Volume, plchanges::QueueVersion};
 use crate::{
     errors::MpdError,
     from_mpd::{FromMpd, LineHandled, ParseErrorExt},
@@ -68,6 +68,23 @@ impl Status {
             audio.split(':').nth(2).and_then(|channels_str| channels_str.parse().ok())
         })
     }
+
+    pub fn queue_version(&self) -> Option<QueueVersion> {
+        self.playlist.map(|version| QueueVersion { partition: self.partition.clone(), version })
+    }
+
+    pub fn mixramp_db(&self) -> Option<f32> {
+        self.mixrampdb.as_ref().and_then(|db| db.parse().ok())
+    }
+
+    /// `None` when mixramp is disabled, MPD reports the delay as `nan` in
+    /// that case
+    pub fn mixramp_delay(&self) -> Option<f32> {
+        self.mixrampdelay
+            .as_ref()
+            .and_then(|delay| delay.parse().ok())
+            .filter(|delay: &f32| delay.is_finite() && *delay >= 0.0)
+    }
 }
 
 impl FromMpd for Status {
@@ -114,7 +131,7 @@ impl FromMpd for Status {
     }
 }
 
-#[derive(Debug, Serialize, Default, PartialEq, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone, Copy)]
 pub enum State {
     Play,
     #[default]
@@ -122,7 +139,7 @@ pub enum State {
     Pause,
 }
 
-#[derive(Debug, Serialize, Default, Clone, Copy, PartialEq, Eq, strum::AsRefStr)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, strum::AsRefStr)]
 pub enum OnOffOneshot {
     #[strum(serialize = "On")]
     On,
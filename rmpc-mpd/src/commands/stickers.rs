@@ -6,9 +6,35 @@ use serde::Serialize;
 
 use crate::{
     errors::MpdError,
+    filter::Tag,
     from_mpd::{FromMpd, LineHandled},
 };
 
+/// Kind of object a sticker is attached to. Every type except
+/// [`StickerType::Song`] requires MPD 0.24 or newer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StickerType {
+    Song,
+    Playlist,
+    /// Sticker attached to a value of the tag, uri is then the tag value, ie.
+    /// name of an album
+    Tag(Tag),
+    /// Only usable with `sticker find`, uri is then a filter expression
+    /// matching songs
+    Filter,
+}
+
+impl StickerType {
+    pub(crate) fn as_mpd_str(&self) -> &str {
+        match self {
+            StickerType::Song => "song",
+            StickerType::Playlist => "playlist",
+            StickerType::Tag(tag) => tag.as_str(),
+            StickerType::Filter => "filter",
+        }
+    }
+}
+
 #[derive(Debug, Default, Serialize, IntoIterator, AsRef, AsMut, Into)]
 pub struct Stickers(pub HashMap<String, String>);
 
@@ -23,6 +49,8 @@ pub struct Sticker {
 
 #[derive(Debug, Serialize, Default)]
 pub struct StickerWithFile {
+    /// Uri of the object the sticker is attached to, file for songs, name for
+    /// playlists and the tag value for tag stickers
     pub file: String,
     pub key: String,
     pub value: String,
@@ -65,9 +93,12 @@ impl FromMpd for Sticker {
 }
 
 impl FromMpd for StickersWithFile {
+    // Every object starts with a line keyed by its type, ie. `file`, `playlist`
+    // or the tag name
     fn next_internal(&mut self, key: &str, value: String) -> Result<LineHandled, MpdError> {
-        if key == "file" {
-            self.0.push(StickerWithFile::default());
+        if key != "sticker" {
+            self.0.push(StickerWithFile { file: value, ..Default::default() });
+            return Ok(LineHandled::Yes);
         }
 
         self.0
@@ -97,3 +128,23 @@ impl FromMpd for StickerWithFile {
         Ok(LineHandled::Yes)
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::{FromMpd, StickersWithFile};
+
+    #[test]
+    fn parses_found_tag_stickers() {
+        let mut result = StickersWithFile::default();
+        for line in
+            ["Album: First album", "sticker: rating=8", "Album: Second: album", "sticker: rating=2"]
+        {
+            result.next(line.to_owned()).unwrap();
+        }
+
+        let result =
+            result.0.iter().map(|s| (s.file.as_str(), s.value.as_str())).collect::<Vec<_>>();
+        assert_eq!(result, vec![("First album", "8"), ("Second: album", "2")]);
+    }
+}
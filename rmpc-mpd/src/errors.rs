@@ -12,6 +12,9 @@ pub enum MpdError {
     ValueExpected(String),
     UnsupportedMpdVersion(&'static str),
     TimedOut(String),
+    /// MPD has no handler for the scheme of the URL, contains the scheme
+    /// including `://`
+    UnsupportedUrlScheme(String),
 }
 
 impl std::error::Error for MpdError {}
@@ -54,6 +57,12 @@ impl Display for MpdError {
                 write!(f, "Unsupported MPD version: '{val}'")
             }
             MpdError::TimedOut(msg) => write!(f, "Reading response from MPD timed out, '{msg}'"),
+            MpdError::UnsupportedUrlScheme(scheme) if scheme == "file://" => {
+                write!(f, "MPD accepts local files only from clients connected through a socket")
+            }
+            MpdError::UnsupportedUrlScheme(scheme) => {
+                write!(f, "MPD has no handler for '{scheme}' URLs")
+            }
         }
     }
 }
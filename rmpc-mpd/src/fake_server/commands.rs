@@ -0,0 +1,1597 @@
+use std::{borrow::Cow, collections::BTreeSet, fmt::Display, str::FromStr, time::Duration};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::{
+    Ack,
+    Session,
+    state::{FakeMpd, StoredPlaylist},
+};
+use crate::{
+    commands::{IdleEvent, Song, State, replay_gain::ReplayGainMode, status::OnOffOneshot},
+    errors::ErrorCode,
+    filter::{AudioFormatOperator, FilterExpr, Tag, TagOperator},
+    song_range::SongRange,
+};
+
+const COMMANDS: &[&str] = &[
+    "add",
+    "addid",
+    "albumart",
+    "binarylimit",
+    "channels",
+    "clear",
+    "close",
+    "commands",
+    "config",
+    "consume",
+    "count",
+    "crossfade",
+    "currentsong",
+    "decoders",
+    "delete",
+    "deleteid",
+    "disableoutput",
+    "enableoutput",
+    "find",
+    "findadd",
+    "getvol",
+    "idle",
+    "list",
+    "listall",
+    "listallinfo",
+    "listfiles",
+    "listpartitions",
+    "listplaylist",
+    "listplaylistinfo",
+    "listplaylists",
+    "load",
+    "lsinfo",
+    "mixrampdb",
+    "mixrampdelay",
+    "move",
+    "moveid",
+    "next",
+    "noidle",
+    "notcommands",
+    "outputs",
+    "partition",
+    "password",
+    "pause",
+    "ping",
+    "play",
+    "playid",
+    "playlistadd",
+    "playlistclear",
+    "playlistdelete",
+    "playlistfind",
+    "playlistid",
+    "playlistinfo",
+    "playlistmove",
+    "playlistsearch",
+    "plchanges",
+    "plchangesposid",
+    "previous",
+    "prio",
+    "prioid",
+    "protocol",
+    "random",
+    "rangeid",
+    "readcomments",
+    "readmessages",
+    "readpicture",
+    "rename",
+    "repeat",
+    "replay_gain_mode",
+    "replay_gain_status",
+    "rescan",
+    "rm",
+    "save",
+    "search",
+    "searchadd",
+    "searchcount",
+    "seek",
+    "seekcur",
+    "seekid",
+    "sendmessage",
+    "setvol",
+    "shuffle",
+    "single",
+    "stats",
+    "status",
+    "sticker",
+    "stickernames",
+    "stickertypes",
+    "stop",
+    "stringnormalization",
+    "subscribe",
+    "swap",
+    "swapid",
+    "tagtypes",
+    "toggleoutput",
+    "unsubscribe",
+    "update",
+    "urlhandlers",
+    "volume",
+];
+
+const TAGS: &[&str] = &[
+    "Artist",
+    "ArtistSort",
+    "Album",
+    "AlbumSort",
+    "AlbumArtist",
+    "AlbumArtistSort",
+    "Title",
+    "TitleSort",
+    "Track",
+    "Name",
+    "Genre",
+    "Mood",
+    "Date",
+    "OriginalDate",
+    "Composer",
+    "ComposerSort",
+    "Performer",
+    "Conductor",
+    "Work",
+    "Movement",
+    "MovementNumber",
+    "ShowMovement",
+    "Ensemble",
+    "Location",
+    "Grouping",
+    "Comment",
+    "Disc",
+    "Label",
+    "MUSICBRAINZ_ARTISTID",
+    "MUSICBRAINZ_ALBUMID",
+    "MUSICBRAINZ_ALBUMARTISTID",
+    "MUSICBRAINZ_TRACKID",
+    "MUSICBRAINZ_RELEASETRACKID",
+    "MUSICBRAINZ_WORKID",
+    "MUSICBRAINZ_RELEASEGROUPID",
+];
+
+/// Song metadata keys which are not tags
+const INTERNAL_KEYS: &[&str] =
+    &["file", "pos", "id", "range", "prio", "time", "duration", "last-modified", "added"];
+
+/// Splits the command line into arguments the way MPD does, arguments can be
+/// double quoted with `"` and `\` escaped inside the quotes
+pub(super) fn split_args(line: &str) -> Result<Vec<String>, Ack> {
+    let mut args = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut arg = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped) => arg.push(escaped),
+                        None => return Err(Ack::new(ErrorCode::Argument, "Missing closing '\"'")),
+                    },
+                    Some(c) => arg.push(c),
+                    None => return Err(Ack::new(ErrorCode::Argument, "Missing closing '\"'")),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                arg.push(c);
+                chars.next();
+            }
+        }
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+pub(super) fn execute(
+    mpd: &mut FakeMpd,
+    session: &mut Session,
+    command: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    match command {
+        "ping" | "stringnormalization" => {}
+        "password" => {
+            if mpd.password.as_deref() != Some(arg(args, 0)?) {
+                return Err(Ack::new(ErrorCode::Password, "incorrect password"));
+            }
+        }
+        "binarylimit" => session.binary_limit = num::<usize>(arg(args, 0)?)?.max(64),
+        "commands" => COMMANDS.iter().for_each(|command| line(out, "command", command)),
+        "notcommands" => {}
+        "tagtypes" => match args.first().map(String::as_str) {
+            None | Some("available") => TAGS.iter().for_each(|tag| line(out, "tagtype", tag)),
+            Some("all" | "clear" | "reset" | "enable" | "disable") => {}
+            Some(other) => {
+                return Err(Ack::new(ErrorCode::Argument, format!("Unknown sub command {other}")));
+            }
+        },
+        "protocol" => match args.first().map(String::as_str) {
+            None | Some("available" | "all" | "clear" | "enable" | "disable") => {}
+            Some(other) => {
+                return Err(Ack::new(ErrorCode::Argument, format!("Unknown sub command {other}")));
+            }
+        },
+        "urlhandlers" => {
+            line(out, "handler", "http://");
+            line(out, "handler", "https://");
+        }
+        "decoders" => {
+            line(out, "plugin", "fake");
+            for suffix in ["flac", "mp3", "ogg", "opus"] {
+                line(out, "suffix", suffix);
+            }
+        }
+        "config" => {
+            return Err(Ack::new(ErrorCode::Permission, "Command only permitted to local clients"));
+        }
+        "partition" => {
+            if arg(args, 0)? != "default" {
+                return Err(Ack::new(ErrorCode::NoExist, "partition does not exist"));
+            }
+        }
+        "listpartitions" => line(out, "partition", "default"),
+        "status" => status(mpd, out),
+        "currentsong" => {
+            if let Some(pos) = mpd.playback.current {
+                write_entry(out, mpd, pos);
+            }
+        }
+        "stats" => stats(mpd, out),
+        "replay_gain_status" => {
+            line(out, "replay_gain_mode", mpd.playback.replay_gain_mode);
+        }
+        "readcomments" => {
+            song_or_stream(mpd, arg(args, 0)?)?;
+        }
+        "play" | "playid" | "pause" | "stop" | "next" | "previous" | "seek" | "seekid"
+        | "seekcur" | "setvol" | "volume" | "getvol" | "repeat" | "random" | "single"
+        | "consume" | "crossfade" | "mixrampdb" | "mixrampdelay" | "replay_gain_mode" => {
+            playback(mpd, command, args, out)?;
+        }
+        "add" | "addid" | "delete" | "deleteid" | "clear" | "move" | "moveid" | "swap"
+        | "swapid" | "shuffle" | "prio" | "prioid" | "rangeid" | "playlistinfo" | "playlistid"
+        | "plchanges" | "plchangesposid" | "playlistfind" | "playlistsearch" => {
+            queue(mpd, command, args, out)?;
+        }
+        "find" | "search" | "findadd" | "searchadd" | "list" | "count" | "searchcount"
+        | "listall" | "listallinfo" | "lsinfo" | "listfiles" | "update" | "rescan" => {
+            database(mpd, command, args, out)?;
+        }
+        "listplaylists" | "listplaylist" | "listplaylistinfo" | "load" | "save" | "rm"
+        | "rename" | "playlistadd" | "playlistdelete" | "playlistmove" | "playlistclear" => {
+            stored_playlist(mpd, command, args, out)?;
+        }
+        "sticker" => sticker(mpd, args, out)?,
+        "stickernames" => {
+            let names = mpd.stickers.values().flat_map(|stickers| stickers.keys());
+            names.collect::<BTreeSet<_>>().iter().for_each(|name| line(out, "name", name));
+        }
+        "stickertypes" => {
+            for ty in ["song", "playlist"].into_iter().chain(TAGS.iter().copied()) {
+                line(out, "stickertype", ty);
+            }
+        }
+        "subscribe" | "unsubscribe" | "channels" | "sendmessage" | "readmessages" => {
+            messages(mpd, session, command, args, out)?;
+        }
+        "outputs" => {
+            for (id, (name, enabled)) in mpd.outputs.iter().enumerate() {
+                line(out, "outputid", id);
+                line(out, "outputname", name);
+                line(out, "plugin", "fake");
+                line(out, "outputenabled", u8::from(*enabled));
+            }
+        }
+        "enableoutput" | "disableoutput" | "toggleoutput" => {
+            let id: usize = num(arg(args, 0)?)?;
+            let Some((_, enabled)) = mpd.outputs.get_mut(id) else {
+                return Err(Ack::new(ErrorCode::NoExist, "No such audio output"));
+            };
+            *enabled = match command {
+                "enableoutput" => true,
+                "disableoutput" => false,
+                _ => !*enabled,
+            };
+            mpd.emit(IdleEvent::Output);
+        }
+        "albumart" | "readpicture" => picture(mpd, session, command, args, out)?,
+        _ => return Err(Ack::new(ErrorCode::UnknownCmd, format!("unknown command \"{command}\""))),
+    }
+    Ok(())
+}
+
+fn status(mpd: &FakeMpd, out: &mut Vec<u8>) {
+    let playback = &mpd.playback;
+    line(out, "volume", playback.volume);
+    line(out, "repeat", u8::from(playback.repeat));
+    line(out, "random", u8::from(playback.random));
+    line(out, "single", playback.single.to_mpd_value());
+    line(out, "consume", playback.consume.to_mpd_value());
+    line(out, "partition", "default");
+    line(out, "playlist", mpd.queue_version);
+    line(out, "playlistlength", mpd.queue.len());
+    line(out, "mixrampdb", 0);
+    line(out, "state", match playback.state {
+        State::Play => "play",
+        State::Stop => "stop",
+        State::Pause => "pause",
+    });
+    if playback.crossfade > 0 {
+        line(out, "xfade", playback.crossfade);
+    }
+    if let Some(entry) = mpd.current_entry() {
+        line(out, "song", playback.current.unwrap_or_default());
+        line(out, "songid", entry.id);
+
+        if playback.state != State::Stop {
+            let duration = entry.song.duration.unwrap_or_default();
+            line(out, "time", format!("{}:{}", playback.elapsed.as_secs(), duration.as_secs()));
+            line(out, "elapsed", format!("{:.3}", playback.elapsed.as_secs_f64()));
+            line(out, "duration", format!("{:.3}", duration.as_secs_f64()));
+            line(out, "bitrate", 0);
+            if let Some(format) = entry.song.metadata.get("format") {
+                line(out, "audio", format.first());
+            }
+        }
+    }
+    if let Some(next) = mpd.next_pos() {
+        line(out, "nextsong", next);
+        line(out, "nextsongid", mpd.queue[next].id);
+    }
+}
+
+fn stats(mpd: &FakeMpd, out: &mut Vec<u8>) {
+    let distinct = |tag: &str| {
+        mpd.library
+            .iter()
+            .filter_map(|song| song.metadata.get(tag))
+            .flat_map(|values| values.iter())
+            .collect::<BTreeSet<_>>()
+            .len()
+    };
+    let playtime: Duration = mpd.library.iter().filter_map(|song| song.duration).sum();
+    let updated = mpd.library.iter().map(|song| song.last_modified).max().unwrap_or_default();
+
+    line(out, "artists", distinct("artist"));
+    line(out, "albums", distinct("album"));
+    line(out, "songs", mpd.library.len());
+    line(out, "uptime", 0);
+    line(out, "db_playtime", playtime.as_secs());
+    line(out, "db_update", updated.timestamp());
+    line(out, "playtime", 0);
+}
+
+fn playback(
+    mpd: &mut FakeMpd,
+    command: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    match command {
+        "play" => match args.first() {
+            Some(pos) => {
+                let pos = num(pos)?;
+                if pos >= mpd.queue.len() {
+                    return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+                }
+                mpd.play_pos(pos);
+            }
+            None => resume(mpd),
+        },
+        "playid" => match args.first() {
+            Some(id) => {
+                let pos = entry_pos(mpd, num(id)?)?;
+                mpd.play_pos(pos);
+            }
+            None => resume(mpd),
+        },
+        "pause" => {
+            let pause = match args.first() {
+                Some(value) => boolean(value)?,
+                None => mpd.playback.state == State::Play,
+            };
+            if mpd.playback.state != State::Stop {
+                mpd.playback.state = if pause { State::Pause } else { State::Play };
+                mpd.emit(IdleEvent::Player);
+            }
+        }
+        "stop" => mpd.stop(),
+        "next" => {
+            if mpd.playback.state != State::Stop {
+                if let Some(pos) = mpd.next_pos() {
+                    mpd.play_pos(pos);
+                } else {
+                    mpd.playback.current = None;
+                    mpd.stop();
+                }
+            }
+        }
+        "previous" => {
+            if let Some(current) = mpd.playback.current
+                && mpd.playback.state != State::Stop
+            {
+                let pos = match current.checked_sub(1) {
+                    Some(pos) => pos,
+                    None if mpd.playback.repeat => mpd.queue.len() - 1,
+                    None => current,
+                };
+                mpd.play_pos(pos);
+            }
+        }
+        "seek" | "seekid" => {
+            let pos = if command == "seek" {
+                let pos = num(arg(args, 0)?)?;
+                if pos >= mpd.queue.len() {
+                    return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+                }
+                pos
+            } else {
+                entry_pos(mpd, num(arg(args, 0)?)?)?
+            };
+            let time = duration(arg(args, 1)?)?;
+            if mpd.playback.current != Some(pos) || mpd.playback.state == State::Stop {
+                mpd.play_pos(pos);
+            }
+            mpd.playback.elapsed = time;
+            mpd.emit(IdleEvent::Player);
+        }
+        "seekcur" => {
+            if mpd.playback.current.is_none() || mpd.playback.state == State::Stop {
+                return Err(Ack::new(ErrorCode::PlayerSync, "Not playing"));
+            }
+            let value = arg(args, 0)?;
+            let elapsed = mpd.playback.elapsed;
+            mpd.playback.elapsed = if let Some(offset) = value.strip_prefix('+') {
+                elapsed + duration(offset)?
+            } else if let Some(offset) = value.strip_prefix('-') {
+                elapsed.saturating_sub(duration(offset)?)
+            } else {
+                duration(value)?
+            };
+            mpd.emit(IdleEvent::Player);
+        }
+        "setvol" => {
+            let volume: u32 = num(arg(args, 0)?)?;
+            if volume > 100 {
+                return Err(Ack::new(ErrorCode::Argument, "Invalid volume value"));
+            }
+            mpd.playback.volume = volume;
+            mpd.emit(IdleEvent::Mixer);
+        }
+        "volume" => {
+            let change: i64 = num(arg(args, 0)?)?;
+            mpd.playback.volume = (i64::from(mpd.playback.volume) + change)
+                .clamp(0, 100)
+                .try_into()
+                .unwrap_or_default();
+            mpd.emit(IdleEvent::Mixer);
+        }
+        "getvol" => line(out, "volume", mpd.playback.volume),
+        "repeat" => {
+            mpd.playback.repeat = boolean(arg(args, 0)?)?;
+            mpd.emit(IdleEvent::Options);
+        }
+        "random" => {
+            mpd.playback.random = boolean(arg(args, 0)?)?;
+            mpd.emit(IdleEvent::Options);
+        }
+        "single" | "consume" => {
+            let value = arg(args, 0)?;
+            let value = OnOffOneshot::from_str(value).map_err(|_| {
+                Ack::new(
+                    ErrorCode::Argument,
+                    format!("Boolean (0/1) or 'oneshot' expected: {value}"),
+                )
+            })?;
+            if command == "single" {
+                mpd.playback.single = value;
+            } else {
+                mpd.playback.consume = value;
+            }
+            mpd.emit(IdleEvent::Options);
+        }
+        "crossfade" => {
+            mpd.playback.crossfade = num(arg(args, 0)?)?;
+            mpd.emit(IdleEvent::Options);
+        }
+        "mixrampdb" | "mixrampdelay" => {
+            float(arg(args, 0)?)?;
+            mpd.emit(IdleEvent::Options);
+        }
+        "replay_gain_mode" => {
+            let value = arg(args, 0)?;
+            mpd.playback.replay_gain_mode = ReplayGainMode::from_str(value).map_err(|_| {
+                Ack::new(ErrorCode::Argument, format!("Unrecognized replay gain mode: {value}"))
+            })?;
+            mpd.emit(IdleEvent::Options);
+        }
+        _ => unreachable!("{command} is not a playback command"),
+    }
+    Ok(())
+}
+
+/// Resumes playback, or starts playing the current or the first song
+fn resume(mpd: &mut FakeMpd) {
+    match (mpd.playback.state, mpd.playback.current) {
+        (State::Pause, Some(_)) => {
+            mpd.playback.state = State::Play;
+            mpd.emit(IdleEvent::Player);
+        }
+        (State::Play, Some(_)) => {}
+        (_, Some(current)) => mpd.play_pos(current),
+        (_, None) if !mpd.queue.is_empty() => mpd.play_pos(0),
+        (_, None) => {}
+    }
+}
+
+fn queue(mpd: &mut FakeMpd, command: &str, args: &[String], out: &mut Vec<u8>) -> Result<(), Ack> {
+    match command {
+        "add" => {
+            let songs = songs_for_uri(mpd, arg(args, 0)?)?;
+            let pos = args.get(1).map(|pos| position(mpd, pos)).transpose()?;
+            mpd.insert_songs(pos, songs);
+        }
+        "addid" => {
+            let song = song_or_stream(mpd, arg(args, 0)?)?;
+            let pos = args.get(1).map(|pos| position(mpd, pos)).transpose()?;
+            for id in mpd.insert_songs(pos, vec![song]) {
+                line(out, "Id", id);
+            }
+        }
+        "delete" => {
+            let (start, end) = range(arg(args, 0)?, mpd.queue.len())?;
+            mpd.remove_songs(start, end);
+        }
+        "deleteid" => {
+            let pos = entry_pos(mpd, num(arg(args, 0)?)?)?;
+            mpd.remove_songs(pos, pos + 1);
+        }
+        "clear" => {
+            mpd.playback.current = None;
+            mpd.stop();
+            mpd.queue.clear();
+            mpd.queue_changed(0);
+        }
+        "move" | "moveid" => {
+            let (start, end) = if command == "move" {
+                range(arg(args, 0)?, mpd.queue.len())?
+            } else {
+                let pos = entry_pos(mpd, num(arg(args, 0)?)?)?;
+                (pos, pos + 1)
+            };
+            let to = position(mpd, arg(args, 1)?)?;
+            if to + (end - start) > mpd.queue.len() {
+                return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+            }
+            mpd.move_songs(start, end, to);
+        }
+        "swap" | "swapid" => {
+            let (a, b) = if command == "swap" {
+                let (a, b): (usize, usize) = (num(arg(args, 0)?)?, num(arg(args, 1)?)?);
+                if a.max(b) >= mpd.queue.len() {
+                    return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+                }
+                (a, b)
+            } else {
+                (entry_pos(mpd, num(arg(args, 0)?)?)?, entry_pos(mpd, num(arg(args, 1)?)?)?)
+            };
+            let current_id = mpd.current_entry().map(|entry| entry.id);
+            mpd.queue.swap(a, b);
+            mpd.playback.current = current_id.and_then(|id| mpd.position_of(id));
+            touch(mpd, &[a, b]);
+        }
+        "shuffle" => {
+            // Reversed instead of shuffled to keep the result deterministic
+            let (start, end) = match args.first() {
+                Some(value) => range(value, mpd.queue.len())?,
+                None => (0, mpd.queue.len()),
+            };
+            let current_id = mpd.current_entry().map(|entry| entry.id);
+            mpd.queue[start..end].reverse();
+            mpd.playback.current = current_id.and_then(|id| mpd.position_of(id));
+            touch(mpd, &(start..end).collect::<Vec<_>>());
+        }
+        "prio" | "prioid" => {
+            let prio: u8 = num(arg(args, 0)?)?;
+            let mut positions = Vec::new();
+            for value in args.iter().skip(1) {
+                if command == "prio" {
+                    let (start, end) = range(value, mpd.queue.len())?;
+                    positions.extend(start..end);
+                } else {
+                    positions.push(entry_pos(mpd, num(value)?)?);
+                }
+            }
+            for pos in &positions {
+                mpd.queue[*pos].prio = prio;
+            }
+            touch(mpd, &positions);
+        }
+        "rangeid" => {
+            let pos = entry_pos(mpd, num(arg(args, 0)?)?)?;
+            let value = arg(args, 1)?;
+            mpd.queue[pos].range = match value.split_once(':') {
+                Some(("", "")) => None,
+                Some((start, end)) => {
+                    let parse =
+                        |value: &str| (!value.is_empty()).then(|| duration(value)).transpose();
+                    Some(SongRange::new(parse(start)?, parse(end)?))
+                }
+                None => return Err(Ack::new(ErrorCode::Argument, "Bad range")),
+            };
+            touch(mpd, &[pos]);
+        }
+        "playlistinfo" => {
+            let (start, end) = match args.first() {
+                Some(value) => range(value, mpd.queue.len())?,
+                None => (0, mpd.queue.len()),
+            };
+            (start..end).for_each(|pos| write_entry(out, mpd, pos));
+        }
+        "playlistid" => match args.first() {
+            Some(id) => write_entry(out, mpd, entry_pos(mpd, num(id)?)?),
+            None => (0..mpd.queue.len()).for_each(|pos| write_entry(out, mpd, pos)),
+        },
+        "plchanges" | "plchangesposid" => {
+            let version: u32 = num(arg(args, 0)?)?;
+            for (pos, entry) in mpd.queue.iter().enumerate() {
+                if entry.changed <= version {
+                    continue;
+                }
+                if command == "plchanges" {
+                    write_entry(out, mpd, pos);
+                } else {
+                    line(out, "cpos", pos);
+                    line(out, "Id", entry.id);
+                }
+            }
+        }
+        "playlistfind" | "playlistsearch" => {
+            let query = Query::parse(args, command == "playlistfind")?;
+            for (pos, entry) in mpd.queue.iter().enumerate() {
+                if query.matches(&entry.song, Some(entry.prio))? {
+                    write_entry(out, mpd, pos);
+                }
+            }
+        }
+        _ => unreachable!("{command} is not a queue command"),
+    }
+    Ok(())
+}
+
+/// Marks the entries in the queue as changed
+fn touch(mpd: &mut FakeMpd, positions: &[usize]) {
+    mpd.queue_version += 1;
+    for pos in positions {
+        mpd.queue[*pos].changed = mpd.queue_version;
+    }
+    mpd.emit(IdleEvent::Playlist);
+}
+
+fn database(
+    mpd: &mut FakeMpd,
+    command: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    match command {
+        "find" | "search" | "findadd" | "searchadd" => {
+            let query = Query::parse(args, command.starts_with("find"))?;
+            let songs = query.songs(mpd)?;
+            if command.ends_with("add") {
+                let pos = query.position.as_deref().map(|pos| position(mpd, pos)).transpose()?;
+                let songs = songs.into_iter().cloned().collect();
+                mpd.insert_songs(pos, songs);
+            } else {
+                for song in songs {
+                    write_song(out, song);
+                }
+            }
+        }
+        "list" => {
+            let tag = Tag::from_name(arg(args, 0)?);
+            let query = Query::parse(&args[1..], true)?;
+            let mut rows = BTreeSet::new();
+            for song in &mpd.library {
+                if !query.matches(song, None)? {
+                    continue;
+                }
+                let groups = query
+                    .groups
+                    .iter()
+                    .map(|group| tag_values(song, group).first().copied().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                for value in tag_values(song, &tag) {
+                    if !value.is_empty() {
+                        rows.insert((groups.clone(), value));
+                    }
+                }
+            }
+
+            let mut previous: Option<&Vec<&str>> = None;
+            for (groups, value) in &rows {
+                // Group values are printed from the first one which changed
+                let changed = previous.map_or(0, |previous| {
+                    previous.iter().zip(groups).take_while(|(a, b)| a == b).count()
+                });
+                if previous != Some(groups) {
+                    for (group, group_value) in query.groups.iter().zip(groups).skip(changed) {
+                        line(out, &tag_name(group.as_str()), group_value);
+                    }
+                }
+                line(out, &tag_name(tag.as_str()), value);
+                previous = Some(groups);
+            }
+        }
+        "count" | "searchcount" => {
+            let query = Query::parse(args, command == "count")?;
+            let songs = query.songs(mpd)?;
+            let write_count = |out: &mut Vec<u8>, songs: &[&Song]| {
+                let playtime: Duration = songs.iter().filter_map(|song| song.duration).sum();
+                line(out, "songs", songs.len());
+                line(out, "playtime", playtime.as_secs());
+            };
+            match query.groups.first() {
+                Some(group) => {
+                    let mut groups = std::collections::BTreeMap::<&str, Vec<&Song>>::new();
+                    for song in songs {
+                        let value = tag_values(song, group).first().copied().unwrap_or_default();
+                        groups.entry(value).or_default().push(song);
+                    }
+                    for (value, songs) in groups {
+                        line(out, &tag_name(group.as_str()), value);
+                        write_count(out, &songs);
+                    }
+                }
+                None => write_count(out, &songs),
+            }
+        }
+        "listall" | "listallinfo" | "lsinfo" | "listfiles" => {
+            let uri = args.first().map_or("", |uri| uri.trim_matches('/'));
+            if let Some(song) = mpd.song(uri) {
+                match command {
+                    "listall" => line(out, "file", &song.file),
+                    "listfiles" => write_file(out, song, &song.file),
+                    _ => write_song(out, song),
+                }
+                return Ok(());
+            }
+
+            let songs =
+                mpd.library.iter().filter(|song| in_dir(&song.file, uri)).collect::<Vec<_>>();
+            if songs.is_empty() && !uri.is_empty() {
+                return Err(Ack::new(ErrorCode::NoExist, "No such directory"));
+            }
+            let recursive = command.starts_with("listall");
+            list_directory(out, command, uri, &songs, recursive);
+
+            if command == "lsinfo" && uri.is_empty() {
+                for (name, playlist) in &mpd.playlists {
+                    line(out, "playlist", name);
+                    line(out, "Last-Modified", timestamp_str(playlist.last_modified));
+                }
+            }
+        }
+        "update" | "rescan" => {
+            line(out, "updating_db", 1);
+            mpd.emit(IdleEvent::Update);
+            mpd.emit(IdleEvent::Database);
+        }
+        _ => unreachable!("{command} is not a database command"),
+    }
+    Ok(())
+}
+
+fn list_directory(out: &mut Vec<u8>, command: &str, dir: &str, songs: &[&Song], recursive: bool) {
+    let prefix = if dir.is_empty() { String::new() } else { format!("{dir}/") };
+    let mut directories = std::collections::BTreeMap::<String, DateTime<Utc>>::new();
+    let mut files = Vec::new();
+    for song in songs {
+        let Some(rest) = song.file.strip_prefix(&prefix) else {
+            continue;
+        };
+        let parts = rest.split('/').collect::<Vec<_>>();
+        let depth = if recursive { parts.len() - 1 } else { parts.len().min(2) - 1 };
+        for len in 1..=depth {
+            let path = format!("{prefix}{}", parts[..len].join("/"));
+            let modified = directories.entry(path).or_default();
+            *modified = (*modified).max(song.last_modified);
+        }
+        if recursive || parts.len() == 1 {
+            files.push(*song);
+        }
+    }
+
+    for (path, modified) in &directories {
+        if command == "listfiles" {
+            line(out, "directory", path.strip_prefix(&prefix).unwrap_or(path));
+        } else {
+            line(out, "directory", path);
+        }
+        if command != "listall" {
+            line(out, "Last-Modified", timestamp_str(*modified));
+        }
+    }
+    for song in files {
+        match command {
+            "listall" => line(out, "file", &song.file),
+            "listfiles" => {
+                write_file(out, song, song.file.strip_prefix(&prefix).unwrap_or_default());
+            }
+            _ => write_song(out, song),
+        }
+    }
+}
+
+fn write_file(out: &mut Vec<u8>, song: &Song, name: &str) {
+    line(out, "file", name);
+    line(out, "size", 0);
+    line(out, "Last-Modified", timestamp_str(song.last_modified));
+}
+
+fn stored_playlist(
+    mpd: &mut FakeMpd,
+    command: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    match command {
+        "listplaylists" => {
+            for (name, playlist) in &mpd.playlists {
+                line(out, "playlist", name);
+                line(out, "Last-Modified", timestamp_str(playlist.last_modified));
+            }
+            return Ok(());
+        }
+        "listplaylist" | "listplaylistinfo" => {
+            let playlist = playlist(mpd, arg(args, 0)?)?;
+            let (start, end) = match args.get(1) {
+                Some(value) => range(value, playlist.songs.len())?,
+                None => (0, playlist.songs.len()),
+            };
+            for uri in &playlist.songs[start..end] {
+                match mpd.song(uri) {
+                    Some(song) if command == "listplaylistinfo" => write_song(out, song),
+                    _ => line(out, "file", uri),
+                }
+            }
+            return Ok(());
+        }
+        "load" => {
+            let songs = &playlist(mpd, arg(args, 0)?)?.songs;
+            let (start, end) = match args.get(1) {
+                Some(value) => range(value, songs.len())?,
+                None => (0, songs.len()),
+            };
+            let songs = songs[start..end]
+                .iter()
+                .map(|uri| mpd.song(uri).cloned().unwrap_or_else(|| stream_song(uri)))
+                .collect();
+            let pos = args.get(2).map(|pos| position(mpd, pos)).transpose()?;
+            mpd.insert_songs(pos, songs);
+            return Ok(());
+        }
+        "save" => {
+            let name = arg(args, 0)?;
+            let uris = mpd.queue.iter().map(|entry| entry.song.file.clone());
+            match (args.get(1).map_or("create", String::as_str), mpd.playlists.get_mut(name)) {
+                ("create", Some(_)) => {
+                    return Err(Ack::new(ErrorCode::Exist, "Playlist already exists"));
+                }
+                ("append", Some(playlist)) => playlist.songs.extend(uris),
+                ("append" | "replace", None) => {
+                    return Err(Ack::new(ErrorCode::NoExist, "No such playlist"));
+                }
+                ("create" | "replace", _) => {
+                    let songs = uris.collect();
+                    mpd.playlists.insert(name.to_owned(), StoredPlaylist {
+                        songs,
+                        last_modified: Utc::now(),
+                    });
+                }
+                (mode, _) => {
+                    return Err(Ack::new(
+                        ErrorCode::Argument,
+                        format!("Unrecognized save mode: {mode}"),
+                    ));
+                }
+            }
+        }
+        "rm" => {
+            if mpd.playlists.remove(arg(args, 0)?).is_none() {
+                return Err(Ack::new(ErrorCode::NoExist, "No such playlist"));
+            }
+        }
+        "rename" => {
+            let (from, to) = (arg(args, 0)?, arg(args, 1)?);
+            if mpd.playlists.contains_key(to) {
+                return Err(Ack::new(ErrorCode::Exist, "Playlist already exists"));
+            }
+            let Some(playlist) = mpd.playlists.remove(from) else {
+                return Err(Ack::new(ErrorCode::NoExist, "No such playlist"));
+            };
+            mpd.playlists.insert(to.to_owned(), playlist);
+        }
+        "playlistadd" => {
+            let name = arg(args, 0)?;
+            let uris = songs_for_uri(mpd, arg(args, 1)?)?
+                .into_iter()
+                .map(|song| song.file)
+                .collect::<Vec<_>>();
+            let playlist = mpd
+                .playlists
+                .entry(name.to_owned())
+                .or_insert_with(|| StoredPlaylist { songs: Vec::new(), last_modified: Utc::now() });
+            let pos = match args.get(2) {
+                Some(pos) => num::<usize>(pos)?,
+                None => playlist.songs.len(),
+            };
+            if pos > playlist.songs.len() {
+                return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+            }
+            playlist.songs.splice(pos..pos, uris);
+        }
+        "playlistdelete" => {
+            let playlist = playlist_mut(mpd, arg(args, 0)?)?;
+            let (start, end) = range(arg(args, 1)?, playlist.songs.len())?;
+            playlist.songs.drain(start..end);
+        }
+        "playlistmove" => {
+            let playlist = playlist_mut(mpd, arg(args, 0)?)?;
+            let (start, end) = range(arg(args, 1)?, playlist.songs.len())?;
+            let to: usize = num(arg(args, 2)?)?;
+            if to + (end - start) > playlist.songs.len() {
+                return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+            }
+            let moved = playlist.songs.drain(start..end).collect::<Vec<_>>();
+            playlist.songs.splice(to..to, moved);
+        }
+        "playlistclear" => playlist_mut(mpd, arg(args, 0)?)?.songs.clear(),
+        _ => unreachable!("{command} is not a stored playlist command"),
+    }
+
+    if let Some(playlist) = args.first().and_then(|name| mpd.playlists.get_mut(name)) {
+        playlist.last_modified = Utc::now();
+    }
+    mpd.emit(IdleEvent::StoredPlaylist);
+    Ok(())
+}
+
+fn playlist<'a>(mpd: &'a FakeMpd, name: &str) -> Result<&'a StoredPlaylist, Ack> {
+    mpd.playlists.get(name).ok_or_else(|| Ack::new(ErrorCode::NoExist, "No such playlist"))
+}
+
+fn playlist_mut<'a>(mpd: &'a mut FakeMpd, name: &str) -> Result<&'a mut StoredPlaylist, Ack> {
+    mpd.playlists.get_mut(name).ok_or_else(|| Ack::new(ErrorCode::NoExist, "No such playlist"))
+}
+
+fn sticker(mpd: &mut FakeMpd, args: &[String], out: &mut Vec<u8>) -> Result<(), Ack> {
+    let (command, ty, uri) = (arg(args, 0)?, arg(args, 1)?, arg(args, 2)?);
+    if ty == "song" && command != "find" && mpd.song(uri).is_none() {
+        return Err(Ack::new(ErrorCode::NoExist, "No such song"));
+    }
+    let key = (ty.to_owned(), uri.to_owned());
+    let no_sticker = || Ack::new(ErrorCode::NoExist, "no such sticker");
+
+    match command {
+        "get" => {
+            let name = arg(args, 3)?;
+            let value = mpd
+                .stickers
+                .get(&key)
+                .and_then(|stickers| stickers.get(name))
+                .ok_or_else(no_sticker)?;
+            line(out, "sticker", format!("{name}={value}"));
+            return Ok(());
+        }
+        "list" => {
+            for (name, value) in mpd.stickers.get(&key).into_iter().flatten() {
+                line(out, "sticker", format!("{name}={value}"));
+            }
+            return Ok(());
+        }
+        "find" => {
+            sticker_find(mpd, ty, uri, &args[3..], out)?;
+            return Ok(());
+        }
+        "set" => {
+            let (name, value) = (arg(args, 3)?, arg(args, 4)?);
+            mpd.stickers.entry(key).or_default().insert(name.to_owned(), value.to_owned());
+        }
+        "inc" | "dec" => {
+            let name = arg(args, 3)?;
+            let change: i64 = args.get(4).map_or(Ok(1), |value| num(value))?;
+            let stickers = mpd.stickers.entry(key).or_default();
+            let current: i64 = stickers.get(name).and_then(|value| value.parse().ok()).unwrap_or(0);
+            let value = if command == "inc" { current + change } else { current - change };
+            stickers.insert(name.to_owned(), value.to_string());
+        }
+        "delete" => {
+            let removed = match args.get(3) {
+                Some(name) => {
+                    mpd.stickers.get_mut(&key).and_then(|stickers| stickers.remove(name)).is_some()
+                }
+                None => mpd.stickers.remove(&key).is_some(),
+            };
+            if !removed {
+                return Err(no_sticker());
+            }
+            mpd.stickers.retain(|_, stickers| !stickers.is_empty());
+        }
+        other => {
+            return Err(Ack::new(ErrorCode::Argument, format!("bad request: {other}")));
+        }
+    }
+    mpd.emit(IdleEvent::Sticker);
+    Ok(())
+}
+
+fn sticker_find(
+    mpd: &FakeMpd,
+    ty: &str,
+    uri: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    let name = arg(args, 0)?;
+    let mut rest = &args[1..];
+    let mut filter = None;
+    if let [op, value, tail @ ..] = rest
+        && !matches!(op.as_str(), "sort" | "window")
+    {
+        filter = Some((op.as_str(), value.as_str()));
+        rest = tail;
+    }
+    let mut sort = None;
+    let mut window = None;
+    while let [option, value, tail @ ..] = rest {
+        match option.as_str() {
+            "sort" => sort = Some(value.as_str()),
+            "window" => window = Some(window_arg(value)?),
+            other => {
+                return Err(Ack::new(ErrorCode::Argument, format!("Unknown argument: {other}")));
+            }
+        }
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        return Err(Ack::new(ErrorCode::Argument, "Too many arguments"));
+    }
+
+    let mut found = Vec::new();
+    for ((sticker_ty, sticker_uri), stickers) in &mpd.stickers {
+        if sticker_ty != ty || (ty == "song" && !in_dir(sticker_uri, uri) && sticker_uri != uri) {
+            continue;
+        }
+        let Some(value) = stickers.get(name) else {
+            continue;
+        };
+        let int_value = value.parse::<i64>().ok();
+        let matched = match filter {
+            None => true,
+            Some(("=", expected)) => value == expected,
+            Some(("<", expected)) => value.as_str() < expected,
+            Some((">", expected)) => value.as_str() > expected,
+            Some(("eq", expected)) => int_value == Some(num(expected)?),
+            Some(("lt", expected)) => {
+                let expected: i64 = num(expected)?;
+                int_value.is_some_and(|value| value < expected)
+            }
+            Some(("gt", expected)) => {
+                let expected: i64 = num(expected)?;
+                int_value.is_some_and(|value| value > expected)
+            }
+            Some(("contains", expected)) => value.contains(expected),
+            Some(("starts_with", expected)) => value.starts_with(expected),
+            Some((op, _)) => {
+                return Err(Ack::new(ErrorCode::Argument, format!("bad operator: {op}")));
+            }
+        };
+        if matched {
+            found.push((sticker_uri.as_str(), value.as_str()));
+        }
+    }
+
+    match sort {
+        None | Some("uri") => {}
+        Some("-uri") => found.reverse(),
+        Some("value") => found.sort_by_key(|(_, value)| *value),
+        Some("-value") => found.sort_by_key(|(_, value)| std::cmp::Reverse(*value)),
+        Some("value_int") => {
+            found.sort_by_key(|(_, value)| value.parse::<i64>().unwrap_or_default());
+        }
+        Some("-value_int") => found
+            .sort_by_key(|(_, value)| std::cmp::Reverse(value.parse::<i64>().unwrap_or_default())),
+        Some(other) => {
+            return Err(Ack::new(ErrorCode::Argument, format!("Unknown sort tag: {other}")));
+        }
+    }
+    let (start, end) = window.unwrap_or((0, found.len()));
+
+    let object_key = match ty {
+        "song" => Cow::Borrowed("file"),
+        "playlist" => Cow::Borrowed("playlist"),
+        tag => tag_name(tag),
+    };
+    for (uri, value) in found.iter().take(end).skip(start) {
+        line(out, &object_key, uri);
+        line(out, "sticker", format!("{name}={value}"));
+    }
+    Ok(())
+}
+
+fn messages(
+    mpd: &mut FakeMpd,
+    session: &Session,
+    command: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    let valid_channel = |channel: &str| {
+        !channel.is_empty()
+            && channel.chars().all(|c| c.is_ascii_alphanumeric() || "_-.:".contains(c))
+    };
+
+    match command {
+        "subscribe" => {
+            let channel = arg(args, 0)?;
+            if !valid_channel(channel) {
+                return Err(Ack::new(ErrorCode::Argument, "invalid channel name"));
+            }
+            let Some(connection) = mpd.connections.get_mut(&session.id) else {
+                return Ok(());
+            };
+            if !connection.channels.insert(channel.to_owned()) {
+                return Err(Ack::new(ErrorCode::Exist, "already subscribed to this channel"));
+            }
+            mpd.emit(IdleEvent::Subscription);
+        }
+        "unsubscribe" => {
+            let channel = arg(args, 0)?;
+            let removed = mpd
+                .connections
+                .get_mut(&session.id)
+                .is_some_and(|connection| connection.channels.remove(channel));
+            if !removed {
+                return Err(Ack::new(ErrorCode::NoExist, "not subscribed to this channel"));
+            }
+            mpd.emit(IdleEvent::Subscription);
+        }
+        "channels" => {
+            let channels = mpd.connections.values().flat_map(|connection| &connection.channels);
+            channels
+                .collect::<BTreeSet<_>>()
+                .iter()
+                .for_each(|channel| line(out, "channel", channel));
+        }
+        "sendmessage" => {
+            let (channel, message) = (arg(args, 0)?, arg(args, 1)?);
+            if !valid_channel(channel) {
+                return Err(Ack::new(ErrorCode::Argument, "invalid channel name"));
+            }
+            if !mpd.send_message(channel, message) {
+                return Err(Ack::new(ErrorCode::NoExist, "nobody is subscribed to this channel"));
+            }
+        }
+        "readmessages" => {
+            if let Some(connection) = mpd.connections.get_mut(&session.id) {
+                for (channel, message) in connection.messages.drain(..) {
+                    line(out, "channel", channel);
+                    line(out, "message", message);
+                }
+            }
+        }
+        _ => unreachable!("{command} is not a messaging command"),
+    }
+    Ok(())
+}
+
+fn picture(
+    mpd: &FakeMpd,
+    session: &Session,
+    command: &str,
+    args: &[String],
+    out: &mut Vec<u8>,
+) -> Result<(), Ack> {
+    let uri = arg(args, 0)?;
+    let offset: usize = num(arg(args, 1)?)?;
+    if mpd.song(uri).is_none() {
+        return Err(Ack::new(ErrorCode::NoExist, "No such file"));
+    }
+
+    // Pictures embedded in the song take precedence over the directory ones
+    let data = mpd
+        .pictures
+        .get(uri)
+        .or_else(|| uri.rsplit_once('/').and_then(|(dir, _)| mpd.pictures.get(dir)));
+    let Some(data) = data else {
+        return if command == "albumart" {
+            Err(Ack::new(ErrorCode::NoExist, "No file exists"))
+        } else {
+            Ok(())
+        };
+    };
+    if offset > data.len() {
+        return Err(Ack::new(ErrorCode::Argument, "Bad file offset"));
+    }
+
+    let chunk = &data[offset..(offset + session.binary_limit).min(data.len())];
+    line(out, "size", data.len());
+    line(out, "binary", chunk.len());
+    out.extend_from_slice(chunk);
+    out.push(b'\n');
+    Ok(())
+}
+
+/// Parsed filter of the database and queue search commands followed by their
+/// `sort`, `window`, `position` and `group` options
+struct Query {
+    filter: Option<FilterExpr>,
+    exact: bool,
+    sort: Option<(String, bool)>,
+    window: Option<(usize, usize)>,
+    position: Option<String>,
+    groups: Vec<Tag>,
+}
+
+impl Query {
+    fn parse(args: &[String], exact: bool) -> Result<Self, Ack> {
+        let is_option = |arg: &str| matches!(arg, "sort" | "window" | "position" | "group");
+        let mut query = Self {
+            filter: None,
+            exact,
+            sort: None,
+            window: None,
+            position: None,
+            groups: Vec::new(),
+        };
+
+        let mut rest = args;
+        if let [expr, tail @ ..] = rest
+            && expr.starts_with('(')
+        {
+            query.filter = Some(
+                expr.parse().map_err(|err| Ack::new(ErrorCode::Argument, format!("{err:#}")))?,
+            );
+            rest = tail;
+        } else {
+            // Legacy `TAG VALUE` pairs
+            let mut pairs = Vec::new();
+            while let [tag, value, tail @ ..] = rest
+                && !is_option(tag)
+            {
+                let op = if exact { TagOperator::Eq } else { TagOperator::Contains };
+                pairs.push(FilterExpr::Tag { tag: Tag::from_name(tag), op, value: value.clone() });
+                rest = tail;
+            }
+            if !pairs.is_empty() {
+                query.filter = Some(FilterExpr::And(pairs));
+            }
+        }
+
+        while let [option, value, tail @ ..] = rest {
+            match option.as_str() {
+                "sort" => {
+                    query.sort = Some(match value.strip_prefix('-') {
+                        Some(tag) => (tag.to_lowercase(), true),
+                        None => (value.to_lowercase(), false),
+                    });
+                }
+                "window" => query.window = Some(window_arg(value)?),
+                "position" => query.position = Some(value.clone()),
+                "group" => query.groups.push(Tag::from_name(value)),
+                other => {
+                    return Err(Ack::new(
+                        ErrorCode::Argument,
+                        format!("Unknown argument: {other}"),
+                    ));
+                }
+            }
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(Ack::new(ErrorCode::Argument, "Incorrect number of filter arguments"));
+        }
+
+        Ok(query)
+    }
+
+    fn matches(&self, song: &Song, prio: Option<u8>) -> Result<bool, Ack> {
+        self.filter.as_ref().map_or(Ok(true), |filter| matches(filter, song, prio, self.exact))
+    }
+
+    /// Matching songs of the library, sorted and windowed
+    fn songs<'a>(&self, mpd: &'a FakeMpd) -> Result<Vec<&'a Song>, Ack> {
+        let mut songs = Vec::new();
+        for song in &mpd.library {
+            if self.matches(song, None)? {
+                songs.push(song);
+            }
+        }
+
+        if let Some((tag, descending)) = &self.sort {
+            songs.sort_by_cached_key(|song| match tag.as_str() {
+                "last-modified" => song.last_modified.to_rfc3339(),
+                "added" => song.added.map(|added| added.to_rfc3339()).unwrap_or_default(),
+                tag => tag_values(song, &Tag::from_name(tag))
+                    .first()
+                    .copied()
+                    .unwrap_or_default()
+                    .to_owned(),
+            });
+            if *descending {
+                songs.reverse();
+            }
+        }
+        if let Some((start, end)) = self.window {
+            songs = songs.into_iter().take(end).skip(start).collect();
+        }
+        Ok(songs)
+    }
+}
+
+fn matches(expr: &FilterExpr, song: &Song, prio: Option<u8>, exact: bool) -> Result<bool, Ack> {
+    let fold = |value: &str| if exact { value.to_owned() } else { value.to_lowercase() };
+
+    Ok(match expr {
+        FilterExpr::Tag { tag, op, value } => {
+            let expected = fold(value);
+            let values = tag_values(song, tag).into_iter().map(fold).collect::<Vec<_>>();
+            let equals = values.contains(&expected) || (values.is_empty() && expected.is_empty());
+            match op {
+                TagOperator::Eq => equals,
+                TagOperator::NotEq => !equals,
+                TagOperator::Contains => values.iter().any(|v| v.contains(&expected)),
+                TagOperator::NotContains => !values.iter().any(|v| v.contains(&expected)),
+                TagOperator::StartsWith => values.iter().any(|v| v.starts_with(&expected)),
+                TagOperator::Regex | TagOperator::NotRegex => {
+                    return Err(Ack::new(
+                        ErrorCode::Argument,
+                        "Regular expressions are not supported",
+                    ));
+                }
+            }
+        }
+        FilterExpr::Not(expr) => !matches(expr, song, prio, exact)?,
+        FilterExpr::And(exprs) => {
+            for expr in exprs {
+                if !matches(expr, song, prio, exact)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        FilterExpr::Base(dir) => in_dir(&song.file, dir),
+        FilterExpr::ModifiedSince(value) => song.last_modified >= timestamp(value)?,
+        FilterExpr::AddedSince(value) => {
+            let since = timestamp(value)?;
+            song.added.is_some_and(|added| added >= since)
+        }
+        FilterExpr::AudioFormat { op, value } => {
+            let format =
+                song.metadata.get("format").map(|format| format.first()).unwrap_or_default();
+            match op {
+                AudioFormatOperator::Eq => format == value,
+                AudioFormatOperator::Mask => {
+                    let parts = format.split(':').collect::<Vec<_>>();
+                    let mask = value.split(':').collect::<Vec<_>>();
+                    parts.len() == mask.len()
+                        && parts.iter().zip(&mask).all(|(part, mask)| *mask == "*" || part == mask)
+                }
+            }
+        }
+        FilterExpr::Prio(min) => prio.is_some_and(|prio| prio >= *min),
+    })
+}
+
+fn tag_values<'a>(song: &'a Song, tag: &Tag) -> Vec<&'a str> {
+    match tag {
+        Tag::File => vec![song.file.as_str()],
+        Tag::Any => song
+            .metadata
+            .iter()
+            .filter(|(key, _)| !INTERNAL_KEYS.contains(&key.as_str()))
+            .flat_map(|(_, values)| values.iter())
+            .collect(),
+        tag => song
+            .metadata
+            .get(&tag.as_str().to_lowercase())
+            .map(|values| values.iter().collect())
+            .unwrap_or_default(),
+    }
+}
+
+fn timestamp(value: &str) -> Result<DateTime<Utc>, Ack> {
+    let invalid = || Ack::new(ErrorCode::Argument, format!("Invalid timestamp: {value}"));
+    match value.parse::<i64>() {
+        Ok(secs) => DateTime::from_timestamp(secs, 0).ok_or_else(invalid),
+        Err(_) => value.parse().map_err(|_| invalid()),
+    }
+}
+
+fn timestamp_str(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Whether the uri is inside the directory, an empty directory is the root of
+/// the library
+fn in_dir(uri: &str, dir: &str) -> bool {
+    let dir = dir.trim_matches('/');
+    dir.is_empty() || uri.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Canonical name of the tag as MPD sends it
+fn tag_name(key: &str) -> Cow<'_, str> {
+    if key.eq_ignore_ascii_case("file") {
+        return Cow::Borrowed("file");
+    }
+    if let Some(tag) = TAGS.iter().find(|tag| tag.eq_ignore_ascii_case(key)) {
+        return Cow::Borrowed(tag);
+    }
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(first) => Cow::Owned(first.to_uppercase().chain(chars).collect()),
+        None => Cow::Borrowed(key),
+    }
+}
+
+fn write_song(out: &mut Vec<u8>, song: &Song) {
+    line(out, "file", &song.file);
+    line(out, "Last-Modified", timestamp_str(song.last_modified));
+    if let Some(added) = song.added {
+        line(out, "Added", timestamp_str(added));
+    }
+
+    let mut tags = song
+        .metadata
+        .iter()
+        .filter(|(key, _)| !INTERNAL_KEYS.contains(&key.as_str()))
+        .collect::<Vec<_>>();
+    tags.sort_by_key(|(key, _)| *key);
+    for (key, values) in tags {
+        let name = tag_name(key);
+        values.iter().for_each(|value| line(out, &name, value));
+    }
+
+    if let Some(duration) = song.duration {
+        line(out, "Time", duration.as_secs());
+        line(out, "duration", format!("{:.3}", duration.as_secs_f64()));
+    }
+}
+
+fn write_entry(out: &mut Vec<u8>, mpd: &FakeMpd, pos: usize) {
+    let entry = &mpd.queue[pos];
+    write_song(out, &entry.song);
+    if let Some(range) = entry.range {
+        let format = |value: Option<Duration>| {
+            value.map(|value| format!("{:.3}", value.as_secs_f64())).unwrap_or_default()
+        };
+        line(out, "Range", format!("{}-{}", format(range.start), format(range.end)));
+    }
+    line(out, "Pos", pos);
+    line(out, "Id", entry.id);
+    if entry.prio > 0 {
+        line(out, "Prio", entry.prio);
+    }
+}
+
+fn line(out: &mut Vec<u8>, key: &str, value: impl Display) {
+    out.extend_from_slice(format!("{key}: {value}\n").as_bytes());
+}
+
+/// Songs added by the uri, either the song itself, all songs in the directory
+/// or a stream
+fn songs_for_uri(mpd: &FakeMpd, uri: &str) -> Result<Vec<Song>, Ack> {
+    if mpd.song(uri).is_some() || uri.contains("://") {
+        return Ok(vec![song_or_stream(mpd, uri)?]);
+    }
+
+    let mut songs =
+        mpd.library.iter().filter(|song| in_dir(&song.file, uri)).cloned().collect::<Vec<_>>();
+    if songs.is_empty() {
+        return Err(Ack::new(ErrorCode::NoExist, "No such directory"));
+    }
+    songs.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(songs)
+}
+
+fn song_or_stream(mpd: &FakeMpd, uri: &str) -> Result<Song, Ack> {
+    if let Some(song) = mpd.song(uri) {
+        return Ok(song.clone());
+    }
+    match uri.split_once("://") {
+        Some(("http" | "https", _)) => Ok(stream_song(uri)),
+        Some(_) => Err(Ack::new(ErrorCode::NoExist, "Unsupported URI scheme")),
+        None => Err(Ack::new(ErrorCode::NoExist, "No such song")),
+    }
+}
+
+fn stream_song(uri: &str) -> Song {
+    Song { file: uri.to_owned(), ..Default::default() }
+}
+
+fn entry_pos(mpd: &FakeMpd, id: u32) -> Result<usize, Ack> {
+    mpd.position_of(id).ok_or_else(|| Ack::new(ErrorCode::NoExist, "No such song"))
+}
+
+fn arg(args: &[String], idx: usize) -> Result<&str, Ack> {
+    args.get(idx)
+        .map(String::as_str)
+        .ok_or_else(|| Ack::new(ErrorCode::Argument, "too few arguments"))
+}
+
+fn num<T: FromStr>(value: &str) -> Result<T, Ack> {
+    value.parse().map_err(|_| Ack::new(ErrorCode::Argument, format!("Integer expected: {value}")))
+}
+
+fn float(value: &str) -> Result<f64, Ack> {
+    value.parse().map_err(|_| Ack::new(ErrorCode::Argument, format!("Float expected: {value}")))
+}
+
+fn duration(value: &str) -> Result<Duration, Ack> {
+    Duration::try_from_secs_f64(float(value)?)
+        .map_err(|_| Ack::new(ErrorCode::Argument, format!("Invalid time: {value}")))
+}
+
+fn boolean(value: &str) -> Result<bool, Ack> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(Ack::new(ErrorCode::Argument, format!("Boolean (0/1) expected: {value}"))),
+    }
+}
+
+/// Parses `N` or `START:END`, an empty end means the end of the list
+fn range(value: &str, len: usize) -> Result<(usize, usize), Ack> {
+    let (start, end) = match value.split_once(':') {
+        Some((start, "")) => (num(start)?, len),
+        Some((start, end)) => (num(start)?, num(end)?),
+        None => {
+            let pos = num(value)?;
+            (pos, pos + 1)
+        }
+    };
+    if start > end || end > len {
+        return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+    }
+    Ok((start, end))
+}
+
+fn window_arg(value: &str) -> Result<(usize, usize), Ack> {
+    range(value, usize::MAX)
+}
+
+/// Parses an absolute position in the queue or one relative to the current
+/// song, ie. `+0` is right after it and `-0` right before it
+fn position(mpd: &FakeMpd, value: &str) -> Result<usize, Ack> {
+    let current =
+        || mpd.playback.current.ok_or_else(|| Ack::new(ErrorCode::Argument, "No current song"));
+    let pos = if let Some(offset) = value.strip_prefix('+') {
+        current()? + 1 + num::<usize>(offset)?
+    } else if let Some(offset) = value.strip_prefix('-') {
+        current()?
+            .checked_sub(num(offset)?)
+            .ok_or_else(|| Ack::new(ErrorCode::Argument, "Bad song index"))?
+    } else {
+        num(value)?
+    };
+    if pos > mpd.queue.len() {
+        return Err(Ack::new(ErrorCode::Argument, "Bad song index"));
+    }
+    Ok(pos)
+}
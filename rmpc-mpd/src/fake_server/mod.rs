@@ -0,0 +1,396 @@
+//! In-process MPD protocol server with an in-memory library, queue, stored
+//! playlists, stickers, channels and idle events. Meant for end-to-end tests
+//! of code built on top of this crate without a running MPD.
+//!
+//! ```ignore
+//! let server = FakeServer::start_tcp(FakeMpd::new().with_song(song))?;
+//! let mut client = Client::init(server.address(), None, "test", None, false, false, None)?;
+//! client.add("music/song.flac", None)?;
+//! server.with_state(|mpd| assert_eq!(mpd.queue_uris(), ["music/song.flac"]));
+//! ```
+//!
+//! Only a subset of MPD is implemented. Time does not advance, regular
+//! expressions in filters are not supported and unsupported commands fail with
+//! an unknown command error.
+
+use std::{
+    net::{TcpListener, TcpStream},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+};
+
+use crate::{
+    address::MpdAddress,
+    commands::IdleEvent,
+    errors::ErrorCode,
+    server::{self, Handler, Msg, lock},
+};
+
+mod commands;
+mod state;
+
+pub use state::{FakeMpd, Playback, QueueEntry, StoredPlaylist};
+
+/// Error response of a failed command
+#[derive(Debug)]
+struct Ack {
+    code: ErrorCode,
+    message: String,
+}
+
+impl Ack {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    fn write(&self, out: &mut Vec<u8>, idx: usize, command: &str) {
+        let code = match self.code {
+            ErrorCode::NotList => 1,
+            ErrorCode::Argument => 2,
+            ErrorCode::Password => 3,
+            ErrorCode::Permission => 4,
+            ErrorCode::UnknownCmd => 5,
+            ErrorCode::NoExist => 50,
+            ErrorCode::PlaylistMax => 51,
+            ErrorCode::System => 52,
+            ErrorCode::PlaylistLoad => 53,
+            ErrorCode::UpdateAlready => 54,
+            ErrorCode::PlayerSync => 55,
+            ErrorCode::Exist => 56,
+        };
+        out.extend_from_slice(
+            format!("ACK [{code}@{idx}] {{{command}}} {}\n", self.message).as_bytes(),
+        );
+    }
+}
+
+/// Per connection state which is not shared with other clients
+pub(crate) struct Session {
+    id: u64,
+    binary_limit: usize,
+}
+
+/// Running fake MPD server, stops accepting connections when dropped
+#[derive(Debug)]
+pub struct FakeServer {
+    mpd: Arc<Mutex<FakeMpd>>,
+    address: MpdAddress,
+    stopped: Arc<AtomicBool>,
+    socket_path: Option<PathBuf>,
+}
+
+impl FakeServer {
+    /// Starts the server on a random port on localhost
+    pub fn start_tcp(mpd: FakeMpd) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = MpdAddress::IpAndPort(listener.local_addr()?.to_string());
+        Self::start(mpd, address, None, listener)
+    }
+
+    /// Starts the server on a unix socket at the given path, the socket is
+    /// removed when the server is dropped
+    pub fn start_unix(mpd: FakeMpd, path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let listener = UnixListener::bind(&path)?;
+        let address = MpdAddress::SocketPath(path.to_string_lossy().into_owned());
+        Self::start(mpd, address, Some(path), listener)
+    }
+
+    fn start(
+        mpd: FakeMpd,
+        address: MpdAddress,
+        socket_path: Option<PathBuf>,
+        listener: impl server::Listener,
+    ) -> std::io::Result<Self> {
+        let server = Self {
+            mpd: Arc::new(Mutex::new(mpd)),
+            address,
+            stopped: Arc::new(AtomicBool::new(false)),
+            socket_path,
+        };
+        server::serve("fake_mpd", listener, Arc::clone(&server.mpd), Arc::clone(&server.stopped))?;
+
+        Ok(server)
+    }
+
+    /// Address to connect to the server
+    pub fn address(&self) -> MpdAddress {
+        self.address.clone()
+    }
+
+    /// Runs the callback with the server state, ie. to assert on it or to
+    /// change it in between commands
+    pub fn with_state<T>(&self, f: impl FnOnce(&mut FakeMpd) -> T) -> T {
+        f(&mut lock(&self.mpd))
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wake up the listener thread so it notices the server was stopped
+        match &self.address {
+            MpdAddress::IpAndPort(addr) => drop(TcpStream::connect(addr)),
+            MpdAddress::SocketPath(path) => drop(UnixStream::connect(path)),
+            MpdAddress::AbstractSocket(_) => {}
+        }
+        if let Some(path) = &self.socket_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Handler for Mutex<FakeMpd> {
+    type Session = Session;
+
+    fn connect(&self, tx: Sender<Msg>) -> Session {
+        Session { id: lock(self).connect(tx), binary_limit: 8192 }
+    }
+
+    fn disconnect(&self, session: &Session) {
+        lock(self).disconnect(session.id);
+    }
+
+    fn greeting(&self) -> String {
+        format!("OK MPD {}\n", lock(self).version)
+    }
+
+    fn command(&self, session: &mut Session, line: &str, out: &mut Vec<u8>) {
+        match execute_line(&mut lock(self), session, line, out) {
+            Ok(()) => out.extend_from_slice(b"OK\n"),
+            Err((command, ack)) => {
+                out.clear();
+                ack.write(out, 0, &command);
+            }
+        }
+    }
+
+    fn command_list(
+        &self,
+        session: &mut Session,
+        list_ok: bool,
+        lines: &[String],
+        out: &mut Vec<u8>,
+    ) {
+        let mut mpd = lock(self);
+        for (idx, line) in lines.iter().enumerate() {
+            if let Err((command, ack)) = execute_line(&mut mpd, session, line, out) {
+                ack.write(out, idx, &command);
+                return;
+            }
+            if list_ok {
+                out.extend_from_slice(b"list_OK\n");
+            }
+        }
+        out.extend_from_slice(b"OK\n");
+    }
+
+    fn idle(&self, session: &mut Session, line: &str, out: &mut Vec<u8>) -> bool {
+        let subsystems = match parse_subsystems(line) {
+            Ok(subsystems) => subsystems,
+            Err(ack) => {
+                ack.write(out, 0, "idle");
+                return true;
+            }
+        };
+        let events = lock(self).take_events(session.id, &subsystems);
+        if events.is_empty() {
+            return false;
+        }
+        out.extend_from_slice(&idle_response(&events));
+        true
+    }
+}
+
+fn execute_line(
+    mpd: &mut FakeMpd,
+    session: &mut Session,
+    line: &str,
+    out: &mut Vec<u8>,
+) -> Result<(), (String, Ack)> {
+    let args = commands::split_args(line).map_err(|ack| (String::new(), ack))?;
+    let Some((command, args)) = args.split_first() else {
+        return Err((String::new(), Ack::new(ErrorCode::UnknownCmd, "No command given")));
+    };
+    let len = out.len();
+    commands::execute(mpd, session, command, args, out).map_err(|ack| {
+        out.truncate(len);
+        (command.clone(), ack)
+    })
+}
+
+fn parse_subsystems(line: &str) -> Result<Vec<IdleEvent>, Ack> {
+    let args = commands::split_args(line)?;
+    args.iter()
+        .skip(1)
+        .map(|name| {
+            enum_map::EnumMap::<IdleEvent, ()>::default()
+                .into_iter()
+                .map(|(event, ())| event)
+                .find(|event| event.to_string() == *name)
+                .ok_or_else(|| {
+                    Ack::new(ErrorCode::Argument, format!("Unrecognized idle event: {name}"))
+                })
+        })
+        .collect()
+}
+
+fn idle_response(events: &[IdleEvent]) -> Vec<u8> {
+    let mut out = String::new();
+    for event in events {
+        out.push_str("changed: ");
+        out.push_str(&event.to_string());
+        out.push('\n');
+    }
+    out.push_str("OK\n");
+    out.into_bytes()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{FakeMpd, FakeServer};
+    use crate::{
+        client::Client,
+        commands::{IdleEvent, Song, State, Volume, metadata_tag::MetadataTag},
+        errors::{ErrorCode, MpdError},
+        filter::{Filter, Tag},
+        mpd_client::MpdClient,
+        proto_client::ProtoClient,
+        queue_position::QueuePosition,
+        single_or_range::SingleOrRange,
+    };
+
+    fn song(file: &str, artist: &str, album: &str) -> Song {
+        Song {
+            file: file.to_owned(),
+            duration: Some(Duration::from_secs(120)),
+            metadata: [
+                ("artist".to_owned(), MetadataTag::from(artist)),
+                ("album".to_owned(), MetadataTag::from(album)),
+            ]
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    fn server() -> FakeServer {
+        FakeServer::start_tcp(FakeMpd::new().with_songs([
+            song("a/1.flac", "Foo", "First"),
+            song("a/2.flac", "Foo", "First"),
+            song("b/3.flac", "Bar's", "Second"),
+        ]))
+        .unwrap()
+    }
+
+    fn client(server: &FakeServer) -> Client<'static> {
+        Client::init(server.address(), None, "test", None, false, false, None).unwrap()
+    }
+
+    #[test]
+    fn edits_queue() {
+        let server = server();
+        let mut client = client(&server);
+
+        client.add("a", None).unwrap();
+        let id = client.add_id("b/3.flac", Some(QueuePosition::Absolute(0))).unwrap();
+        client.play_id(id).unwrap();
+        client.move_in_queue(SingleOrRange::single(0), QueuePosition::Absolute(2)).unwrap();
+        client.delete_from_queue(SingleOrRange::single(0)).unwrap();
+
+        let queue = client.playlist_info().unwrap().unwrap();
+        assert_eq!(
+            queue.iter().map(|song| (song.file.as_str(), song.pos())).collect::<Vec<_>>(),
+            [("a/2.flac", Some(0)), ("b/3.flac", Some(1))]
+        );
+        let status = client.get_status().unwrap();
+        assert_eq!((status.state, status.songid), (State::Play, Some(id)));
+        server.with_state(|mpd| assert_eq!(mpd.queue_uris(), ["a/2.flac", "b/3.flac"]));
+    }
+
+    #[test]
+    fn finds_songs() {
+        let server = server();
+        let mut client = client(&server);
+
+        let found = client.find(&[Filter::new(Tag::Artist, "Bar's")]).unwrap();
+        let searched = client.search(&[Filter::new(Tag::Album, "first")], false).unwrap();
+        let albums = client.list_tag(Tag::Album, None).unwrap();
+
+        assert_eq!(found.iter().map(|song| song.file.as_str()).collect::<Vec<_>>(), ["b/3.flac"]);
+        assert_eq!(searched.len(), 2);
+        assert_eq!(albums.0, ["First", "Second"]);
+        assert!(client.find(&[Filter::new(Tag::Album, "first")]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stores_stickers() {
+        let server = server();
+        let mut client = client(&server);
+
+        client.set_sticker("a/1.flac", "rating", "5").unwrap();
+
+        assert_eq!(client.sticker("a/1.flac", "rating").unwrap().unwrap().value, "5");
+        assert_eq!(client.sticker("a/2.flac", "rating").unwrap(), None);
+        assert!(matches!(
+            client.set_sticker("missing.flac", "rating", "5"),
+            Err(MpdError::Mpd(failure)) if failure.code == ErrorCode::NoExist
+        ));
+        server.with_state(|mpd| {
+            assert_eq!(mpd.stickers[&("song".to_owned(), "a/1.flac".to_owned())]["rating"], "5");
+        });
+    }
+
+    #[test]
+    fn notifies_idle_clients() {
+        let server = server();
+        let mut idle = client(&server);
+        let mut other = client(&server);
+
+        idle.enter_idle(None).unwrap();
+        other.set_volume(Volume::new(50)).unwrap();
+        let events = idle.read_response::<Vec<IdleEvent>>().unwrap();
+
+        assert_eq!(events, [IdleEvent::Mixer]);
+        server.with_state(|mpd| mpd.emit(IdleEvent::Database));
+        assert_eq!(idle.idle(None).unwrap(), [IdleEvent::Database]);
+    }
+
+    #[test]
+    fn serves_album_art_in_chunks() {
+        let data = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+        let server = FakeServer::start_tcp(
+            FakeMpd::new()
+                .with_song(song("a/1.flac", "Foo", "First"))
+                .with_picture("a", data.clone()),
+        )
+        .unwrap();
+        let mut client = client(&server);
+
+        client.binary_limit(64).unwrap();
+
+        assert_eq!(client.albumart("a/1.flac").unwrap(), Some(data));
+    }
+
+    #[test]
+    fn serves_unix_socket() {
+        let path = std::env::temp_dir().join(format!("rmpc-fake-mpd-{}.sock", std::process::id()));
+        let server = FakeServer::start_unix(FakeMpd::new(), &path).unwrap();
+
+        let mut client = client(&server);
+        client.subscribe("test").unwrap();
+
+        assert_eq!(client.channels().unwrap().0, ["test"]);
+        drop(client);
+        drop(server);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::mpsc::Sender,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use enum_map::EnumMap;
+use rmpc_shared::version::Version;
+
+use crate::{
+    commands::{IdleEvent, Song, State, replay_gain::ReplayGainMode, status::OnOffOneshot},
+    server::Msg,
+    song_range::SongRange,
+};
+
+/// Song in the queue of the fake server
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueEntry {
+    pub id: u32,
+    pub song: Song,
+    pub prio: u8,
+    pub range: Option<SongRange>,
+    /// Queue version in which the entry was last changed, used by `plchanges`
+    pub(super) changed: u32,
+}
+
+/// Player state of the fake server. Time does not advance on its own, elapsed
+/// only changes by seeking or switching songs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Playback {
+    pub state: State,
+    /// Position of the current song in the queue
+    pub current: Option<usize>,
+    pub elapsed: Duration,
+    pub volume: u32,
+    pub repeat: bool,
+    pub random: bool,
+    pub single: OnOffOneshot,
+    pub consume: OnOffOneshot,
+    pub crossfade: u32,
+    pub replay_gain_mode: ReplayGainMode,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            state: State::Stop,
+            current: None,
+            elapsed: Duration::ZERO,
+            volume: 100,
+            repeat: false,
+            random: false,
+            single: OnOffOneshot::Off,
+            consume: OnOffOneshot::Off,
+            crossfade: 0,
+            replay_gain_mode: ReplayGainMode::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredPlaylist {
+    pub songs: Vec<String>,
+    pub last_modified: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub(super) struct Connection {
+    tx: Sender<Msg>,
+    events: HashSet<IdleEvent>,
+    pub(super) channels: HashSet<String>,
+    pub(super) messages: Vec<(String, String)>,
+}
+
+/// In-memory state of the fake MPD server. Fields can be freely inspected and
+/// modified through [`super::FakeServer::with_state`], use [`FakeMpd::emit`]
+/// to notify idling clients about the changes.
+#[derive(Debug)]
+pub struct FakeMpd {
+    pub version: Version,
+    /// Password required by the `password` command, commands do not require
+    /// authentication
+    pub password: Option<String>,
+    pub library: Vec<Song>,
+    pub queue: Vec<QueueEntry>,
+    pub playback: Playback,
+    pub playlists: BTreeMap<String, StoredPlaylist>,
+    /// Stickers keyed by the sticker type, ie. `song`, `playlist` or a tag
+    /// name, and the uri of the object
+    pub stickers: BTreeMap<(String, String), BTreeMap<String, String>>,
+    /// Pictures returned by `albumart` and `readpicture`, keyed by song uri or
+    /// a directory
+    pub pictures: HashMap<String, Vec<u8>>,
+    /// Names of the outputs and whether they are enabled
+    pub outputs: Vec<(String, bool)>,
+    pub(super) queue_version: u32,
+    next_id: u32,
+    next_connection_id: u64,
+    pub(super) connections: HashMap<u64, Connection>,
+}
+
+impl Default for FakeMpd {
+    fn default() -> Self {
+        Self {
+            version: Version::new(0, 24, 0),
+            password: None,
+            library: Vec::new(),
+            queue: Vec::new(),
+            playback: Playback::default(),
+            playlists: BTreeMap::new(),
+            stickers: BTreeMap::new(),
+            pictures: HashMap::new(),
+            outputs: vec![("Fake output".to_owned(), true)],
+            queue_version: 1,
+            next_id: 1,
+            next_connection_id: 0,
+            connections: HashMap::new(),
+        }
+    }
+}
+
+impl FakeMpd {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Adds the song to the library, only the file, duration, metadata and
+    /// modification times are used. Metadata keys are expected in lowercase
+    /// the same way they are parsed from MPD.
+    pub fn with_song(mut self, song: Song) -> Self {
+        self.library.push(song);
+        self
+    }
+
+    pub fn with_songs(mut self, songs: impl IntoIterator<Item = Song>) -> Self {
+        self.library.extend(songs);
+        self
+    }
+
+    pub fn with_playlist(
+        mut self,
+        name: impl Into<String>,
+        songs: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.playlists.insert(name.into(), StoredPlaylist {
+            songs: songs.into_iter().map(Into::into).collect(),
+            last_modified: Utc::now(),
+        });
+        self
+    }
+
+    pub fn with_sticker(
+        mut self,
+        uri: impl Into<String>,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.stickers
+            .entry(("song".to_owned(), uri.into()))
+            .or_default()
+            .insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_picture(mut self, uri: impl Into<String>, data: Vec<u8>) -> Self {
+        self.pictures.insert(uri.into(), data);
+        self
+    }
+
+    /// Uris of the songs in the queue in order
+    pub fn queue_uris(&self) -> Vec<&str> {
+        self.queue.iter().map(|entry| entry.song.file.as_str()).collect()
+    }
+
+    pub fn current_entry(&self) -> Option<&QueueEntry> {
+        self.playback.current.and_then(|pos| self.queue.get(pos))
+    }
+
+    /// Notifies all connected clients about the change
+    pub fn emit(&mut self, event: IdleEvent) {
+        for connection in self.connections.values_mut() {
+            connection.events.insert(event);
+            let _ = connection.tx.send(Msg::Wake);
+        }
+    }
+
+    pub(super) fn song(&self, uri: &str) -> Option<&Song> {
+        self.library.iter().find(|song| song.file == uri)
+    }
+
+    pub(super) fn connect(&mut self, tx: Sender<Msg>) -> u64 {
+        let id = self.next_connection_id;
+        self.next_connection_id += 1;
+        self.connections.insert(id, Connection {
+            tx,
+            events: HashSet::new(),
+            channels: HashSet::new(),
+            messages: Vec::new(),
+        });
+        id
+    }
+
+    pub(super) fn disconnect(&mut self, id: u64) {
+        if let Some(connection) = self.connections.remove(&id)
+            && !connection.channels.is_empty()
+        {
+            self.emit(IdleEvent::Subscription);
+        }
+    }
+
+    /// Takes the pending events of the connection the client is interested
+    /// in, all of them when the list is empty
+    pub(super) fn take_events(&mut self, id: u64, subsystems: &[IdleEvent]) -> Vec<IdleEvent> {
+        let Some(connection) = self.connections.get_mut(&id) else {
+            return Vec::new();
+        };
+
+        let mut result = Vec::new();
+        for (event, ()) in EnumMap::<IdleEvent, ()>::default() {
+            if (subsystems.is_empty() || subsystems.contains(&event))
+                && connection.events.remove(&event)
+            {
+                result.push(event);
+            }
+        }
+        result
+    }
+
+    pub(super) fn send_message(&mut self, channel: &str, message: &str) -> bool {
+        let mut delivered = false;
+        for connection in self.connections.values_mut() {
+            if connection.channels.contains(channel) {
+                connection.messages.push((channel.to_owned(), message.to_owned()));
+                connection.events.insert(IdleEvent::Message);
+                let _ = connection.tx.send(Msg::Wake);
+                delivered = true;
+            }
+        }
+        delivered
+    }
+
+    pub(super) fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub(super) fn new_entry(&mut self, song: Song) -> QueueEntry {
+        QueueEntry { id: self.next_id(), song, prio: 0, range: None, changed: 0 }
+    }
+
+    /// Bumps the queue version and marks every entry from the given position
+    /// as changed
+    pub(super) fn queue_changed(&mut self, from: usize) {
+        self.queue_version += 1;
+        let version = self.queue_version;
+        for entry in self.queue.iter_mut().skip(from) {
+            entry.changed = version;
+        }
+        self.emit(IdleEvent::Playlist);
+    }
+
+    pub(super) fn position_of(&self, id: u32) -> Option<usize> {
+        self.queue.iter().position(|entry| entry.id == id)
+    }
+
+    /// Inserts the songs at the position, or the end of the queue, and keeps
+    /// the current song pointing to the same entry
+    pub(super) fn insert_songs(&mut self, pos: Option<usize>, songs: Vec<Song>) -> Vec<u32> {
+        let pos = pos.unwrap_or(self.queue.len()).min(self.queue.len());
+        let entries = songs.into_iter().map(|song| self.new_entry(song)).collect::<Vec<_>>();
+        let ids = entries.iter().map(|entry| entry.id).collect();
+        let count = entries.len();
+        self.queue.splice(pos..pos, entries);
+        if let Some(current) = &mut self.playback.current
+            && *current >= pos
+        {
+            *current += count;
+        }
+        self.queue_changed(pos);
+        ids
+    }
+
+    /// Removes the entries in the range and stops playback when the current
+    /// song was removed
+    pub(super) fn remove_songs(&mut self, start: usize, end: usize) {
+        self.queue.drain(start..end);
+        match self.playback.current {
+            Some(current) if (start..end).contains(&current) => {
+                self.playback.current = None;
+                self.playback.state = State::Stop;
+                self.playback.elapsed = Duration::ZERO;
+                self.emit(IdleEvent::Player);
+            }
+            Some(current) if current >= end => {
+                self.playback.current = Some(current - (end - start));
+            }
+            _ => {}
+        }
+        self.queue_changed(start);
+    }
+
+    /// Moves the entries in the range so the first of them ends up at `to`
+    pub(super) fn move_songs(&mut self, start: usize, end: usize, to: usize) {
+        let current_id = self.current_entry().map(|entry| entry.id);
+        let moved = self.queue.drain(start..end).collect::<Vec<_>>();
+        let to = to.min(self.queue.len());
+        self.queue.splice(to..to, moved);
+        self.playback.current = current_id.and_then(|id| self.position_of(id));
+        self.queue_changed(start.min(to));
+    }
+
+    pub(super) fn play_pos(&mut self, pos: usize) {
+        self.playback.current = Some(pos);
+        self.playback.state = State::Play;
+        self.playback.elapsed =
+            self.queue[pos].range.and_then(|range| range.start).unwrap_or_default();
+        self.emit(IdleEvent::Player);
+    }
+
+    pub(super) fn stop(&mut self) {
+        self.playback.state = State::Stop;
+        self.playback.elapsed = Duration::ZERO;
+        self.emit(IdleEvent::Player);
+    }
+
+    /// Position of the song played after the current one
+    pub(super) fn next_pos(&self) -> Option<usize> {
+        let current = self.playback.current?;
+        if current + 1 < self.queue.len() {
+            Some(current + 1)
+        } else if self.playback.repeat && !self.queue.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
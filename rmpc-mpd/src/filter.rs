@@ -1,5 +1,6 @@
-use std::{borrow::Cow, fmt::Write as _};
+use std::{borrow::Cow, fmt::Write as _, str::FromStr};
 
+use anyhow::{Context, bail};
 use strum::Display;
 
 use crate::mpd_client::StrExt;
@@ -32,6 +33,20 @@ impl Tag {
             Tag::Custom(v) => v,
         }
     }
+
+    /// Tag of the given name, matched case insensitively
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "any" => Tag::Any,
+            "artist" => Tag::Artist,
+            "albumartist" => Tag::AlbumArtist,
+            "album" => Tag::Album,
+            "title" => Tag::Title,
+            "file" => Tag::File,
+            "genre" => Tag::Genre,
+            _ => Tag::Custom(name.to_owned()),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -44,7 +59,7 @@ pub enum FilterKind {
     Contains,
     Regex,
     NotRegex,
-    CustomQuery(String),
+    CustomQuery(FilterExpr),
 }
 
 #[derive(Debug)]
@@ -84,27 +99,311 @@ impl<'value> Filter<'value> {
     }
 
     pub fn to_query_str(&self) -> String {
-        match &self.kind {
-            FilterKind::Exact => {
-                format!("{} == '{}'", self.tag.as_str(), self.value.escape_filter())
+        FilterExpr::from(self).to_query_str()
+    }
+}
+
+impl From<FilterExpr> for Filter<'static> {
+    fn from(expr: FilterExpr) -> Self {
+        Filter::new(String::new(), "").with_type(FilterKind::CustomQuery(expr))
+    }
+}
+
+impl From<&Filter<'_>> for FilterExpr {
+    fn from(filter: &Filter<'_>) -> Self {
+        let op = match &filter.kind {
+            FilterKind::Exact => TagOperator::Eq,
+            FilterKind::NotExact => TagOperator::NotEq,
+            FilterKind::StartsWith => TagOperator::StartsWith,
+            FilterKind::Contains => TagOperator::Contains,
+            FilterKind::Regex => TagOperator::Regex,
+            FilterKind::NotRegex => TagOperator::NotRegex,
+            FilterKind::CustomQuery(expr) => return expr.clone(),
+        };
+
+        FilterExpr::Tag { tag: filter.tag.clone(), op, value: filter.value.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagOperator {
+    Eq,
+    NotEq,
+    Contains,
+    NotContains,
+    StartsWith,
+    Regex,
+    NotRegex,
+}
+
+impl TagOperator {
+    fn as_str(self) -> &'static str {
+        match self {
+            TagOperator::Eq => "==",
+            TagOperator::NotEq => "!=",
+            TagOperator::Contains => "contains",
+            TagOperator::NotContains => "!contains",
+            TagOperator::StartsWith => "starts_with",
+            TagOperator::Regex => "=~",
+            TagOperator::NotRegex => "!~",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormatOperator {
+    /// Exact match of `samplerate:bits:channels`
+    Eq,
+    /// Match with `*` used as a wildcard in place of any of the parts
+    Mask,
+}
+
+/// MPD filter expression as described in
+/// <https://mpd.readthedocs.io/en/latest/protocol.html#filters>.
+///
+/// Parses from and displays as the expression syntax, the displayed values are
+/// escaped. Use [`FilterExpr::to_query_str`] when the expression is sent to MPD
+/// inside a double quoted argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterExpr {
+    /// `(TAG OP 'VALUE')`, [`Tag::File`] compares the whole song uri
+    Tag { tag: Tag, op: TagOperator, value: String },
+    /// `(!EXPR)`
+    Not(Box<FilterExpr>),
+    /// `(EXPR AND EXPR ...)`, must not be empty
+    And(Vec<FilterExpr>),
+    /// `(base 'VALUE')`, songs inside the given directory
+    Base(String),
+    /// `(modified-since 'VALUE')`, ISO 8601 or UNIX timestamp
+    ModifiedSince(String),
+    /// `(added-since 'VALUE')`, ISO 8601 or UNIX timestamp
+    AddedSince(String),
+    /// `(AudioFormat == 'VALUE')` or `(AudioFormat =~ 'VALUE')`
+    AudioFormat { op: AudioFormatOperator, value: String },
+    /// `(prio >= VALUE)`, only applicable to the queue
+    Prio(u8),
+}
+
+impl FilterExpr {
+    /// The expression with values escaped once more so it can be placed
+    /// inside a double quoted argument of an MPD command
+    pub fn to_query_str(&self) -> String {
+        let mut result = String::new();
+        self.write(&mut result, &|value| value.escape_filter());
+        result
+    }
+
+    fn write(&self, out: &mut String, escape: &impl Fn(&str) -> String) {
+        let _ = match self {
+            FilterExpr::Tag { tag, op, value } => {
+                write!(out, "({} {} '{}')", tag.as_str(), op.as_str(), escape(value))
             }
-            FilterKind::NotExact => {
-                format!("{} != '{}'", self.tag.as_str(), self.value.escape_filter())
+            FilterExpr::Not(expr) => {
+                out.push_str("(!");
+                expr.write(out, escape);
+                write!(out, ")")
             }
-            FilterKind::StartsWith => {
-                format!("{} starts_with '{}'", self.tag.as_str(), self.value.escape_filter())
+            FilterExpr::And(exprs) => {
+                out.push('(');
+                for (idx, expr) in exprs.iter().enumerate() {
+                    if idx > 0 {
+                        out.push_str(" AND ");
+                    }
+                    expr.write(out, escape);
+                }
+                write!(out, ")")
             }
-            FilterKind::Contains => {
-                format!("{} contains '{}'", self.tag.as_str(), self.value.escape_filter())
+            FilterExpr::Base(value) => write!(out, "(base '{}')", escape(value)),
+            FilterExpr::ModifiedSince(value) => {
+                write!(out, "(modified-since '{}')", escape(value))
             }
-            FilterKind::Regex => {
-                format!("{} =~ '{}'", self.tag.as_str(), self.value.escape_filter())
+            FilterExpr::AddedSince(value) => write!(out, "(added-since '{}')", escape(value)),
+            FilterExpr::AudioFormat { op: AudioFormatOperator::Eq, value } => {
+                write!(out, "(AudioFormat == '{}')", escape(value))
             }
-            FilterKind::NotRegex => {
-                format!("{} !~ '{}'", self.tag.as_str(), self.value.escape_filter())
+            FilterExpr::AudioFormat { op: AudioFormatOperator::Mask, value } => {
+                write!(out, "(AudioFormat =~ '{}')", escape(value))
             }
-            FilterKind::CustomQuery(query) => query.escape_filter(),
+            FilterExpr::Prio(prio) => write!(out, "(prio >= {prio})"),
+        };
+    }
+}
+
+impl std::fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut result = String::new();
+        self.write(&mut result, &|value| value.replace('\\', r"\\").replace('\'', r"\'"));
+        f.write_str(&result)
+    }
+}
+
+/// Parses an expression in MPD's filter syntax. Multiple expressions joined by
+/// `AND` are accepted at the top level without the surrounding parentheses.
+impl FromStr for FilterExpr {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> anyhow::Result<Self> {
+        let mut parser = FilterParser { input, pos: 0 };
+        let mut exprs = vec![parser.expr()?];
+        while parser.eat_keyword("AND") {
+            exprs.push(parser.expr()?);
+        }
+        parser.skip_whitespace();
+        if parser.pos < input.len() {
+            bail!("Unexpected '{}' at position {}", parser.rest(), parser.pos);
         }
+
+        Ok(if exprs.len() == 1 { exprs.remove(0) } else { FilterExpr::And(exprs) })
+    }
+}
+
+struct FilterParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> FilterParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.input.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> anyhow::Result<()> {
+        if !self.eat(token) {
+            bail!("Expected '{token}' at position {}", self.pos);
+        }
+        Ok(())
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let is_keyword = rest.get(..keyword.len()).is_some_and(|v| v == keyword)
+            && !rest[keyword.len()..].starts_with(Self::is_word_char);
+        if is_keyword {
+            self.pos += keyword.len();
+        }
+        is_keyword
+    }
+
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || c == '-'
+    }
+
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let word = &rest[..rest.find(|c| !Self::is_word_char(c)).unwrap_or(rest.len())];
+        self.pos += word.len();
+        word
+    }
+
+    fn expr(&mut self) -> anyhow::Result<FilterExpr> {
+        self.expect("(")?;
+        self.skip_whitespace();
+
+        let expr = if self.eat("!") {
+            FilterExpr::Not(Box::new(self.expr()?))
+        } else if self.rest().starts_with('(') {
+            let mut exprs = vec![self.expr()?];
+            while self.eat_keyword("AND") {
+                exprs.push(self.expr()?);
+            }
+            if exprs.len() == 1 { exprs.remove(0) } else { FilterExpr::And(exprs) }
+        } else {
+            let name_pos = self.pos;
+            let name = self.word();
+            match name.to_lowercase().as_str() {
+                "" => bail!("Expected tag name at position {name_pos}"),
+                "base" => FilterExpr::Base(self.value()?),
+                "modified-since" => FilterExpr::ModifiedSince(self.value()?),
+                "added-since" => FilterExpr::AddedSince(self.value()?),
+                "audioformat" => {
+                    let op = if self.eat("==") {
+                        AudioFormatOperator::Eq
+                    } else if self.eat("=~") {
+                        AudioFormatOperator::Mask
+                    } else {
+                        bail!("Expected '==' or '=~' at position {}", self.pos);
+                    };
+                    FilterExpr::AudioFormat { op, value: self.value()? }
+                }
+                "prio" => {
+                    self.expect(">=")?;
+                    let prio_pos = self.pos;
+                    let prio = self.word();
+                    FilterExpr::Prio(prio.parse().with_context(|| {
+                        format!("Invalid priority '{prio}' at position {prio_pos}")
+                    })?)
+                }
+                _ => {
+                    let op = self.tag_operator()?;
+                    FilterExpr::Tag { tag: Tag::from_name(name), op, value: self.value()? }
+                }
+            }
+        };
+
+        self.expect(")")?;
+        Ok(expr)
+    }
+
+    fn tag_operator(&mut self) -> anyhow::Result<TagOperator> {
+        for op in [
+            TagOperator::Eq,
+            TagOperator::NotEq,
+            TagOperator::Regex,
+            TagOperator::NotRegex,
+            TagOperator::NotContains,
+            TagOperator::Contains,
+            TagOperator::StartsWith,
+        ] {
+            if self.eat(op.as_str()) {
+                return Ok(op);
+            }
+        }
+        bail!("Expected comparison operator at position {}", self.pos)
+    }
+
+    /// Single or double quoted value, backslash escapes the following character
+    fn value(&mut self) -> anyhow::Result<String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut chars = self.rest().char_indices();
+        let quote = match chars.next() {
+            Some((_, quote @ ('\'' | '"'))) => quote,
+            _ => bail!("Expected quoted value at position {start}"),
+        };
+
+        let mut value = String::new();
+        let mut escaped = false;
+        for (idx, c) in chars {
+            if escaped {
+                value.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                self.pos += idx + c.len_utf8();
+                return Ok(value);
+            } else {
+                value.push(c);
+            }
+        }
+
+        bail!("Unterminated value at position {start}")
     }
 }
 
@@ -115,9 +414,9 @@ impl FilterExt for &[Filter<'_>] {
     fn to_query_str(&self) -> String {
         self.iter().enumerate().fold(String::new(), |mut acc, (idx, filter)| {
             if idx > 0 {
-                let _ = write!(acc, " AND ({})", filter.to_query_str());
+                let _ = write!(acc, " AND {}", filter.to_query_str());
             } else {
-                let _ = write!(acc, "({})", filter.to_query_str());
+                acc.push_str(&filter.to_query_str());
             }
             acc
         })
@@ -125,6 +424,7 @@ impl FilterExt for &[Filter<'_>] {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod filter_tests {
     use test_case::test_case;
 
@@ -182,4 +482,69 @@ mod filter_tests {
 
         assert_eq!(input.to_query_str(), "(Album == 'the greatest') AND (Artist == 'mrs singer')");
     }
+
+    #[test]
+    fn custom_query() {
+        let expr: FilterExpr = "(artist == 'foo')".parse().unwrap();
+        let input: &[Filter<'_>] = &[Filter::from(expr)];
+
+        assert_eq!(input.to_query_str(), "(Artist == 'foo')");
+    }
+
+    #[test]
+    fn parses_nested_expressions() {
+        let expr: FilterExpr =
+            "(!(artist == 'foo')) AND ((base \"music/rock\") AND (modified-since '2024-01-01'))"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            expr,
+            FilterExpr::And(vec![
+                FilterExpr::Not(Box::new(FilterExpr::Tag {
+                    tag: Tag::Artist,
+                    op: TagOperator::Eq,
+                    value: "foo".to_owned(),
+                })),
+                FilterExpr::And(vec![
+                    FilterExpr::Base("music/rock".to_owned()),
+                    FilterExpr::ModifiedSince("2024-01-01".to_owned()),
+                ]),
+            ])
+        );
+    }
+
+    #[test_case("(AudioFormat == '44100:16:2')", &FilterExpr::AudioFormat { op: AudioFormatOperator::Eq, value: "44100:16:2".to_owned() })]
+    #[test_case("(audioformat =~ '*:24:*')", &FilterExpr::AudioFormat { op: AudioFormatOperator::Mask, value: "*:24:*".to_owned() })]
+    #[test_case("(prio >= 42)", &FilterExpr::Prio(42))]
+    #[test_case("(added-since '1700000000')", &FilterExpr::AddedSince("1700000000".to_owned()))]
+    #[test_case("(file == 'a/b.flac')", &FilterExpr::Tag { tag: Tag::File, op: TagOperator::Eq, value: "a/b.flac".to_owned() })]
+    #[test_case("(MUSICBRAINZ_TRACKID !contains 'x')", &FilterExpr::Tag { tag: Tag::Custom("MUSICBRAINZ_TRACKID".to_owned()), op: TagOperator::NotContains, value: "x".to_owned() })]
+    #[test_case("(genre starts_with 'Rock')", &FilterExpr::Tag { tag: Tag::Genre, op: TagOperator::StartsWith, value: "Rock".to_owned() })]
+    fn parses_expression(input: &str, expected: &FilterExpr) {
+        assert_eq!(&input.parse::<FilterExpr>().unwrap(), expected);
+    }
+
+    #[test_case("(artist == 'foo'"; "unclosed parenthesis")]
+    #[test_case("(artist == 'foo)"; "unterminated value")]
+    #[test_case("(artist 'foo')"; "missing operator")]
+    #[test_case("(prio >= high)"; "invalid priority")]
+    #[test_case("(artist == 'foo') OR (album == 'bar')"; "unknown keyword")]
+    #[test_case("artist == 'foo'"; "missing parenthesis")]
+    fn rejects_invalid_expression(input: &str) {
+        assert!(input.parse::<FilterExpr>().is_err());
+    }
+
+    #[test]
+    fn escapes_values() {
+        let expr = FilterExpr::Tag {
+            tag: Tag::Title,
+            op: TagOperator::Eq,
+            value: r#"it's a "\" test"#.to_owned(),
+        };
+
+        assert_eq!(expr.to_string(), r#"(Title == 'it\'s a "\\" test')"#);
+        assert_eq!(expr.to_string().parse::<FilterExpr>().unwrap(), expr);
+        assert_eq!(expr.to_query_str(), r#"(Title == 'it\\'s a \"\\\\\" test')"#);
+    }
 }
@@ -1,13 +1,21 @@
 pub mod address;
 pub mod client;
+pub mod command_list;
 pub mod commands;
 pub mod errors;
+#[cfg(feature = "fake-server")]
+pub mod fake_server;
 pub mod filter;
 pub mod from_mpd;
 pub mod mpd_client;
 pub mod proto_client;
 pub mod queue_position;
+pub mod recording;
+mod server;
 pub mod single_or_range;
+pub mod song_range;
+#[cfg(feature = "tokio")]
+pub mod tokio_client;
 
 #[cfg(test)]
 mod tests {
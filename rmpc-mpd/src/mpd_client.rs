@@ -1,6 +1,7 @@
-use std::{fmt::Write, str::FromStr};
+use std::{collections::HashMap, fmt::Write, str::FromStr, time::Duration};
 
 use anyhow::Result;
+use itertools::Itertools;
 use rmpc_shared::version::Version;
 use strum::AsRefStr;
 
@@ -10,18 +11,25 @@ use super::{
         ListFiles,
         LsInfo,
         Mounts,
+        Neighbors,
         Playlist,
         Song,
+        Stats,
         Status,
         Update,
         Volume,
+        comments::Comments,
+        count::{Count, GroupedCounts},
         decoders::Decoders,
+        fingerprint::Fingerprint,
         list::{MpdGroupedList, MpdList},
         list_playlist::FileList,
         mpd_config::MpdConfig,
         outputs::Outputs,
+        plchanges::{PosIds, QueueDiff, QueueVersion},
+        replay_gain::{ReplayGainMode, ReplayGainStatus},
         status::OnOffOneshot,
-        stickers::{Sticker, Stickers, StickersWithFile},
+        stickers::{Sticker, StickerType, Stickers, StickersWithFile},
         volume::Bound,
     },
     errors::MpdError,
@@ -32,6 +40,7 @@ use crate::{
     filter::{Filter, FilterExt, Tag},
     queue_position::QueuePosition,
     single_or_range::SingleOrRange,
+    song_range::SongRange,
 };
 
 type MpdResult<T> = Result<T, MpdError>;
@@ -105,8 +114,13 @@ pub trait MpdCommand {
     fn send_set_volume(&mut self, volume: Volume) -> MpdResult<()>;
     fn send_volume(&mut self, change: ValueChange) -> MpdResult<()>;
     fn send_crossfade(&mut self, seconds: u32) -> MpdResult<()>;
+    fn send_mixramp_db(&mut self, db: f32) -> MpdResult<()>;
+    fn send_mixramp_delay(&mut self, seconds: f32) -> MpdResult<()>;
+    fn send_replay_gain_mode(&mut self, mode: ReplayGainMode) -> MpdResult<()>;
+    fn send_replay_gain_status(&mut self) -> MpdResult<()>;
     fn send_get_current_song(&mut self) -> MpdResult<()>;
     fn send_get_status(&mut self) -> MpdResult<()>;
+    fn send_stats(&mut self) -> MpdResult<()>;
     fn send_pause_toggle(&mut self) -> MpdResult<()>;
     fn send_pause(&mut self) -> MpdResult<()>;
     fn send_unpause(&mut self) -> MpdResult<()>;
@@ -117,6 +131,8 @@ pub trait MpdCommand {
     fn send_play_id(&mut self, id: u32) -> MpdResult<()>;
     fn send_stop(&mut self) -> MpdResult<()>;
     fn send_seek_current(&mut self, value: ValueChange) -> MpdResult<()>;
+    fn send_seek(&mut self, pos: usize, time: Duration) -> MpdResult<()>;
+    fn send_seek_id(&mut self, id: u32, time: Duration) -> MpdResult<()>;
     fn send_repeat(&mut self, enabled: bool) -> MpdResult<()>;
     fn send_random(&mut self, enabled: bool) -> MpdResult<()>;
     fn send_single(&mut self, single: OnOffOneshot) -> MpdResult<()>;
@@ -124,7 +140,9 @@ pub trait MpdCommand {
     fn send_mount(&mut self, name: &str, path: &str) -> MpdResult<()>;
     fn send_unmount(&mut self, name: &str) -> MpdResult<()>;
     fn send_list_mounts(&mut self) -> MpdResult<()>;
+    fn send_list_neighbors(&mut self) -> MpdResult<()>;
     fn send_add(&mut self, path: &str, position: Option<QueuePosition>) -> MpdResult<()>;
+    fn send_add_id(&mut self, path: &str, position: Option<QueuePosition>) -> MpdResult<()>;
     fn send_clear(&mut self) -> MpdResult<()>;
     fn send_swap_position(&mut self, song1: usize, song2: usize) -> MpdResult<()>;
     fn send_swap_id(&mut self, id1: u32, id2: u32) -> MpdResult<()>;
@@ -132,8 +150,12 @@ pub trait MpdCommand {
     fn send_delete_from_queue(&mut self, songs: SingleOrRange) -> MpdResult<()>;
     fn send_playlist_info(&mut self) -> MpdResult<()>;
     fn send_playlist_id(&mut self, id: u32) -> MpdResult<()>;
+    fn send_plchanges(&mut self, version: u32) -> MpdResult<()>;
+    fn send_plchanges_posid(&mut self, version: u32) -> MpdResult<()>;
     fn send_find(&mut self, filter: &[Filter<'_>]) -> MpdResult<()>;
     fn send_search(&mut self, filter: &[Filter<'_>]) -> MpdResult<()>;
+    fn send_playlist_find(&mut self, filter: &[Filter<'_>]) -> MpdResult<()>;
+    fn send_playlist_search(&mut self, filter: &[Filter<'_>]) -> MpdResult<()>;
     fn send_move_in_queue(&mut self, from: SingleOrRange, to: QueuePosition) -> MpdResult<()>;
     fn send_move_id(&mut self, id: u32, to: QueuePosition) -> MpdResult<()>;
     fn send_find_add(
@@ -153,12 +175,19 @@ pub trait MpdCommand {
         group_tags: &[Tag],
         filter: Option<&[Filter<'_>]>,
     ) -> MpdResult<()>;
+    fn send_count(&mut self, filter: Option<&[Filter<'_>]>, group: Option<&Tag>) -> MpdResult<()>;
+    fn send_search_count(&mut self, filter: &[Filter<'_>], group: Option<&Tag>) -> MpdResult<()>;
     fn send_shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()>;
+    fn send_prio(&mut self, priority: u8, songs: &[SingleOrRange]) -> MpdResult<()>;
+    fn send_prio_id(&mut self, priority: u8, ids: &[u32]) -> MpdResult<()>;
+    fn send_range_id(&mut self, id: u32, range: SongRange) -> MpdResult<()>;
     fn send_list_all(&mut self, path: Option<&str>) -> MpdResult<()>;
     fn send_lsinfo(&mut self, path: Option<&str>) -> MpdResult<()>;
     fn send_list_files(&mut self, path: Option<&str>) -> MpdResult<()>;
     fn send_read_picture(&mut self, path: &str) -> MpdResult<String>;
     fn send_albumart(&mut self, path: &str) -> MpdResult<String>;
+    fn send_read_comments(&mut self, path: &str) -> MpdResult<()>;
+    fn send_get_fingerprint(&mut self, path: &str) -> MpdResult<()>;
     fn send_list_playlists(&mut self) -> MpdResult<()>;
     fn send_list_playlist(&mut self, name: &str) -> MpdResult<()>;
     fn send_list_playlist_info(
@@ -192,18 +221,42 @@ pub trait MpdCommand {
     fn send_toggle_output(&mut self, id: u32) -> MpdResult<()>;
     fn send_enable_output(&mut self, id: u32) -> MpdResult<()>;
     fn send_disable_output(&mut self, id: u32) -> MpdResult<()>;
+    fn send_output_set(&mut self, id: u32, name: &str, value: &str) -> MpdResult<()>;
     fn send_decoders(&mut self) -> MpdResult<()>;
-    fn send_sticker(&mut self, uri: &str, name: &str) -> MpdResult<()>;
-    fn send_set_sticker(&mut self, uri: &str, name: &str, value: &str) -> MpdResult<()>;
-    fn send_delete_sticker(&mut self, uri: &str, name: &str) -> MpdResult<()>;
-    fn send_delete_all_stickers(&mut self, uri: &str) -> MpdResult<()>;
-    fn send_list_stickers(&mut self, uri: &str) -> MpdResult<()>;
+    fn send_sticker(&mut self, ty: &StickerType, uri: &str, name: &str) -> MpdResult<()>;
+    fn send_set_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+        value: &str,
+    ) -> MpdResult<()>;
+    fn send_inc_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+        value: u32,
+    ) -> MpdResult<()>;
+    fn send_dec_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+        value: u32,
+    ) -> MpdResult<()>;
+    fn send_delete_sticker(&mut self, ty: &StickerType, uri: &str, name: &str) -> MpdResult<()>;
+    fn send_delete_all_stickers(&mut self, ty: &StickerType, uri: &str) -> MpdResult<()>;
+    fn send_list_stickers(&mut self, ty: &StickerType, uri: &str) -> MpdResult<()>;
     fn send_find_stickers(
         &mut self,
+        ty: &StickerType,
         uri: &str,
         name: &str,
         opts: StickerFindOptions,
     ) -> MpdResult<()>;
+    fn send_sticker_names(&mut self) -> MpdResult<()>;
+    fn send_sticker_types(&mut self) -> MpdResult<()>;
     fn send_switch_to_partition(&mut self, name: &str) -> MpdResult<()>;
     fn send_new_partition(&mut self, name: &str) -> MpdResult<()>;
     fn send_delete_partition(&mut self, name: &str) -> MpdResult<()>;
@@ -228,6 +281,19 @@ pub trait MpdCommand {
     ) -> MpdResult<()>;
     fn send_string_normalization_all(&mut self) -> MpdResult<()>;
     fn send_string_normalization_clear(&mut self) -> MpdResult<()>;
+    fn send_tag_types(&mut self) -> MpdResult<()>;
+    fn send_tag_types_available(&mut self) -> MpdResult<()>;
+    fn send_tag_types_enable(&mut self, tags: &[String]) -> MpdResult<()>;
+    fn send_tag_types_disable(&mut self, tags: &[String]) -> MpdResult<()>;
+    fn send_tag_types_all(&mut self) -> MpdResult<()>;
+    fn send_tag_types_clear(&mut self) -> MpdResult<()>;
+    fn send_protocol(&mut self) -> MpdResult<()>;
+    fn send_protocol_available(&mut self) -> MpdResult<()>;
+    fn send_protocol_enable(&mut self, features: &[String]) -> MpdResult<()>;
+    fn send_protocol_disable(&mut self, features: &[String]) -> MpdResult<()>;
+    fn send_protocol_all(&mut self) -> MpdResult<()>;
+    fn send_protocol_clear(&mut self) -> MpdResult<()>;
+    fn send_url_handlers(&mut self) -> MpdResult<()>;
 }
 
 #[allow(dead_code)]
@@ -249,8 +315,17 @@ pub trait MpdClient: Sized {
     /// Set playback volume relative to current
     fn volume(&mut self, change: ValueChange) -> MpdResult<()>;
     fn crossfade(&mut self, seconds: u32) -> MpdResult<()>;
+    /// Sets the volume threshold in decibels at which songs are overlapped.
+    /// Requires songs to have mixramp tags.
+    fn mixramp_db(&mut self, db: f32) -> MpdResult<()>;
+    /// Sets the additional time subtracted from the mixramp overlap, `NaN`
+    /// disables mixramp and falls back to crossfading
+    fn mixramp_delay(&mut self, seconds: f32) -> MpdResult<()>;
+    fn replay_gain_mode(&mut self, mode: ReplayGainMode) -> MpdResult<()>;
+    fn replay_gain_status(&mut self) -> MpdResult<ReplayGainStatus>;
     fn get_current_song(&mut self) -> MpdResult<Option<Song>>;
     fn get_status(&mut self) -> MpdResult<Status>;
+    fn stats(&mut self) -> MpdResult<Stats>;
     // Playback control
     fn pause_toggle(&mut self) -> MpdResult<()>;
     fn pause(&mut self) -> MpdResult<()>;
@@ -262,6 +337,11 @@ pub trait MpdClient: Sized {
     fn play_id(&mut self, id: u32) -> MpdResult<()>;
     fn stop(&mut self) -> MpdResult<()>;
     fn seek_current(&mut self, value: ValueChange) -> MpdResult<()>;
+    /// Starts playing the song at the given position in the queue from the
+    /// given time
+    fn seek(&mut self, pos: usize, time: Duration) -> MpdResult<()>;
+    /// Starts playing the song with the given id from the given time
+    fn seek_id(&mut self, id: u32, time: Duration) -> MpdResult<()>;
     fn repeat(&mut self, enabled: bool) -> MpdResult<()>;
     fn random(&mut self, enabled: bool) -> MpdResult<()>;
     fn single(&mut self, single: OnOffOneshot) -> MpdResult<()>;
@@ -270,8 +350,16 @@ pub trait MpdClient: Sized {
     fn mount(&mut self, name: &str, path: &str) -> MpdResult<()>;
     fn unmount(&mut self, name: &str) -> MpdResult<()>;
     fn list_mounts(&mut self) -> MpdResult<Mounts>;
+    /// Storages discovered by the neighbor plugins configured in MPD
+    fn list_neighbors(&mut self) -> MpdResult<Neighbors>;
     // Current queue
     fn add(&mut self, path: &str, position: Option<QueuePosition>) -> MpdResult<()>;
+    /// Adds a song to the queue and returns its id. Unlike `add` directories
+    /// are not supported.
+    fn add_id(&mut self, path: &str, position: Option<QueuePosition>) -> MpdResult<u32>;
+    /// Sets the portion of the song with the given id which should be played,
+    /// full range removes it
+    fn range_id(&mut self, id: u32, range: SongRange) -> MpdResult<()>;
     fn clear(&mut self) -> MpdResult<()>;
     // Swaps the songs at position SONG1 and SONG2 in the current playlist. Zero
     // based index.
@@ -281,8 +369,25 @@ pub trait MpdClient: Sized {
     fn delete_from_queue(&mut self, songs: SingleOrRange) -> MpdResult<()>;
     fn playlist_info(&mut self) -> MpdResult<Option<Vec<Song>>>;
     fn playlist_id(&mut self, id: u32) -> MpdResult<Option<Song>>;
+    /// Songs in the queue which were added or changed since the given
+    /// playlist version.
+    fn plchanges(&mut self, version: u32) -> MpdResult<Vec<Song>>;
+    /// Same as [`MpdClient::plchanges`] but only returns positions and ids.
+    fn plchanges_posid(&mut self, version: u32) -> MpdResult<PosIds>;
+    /// Computes changes of the queue since version `from`. `known_positions`
+    /// maps ids of songs in the queue at version `from` to their positions.
+    /// Returns `None` if the changes cannot be determined, for example because
+    /// the current partition changed. The whole queue has to be fetched in that
+    /// case.
+    fn queue_diff(
+        &mut self,
+        from: &QueueVersion,
+        known_positions: &HashMap<u32, usize>,
+    ) -> MpdResult<Option<QueueDiff>>;
     fn find(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>>;
     fn search(&mut self, filter: &[Filter<'_>], ignore_diacritics: bool) -> MpdResult<Vec<Song>>;
+    fn playlist_find(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>>;
+    fn playlist_search(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>>;
     fn move_in_queue(&mut self, from: SingleOrRange, to: QueuePosition) -> MpdResult<()>;
     fn move_id(&mut self, id: u32, to: QueuePosition) -> MpdResult<()>;
     fn find_one(&mut self, filter: &[Filter<'_>]) -> MpdResult<Option<Song>>;
@@ -300,8 +405,30 @@ pub trait MpdClient: Sized {
         group_tags: &[Tag],
         filter: Option<&[Filter<'_>]>,
     ) -> MpdResult<MpdGroupedList>;
+    /// Number of songs and their total playtime matching the filter
+    fn count(&mut self, filter: &[Filter<'_>]) -> MpdResult<Count>;
+    /// Same as [`MpdClient::count`] but grouped by values of `group`. All songs
+    /// are counted when no filter is given.
+    fn count_grouped(
+        &mut self,
+        group: Tag,
+        filter: Option<&[Filter<'_>]>,
+    ) -> MpdResult<GroupedCounts>;
+    /// Same as [`MpdClient::count`] but the filter is case insensitive
+    fn search_count(&mut self, filter: &[Filter<'_>]) -> MpdResult<Count>;
+    /// Same as [`MpdClient::count_grouped`] but the filter is case insensitive
+    fn search_count_grouped(
+        &mut self,
+        group: Tag,
+        filter: &[Filter<'_>],
+    ) -> MpdResult<GroupedCounts>;
     /// Shuffles the current queue.
     fn shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()>;
+    /// Sets the priority of the songs at the given positions or ranges in the
+    /// queue. Only has an effect in random mode.
+    fn prio(&mut self, priority: u8, songs: &[SingleOrRange]) -> MpdResult<()>;
+    /// Same as [`MpdClient::prio`] but the songs are addressed by their ids.
+    fn prio_id(&mut self, priority: u8, ids: &[u32]) -> MpdResult<()>;
     // Database
     fn add_random_songs(&mut self, count: usize, filter: Option<&[Filter<'_>]>) -> MpdResult<()>;
     fn add_random_tag(&mut self, count: usize, tag: Tag) -> MpdResult<()>;
@@ -311,6 +438,12 @@ pub trait MpdClient: Sized {
     fn list_files(&mut self, path: Option<&str>) -> MpdResult<ListFiles>;
     fn read_picture(&mut self, path: &str) -> MpdResult<Option<Vec<u8>>>;
     fn albumart(&mut self, path: &str) -> MpdResult<Option<Vec<u8>>>;
+    /// All tags of the file as they are stored in it, including the ones MPD
+    /// does not know about
+    fn read_comments(&mut self, path: &str) -> MpdResult<Comments>;
+    /// Chromaprint fingerprint of the file. MPD has to decode the file to
+    /// compute it so this can take a while.
+    fn get_fingerprint(&mut self, path: &str) -> MpdResult<Fingerprint>;
     // Stored playlists
     fn list_playlists(&mut self) -> MpdResult<Vec<Playlist>>;
     fn list_playlist(&mut self, name: &str) -> MpdResult<FileList>;
@@ -342,6 +475,9 @@ pub trait MpdClient: Sized {
     fn toggle_output(&mut self, id: u32) -> MpdResult<()>;
     fn enable_output(&mut self, id: u32) -> MpdResult<()>;
     fn disable_output(&mut self, id: u32) -> MpdResult<()>;
+    /// Sets a runtime attribute of the output, the supported attributes
+    /// depend on the output plugin
+    fn output_set(&mut self, id: u32, name: &str, value: &str) -> MpdResult<()>;
     // Decoders
     fn decoders(&mut self) -> MpdResult<Decoders>;
     // Stickers
@@ -366,6 +502,46 @@ pub trait MpdClient: Sized {
         name: &str,
         opts: StickerFindOptions,
     ) -> MpdResult<StickersWithFile>;
+    /// Same as [`MpdClient::sticker`] for any type of object.
+    fn typed_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+    ) -> MpdResult<Option<Sticker>>;
+    /// Same as [`MpdClient::set_sticker`] for any type of object.
+    fn set_typed_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+        value: &str,
+    ) -> MpdResult<()>;
+    /// Same as [`MpdClient::delete_sticker`] for any type of object.
+    fn delete_typed_sticker(&mut self, ty: &StickerType, uri: &str, name: &str) -> MpdResult<()>;
+    /// Same as [`MpdClient::list_stickers`] for any type of object.
+    fn list_typed_stickers(&mut self, ty: &StickerType, uri: &str) -> MpdResult<Stickers>;
+    /// Same as [`MpdClient::find_stickers`] for any type of object. Uri is the
+    /// tag value for tag stickers and may be empty to match all objects.
+    fn find_typed_stickers(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+        opts: StickerFindOptions,
+    ) -> MpdResult<StickersWithFile>;
+    /// Atomically increments the numeric sticker by the given value, creating
+    /// it if it does not exist yet. Requires MPD 0.24.
+    fn inc_sticker(&mut self, ty: &StickerType, uri: &str, name: &str, value: u32)
+    -> MpdResult<()>;
+    /// Atomically decrements the numeric sticker by the given value. Requires
+    /// MPD 0.24.
+    fn dec_sticker(&mut self, ty: &StickerType, uri: &str, name: &str, value: u32)
+    -> MpdResult<()>;
+    /// Names of all stickers in the sticker database. Requires MPD 0.24.
+    fn sticker_names(&mut self) -> MpdResult<MpdList>;
+    /// Types of objects stickers can be attached to. Requires MPD 0.24.
+    fn sticker_types(&mut self) -> MpdResult<MpdList>;
 
     // Partitions
     fn switch_to_partition(&mut self, name: &str) -> MpdResult<()>;
@@ -392,6 +568,32 @@ pub trait MpdClient: Sized {
     ) -> MpdResult<()>;
     fn string_normalization_all(&mut self) -> MpdResult<()>;
     fn string_normalization_clear(&mut self) -> MpdResult<()>;
+
+    // Tag types
+    /// Lists tag types currently enabled for this connection
+    fn tag_types(&mut self) -> MpdResult<MpdList>;
+    /// Lists all tag types supported by the server regardless of which are
+    /// enabled. Requires MPD 0.24.
+    fn tag_types_available(&mut self) -> MpdResult<MpdList>;
+    fn tag_types_enable(&mut self, tags: &[String]) -> MpdResult<()>;
+    fn tag_types_disable(&mut self, tags: &[String]) -> MpdResult<()>;
+    fn tag_types_all(&mut self) -> MpdResult<()>;
+    fn tag_types_clear(&mut self) -> MpdResult<()>;
+
+    // Protocol features
+    /// Lists protocol features currently enabled for this connection. Requires
+    /// MPD 0.24.
+    fn protocol(&mut self) -> MpdResult<MpdList>;
+    /// Lists all protocol features supported by the server regardless of
+    /// which are enabled. Requires MPD 0.24.
+    fn protocol_available(&mut self) -> MpdResult<MpdList>;
+    fn protocol_enable(&mut self, features: &[String]) -> MpdResult<()>;
+    fn protocol_disable(&mut self, features: &[String]) -> MpdResult<()>;
+    fn protocol_all(&mut self) -> MpdResult<()>;
+    fn protocol_clear(&mut self) -> MpdResult<()>;
+    /// Lists URL schemes including `://` the server can play. `file://` is
+    /// only present for clients connected through a local socket.
+    fn url_handlers(&mut self) -> MpdResult<MpdList>;
 }
 
 impl<T: SocketClient> MpdCommand for T {
@@ -476,6 +678,22 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute(&format!("crossfade {seconds}"))
     }
 
+    fn send_mixramp_db(&mut self, db: f32) -> MpdResult<()> {
+        self.execute(&format!("mixrampdb {db}"))
+    }
+
+    fn send_mixramp_delay(&mut self, seconds: f32) -> MpdResult<()> {
+        self.execute(&format!("mixrampdelay {seconds}"))
+    }
+
+    fn send_replay_gain_mode(&mut self, mode: ReplayGainMode) -> MpdResult<()> {
+        self.execute(&format!("replay_gain_mode {mode}"))
+    }
+
+    fn send_replay_gain_status(&mut self) -> MpdResult<()> {
+        self.execute("replay_gain_status")
+    }
+
     fn send_get_current_song(&mut self) -> MpdResult<()> {
         self.execute("currentsong")
     }
@@ -484,6 +702,10 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute("status")
     }
 
+    fn send_stats(&mut self) -> MpdResult<()> {
+        self.execute("stats")
+    }
+
     fn send_pause_toggle(&mut self) -> MpdResult<()> {
         self.execute("pause")
     }
@@ -524,6 +746,14 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute(&format!("seekcur {}", value.to_mpd_str()))
     }
 
+    fn send_seek(&mut self, pos: usize, time: Duration) -> MpdResult<()> {
+        self.execute(&format!("seek {pos} {}", time.as_secs_f64()))
+    }
+
+    fn send_seek_id(&mut self, id: u32, time: Duration) -> MpdResult<()> {
+        self.execute(&format!("seekid {id} {}", time.as_secs_f64()))
+    }
+
     fn send_repeat(&mut self, enabled: bool) -> MpdResult<()> {
         self.execute(&format!("repeat {}", u8::from(enabled)))
     }
@@ -556,12 +786,22 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute("listmounts")
     }
 
+    fn send_list_neighbors(&mut self) -> MpdResult<()> {
+        self.execute("listneighbors")
+    }
+
     fn send_add(&mut self, uri: &str, position: Option<QueuePosition>) -> MpdResult<()> {
         let position_arg: String =
             position.map_or(String::new(), |v| format!(" {}", v.as_mpd_str()));
         self.execute(&format!("add {}{position_arg}", uri.quote_and_escape()))
     }
 
+    fn send_add_id(&mut self, uri: &str, position: Option<QueuePosition>) -> MpdResult<()> {
+        let position_arg: String =
+            position.map_or(String::new(), |v| format!(" {}", v.as_mpd_str()));
+        self.execute(&format!("addid {}{position_arg}", uri.quote_and_escape()))
+    }
+
     fn send_clear(&mut self) -> MpdResult<()> {
         self.execute("clear")
     }
@@ -590,6 +830,14 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute(&format!("playlistid {id}"))
     }
 
+    fn send_plchanges(&mut self, version: u32) -> MpdResult<()> {
+        self.execute(&format!("plchanges {version}"))
+    }
+
+    fn send_plchanges_posid(&mut self, version: u32) -> MpdResult<()> {
+        self.execute(&format!("plchangesposid {version}"))
+    }
+
     fn send_find(&mut self, filter: &[Filter<'_>]) -> MpdResult<()> {
         self.execute(&format!("find \"({})\"", filter.to_query_str()))
     }
@@ -601,6 +849,14 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute(&format!("search \"({query})\""))
     }
 
+    fn send_playlist_find(&mut self, filter: &[Filter<'_>]) -> MpdResult<()> {
+        self.execute(&format!("playlistfind \"({})\"", filter.to_query_str()))
+    }
+
+    fn send_playlist_search(&mut self, filter: &[Filter<'_>]) -> MpdResult<()> {
+        self.execute(&format!("playlistsearch \"({})\"", filter.to_query_str()))
+    }
+
     fn send_move_in_queue(&mut self, from: SingleOrRange, to: QueuePosition) -> MpdResult<()> {
         self.execute(&format!("move {} {}", from.as_mpd_range(), to.as_mpd_str()))
     }
@@ -662,6 +918,22 @@ impl<T: SocketClient> MpdCommand for T {
         }
     }
 
+    fn send_count(&mut self, filter: Option<&[Filter<'_>]>, group: Option<&Tag>) -> MpdResult<()> {
+        let mut cmd = "count".to_owned();
+        if let Some(filter) = filter {
+            let _ = write!(cmd, " \"({})\"", filter.to_query_str());
+        }
+        if let Some(group) = group {
+            let _ = write!(cmd, " group {}", group.as_str());
+        }
+        self.execute(&cmd)
+    }
+
+    fn send_search_count(&mut self, filter: &[Filter<'_>], group: Option<&Tag>) -> MpdResult<()> {
+        let group = group.map_or(String::new(), |group| format!(" group {}", group.as_str()));
+        self.execute(&format!("searchcount \"({})\"{group}", filter.to_query_str()))
+    }
+
     fn send_shuffle(&mut self, range: Option<SingleOrRange>) -> MpdResult<()> {
         if let Some(range) = range {
             self.execute(&format!("shuffle {}", range.as_mpd_range()))
@@ -670,6 +942,19 @@ impl<T: SocketClient> MpdCommand for T {
         }
     }
 
+    fn send_prio(&mut self, priority: u8, songs: &[SingleOrRange]) -> MpdResult<()> {
+        let ranges = songs.iter().map(SingleOrRange::as_mpd_range).join(" ");
+        self.execute(&format!("prio {priority} {ranges}"))
+    }
+
+    fn send_prio_id(&mut self, priority: u8, ids: &[u32]) -> MpdResult<()> {
+        self.execute(&format!("prioid {priority} {}", ids.iter().join(" ")))
+    }
+
+    fn send_range_id(&mut self, id: u32, range: SongRange) -> MpdResult<()> {
+        self.execute(&format!("rangeid {id} {}", range.as_mpd_str()))
+    }
+
     fn send_list_all(&mut self, path: Option<&str>) -> MpdResult<()> {
         if let Some(path) = path {
             self.execute(&format!("listall {}", path.quote_and_escape()))
@@ -706,6 +991,14 @@ impl<T: SocketClient> MpdCommand for T {
         Ok(cmd)
     }
 
+    fn send_read_comments(&mut self, path: &str) -> MpdResult<()> {
+        self.execute(&format!("readcomments {}", path.quote_and_escape()))
+    }
+
+    fn send_get_fingerprint(&mut self, path: &str) -> MpdResult<()> {
+        self.execute(&format!("getfingerprint {}", path.quote_and_escape()))
+    }
+
     fn send_list_playlists(&mut self) -> MpdResult<()> {
         self.execute("listplaylists")
     }
@@ -827,51 +1120,103 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute(&format!("disableoutput {id}"))
     }
 
+    fn send_output_set(&mut self, id: u32, name: &str, value: &str) -> MpdResult<()> {
+        self.execute(&format!(
+            "outputset {id} {} {}",
+            name.quote_and_escape(),
+            value.quote_and_escape()
+        ))
+    }
+
     fn send_decoders(&mut self) -> MpdResult<()> {
         self.execute("decoders")
     }
 
-    fn send_sticker(&mut self, uri: &str, key: &str) -> MpdResult<()> {
+    fn send_sticker(&mut self, ty: &StickerType, uri: &str, key: &str) -> MpdResult<()> {
         self.execute(&format!(
-            "sticker get song {} {}",
+            "sticker get {} {} {}",
+            ty.as_mpd_str(),
             uri.quote_and_escape(),
             key.quote_and_escape()
         ))
     }
 
-    fn send_set_sticker(&mut self, uri: &str, key: &str, value: &str) -> MpdResult<()> {
+    fn send_set_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        key: &str,
+        value: &str,
+    ) -> MpdResult<()> {
         self.execute(&format!(
-            "sticker set song {} {} {}",
+            "sticker set {} {} {} {}",
+            ty.as_mpd_str(),
             uri.quote_and_escape(),
             key.quote_and_escape(),
             value.quote_and_escape()
         ))
     }
 
-    fn send_delete_sticker(&mut self, uri: &str, key: &str) -> MpdResult<()> {
+    fn send_inc_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        key: &str,
+        value: u32,
+    ) -> MpdResult<()> {
+        self.execute(&format!(
+            "sticker inc {} {} {} {value}",
+            ty.as_mpd_str(),
+            uri.quote_and_escape(),
+            key.quote_and_escape(),
+        ))
+    }
+
+    fn send_dec_sticker(
+        &mut self,
+        ty: &StickerType,
+        uri: &str,
+        key: &str,
+        value: u32,
+    ) -> MpdResult<()> {
+        self.execute(&format!(
+            "sticker dec {} {} {} {value}",
+            ty.as_mpd_str(),
+            uri.quote_and_escape(),
+            key.quote_and_escape(),
+        ))
+    }
+
+    fn send_delete_sticker(&mut self, ty: &StickerType, uri: &str, key: &str) -> MpdResult<()> {
         self.execute(&format!(
-            "sticker delete song {} {}",
+            "sticker delete {} {} {}",
+            ty.as_mpd_str(),
             uri.quote_and_escape(),
             key.quote_and_escape()
         ))
     }
 
-    fn send_delete_all_stickers(&mut self, uri: &str) -> MpdResult<()> {
-        self.execute(&format!("sticker delete song {}", uri.quote_and_escape()))
+    fn send_delete_all_stickers(&mut self, ty: &StickerType, uri: &str) -> MpdResult<()> {
+        self.execute(&format!("sticker delete {} {}", ty.as_mpd_str(), uri.quote_and_escape()))
     }
 
-    fn send_list_stickers(&mut self, uri: &str) -> MpdResult<()> {
-        self.execute(&format!("sticker list song {}", uri.quote_and_escape()))
+    fn send_list_stickers(&mut self, ty: &StickerType, uri: &str) -> MpdResult<()> {
+        self.execute(&format!("sticker list {} {}", ty.as_mpd_str(), uri.quote_and_escape()))
     }
 
     fn send_find_stickers(
         &mut self,
+        ty: &StickerType,
         uri: &str,
         key: &str,
         opts: StickerFindOptions,
     ) -> MpdResult<()> {
-        let mut cmd =
-            format!("sticker find song {} {}", uri.quote_and_escape(), key.quote_and_escape());
+        let mut cmd = format!(
+            "sticker find {} {} {}",
+            ty.as_mpd_str(),
+            uri.quote_and_escape(),
+            key.quote_and_escape()
+        );
         if let Some(ref filter) = opts.filter {
             cmd.push(' ');
             filter.write_to(&mut cmd);
@@ -885,6 +1230,14 @@ impl<T: SocketClient> MpdCommand for T {
         self.execute(&cmd)
     }
 
+    fn send_sticker_names(&mut self) -> MpdResult<()> {
+        self.execute("stickernames")
+    }
+
+    fn send_sticker_types(&mut self) -> MpdResult<()> {
+        self.execute("stickertypes")
+    }
+
     fn send_switch_to_partition(&mut self, name: &str) -> MpdResult<()> {
         self.execute(&format!("partition {}", name.quote_and_escape()))
     }
@@ -964,6 +1317,108 @@ impl<T: SocketClient> MpdCommand for T {
     fn send_string_normalization_clear(&mut self) -> MpdResult<()> {
         self.execute("stringnormalization clear")
     }
+
+    fn send_tag_types(&mut self) -> MpdResult<()> {
+        self.execute("tagtypes")
+    }
+
+    fn send_tag_types_available(&mut self) -> MpdResult<()> {
+        self.execute("tagtypes available")
+    }
+
+    fn send_tag_types_enable(&mut self, tags: &[String]) -> MpdResult<()> {
+        debug_assert!(!tags.is_empty());
+
+        let mut buf = String::from("tagtypes enable");
+        for tag in tags {
+            buf.push(' ');
+            buf.push_str(&tag.quote_and_escape());
+        }
+        self.execute(&buf)
+    }
+
+    fn send_tag_types_disable(&mut self, tags: &[String]) -> MpdResult<()> {
+        debug_assert!(!tags.is_empty());
+
+        let mut buf = String::from("tagtypes disable");
+        for tag in tags {
+            buf.push(' ');
+            buf.push_str(&tag.quote_and_escape());
+        }
+        self.execute(&buf)
+    }
+
+    fn send_tag_types_all(&mut self) -> MpdResult<()> {
+        self.execute("tagtypes all")
+    }
+
+    fn send_tag_types_clear(&mut self) -> MpdResult<()> {
+        self.execute("tagtypes clear")
+    }
+
+    fn send_protocol(&mut self) -> MpdResult<()> {
+        if self.version() < Version::new(0, 24, 0) {
+            Err(MpdError::UnsupportedMpdVersion("protocol can be used since MPD 0.24.0"))
+        } else {
+            self.execute("protocol")
+        }
+    }
+
+    fn send_protocol_available(&mut self) -> MpdResult<()> {
+        if self.version() < Version::new(0, 24, 0) {
+            Err(MpdError::UnsupportedMpdVersion("protocol can be used since MPD 0.24.0"))
+        } else {
+            self.execute("protocol available")
+        }
+    }
+
+    fn send_protocol_enable(&mut self, features: &[String]) -> MpdResult<()> {
+        debug_assert!(!features.is_empty());
+        if self.version() < Version::new(0, 24, 0) {
+            return Err(MpdError::UnsupportedMpdVersion("protocol can be used since MPD 0.24.0"));
+        }
+
+        let mut buf = String::from("protocol enable");
+        for feature in features {
+            buf.push(' ');
+            buf.push_str(&feature.quote_and_escape());
+        }
+        self.execute(&buf)
+    }
+
+    fn send_protocol_disable(&mut self, features: &[String]) -> MpdResult<()> {
+        debug_assert!(!features.is_empty());
+        if self.version() < Version::new(0, 24, 0) {
+            return Err(MpdError::UnsupportedMpdVersion("protocol can be used since MPD 0.24.0"));
+        }
+
+        let mut buf = String::from("protocol disable");
+        for feature in features {
+            buf.push(' ');
+            buf.push_str(&feature.quote_and_escape());
+        }
+        self.execute(&buf)
+    }
+
+    fn send_protocol_all(&mut self) -> MpdResult<()> {
+        if self.version() < Version::new(0, 24, 0) {
+            Err(MpdError::UnsupportedMpdVersion("protocol can be used since MPD 0.24.0"))
+        } else {
+            self.execute("protocol all")
+        }
+    }
+
+    fn send_protocol_clear(&mut self) -> MpdResult<()> {
+        if self.version() < Version::new(0, 24, 0) {
+            Err(MpdError::UnsupportedMpdVersion("protocol can be used since MPD 0.24.0"))
+        } else {
+            self.execute("protocol clear")
+        }
+    }
+
+    fn send_url_handlers(&mut self) -> MpdResult<()> {
+        self.execute("urlhandlers")
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, strum::IntoStaticStr, strum::AsRefStr)]
@@ -1143,7 +1598,8 @@ pub enum StringNormalizationFeature {
     StripDiacritics,
 }
 
-pub(crate) trait StrExt {
+/// Escaping of arguments sent to MPD, for code which builds commands by hand
+pub trait StrExt {
     fn escape_filter(self) -> String;
     fn quote_and_escape(self) -> String;
 }
@@ -247,27 +247,33 @@ impl<T: SocketClient> ProtoClient for T {
             }
         }?;
 
-        let mut line = String::from_utf8_lossy_as_owned(buf);
+        parse_line(buf, bytes_read)
+    }
+}
 
-        if bytes_read == 0 {
-            log::error!("Got an empty line in MPD's response");
-            return Err(MpdError::ValueExpected(
-                "Expected value when reading MPD's response but the stream reached EOF".to_string(),
-            ));
-        }
+/// Turns a single line read from MPD, including the trailing new line, into
+/// [`MpdLine`]. Shared between the blocking and the async client.
+pub(crate) fn parse_line(buf: Vec<u8>, bytes_read: usize) -> Result<MpdLine, MpdError> {
+    let mut line = String::from_utf8_lossy_as_owned(buf);
 
-        if line.starts_with("OK") || line.starts_with("list_OK") {
-            log::trace!(line = line.as_str().trim(); "Read MPD line OK");
-            return Ok(MpdLine::Ok);
-        }
-        if line.starts_with("ACK") {
-            log::debug!("Read MPD line with error: '{}'", line.as_str().trim());
-            return Err(MpdError::Mpd(MpdFailureResponse::from_str(&line)?));
-        }
-        line.pop(); // pop the new line
-        log::trace!(line = line.as_str().trim(); "Read MPD line");
-        Ok(MpdLine::Value(line))
+    if bytes_read == 0 {
+        log::error!("Got an empty line in MPD's response");
+        return Err(MpdError::ValueExpected(
+            "Expected value when reading MPD's response but the stream reached EOF".to_string(),
+        ));
+    }
+
+    if line.starts_with("OK") || line.starts_with("list_OK") {
+        log::trace!(line = line.as_str().trim(); "Read MPD line OK");
+        return Ok(MpdLine::Ok);
+    }
+    if line.starts_with("ACK") {
+        log::debug!("Read MPD line with error: '{}'", line.as_str().trim());
+        return Err(MpdError::Mpd(MpdFailureResponse::from_str(&line)?));
     }
+    line.pop(); // pop the new line
+    log::trace!(line = line.as_str().trim(); "Read MPD line");
+    Ok(MpdLine::Value(line))
 }
 
 #[cfg(test)]
@@ -0,0 +1,439 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener},
+    path::Path,
+    sync::{Arc, Mutex, mpsc::Sender},
+};
+
+use anyhow::{Context, bail};
+
+use crate::server::{self, Handler, Msg, lock};
+
+const HEADER: &str = "# rmpc-mpd protocol recording\n";
+
+/// Records everything sent to and received from MPD so the session can later
+/// be replayed by [`ReplayServer`]. Each record is written as a header line
+/// `<direction> <length> <connection>` followed by the raw data, `>` is data
+/// sent to MPD and `<` data received from it. Passwords are redacted.
+#[derive(Clone)]
+pub struct Recorder {
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+    connection: String,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Recorder {{ connection: {:?} }}", self.connection)
+    }
+}
+
+impl Recorder {
+    pub fn new(mut out: impl Write + Send + 'static) -> std::io::Result<Self> {
+        out.write_all(HEADER.as_bytes())?;
+        Ok(Self { out: Arc::new(Mutex::new(Box::new(out))), connection: String::new() })
+    }
+
+    /// Creates a recorder which writes to the file at the given path, the
+    /// file is truncated if it already exists
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::new(std::fs::File::create(path)?)
+    }
+
+    /// Recorder writing to the same output, tagging the records with the
+    /// given connection name
+    pub(crate) fn connection(&self, name: &str) -> Self {
+        Self { out: Arc::clone(&self.out), connection: name.replace(char::is_whitespace, "_") }
+    }
+
+    pub(crate) fn sent(&self, bytes: &[u8]) {
+        let redacted = String::from_utf8_lossy(bytes)
+            .split_inclusive('\n')
+            .map(|line| if line.starts_with("password ") { "password \"***\"\n" } else { line })
+            .collect::<String>();
+        self.write_record('>', redacted.as_bytes());
+    }
+
+    pub(crate) fn received(&self, bytes: &[u8]) {
+        self.write_record('<', bytes);
+    }
+
+    fn write_record(&self, direction: char, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        let mut record = format!("{direction} {} {}\n", bytes.len(), self.connection).into_bytes();
+        record.extend_from_slice(bytes);
+        record.push(b'\n');
+
+        let mut out = lock(&self.out);
+        if let Err(err) = out.write_all(&record).and_then(|()| out.flush()) {
+            log::warn!(err:?; "Failed to write protocol recording");
+        }
+    }
+}
+
+/// Reader which passes everything read from the inner reader to the recorder
+pub(crate) struct RecordingReader<R> {
+    inner: R,
+    recorder: Option<Recorder>,
+}
+
+impl<R> RecordingReader<R> {
+    pub(crate) fn new(inner: R, recorder: Option<Recorder>) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl<R: Read> Read for RecordingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(recorder) = &self.recorder {
+            recorder.received(&buf[..read]);
+        }
+        Ok(read)
+    }
+}
+
+/// Responses parsed from a recording made by [`Recorder`], keyed by the
+/// request which produced them
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Recording {
+    handshake: String,
+    responses: HashMap<String, VecDeque<Vec<u8>>>,
+}
+
+#[derive(Default)]
+struct ConnectionState {
+    handshake: Vec<u8>,
+    sent: String,
+    current: Option<(String, Vec<u8>)>,
+}
+
+impl Recording {
+    pub fn read(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .with_context(|| format!("Failed to read recording '{}'", path.display()))?;
+        Self::parse(&data)
+    }
+
+    pub fn parse(mut data: &[u8]) -> anyhow::Result<Self> {
+        data = data.strip_prefix(HEADER.as_bytes()).context("Not a protocol recording")?;
+
+        let mut result = Self::default();
+        let mut connections: HashMap<String, ConnectionState> = HashMap::new();
+        while !data.is_empty() {
+            let header_end =
+                data.iter().position(|b| *b == b'\n').context("Unterminated record header")?;
+            let header = std::str::from_utf8(&data[..header_end])?;
+            let mut parts = header.splitn(3, ' ');
+            let (Some(direction), Some(len), connection) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                bail!("Invalid record header '{header}'");
+            };
+            let len: usize =
+                len.parse().with_context(|| format!("Invalid record header '{header}'"))?;
+            let body = data
+                .get(header_end + 1..header_end + 1 + len)
+                .with_context(|| format!("Record '{header}' is truncated"))?;
+            data = data.get(header_end + 2 + len..).unwrap_or_default();
+
+            let state = connections.entry(connection.unwrap_or_default().to_owned()).or_default();
+            match direction {
+                ">" => {
+                    if let Some((request, response)) = state.current.take() {
+                        result.push(request, response);
+                    }
+                    state.sent.push_str(&String::from_utf8_lossy(body));
+                }
+                "<" if state.sent.is_empty() => match &mut state.current {
+                    Some((_, response)) => response.extend_from_slice(body),
+                    None => state.handshake.extend_from_slice(body),
+                },
+                "<" => {
+                    let mut requests = split_requests(&std::mem::take(&mut state.sent));
+                    let last = requests.pop().unwrap_or_default();
+                    for request in requests {
+                        result.push(request, Vec::new());
+                    }
+                    state.current = Some((last, body.to_vec()));
+                }
+                _ => bail!("Invalid record direction in '{header}'"),
+            }
+        }
+
+        for state in connections.into_values() {
+            if let Some((request, response)) = state.current {
+                result.push(request, response);
+            }
+            if result.handshake.is_empty() {
+                let handshake = String::from_utf8_lossy(&state.handshake);
+                if let Some(line) = handshake.lines().next() {
+                    result.handshake = format!("{line}\n");
+                }
+            }
+        }
+
+        if result.handshake.is_empty() {
+            bail!("Recording does not contain the MPD handshake");
+        }
+
+        Ok(result)
+    }
+
+    fn push(&mut self, request: String, response: Vec<u8>) {
+        let is_idle = request.starts_with("idle");
+        if request == "noidle\n"
+            || response.is_empty()
+            || (is_idle && !response.windows(8).any(|w| w == b"changed:"))
+        {
+            return;
+        }
+        self.responses.entry(request).or_default().push_back(response);
+    }
+
+    /// Next recorded response to the request. The last response is repeated
+    /// once all of them were used, except for idle which then never responds.
+    fn respond(&mut self, request: &str) -> Option<Vec<u8>> {
+        let responses = self.responses.get_mut(request)?;
+        if responses.len() > 1 || request.starts_with("idle") {
+            responses.pop_front()
+        } else {
+            responses.front().cloned()
+        }
+    }
+
+    /// Writes the next recorded response to the request or an unknown
+    /// command error when there is none
+    fn respond_or_fail(&mut self, request: &str, out: &mut Vec<u8>) {
+        if let Some(response) = self.respond(request) {
+            out.extend_from_slice(&response);
+        } else {
+            let command = request.split_whitespace().next().unwrap_or_default();
+            out.extend_from_slice(
+                format!("ACK [5@0] {{{command}}} No recorded response\n").as_bytes(),
+            );
+        }
+    }
+}
+
+/// Splits the data sent to MPD into separate requests, a whole command list is
+/// a single request
+fn split_requests(data: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut command_list: Option<String> = None;
+    for line in data.split_inclusive('\n') {
+        match &mut command_list {
+            Some(list) => {
+                list.push_str(line);
+                if line.trim_end() == "command_list_end" {
+                    result.push(std::mem::take(list));
+                    command_list = None;
+                }
+            }
+            None if line.starts_with("command_list_") => command_list = Some(line.to_owned()),
+            None => result.push(line.to_owned()),
+        }
+    }
+    result.extend(command_list);
+    result
+}
+
+/// Fake MPD server listening on localhost which responds with the responses
+/// from a [`Recording`]. Requests which were not recorded fail with an ACK,
+/// passwords are accepted when the recording contains a successful login. The
+/// server runs until the process exits.
+#[derive(Debug)]
+pub struct ReplayServer {
+    addr: SocketAddr,
+}
+
+impl ReplayServer {
+    pub fn start(recording: Recording) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let recording = Arc::new(Mutex::new(recording));
+        server::serve("mpd_replay", listener, recording, Arc::default())?;
+
+        Ok(Self { addr })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Handler for Mutex<Recording> {
+    type Session = ();
+
+    fn connect(&self, _tx: Sender<Msg>) {}
+
+    fn disconnect(&self, (): &()) {}
+
+    fn greeting(&self) -> String {
+        lock(self).handshake.clone()
+    }
+
+    fn command(&self, (): &mut (), line: &str, out: &mut Vec<u8>) {
+        if line.starts_with("password ") {
+            lock(self).respond_or_fail("password \"***\"\n", out);
+        } else {
+            lock(self).respond_or_fail(&format!("{line}\n"), out);
+        }
+    }
+
+    fn command_list(&self, (): &mut (), list_ok: bool, lines: &[String], out: &mut Vec<u8>) {
+        let mut request =
+            if list_ok { "command_list_ok_begin\n" } else { "command_list_begin\n" }.to_owned();
+        for line in lines {
+            request.push_str(line);
+            request.push('\n');
+        }
+        request.push_str("command_list_end\n");
+        lock(self).respond_or_fail(&request, out);
+    }
+
+    fn idle(&self, (): &mut (), line: &str, out: &mut Vec<u8>) -> bool {
+        // Once nothing more was recorded idle waits until the client cancels it
+        match lock(self).respond(&format!("{line}\n")) {
+            Some(response) => {
+                out.extend_from_slice(&response);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use super::{Recorder, Recording, ReplayServer};
+    use crate::{
+        address::MpdAddress,
+        client::Client,
+        commands::{IdleEvent, status::State},
+        errors::MpdError,
+        mpd_client::MpdClient,
+        proto_client::{ProtoClient, SocketClient},
+    };
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn recording() -> Vec<u8> {
+        let buf = SharedBuf::default();
+        let recorder = Recorder::new(buf.clone()).unwrap();
+        let command = recorder.connection("command");
+        let idle = recorder.connection("idle");
+
+        command.received(b"OK MPD 0.23.5\n");
+        idle.received(b"OK MPD 0.23.5\n");
+        command.sent(b"password \"secret\"\n");
+        command.received(b"OK\n");
+        command.sent(b"binarylimit 262144\n");
+        command.received(b"OK\n");
+        idle.sent(b"idle\n");
+        command.sent(b"commands\n");
+        command.received(b"command: status\n");
+        command.received(b"OK\n");
+        idle.received(b"changed: player\nOK\n");
+        command.sent(b"status\n");
+        command.received(b"state: play\nOK\n");
+        command.sent(b"command_list_ok_begin\n");
+        command.sent(b"status\n");
+        command.sent(b"command_list_end\n");
+        command.received(b"state: pause\nlist_OK\nOK\n");
+        command.sent(b"status\n");
+        command.received(b"state: stop\nOK\n");
+
+        buf.0.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn redacts_password() {
+        let data = String::from_utf8(recording()).unwrap();
+
+        assert!(!data.contains("secret"));
+        assert!(data.contains("> 15 command\npassword \"***\"\n"));
+    }
+
+    #[test]
+    fn parses_responses_by_request() {
+        let mut recording = Recording::parse(&recording()).unwrap();
+
+        assert_eq!(recording.handshake, "OK MPD 0.23.5\n");
+        assert_eq!(recording.respond("commands\n").unwrap(), b"command: status\nOK\n");
+        assert_eq!(
+            recording.respond("command_list_ok_begin\nstatus\ncommand_list_end\n").unwrap(),
+            b"state: pause\nlist_OK\nOK\n"
+        );
+        assert_eq!(recording.respond("status\n").unwrap(), b"state: play\nOK\n");
+        assert_eq!(recording.respond("status\n").unwrap(), b"state: stop\nOK\n");
+        assert_eq!(recording.respond("status\n").unwrap(), b"state: stop\nOK\n");
+        assert_eq!(recording.respond("idle\n").unwrap(), b"changed: player\nOK\n");
+        assert_eq!(recording.respond("idle\n"), None);
+    }
+
+    #[test]
+    fn replays_recording_to_client() {
+        let server = ReplayServer::start(Recording::parse(&recording()).unwrap()).unwrap();
+
+        let mut client = Client::init(
+            MpdAddress::IpAndPort(server.addr().to_string()),
+            Some("other".to_owned().into()),
+            "test",
+            None,
+            false,
+            false,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(SocketClient::version(&client).to_string(), "0.23.5");
+        assert!(client.supported_commands.contains("status"));
+        assert_eq!(client.get_status().unwrap().state, State::Play);
+        assert!(matches!(
+            client.execute("outputs").and_then(|()| client.read_ok()),
+            Err(MpdError::Mpd(_))
+        ));
+    }
+
+    #[test]
+    fn replays_idle_until_recording_is_exhausted() {
+        let server = ReplayServer::start(Recording::parse(&recording()).unwrap()).unwrap();
+        let mut client = Client::init(
+            MpdAddress::IpAndPort(server.addr().to_string()),
+            None,
+            "test",
+            None,
+            false,
+            false,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(client.idle(None).unwrap(), [IdleEvent::Player]);
+        client.enter_idle(None).unwrap();
+        client.noidle().unwrap();
+        assert_eq!(client.get_status().unwrap().state, State::Play);
+    }
+}
@@ -0,0 +1,226 @@
+//! Connection handling shared by the servers which speak the MPD protocol to
+//! clients in tests, ie. the fake server and the replay server. The protocol
+//! framing of command lists and idle is done here, the responses come from a
+//! [`Handler`].
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    os::unix::net::{UnixListener, UnixStream},
+    sync::{
+        Arc,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+};
+
+pub(crate) enum Msg {
+    Line(String),
+    /// Idle events or messages are pending for the connection, only sent by
+    /// the fake server
+    #[cfg_attr(not(feature = "fake-server"), allow(dead_code))]
+    Wake,
+    Closed,
+}
+
+/// Responds to the requests of the connected clients
+pub(crate) trait Handler: Send + Sync + 'static {
+    /// Per connection state
+    type Session: Send;
+
+    /// Registers a new connection, [`Msg::Wake`] can be sent to `tx` when an
+    /// idle response might be ready
+    fn connect(&self, tx: Sender<Msg>) -> Self::Session;
+
+    fn disconnect(&self, session: &Self::Session);
+
+    /// First line sent to a client after it connected
+    fn greeting(&self) -> String;
+
+    fn command(&self, session: &mut Self::Session, line: &str, out: &mut Vec<u8>);
+
+    /// Executes the commands between `command_list_begin` or
+    /// `command_list_ok_begin` and `command_list_end`
+    fn command_list(
+        &self,
+        session: &mut Self::Session,
+        list_ok: bool,
+        lines: &[String],
+        out: &mut Vec<u8>,
+    );
+
+    /// Writes the response to the idle command when it is ready and returns
+    /// whether it was. Called again when the connection is woken up and when
+    /// the idle is cancelled by `noidle`, in which case `OK` is sent if there
+    /// is still no response.
+    fn idle(&self, session: &mut Self::Session, line: &str, out: &mut Vec<u8>) -> bool;
+}
+
+/// Listener the server accepts connections from
+pub(crate) trait Listener: Send + 'static {
+    type Stream: Read + Write + Send + 'static;
+
+    /// Accepts the next connection and returns its read and write halves
+    fn accept_split(&self) -> std::io::Result<(Self::Stream, Self::Stream)>;
+}
+
+impl Listener for TcpListener {
+    type Stream = TcpStream;
+
+    fn accept_split(&self) -> std::io::Result<(TcpStream, TcpStream)> {
+        let (stream, _) = self.accept()?;
+        Ok((stream.try_clone()?, stream))
+    }
+}
+
+impl Listener for UnixListener {
+    type Stream = UnixStream;
+
+    fn accept_split(&self) -> std::io::Result<(UnixStream, UnixStream)> {
+        let (stream, _) = self.accept()?;
+        Ok((stream.try_clone()?, stream))
+    }
+}
+
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Accepts connections on a new thread until `stopped` is set. Threads are
+/// named after `name`.
+pub(crate) fn serve<H: Handler>(
+    name: &'static str,
+    listener: impl Listener,
+    handler: Arc<H>,
+    stopped: Arc<AtomicBool>,
+) -> std::io::Result<()> {
+    std::thread::Builder::new().name(name.to_string()).spawn(move || {
+        loop {
+            let accepted = listener.accept_split();
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            match accepted {
+                Ok((read, write)) => spawn_connection(name, read, write, &handler),
+                Err(err) => log::error!(err:?, server = name; "Failed to accept connection"),
+            }
+        }
+    })?;
+
+    Ok(())
+}
+
+fn spawn_connection<H: Handler>(
+    name: &'static str,
+    read: impl Read + Send + 'static,
+    write: impl Write + Send + 'static,
+    handler: &Arc<H>,
+) {
+    let (tx, rx) = channel();
+    let mut session = handler.connect(tx.clone());
+
+    let reader = std::thread::Builder::new().name(format!("{name}_read")).spawn(move || {
+        let mut read = BufReader::new(read);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match read.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf).trim_end_matches('\n').to_owned();
+                    if tx.send(Msg::Line(line)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = tx.send(Msg::Closed);
+    });
+    if let Err(err) = reader {
+        log::error!(err:?, server = name; "Failed to spawn reader thread");
+        handler.disconnect(&session);
+        return;
+    }
+
+    let handler = Arc::clone(handler);
+    let result = std::thread::Builder::new().name(format!("{name}_conn")).spawn(move || {
+        if let Err(err) = run_connection(&*handler, &mut session, &rx, write) {
+            log::debug!(err:?, server = name; "Connection closed");
+        }
+        handler.disconnect(&session);
+    });
+    if let Err(err) = result {
+        log::error!(err:?, server = name; "Failed to spawn connection thread");
+    }
+}
+
+fn run_connection<H: Handler>(
+    handler: &H,
+    session: &mut H::Session,
+    rx: &Receiver<Msg>,
+    mut write: impl Write,
+) -> std::io::Result<()> {
+    write.write_all(handler.greeting().as_bytes())?;
+
+    let mut command_list: Option<(bool, Vec<String>)> = None;
+    let mut idle: Option<String> = None;
+
+    while let Ok(msg) = rx.recv() {
+        let mut out = Vec::new();
+        let line = match msg {
+            Msg::Line(line) => line,
+            Msg::Wake => {
+                if let Some(idle_line) = &idle
+                    && handler.idle(session, idle_line, &mut out)
+                {
+                    write.write_all(&out)?;
+                    idle = None;
+                }
+                continue;
+            }
+            Msg::Closed => return Ok(()),
+        };
+
+        if let Some(idle_line) = idle.take() {
+            if line != "noidle" {
+                // MPD closes the connection when anything else is sent while idle
+                return Ok(());
+            }
+            if !handler.idle(session, &idle_line, &mut out) {
+                out.extend_from_slice(b"OK\n");
+            }
+            write.write_all(&out)?;
+            continue;
+        }
+
+        match &mut command_list {
+            Some((list_ok, lines)) if line == "command_list_end" => {
+                handler.command_list(session, *list_ok, lines, &mut out);
+                command_list = None;
+            }
+            Some((_, lines)) => {
+                lines.push(line);
+                continue;
+            }
+            None if line == "command_list_begin" || line == "command_list_ok_begin" => {
+                command_list = Some((line == "command_list_ok_begin", Vec::new()));
+                continue;
+            }
+            None if line == "close" => return Ok(()),
+            // MPD ignores noidle outside of idle
+            None if line == "noidle" => continue,
+            None if line == "idle" || line.starts_with("idle ") => {
+                if !handler.idle(session, &line, &mut out) {
+                    idle = Some(line);
+                }
+            }
+            None => handler.command(session, &line, &mut out),
+        }
+        write.write_all(&out)?;
+    }
+
+    Ok(())
+}
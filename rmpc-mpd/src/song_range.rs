@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+/// Portion of a song in the queue which should be played. Missing start means
+/// the beginning of the song and missing end means the end of the song.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SongRange {
+    pub start: Option<Duration>,
+    pub end: Option<Duration>,
+}
+
+impl SongRange {
+    #[must_use]
+    pub fn new(start: Option<Duration>, end: Option<Duration>) -> Self {
+        Self { start, end }
+    }
+
+    /// Whether the whole song is played
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.start.is_none_or(|start| start.is_zero()) && self.end.is_none()
+    }
+
+    #[must_use]
+    pub fn as_mpd_str(&self) -> String {
+        let start = self.start.map(|v| v.as_secs_f64().to_string()).unwrap_or_default();
+        let end = self.end.map(|v| v.as_secs_f64().to_string()).unwrap_or_default();
+        format!("{start}:{end}")
+    }
+}
+
+/// Parses the range in the format MPD reports it in song metadata, ie.
+/// `10.000-20.000` or `10.000-`
+impl std::str::FromStr for SongRange {
+    type Err = anyhow::Error;
+
+    fn from_str(v: &str) -> anyhow::Result<Self> {
+        let (start, end) = v.split_once('-').ok_or(anyhow::anyhow!("Invalid song range: '{v}'"))?;
+        let parse = |value: &str| -> anyhow::Result<Option<Duration>> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(Duration::try_from_secs_f64(value.parse()?)?))
+        };
+
+        Ok(Self { start: parse(start)?, end: parse(end)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_song_range_fromstr() {
+        assert_eq!(
+            "10.000-20.500".parse::<SongRange>().unwrap(),
+            SongRange::new(Some(Duration::from_secs(10)), Some(Duration::from_millis(20500)))
+        );
+        assert_eq!(
+            "10.000-".parse::<SongRange>().unwrap(),
+            SongRange::new(Some(Duration::from_secs(10)), None)
+        );
+        assert!("10.000".parse::<SongRange>().is_err());
+    }
+
+    #[test]
+    fn test_song_range_as_mpd_str() {
+        assert_eq!(SongRange::default().as_mpd_str(), ":");
+        assert_eq!(SongRange::new(Some(Duration::from_millis(1500)), None).as_mpd_str(), "1.5:");
+        assert_eq!(
+            SongRange::new(Some(Duration::from_secs(1)), Some(Duration::from_secs(90)))
+                .as_mpd_str(),
+            "1:90"
+        );
+    }
+}
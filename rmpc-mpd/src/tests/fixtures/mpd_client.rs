@@ -14,16 +14,22 @@ use crate::{
         LsInfo,
         Playlist,
         Song,
+        Stats,
         Status,
         Update,
         Volume,
+        comments::Comments,
+        count::{Count, GroupedCounts},
+        fingerprint::Fingerprint,
         list::{MpdGroupedList, MpdList},
         list_all::ListAll,
         list_playlist::FileList,
         metadata_tag::MetadataTagExt,
         mpd_config::MpdConfig,
+        plchanges::{PosIds, QueueDiff, QueueVersion},
+        replay_gain::{ReplayGainMode, ReplayGainStatus},
         status::OnOffOneshot,
-        stickers::Sticker,
+        stickers::{Sticker, StickerType},
         volume::Bound,
     },
     errors::MpdError,
@@ -38,6 +44,7 @@ use crate::{
     proto_client::SocketClient,
     queue_position::QueuePosition,
     single_or_range::SingleOrRange,
+    song_range::SongRange,
 };
 
 #[fixture]
@@ -64,6 +71,7 @@ pub fn client() -> TestMpdClient {
                 duration: Some(Duration::from_secs(i.into())),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             })
         })
         .collect();
@@ -180,6 +188,24 @@ impl MpdClient for TestMpdClient {
         Ok(())
     }
 
+    fn mixramp_db(&mut self, db: f32) -> MpdResult<()> {
+        self.status.mixrampdb = Some(db.to_string());
+        Ok(())
+    }
+
+    fn mixramp_delay(&mut self, seconds: f32) -> MpdResult<()> {
+        self.status.mixrampdelay = Some(seconds.to_string());
+        Ok(())
+    }
+
+    fn replay_gain_mode(&mut self, _mode: ReplayGainMode) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn replay_gain_status(&mut self) -> MpdResult<ReplayGainStatus> {
+        todo!("Not yet implemented")
+    }
+
     fn get_current_song(&mut self) -> MpdResult<Option<Song>> {
         Ok(self.current_song_idx.and_then(|idx| self.songs.get(idx).cloned()))
     }
@@ -188,6 +214,10 @@ impl MpdClient for TestMpdClient {
         Ok(self.status.clone())
     }
 
+    fn stats(&mut self) -> MpdResult<Stats> {
+        todo!("Not yet implemented")
+    }
+
     fn pause_toggle(&mut self) -> MpdResult<()> {
         use crate::commands::State as S;
         self.status.state = match self.status.state {
@@ -257,6 +287,14 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn seek(&mut self, _pos: usize, _time: Duration) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn seek_id(&mut self, _id: u32, _time: Duration) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn repeat(&mut self, enabled: bool) -> MpdResult<()> {
         self.status.repeat = enabled;
         Ok(())
@@ -289,10 +327,22 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn list_neighbors(&mut self) -> MpdResult<crate::commands::Neighbors> {
+        todo!("Not yet implemented")
+    }
+
     fn add(&mut self, _path: &str, _position: Option<QueuePosition>) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
 
+    fn add_id(&mut self, _path: &str, _position: Option<QueuePosition>) -> MpdResult<u32> {
+        todo!("Not yet implemented")
+    }
+
+    fn range_id(&mut self, _id: u32, _range: SongRange) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn clear(&mut self) -> MpdResult<()> {
         self.songs.clear();
         self.current_song_idx = None;
@@ -324,6 +374,22 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn plchanges(&mut self, _version: u32) -> MpdResult<Vec<Song>> {
+        todo!("Not yet implemented")
+    }
+
+    fn plchanges_posid(&mut self, _version: u32) -> MpdResult<PosIds> {
+        todo!("Not yet implemented")
+    }
+
+    fn queue_diff(
+        &mut self,
+        _from: &QueueVersion,
+        _known_positions: &HashMap<u32, usize>,
+    ) -> MpdResult<Option<QueueDiff>> {
+        todo!("Not yet implemented")
+    }
+
     /// `FilterKind` not implemented, everything is treated as Contains
     fn find(&mut self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
         Ok(self
@@ -429,6 +495,14 @@ impl MpdClient for TestMpdClient {
             .collect())
     }
 
+    fn playlist_find(&mut self, _filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
+        todo!("Not yet implemented")
+    }
+
+    fn playlist_search(&mut self, _filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
+        todo!("Not yet implemented")
+    }
+
     fn move_in_queue(&mut self, _from: SingleOrRange, _to: QueuePosition) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
@@ -475,10 +549,42 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn count(&mut self, _filter: &[Filter<'_>]) -> MpdResult<Count> {
+        todo!("Not yet implemented")
+    }
+
+    fn count_grouped(
+        &mut self,
+        _group: Tag,
+        _filter: Option<&[Filter<'_>]>,
+    ) -> MpdResult<GroupedCounts> {
+        todo!("Not yet implemented")
+    }
+
+    fn search_count(&mut self, _filter: &[Filter<'_>]) -> MpdResult<Count> {
+        todo!("Not yet implemented")
+    }
+
+    fn search_count_grouped(
+        &mut self,
+        _group: Tag,
+        _filter: &[Filter<'_>],
+    ) -> MpdResult<GroupedCounts> {
+        todo!("Not yet implemented")
+    }
+
     fn shuffle(&mut self, _range: Option<SingleOrRange>) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
 
+    fn prio(&mut self, _priority: u8, _songs: &[SingleOrRange]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn prio_id(&mut self, _priority: u8, _ids: &[u32]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn add_random_songs(&mut self, _: usize, _: Option<&[Filter<'_>]>) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
@@ -507,6 +613,14 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn read_comments(&mut self, _path: &str) -> MpdResult<Comments> {
+        todo!("Not yet implemented")
+    }
+
+    fn get_fingerprint(&mut self, _path: &str) -> MpdResult<Fingerprint> {
+        todo!("Not yet implemented")
+    }
+
     fn list_playlists(&mut self) -> MpdResult<Vec<Playlist>> {
         self.playlists
             .iter()
@@ -542,6 +656,7 @@ impl MpdClient for TestMpdClient {
                         metadata: HashMap::default(),
                         last_modified: chrono::Utc::now(),
                         added: None,
+                        prio: 0,
                     })
                     .collect())
             },
@@ -610,6 +725,10 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn output_set(&mut self, _id: u32, _name: &str, _value: &str) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
     fn decoders(&mut self) -> MpdResult<crate::commands::decoders::Decoders> {
         todo!("Not yet implemented")
     }
@@ -650,6 +769,80 @@ impl MpdClient for TestMpdClient {
         todo!("Not yet implemented")
     }
 
+    fn typed_sticker(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+        _name: &str,
+    ) -> MpdResult<Option<Sticker>> {
+        todo!("Not yet implemented")
+    }
+
+    fn set_typed_sticker(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+        _name: &str,
+        _value: &str,
+    ) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn delete_typed_sticker(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+        _name: &str,
+    ) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn list_typed_stickers(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+    ) -> MpdResult<crate::commands::stickers::Stickers> {
+        todo!("Not yet implemented")
+    }
+
+    fn find_typed_stickers(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+        _name: &str,
+        _opts: StickerFindOptions,
+    ) -> MpdResult<crate::commands::stickers::StickersWithFile> {
+        todo!("Not yet implemented")
+    }
+
+    fn inc_sticker(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+        _name: &str,
+        _value: u32,
+    ) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn dec_sticker(
+        &mut self,
+        _ty: &StickerType,
+        _uri: &str,
+        _name: &str,
+        _value: u32,
+    ) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn sticker_names(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn sticker_types(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
     fn switch_to_partition(&mut self, _name: &str) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
@@ -711,6 +904,58 @@ impl MpdClient for TestMpdClient {
     fn string_normalization_clear(&mut self) -> MpdResult<()> {
         todo!("Not yet implemented")
     }
+
+    fn tag_types(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_available(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_enable(&mut self, _tags: &[String]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_disable(&mut self, _tags: &[String]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_all(&mut self) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn tag_types_clear(&mut self) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn protocol(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn protocol_available(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
+
+    fn protocol_enable(&mut self, _features: &[String]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn protocol_disable(&mut self, _features: &[String]) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn protocol_all(&mut self) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn protocol_clear(&mut self) -> MpdResult<()> {
+        todo!("Not yet implemented")
+    }
+
+    fn url_handlers(&mut self) -> MpdResult<MpdList> {
+        todo!("Not yet implemented")
+    }
 }
 
 impl SocketClient for TestMpdClient {
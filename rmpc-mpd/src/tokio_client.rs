@@ -0,0 +1,984 @@
+//! Async MPD client built on top of tokio.
+//!
+//! Commands are sent over one connection while idle runs on its own dedicated
+//! connection and is exposed as a stream of [`IdleEvent`]s. Requests therefore
+//! never have to interrupt idle with `noidle` and can be issued from any number
+//! of tasks at once. Responses are parsed by the same [`FromMpd`]
+//! implementations the blocking [`crate::client::Client`] uses.
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::BufRead,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+use log::debug;
+use rmpc_shared::version::Version;
+use socket2::{SockRef, TcpKeepalive};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream},
+    net::{TcpStream, UnixStream},
+    sync::{Mutex, MutexGuard, mpsc, watch},
+    task::JoinHandle,
+};
+
+use crate::{
+    address::{MpdAddress, MpdPassword},
+    commands::{
+        IdleEvent,
+        Song,
+        Status,
+        Volume,
+        list::MpdList,
+        messages::Messages,
+        plchanges::{PosIds, QueueDiff, QueueVersion},
+        replay_gain::{ReplayGainMode, ReplayGainStatus},
+        status::OnOffOneshot,
+        stickers::{Sticker, StickerType},
+    },
+    errors::{ErrorCode, MpdError, MpdFailureResponse},
+    filter::Filter,
+    from_mpd::{FromMpd, split_line},
+    mpd_client::{MpdCommand, StrExt, ValueChange},
+    proto_client::{BinaryMpdResponse, MpdLine, SocketClient, parse_line},
+};
+
+type MpdResult<T> = Result<T, MpdError>;
+type ChannelMessages = Vec<(String, Vec<String>)>;
+
+/// MPD drops clients which did not send anything for `connection_timeout`
+/// seconds, connections unused for longer than this are checked with `ping`
+/// before being used.
+const STALE_CONNECTION_AFTER: Duration = Duration::from_secs(10);
+
+trait AsyncStream: AsyncRead + AsyncWrite + Send + Unpin {}
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> AsyncStream for T {}
+
+/// Where and how connections to MPD are opened
+#[derive(Debug, Clone)]
+struct Endpoint {
+    addr: MpdAddress,
+    password: Option<MpdPassword>,
+    enable_keepalive: bool,
+}
+
+impl Endpoint {
+    async fn open(&self) -> MpdResult<Connection> {
+        let stream: Box<dyn AsyncStream> = match &self.addr {
+            MpdAddress::IpAndPort(addr) => {
+                let stream = TcpStream::connect(addr).await?;
+                if self.enable_keepalive {
+                    SockRef::from(&stream).set_tcp_keepalive(
+                        &TcpKeepalive::new()
+                            .with_time(Duration::from_secs(30))
+                            .with_interval(Duration::from_secs(10))
+                            .with_retries(3),
+                    )?;
+                }
+                Box::new(stream)
+            }
+            MpdAddress::SocketPath(addr) => Box::new(UnixStream::connect(addr).await?),
+            #[cfg(target_os = "linux")]
+            MpdAddress::AbstractSocket(addr) => {
+                // tokio cannot connect to abstract sockets, std does but blocks
+                let name = addr.clone();
+                let stream = tokio::task::spawn_blocking(move || {
+                    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                    let stream = std::os::unix::net::UnixStream::connect_addr(&addr)?;
+                    stream.set_nonblocking(true)?;
+                    Ok::<_, std::io::Error>(stream)
+                })
+                .await
+                .map_err(|err| MpdError::Generic(format!("Failed to connect to MPD: {err}")))??;
+                Box::new(UnixStream::from_std(stream)?)
+            }
+            #[cfg(not(target_os = "linux"))]
+            MpdAddress::AbstractSocket(_addr) => {
+                return Err(MpdError::Generic(
+                    "Abstract socket only supported on Linux".to_string(),
+                ));
+            }
+        };
+
+        let conn = Connection::handshake(stream, self.password.as_ref()).await?;
+        debug!(addr:? = self.addr, version = conn.version.to_string().as_str(); "Async MPD client connected");
+
+        Ok(conn)
+    }
+}
+
+struct Connection {
+    stream: BufStream<Box<dyn AsyncStream>>,
+    version: Version,
+    last_used: Instant,
+    /// A command was sent but its response was not read to the end, either
+    /// because it failed to parse or because the request future was dropped.
+    /// Such connection has to be reopened before it can be used again.
+    dirty: bool,
+}
+
+impl Connection {
+    async fn handshake(
+        stream: Box<dyn AsyncStream>,
+        password: Option<&MpdPassword>,
+    ) -> MpdResult<Self> {
+        let mut stream = BufStream::new(stream);
+        let mut buf = String::new();
+        stream.read_line(&mut buf).await?;
+        if !buf.starts_with("OK") {
+            return Err(MpdError::Generic(format!("Handshake validation failed. '{buf}'")));
+        }
+        let Some(version): Option<Version> =
+            buf.strip_prefix("OK MPD ").and_then(|v| v.trim().parse().ok())
+        else {
+            return Err(MpdError::Generic(format!(
+                "Handshake validation failed. Cannot parse version from '{buf}'"
+            )));
+        };
+
+        let mut conn = Self { stream, version, last_used: Instant::now(), dirty: false };
+
+        if let Some(MpdPassword(password)) = password {
+            debug!("Used password auth to MPD");
+            conn.write_command(&format!("password {}", password.as_str().quote_and_escape()))
+                .await?;
+            conn.read_ok().await?;
+        }
+
+        // 2^18 seems to be max limit supported by MPD and higher values dont
+        // have any effect
+        conn.write_command(&format!("binarylimit {}", 2u64.pow(18))).await?;
+        conn.read_ok().await?;
+
+        Ok(conn)
+    }
+
+    async fn write_command(&mut self, command: &str) -> MpdResult<()> {
+        log::trace!(command; "Executing MPD command");
+        self.write_all([command, "\n"].concat().as_bytes()).await
+    }
+
+    /// Writes commands which are already terminated by a new line
+    async fn write_all(&mut self, commands: &[u8]) -> MpdResult<()> {
+        self.dirty = true;
+        self.last_used = Instant::now();
+        self.stream.write_all(commands).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    async fn read_line(&mut self) -> MpdResult<MpdLine> {
+        let mut buf = Vec::new();
+        let bytes_read = self.stream.read_until(b'\n', &mut buf).await?;
+        let line = parse_line(buf, bytes_read);
+        // Both OK and ACK terminate the response
+        if matches!(line, Ok(MpdLine::Ok) | Err(MpdError::Mpd(_))) {
+            self.dirty = false;
+        }
+        line
+    }
+
+    async fn read_ok(&mut self) -> MpdResult<()> {
+        match self.read_line().await? {
+            MpdLine::Ok => Ok(()),
+            MpdLine::Value(val) => Err(MpdError::Generic(format!("Expected 'OK' but got '{val}'"))),
+        }
+    }
+
+    /// Reads the whole response, `None` when MPD sent no values at all
+    async fn read_response<V>(&mut self) -> MpdResult<Option<V>>
+    where
+        V: FromMpd + Default,
+    {
+        let mut result = V::default();
+        let mut found_any = false;
+        loop {
+            match self.read_line().await? {
+                MpdLine::Ok => return Ok(found_any.then_some(result)),
+                MpdLine::Value(val) => {
+                    found_any = true;
+                    result.next(val)?;
+                }
+            }
+        }
+    }
+
+    async fn read_bin_chunk(
+        &mut self,
+        binary_buf: &mut Vec<u8>,
+    ) -> MpdResult<Option<BinaryMpdResponse>> {
+        let mut result = BinaryMpdResponse::default();
+        loop {
+            match self.read_line().await? {
+                MpdLine::Ok => {
+                    log::warn!("Expected binary data but got 'OK'");
+                    return Ok(None);
+                }
+                MpdLine::Value(val) => {
+                    let (key, value) = split_line(val)?;
+                    match key.to_lowercase().as_ref() {
+                        "size" => result.size_total = value.parse()?,
+                        "type" => result.mime_type = Some(value),
+                        "binary" => {
+                            result.bytes_read = value.parse()?;
+                            break;
+                        }
+                        key => {
+                            return Err(MpdError::Generic(format!(
+                                "Unexpected key when parsing binary response: '{key}'"
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+
+        (&mut self.stream).take(result.bytes_read).read_to_end(binary_buf).await?;
+        // MPD prints an empty new line at the end of binary response
+        self.stream.read_line(&mut String::new()).await?;
+        self.read_ok().await?;
+
+        Ok(Some(result))
+    }
+}
+
+/// Collects the commands written by the [`MpdCommand`] builders, so the async
+/// client sends exactly what the blocking [`crate::client::Client`] would.
+/// Only the `send_*` builders can be used with it, it has nothing to read.
+#[derive(Debug)]
+pub struct CommandBuffer {
+    version: Version,
+    commands: Vec<u8>,
+    empty: std::io::Empty,
+}
+
+impl SocketClient for CommandBuffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.commands.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn read(&mut self) -> &mut impl BufRead {
+        &mut self.empty
+    }
+
+    fn version(&self) -> Version {
+        self.version
+    }
+
+    fn clear_read_buf(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Async client for MPD. Requests from concurrent tasks are queued and sent
+/// one after another over a single connection. The connection is reopened
+/// transparently when MPD closed it in the meantime.
+///
+/// Channels are subscribed to on the idle connection, because MPD reports
+/// messages only to the connection which is subscribed. Messages are read as
+/// soon as idle reports them and kept until [`AsyncClient::read_messages`].
+#[derive(derive_more::Debug)]
+pub struct AsyncClient {
+    endpoint: Endpoint,
+    version: Version,
+    #[debug(skip)]
+    conn: Mutex<Connection>,
+    channels: watch::Sender<BTreeSet<String>>,
+    messages: Arc<StdMutex<ChannelMessages>>,
+}
+
+impl AsyncClient {
+    pub async fn connect(
+        addr: MpdAddress,
+        password: Option<MpdPassword>,
+        enable_keepalive: bool,
+    ) -> MpdResult<Self> {
+        let endpoint = Endpoint { addr, password, enable_keepalive };
+        let conn = endpoint.open().await?;
+
+        Ok(Self::new(endpoint, conn))
+    }
+
+    fn new(endpoint: Endpoint, conn: Connection) -> Self {
+        Self {
+            endpoint,
+            version: conn.version,
+            conn: Mutex::new(conn),
+            channels: watch::Sender::new(BTreeSet::new()),
+            messages: Arc::default(),
+        }
+    }
+
+    /// Version of the MPD server reported when the client connected
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    async fn connection(&self) -> MpdResult<MutexGuard<'_, Connection>> {
+        let mut conn = self.conn.lock().await;
+
+        if !conn.dirty && conn.last_used.elapsed() > STALE_CONNECTION_AFTER {
+            let pong = match conn.write_command("ping").await {
+                Ok(()) => conn.read_ok().await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = pong {
+                debug!(err:?; "MPD connection is no longer alive");
+                conn.dirty = true;
+            }
+        }
+
+        if conn.dirty {
+            debug!(addr:? = self.endpoint.addr; "Reopening MPD connection");
+            *conn = self.endpoint.open().await?;
+        }
+
+        Ok(conn)
+    }
+
+    /// Sends a command which responds only with `OK`
+    pub async fn execute_ok(&self, command: &str) -> MpdResult<()> {
+        let mut conn = self.connection().await?;
+        conn.write_command(command).await?;
+        conn.read_ok().await
+    }
+
+    /// Sends a command and parses its response into `V`
+    pub async fn execute<V>(&self, command: &str) -> MpdResult<V>
+    where
+        V: FromMpd + Default,
+    {
+        Ok(self.execute_opt(command).await?.unwrap_or_default())
+    }
+
+    /// Sends a command and parses its response into `V`, `None` when MPD
+    /// responded without any values
+    pub async fn execute_opt<V>(&self, command: &str) -> MpdResult<Option<V>>
+    where
+        V: FromMpd + Default,
+    {
+        let mut conn = self.connection().await?;
+        conn.write_command(command).await?;
+        conn.read_response().await
+    }
+
+    /// Sends a command with a binary response, ie. `albumart` or
+    /// `readpicture`, and reads it whole. The command must not contain the
+    /// offset, it is appended for each requested chunk.
+    pub async fn execute_bin(&self, command: &str) -> MpdResult<Option<Vec<u8>>> {
+        let mut conn = self.connection().await?;
+        let mut buf = Vec::new();
+        loop {
+            conn.write_command(&format!("{command} {}", buf.len())).await?;
+            match conn.read_bin_chunk(&mut buf).await? {
+                Some(response) => {
+                    if buf.len() >= response.size_total as usize || response.bytes_read == 0 {
+                        log::trace!(len = buf.len(); "Finished reading binary response");
+                        break;
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(buf))
+    }
+
+    fn build(&self, build: impl FnOnce(&mut CommandBuffer) -> MpdResult<()>) -> MpdResult<Vec<u8>> {
+        let mut buf =
+            CommandBuffer { version: self.version, commands: Vec::new(), empty: std::io::empty() };
+        build(&mut buf)?;
+        Ok(buf.commands)
+    }
+
+    /// Sends the commands written by the [`MpdCommand`] builders, ie.
+    /// `|c| c.send_play()`, which respond only with `OK`. Command lists are
+    /// built the same way as with the blocking client.
+    pub async fn send_ok(
+        &self,
+        build: impl FnOnce(&mut CommandBuffer) -> MpdResult<()>,
+    ) -> MpdResult<()> {
+        let commands = self.build(build)?;
+        let mut conn = self.connection().await?;
+        conn.write_all(&commands).await?;
+        conn.read_ok().await
+    }
+
+    /// Sends the commands written by the [`MpdCommand`] builders and parses
+    /// the response into `V`
+    pub async fn send<V>(
+        &self,
+        build: impl FnOnce(&mut CommandBuffer) -> MpdResult<()>,
+    ) -> MpdResult<V>
+    where
+        V: FromMpd + Default,
+    {
+        Ok(self.send_opt(build).await?.unwrap_or_default())
+    }
+
+    /// Sends the commands written by the [`MpdCommand`] builders and parses
+    /// the response into `V`, `None` when MPD responded without any values
+    pub async fn send_opt<V>(
+        &self,
+        build: impl FnOnce(&mut CommandBuffer) -> MpdResult<()>,
+    ) -> MpdResult<Option<V>>
+    where
+        V: FromMpd + Default,
+    {
+        let commands = self.build(build)?;
+        let mut conn = self.connection().await?;
+        conn.write_all(&commands).await?;
+        conn.read_response().await
+    }
+
+    pub async fn status(&self) -> MpdResult<Status> {
+        self.send(|c| c.send_get_status()).await
+    }
+
+    pub async fn current_song(&self) -> MpdResult<Option<Song>> {
+        self.send_opt(|c| c.send_get_current_song()).await
+    }
+
+    pub async fn albumart(&self, uri: &str) -> MpdResult<Option<Vec<u8>>> {
+        self.execute_bin(&format!("albumart {}", uri.quote_and_escape())).await
+    }
+
+    pub async fn read_picture(&self, uri: &str) -> MpdResult<Option<Vec<u8>>> {
+        self.execute_bin(&format!("readpicture {}", uri.quote_and_escape())).await
+    }
+
+    // Playback
+    pub async fn play(&self) -> MpdResult<()> {
+        self.send_ok(|c| c.send_play()).await
+    }
+
+    pub async fn pause(&self) -> MpdResult<()> {
+        self.send_ok(|c| c.send_pause()).await
+    }
+
+    pub async fn pause_toggle(&self) -> MpdResult<()> {
+        self.send_ok(|c| c.send_pause_toggle()).await
+    }
+
+    pub async fn stop(&self) -> MpdResult<()> {
+        self.send_ok(|c| c.send_stop()).await
+    }
+
+    pub async fn next(&self) -> MpdResult<()> {
+        self.send_ok(|c| c.send_next()).await
+    }
+
+    pub async fn prev(&self) -> MpdResult<()> {
+        self.send_ok(|c| c.send_prev()).await
+    }
+
+    pub async fn seek_current(&self, value: ValueChange) -> MpdResult<()> {
+        self.send_ok(|c| c.send_seek_current(value)).await
+    }
+
+    pub async fn get_volume(&self) -> MpdResult<Volume> {
+        self.send(|c| c.send_get_volume()).await
+    }
+
+    pub async fn set_volume(&self, volume: Volume) -> MpdResult<()> {
+        self.send_ok(|c| c.send_set_volume(volume)).await
+    }
+
+    /// Sets the volume or changes it relative to the current one
+    pub async fn volume(&self, change: ValueChange) -> MpdResult<()> {
+        self.send_ok(|c| c.send_volume(change)).await
+    }
+
+    pub async fn crossfade(&self, seconds: u32) -> MpdResult<()> {
+        self.send_ok(|c| c.send_crossfade(seconds)).await
+    }
+
+    pub async fn replay_gain_mode(&self, mode: ReplayGainMode) -> MpdResult<()> {
+        self.send_ok(|c| c.send_replay_gain_mode(mode)).await
+    }
+
+    pub async fn replay_gain_status(&self) -> MpdResult<ReplayGainStatus> {
+        self.send(|c| c.send_replay_gain_status()).await
+    }
+
+    pub async fn repeat(&self, enabled: bool) -> MpdResult<()> {
+        self.send_ok(|c| c.send_repeat(enabled)).await
+    }
+
+    pub async fn random(&self, enabled: bool) -> MpdResult<()> {
+        self.send_ok(|c| c.send_random(enabled)).await
+    }
+
+    pub async fn single(&self, single: OnOffOneshot) -> MpdResult<()> {
+        self.send_ok(|c| c.send_single(single)).await
+    }
+
+    pub async fn consume(&self, consume: OnOffOneshot) -> MpdResult<()> {
+        self.send_ok(|c| c.send_consume(consume)).await
+    }
+
+    // Queue and database
+    pub async fn playlist_info(&self) -> MpdResult<Option<Vec<Song>>> {
+        self.send_opt(|c| c.send_playlist_info()).await
+    }
+
+    pub async fn playlist_id(&self, id: u32) -> MpdResult<Option<Song>> {
+        self.send_opt(|c| c.send_playlist_id(id)).await
+    }
+
+    pub async fn plchanges(&self, version: u32) -> MpdResult<Vec<Song>> {
+        self.send(|c| c.send_plchanges(version)).await
+    }
+
+    pub async fn plchanges_posid(&self, version: u32) -> MpdResult<PosIds> {
+        self.send(|c| c.send_plchanges_posid(version)).await
+    }
+
+    /// Changes made to the queue since the given version, the same as
+    /// [`crate::mpd_client::MpdClient::queue_diff`]
+    pub async fn queue_diff(
+        &self,
+        from: &QueueVersion,
+        known_positions: &HashMap<u32, usize>,
+    ) -> MpdResult<Option<QueueDiff>> {
+        let status = self.status().await?;
+        let Some(to) = status.queue_version().filter(|to| to.partition == from.partition) else {
+            return Ok(None);
+        };
+
+        let changes = self.plchanges_posid(from.version).await?.0;
+        // Songs which are new or kept their position but were still reported as changed
+        // (priority, tags of a stream, ...) need their metadata fetched again
+        let needs_metadata = changes
+            .iter()
+            .any(|change| known_positions.get(&change.id).is_none_or(|pos| *pos == change.pos));
+
+        let (songs, moved) = if needs_metadata {
+            (self.plchanges(from.version).await?, Vec::new())
+        } else {
+            (Vec::new(), changes)
+        };
+
+        Ok(Some(QueueDiff {
+            from: from.clone(),
+            to,
+            length: status.playlistlength as usize,
+            songs,
+            moved,
+        }))
+    }
+
+    pub async fn find(&self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
+        self.send(|c| c.send_find(filter)).await
+    }
+
+    pub async fn find_one(&self, filter: &[Filter<'_>]) -> MpdResult<Option<Song>> {
+        Ok(self.find(filter).await?.pop())
+    }
+
+    pub async fn search(&self, filter: &[Filter<'_>]) -> MpdResult<Vec<Song>> {
+        self.send(|c| c.send_search(filter)).await
+    }
+
+    // Stickers
+    /// Sticker of the song, `None` when it does not exist
+    pub async fn sticker(&self, uri: &str, name: &str) -> MpdResult<Option<Sticker>> {
+        match self.send(|c| c.send_sticker(&StickerType::Song, uri, name)).await {
+            Ok(sticker) => Ok(Some(sticker)),
+            Err(MpdError::Mpd(MpdFailureResponse { code: ErrorCode::NoExist, .. })) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn set_sticker(&self, uri: &str, name: &str, value: &str) -> MpdResult<()> {
+        self.send_ok(|c| c.send_set_sticker(&StickerType::Song, uri, name, value)).await
+    }
+
+    /// Atomically increments the numeric sticker by the given value, creating
+    /// it if it does not exist yet. Requires MPD 0.24.
+    pub async fn inc_sticker(
+        &self,
+        ty: &StickerType,
+        uri: &str,
+        name: &str,
+        value: u32,
+    ) -> MpdResult<()> {
+        self.send_ok(|c| c.send_inc_sticker(ty, uri, name, value)).await
+    }
+
+    // Client to client
+    /// Channels which have at least one subscriber
+    pub async fn channels(&self) -> MpdResult<MpdList> {
+        self.send(|c| c.send_channels()).await
+    }
+
+    pub async fn send_message(&self, channel: &str, content: &str) -> MpdResult<()> {
+        self.send_ok(|c| c.send_send_message(channel, content)).await
+    }
+
+    /// Subscribes the idle connections to the channel. Running idle streams
+    /// reopen their connection to apply it.
+    pub fn subscribe(&self, channel: &str) {
+        self.channels.send_if_modified(|channels| channels.insert(channel.to_owned()));
+    }
+
+    pub fn unsubscribe(&self, channel: &str) {
+        self.channels.send_if_modified(|channels| channels.remove(channel));
+    }
+
+    /// Takes the messages received by the idle connections since the last call
+    pub fn read_messages(&self) -> Messages {
+        Messages(std::mem::take(&mut *lock(&self.messages)))
+    }
+
+    /// Opens a new connection dedicated to idle. Events for the given
+    /// subsystems, or all of them when empty, are delivered through the
+    /// returned stream. The stream yields an error and ends when the
+    /// connection is lost, it is up to the caller to call this again.
+    pub async fn idle(&self, subsystems: &[IdleEvent]) -> MpdResult<IdleStream> {
+        let conn = self.endpoint.open().await?;
+
+        Ok(IdleStream::spawn(IdleTask {
+            conn,
+            endpoint: self.endpoint.clone(),
+            channels: self.channels.subscribe(),
+            messages: Arc::clone(&self.messages),
+            command: std::iter::once("idle".to_owned())
+                .chain(subsystems.iter().map(ToString::to_string))
+                .collect::<Vec<_>>()
+                .join(" "),
+        }))
+    }
+}
+
+fn lock(messages: &StdMutex<ChannelMessages>) -> std::sync::MutexGuard<'_, ChannelMessages> {
+    messages.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+struct IdleTask {
+    conn: Connection,
+    endpoint: Endpoint,
+    channels: watch::Receiver<BTreeSet<String>>,
+    messages: Arc<StdMutex<ChannelMessages>>,
+    command: String,
+}
+
+impl IdleTask {
+    async fn run(mut self, tx: &mpsc::UnboundedSender<MpdResult<IdleEvent>>) -> MpdResult<()> {
+        self.subscribe().await;
+        loop {
+            let events = tokio::select! {
+                events = Self::idle(&mut self.conn, &self.command) => Some(events?),
+                Ok(()) = self.channels.changed() => None,
+            };
+
+            let Some(events) = events else {
+                // Leaving idle to change the subscriptions would need noidle,
+                // a fresh connection is simpler and subscriptions rarely change
+                debug!("Subscribed channels changed, reopening idle connection");
+                self.conn = self.endpoint.open().await?;
+                self.subscribe().await;
+                continue;
+            };
+
+            for event in events {
+                if event == IdleEvent::Message {
+                    self.read_messages().await?;
+                }
+                if tx.send(Ok(event)).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn idle(conn: &mut Connection, command: &str) -> MpdResult<Vec<IdleEvent>> {
+        conn.write_command(command).await?;
+        Ok(conn.read_response::<Vec<IdleEvent>>().await?.unwrap_or_default())
+    }
+
+    async fn subscribe(&mut self) {
+        let channels = self.channels.borrow_and_update().clone();
+        for channel in channels {
+            let result = match self
+                .conn
+                .write_command(&format!("subscribe {}", channel.as_str().quote_and_escape()))
+                .await
+            {
+                Ok(()) => self.conn.read_ok().await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                log::error!(err:?, channel = channel.as_str(); "Failed to subscribe to channel");
+            }
+        }
+    }
+
+    async fn read_messages(&mut self) -> MpdResult<()> {
+        self.conn.write_command("readmessages").await?;
+        let Some(Messages(received)) = self.conn.read_response::<Messages>().await? else {
+            return Ok(());
+        };
+
+        let mut messages = lock(&self.messages);
+        for (channel, new) in received {
+            match messages.iter_mut().find(|(c, _)| *c == channel) {
+                Some((_, pending)) => pending.extend(new),
+                None => messages.push((channel, new)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stream of [`IdleEvent`]s read from a dedicated connection. The connection
+/// is closed when the stream is dropped.
+#[derive(Debug)]
+pub struct IdleStream {
+    rx: mpsc::UnboundedReceiver<MpdResult<IdleEvent>>,
+    task: JoinHandle<()>,
+}
+
+impl IdleStream {
+    fn spawn(task: IdleTask) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let task = tokio::spawn(async move {
+            if let Err(err) = task.run(&tx).await {
+                log::error!(err:?; "Idle connection to MPD failed");
+                let _ = tx.send(Err(err));
+            }
+        });
+
+        Self { rx, task }
+    }
+
+    /// Waits for the next event, `None` once the stream has ended
+    pub async fn recv(&mut self) -> Option<MpdResult<IdleEvent>> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for IdleStream {
+    type Item = MpdResult<IdleEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for IdleStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufStream, DuplexStream, duplex};
+
+    use super::{AsyncClient, Connection, Endpoint, IdleStream, IdleTask};
+    use crate::{
+        address::MpdAddress,
+        commands::{IdleEvent, Volume, status::State, stickers::Sticker},
+        errors::{ErrorCode, MpdError},
+        mpd_client::{MpdCommand, ValueChange},
+    };
+
+    /// Pretends to be MPD, expects the given commands in order and answers
+    /// each with the paired response
+    async fn serve(stream: DuplexStream, exchanges: Vec<(&'static str, &'static [u8])>) {
+        let mut stream = BufStream::new(stream);
+        stream.write_all(b"OK MPD 0.24.0\n").await.unwrap();
+        stream.flush().await.unwrap();
+
+        let handshake: [(&str, &[u8]); 1] = [("binarylimit 262144", b"OK\n")];
+        for (expected, response) in handshake.into_iter().chain(exchanges) {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            assert_eq!(line.trim_end(), expected);
+            stream.write_all(response).await.unwrap();
+            stream.flush().await.unwrap();
+        }
+    }
+
+    async fn connection(exchanges: Vec<(&'static str, &'static [u8])>) -> Connection {
+        let (client, server) = duplex(1024);
+        tokio::spawn(serve(server, exchanges));
+
+        Connection::handshake(Box::new(client), None).await.unwrap()
+    }
+
+    async fn client(exchanges: Vec<(&'static str, &'static [u8])>) -> AsyncClient {
+        let conn = connection(exchanges).await;
+
+        AsyncClient::new(endpoint(), conn)
+    }
+
+    fn endpoint() -> Endpoint {
+        Endpoint { addr: MpdAddress::default(), password: None, enable_keepalive: false }
+    }
+
+    fn idle_task(conn: Connection, client: &AsyncClient, command: &str) -> IdleTask {
+        IdleTask {
+            conn,
+            endpoint: endpoint(),
+            channels: client.channels.subscribe(),
+            messages: Arc::clone(&client.messages),
+            command: command.to_owned(),
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_response_with_from_mpd() {
+        let client = client(vec![("status", b"volume: 50\nstate: play\nOK\n")]).await;
+
+        let status = client.status().await.unwrap();
+
+        assert_eq!(client.version().minor, 24);
+        assert_eq!(status.volume, Volume::new(50));
+        assert_eq!(status.state, State::Play);
+    }
+
+    #[tokio::test]
+    async fn returns_none_for_empty_response() {
+        let client = client(vec![("currentsong", b"OK\n")]).await;
+
+        assert_eq!(client.current_song().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn connection_is_usable_after_mpd_error() {
+        let client = client(vec![
+            ("play 99", b"ACK [50@0] {play} song doesn't exist: \"99\"\n"),
+            ("play 0", b"OK\n"),
+        ])
+        .await;
+
+        let err = client.execute_ok("play 99").await.unwrap_err();
+
+        assert!(matches!(err, MpdError::Mpd(ref failure) if failure.code == ErrorCode::NoExist));
+        assert!(!client.conn.lock().await.dirty);
+        assert_eq!(client.execute_ok("play 0").await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn reads_binary_response_in_chunks() {
+        let client = client(vec![
+            ("albumart \"song.flac\" 0", b"size: 5\nbinary: 3\nabc\nOK\n"),
+            ("albumart \"song.flac\" 3", b"size: 5\nbinary: 2\nde\nOK\n"),
+        ])
+        .await;
+
+        let data = client.albumart("song.flac").await.unwrap();
+
+        assert_eq!(data, Some(b"abcde".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn idle_stream_yields_events() {
+        let conn = connection(vec![
+            ("idle player mixer", b"changed: player\nchanged: mixer\nOK\n"),
+            ("idle player mixer", b"changed: player\nOK\n"),
+        ])
+        .await;
+
+        let client = client(vec![]).await;
+
+        let mut stream = IdleStream::spawn(idle_task(conn, &client, "idle player mixer"));
+
+        assert_eq!(stream.recv().await, Some(Ok(IdleEvent::Player)));
+        assert_eq!(stream.recv().await, Some(Ok(IdleEvent::Mixer)));
+        assert_eq!(stream.recv().await, Some(Ok(IdleEvent::Player)));
+        // fake server hung up
+        assert!(matches!(stream.recv().await, Some(Err(_))));
+        assert_eq!(stream.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn sends_commands_built_by_mpd_command() {
+        let client = client(vec![
+            ("volume -5", b"OK\n"),
+            ("sticker get song \"a \\\"b\\\".flac\" \"playCount\"", b"sticker: playCount=3\nOK\n"),
+            (
+                "sticker get song \"c.flac\" \"playCount\"",
+                b"ACK [50@0] {sticker} no such sticker\n",
+            ),
+            ("command_list_begin", b""),
+            ("repeat 1", b""),
+            ("single 0", b""),
+            ("command_list_end", b"OK\n"),
+        ])
+        .await;
+
+        client.volume(ValueChange::Decrease(5)).await.unwrap();
+        let sticker = client.sticker("a \"b\".flac", "playCount").await.unwrap();
+        let missing = client.sticker("c.flac", "playCount").await.unwrap();
+        client
+            .send_ok(|c| {
+                c.send_start_cmd_list()?;
+                c.send_repeat(true)?;
+                c.send_single(crate::commands::status::OnOffOneshot::Off)?;
+                c.send_execute_cmd_list()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(sticker, Some(Sticker { key: "playCount".to_owned(), value: "3".to_owned() }));
+        assert_eq!(missing, None);
+    }
+
+    #[tokio::test]
+    async fn idle_reads_messages_of_subscribed_channels() {
+        let client = client(vec![]).await;
+        client.subscribe("rmpc");
+        let conn = connection(vec![
+            ("subscribe \"rmpc\"", b"OK\n"),
+            ("idle", b"changed: message\nOK\n"),
+            ("readmessages", b"channel: rmpc\nmessage: hello\nOK\n"),
+        ])
+        .await;
+
+        let mut stream = IdleStream::spawn(idle_task(conn, &client, "idle"));
+
+        assert_eq!(stream.recv().await, Some(Ok(IdleEvent::Message)));
+        assert_eq!(client.read_messages().0, vec![("rmpc".to_owned(), vec!["hello".to_owned()])]);
+        assert!(client.read_messages().0.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn connects_to_abstract_socket() {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            os::{linux::net::SocketAddrExt, unix::net::SocketAddr},
+        };
+
+        let name = format!("rmpc-mpd-test-{}", std::process::id());
+        let listener = std::os::unix::net::UnixListener::bind_addr(
+            &SocketAddr::from_abstract_name(&name).unwrap(),
+        )
+        .unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"OK MPD 0.24.0\n").unwrap();
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line).unwrap();
+            stream.write_all(b"OK\n").unwrap();
+        });
+
+        let client =
+            AsyncClient::connect(MpdAddress::AbstractSocket(name), None, false).await.unwrap();
+
+        assert_eq!(client.version().minor, 24);
+    }
+}
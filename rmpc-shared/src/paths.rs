@@ -36,6 +36,13 @@ pub fn cache_dir() -> Option<PathBuf> {
         .or_else(|| home_dir().map(|home| home.join(".cache")))
 }
 
+pub fn state_dir() -> Option<PathBuf> {
+    ENV.var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".local").join("state")))
+}
+
 pub fn runtime_dir() -> Option<PathBuf> {
     ENV.var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|p| p.is_absolute())
 }
@@ -44,6 +51,10 @@ pub fn rmpc_config_dir() -> Option<PathBuf> {
     config_dir().map(|config_dir| config_dir.join("rmpc"))
 }
 
+pub fn rmpc_state_dir() -> Option<PathBuf> {
+    state_dir().map(|state_dir| state_dir.join("rmpc"))
+}
+
 pub fn rmpcd_config_dir() -> Option<PathBuf> {
     config_dir().map(|config_dir| config_dir.join("rmpcd"))
 }
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use rmpc_mpd::mpd_client::AlbumArtOrder;
 use rmpc_shared::paths::utils::tilde_expand;
@@ -18,6 +18,7 @@ pub struct AlbumArtConfigFile {
     pub vertical_align: VerticalAlignFile,
     pub horizontal_align: HorizontalAlignFile,
     pub custom_loader: Option<Vec<String>>,
+    pub cache: AlbumArtCacheConfigFile,
 }
 
 impl Default for AlbumArtConfigFile {
@@ -30,6 +31,28 @@ impl Default for AlbumArtConfigFile {
             vertical_align: VerticalAlignFile::default(),
             horizontal_align: HorizontalAlignFile::default(),
             custom_loader: None,
+            cache: AlbumArtCacheConfigFile::default(),
+        }
+    }
+}
+
+/// Album art cache stored in `cache_dir`
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(default)]
+pub struct AlbumArtCacheConfigFile {
+    pub enabled: bool,
+    pub key: AlbumArtCacheKeyFile,
+    pub max_size_mb: u64,
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for AlbumArtCacheConfigFile {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            key: AlbumArtCacheKeyFile::default(),
+            max_size_mb: 200,
+            max_age_days: Some(90),
         }
     }
 }
@@ -43,6 +66,39 @@ pub struct AlbumArtConfig {
     pub vertical_align: VerticalAlign,
     pub horizontal_align: HorizontalAlign,
     pub custom_loader: Option<Arc<Vec<String>>>,
+    pub cache: AlbumArtCacheConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlbumArtCacheConfig {
+    pub enabled: bool,
+    pub key: AlbumArtCacheKey,
+    /// Maximum size of the cache in bytes
+    pub max_size: u64,
+    pub max_age: Option<Duration>,
+}
+
+impl Default for AlbumArtCacheConfig {
+    fn default() -> Self {
+        AlbumArtCacheConfigFile::default().into()
+    }
+}
+
+#[derive(Default, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AlbumArtCacheKeyFile {
+    #[default]
+    Directory,
+    Album,
+}
+/// What identifies a single album art in the cache
+#[derive(Default, Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlbumArtCacheKey {
+    /// Songs in the same directory share the album art
+    #[default]
+    Directory,
+    /// Songs with the same album and album artist share the album art. Falls
+    /// back to the directory for songs without the album tag.
+    Album,
 }
 
 #[derive(Default, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -69,6 +125,17 @@ pub enum AlbumArtOrderFile {
     FileOnly,
 }
 
+impl From<AlbumArtOrderFile> for AlbumArtOrder {
+    fn from(value: AlbumArtOrderFile) -> Self {
+        match value {
+            AlbumArtOrderFile::EmbeddedFirst => AlbumArtOrder::EmbeddedFirst,
+            AlbumArtOrderFile::FileFirst => AlbumArtOrder::FileFirst,
+            AlbumArtOrderFile::EmbeddedOnly => AlbumArtOrder::EmbeddedOnly,
+            AlbumArtOrderFile::FileOnly => AlbumArtOrder::FileOnly,
+        }
+    }
+}
+
 #[derive(Default, Display, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum VerticalAlignFile {
     Top,
@@ -114,12 +181,7 @@ impl From<AlbumArtConfigFile> for AlbumArtConfig {
         let size = value.max_size_px;
         AlbumArtConfig {
             method: ImageMethod::default(),
-            order: match value.order {
-                AlbumArtOrderFile::EmbeddedFirst => AlbumArtOrder::EmbeddedFirst,
-                AlbumArtOrderFile::FileFirst => AlbumArtOrder::FileFirst,
-                AlbumArtOrderFile::EmbeddedOnly => AlbumArtOrder::EmbeddedOnly,
-                AlbumArtOrderFile::FileOnly => AlbumArtOrder::FileOnly,
-            },
+            order: value.order.into(),
             max_size_px: Size {
                 width: if size.width == 0 { u16::MAX } else { size.width },
                 height: if size.height == 0 { u16::MAX } else { size.height },
@@ -130,6 +192,27 @@ impl From<AlbumArtConfigFile> for AlbumArtConfig {
             custom_loader: value.custom_loader.map(|arr| {
                 Arc::new(arr.into_iter().map(|v| tilde_expand(&v).into_owned()).collect())
             }),
+            cache: value.cache.into(),
+        }
+    }
+}
+
+impl From<AlbumArtCacheConfigFile> for AlbumArtCacheConfig {
+    fn from(value: AlbumArtCacheConfigFile) -> Self {
+        AlbumArtCacheConfig {
+            enabled: value.enabled,
+            key: value.key.into(),
+            max_size: value.max_size_mb.saturating_mul(1024 * 1024),
+            max_age: value.max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        }
+    }
+}
+
+impl From<AlbumArtCacheKeyFile> for AlbumArtCacheKey {
+    fn from(value: AlbumArtCacheKeyFile) -> Self {
+        match value {
+            AlbumArtCacheKeyFile::Directory => AlbumArtCacheKey::Directory,
+            AlbumArtCacheKeyFile::Album => AlbumArtCacheKey::Album,
         }
     }
 }
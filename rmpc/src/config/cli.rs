@@ -31,6 +31,15 @@ pub struct Args {
     #[command(flatten)]
     pub partition: Partition,
 
+    #[arg(long, value_hint = ValueHint::AnyPath, value_name = "FILE")]
+    /// Record the communication with MPD to the given file to be attached to
+    /// a bug report. Passwords are redacted.
+    pub record: Option<PathBuf>,
+    #[arg(long, value_hint = ValueHint::AnyPath, value_name = "FILE", conflicts_with = "address")]
+    /// Connect to a fake MPD server which responds with the responses from a
+    /// file recorded by `--record` instead of a real MPD
+    pub replay: Option<PathBuf>,
+
     #[arg(short, long)]
     /// Print rmpc version and exit
     pub version: bool,
@@ -107,7 +116,8 @@ pub enum Command {
         #[arg(short, long, default_value = "false")]
         wait: bool,
     },
-    /// Saves the current album art to a file.
+    /// Saves the current album art to a file. Can also manage the album art
+    /// cache in `cache_dir`.
     /// Exit codes:
     ///   * 0: Success
     ///   * 1: Error
@@ -115,8 +125,15 @@ pub enum Command {
     ///   * 3: No song playing
     AlbumArt {
         /// Output file where to save the album art, "-" for stdout
-        #[arg(short, long)]
-        output: String,
+        #[arg(short, long, required_unless_present_any = ["cache_clear", "prefetch"])]
+        output: Option<String>,
+        /// Removes all album art from the cache
+        #[arg(long = "cache-clear", default_value_t = false)]
+        cache_clear: bool,
+        /// Caches album art of all albums in the database which are not cached
+        /// yet and prints a summary
+        #[arg(long, default_value_t = false)]
+        prefetch: bool,
     },
     /// Prints information about optional runtime dependencies
     DebugInfo,
@@ -136,11 +153,25 @@ pub enum Command {
         /// Files to list from MPD's music database
         files: Vec<String>,
     },
+    /// Searches the music database with a MPD filter expression, ie.
+    /// `(artist == 'foo') AND (!(album contains 'live'))`, and prints the found
+    /// songs
+    Search {
+        /// Filter expression to search by
+        query: String,
+        /// Compare case sensitively, uses MPD's find instead of search
+        #[arg(short, long, default_value_t = false)]
+        exact: bool,
+    },
     /// Plays song at the position in the current playlist. Defaults to current
     /// paused song.
     Play {
         /// Index of the song in the queue
         position: Option<usize>,
+        /// Start playback at the given timestamp, ie. `1:23`. Applies to the
+        /// current song when position is not given
+        #[arg(long)]
+        at: Option<String>,
     },
     /// Pause playback
     Pause,
@@ -208,6 +239,20 @@ pub enum Command {
         #[arg(short, long = "skip-oneshot")]
         skip_oneshot: bool,
     },
+    /// Sets the replay gain mode. Prints current replay gain status if no
+    /// mode is given.
+    ReplayGain {
+        mode: Option<ReplayGainMode>,
+    },
+    /// Sets the mixramp threshold in dB at which songs are overlapped
+    MixRampDb {
+        #[arg(allow_negative_numbers(true))]
+        value: f32,
+    },
+    /// Sets the mixramp delay in seconds, nan disables mixramp
+    MixRampDelay {
+        value: f32,
+    },
     /// Seeks current song(seconds), relative if prefixed by + or -
     Seek {
         #[arg(allow_negative_numbers(true))]
@@ -241,6 +286,23 @@ pub enum Command {
         #[arg(short, long)]
         positions: Vec<usize>,
     },
+    /// Set priority of song(s) in the current queue. Songs with higher
+    /// priority are played first when random mode is enabled. Will read
+    /// positions from stdin if neither positions nor ids are provided, one
+    /// song index per line.
+    Priority {
+        /// Priority between 0 and 255. 0 is the default priority of every
+        /// song in the queue.
+        priority: u8,
+        /// Zero based index of the song in the queue. Can be specified
+        /// multiple times to set priority of multiple songs at once.
+        #[arg(short, long)]
+        positions: Vec<usize>,
+        /// Id of the song in the queue. Can be specified multiple times to set
+        /// priority of multiple songs at once.
+        #[arg(short, long, conflicts_with = "positions")]
+        ids: Vec<u32>,
+    },
     /// Add a song from youtube to the current queue.
     AddYt {
         url: String,
@@ -302,6 +364,14 @@ pub enum Command {
     },
     /// List MPD decoder plugins
     Decoders,
+    /// Finds songs which are likely the same recording by their audio
+    /// fingerprints and prints groups of them as JSON. Fingerprints are cached
+    /// in the `cache_dir` if configured.
+    Duplicates {
+        /// Only search songs under this directory
+        #[arg(short, long)]
+        path: Option<String>,
+    },
     /// Prints various information like the playback status
     Status,
     /// Prints info about the current song.
@@ -323,6 +393,8 @@ pub enum Command {
     },
     /// List currently mounted storages
     ListMounts,
+    /// List storages discovered by MPD's neighbor plugins which can be mounted
+    ListNeighbors,
     /// List the currently existing partitions
     ListPartitions,
     /// Manipulate and query song stickers
@@ -330,6 +402,12 @@ pub enum Command {
         #[command(subcommand)]
         cmd: StickerCmd,
     },
+    /// Save, restore and list snapshots of the queue and playback state.
+    /// Snapshots are stored in rmpc's state directory.
+    Snapshot {
+        #[command(subcommand)]
+        cmd: SnapshotCmd,
+    },
     /// Send a remote command to running rmpc instance
     Remote {
         /// PID of the rmpc instance to send the remote command to. If not
@@ -467,6 +545,24 @@ pub enum StickerCmd {
     },
 }
 
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+#[clap(rename_all = "lower")]
+pub enum SnapshotCmd {
+    /// Save the current queue, song, elapsed time and playback options
+    Save {
+        /// Name of the snapshot, an existing snapshot is overwritten
+        name: String,
+    },
+    /// Replace the current queue with the one from the snapshot and resume
+    /// playback where it was
+    Restore {
+        /// Name of the snapshot to restore
+        name: String,
+    },
+    /// Print saved snapshots as JSON, the most recent first
+    List,
+}
+
 #[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
 pub enum OnOff {
     /// Enable
@@ -484,3 +580,15 @@ pub enum OnOffOneshot {
     /// Track get removed from playlist after it has been played
     Oneshot,
 }
+
+#[derive(Parser, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum ReplayGainMode {
+    /// Do not apply replay gain
+    Off,
+    /// Always use the track gain
+    Track,
+    /// Always use the album gain
+    Album,
+    /// Use album gain when playing an album in order, track gain otherwise
+    Auto,
+}
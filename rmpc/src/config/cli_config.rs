@@ -1,10 +1,15 @@
 use std::path::PathBuf;
 
-use rmpc_mpd::address::MpdPassword;
+use rmpc_mpd::{address::MpdPassword, mpd_client::AlbumArtOrder};
 use rmpc_shared::paths::utils::{absolute_env_var_expand_path, env_var_expand, tilde_expand};
 use serde::{Deserialize, Serialize};
 
-use super::{Config, ConfigFile, MpdAddress};
+use super::{
+    Config,
+    ConfigFile,
+    MpdAddress,
+    album_art::{AlbumArtCacheConfig, AlbumArtConfigFile},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(default)]
@@ -14,6 +19,7 @@ pub struct CliConfigFile {
     cache_dir: Option<PathBuf>,
     lyrics_dir: Option<String>,
     extra_yt_dlp_args: Vec<String>,
+    album_art: AlbumArtConfigFile,
 }
 
 impl Default for CliConfigFile {
@@ -24,6 +30,7 @@ impl Default for CliConfigFile {
             cache_dir: None,
             lyrics_dir: None,
             extra_yt_dlp_args: Vec::new(),
+            album_art: AlbumArtConfigFile::default(),
         }
     }
 }
@@ -35,6 +42,8 @@ pub struct CliConfig {
     pub cache_dir: Option<PathBuf>,
     pub lyrics_dir: Option<String>,
     pub extra_yt_dlp_args: Vec<String>,
+    pub album_art_order: AlbumArtOrder,
+    pub album_art_cache: AlbumArtCacheConfig,
 }
 
 impl CliConfig {
@@ -57,6 +66,7 @@ impl From<ConfigFile> for CliConfigFile {
             cache_dir: value.cache_dir,
             lyrics_dir: value.lyrics_dir,
             extra_yt_dlp_args: value.extra_yt_dlp_args,
+            album_art: value.album_art,
         }
     }
 }
@@ -69,6 +79,8 @@ impl From<Config> for CliConfig {
             cache_dir: value.cache_dir,
             lyrics_dir: value.lyrics_dir,
             extra_yt_dlp_args: value.extra_yt_dlp_args,
+            album_art_order: value.album_art.order,
+            album_art_cache: value.album_art.cache,
         }
     }
 }
@@ -81,6 +93,8 @@ impl From<&Config> for CliConfig {
             cache_dir: value.cache_dir.clone(),
             lyrics_dir: value.lyrics_dir.clone(),
             extra_yt_dlp_args: value.extra_yt_dlp_args.clone(),
+            album_art_order: value.album_art.order,
+            album_art_cache: value.album_art.cache.clone(),
         }
     }
 }
@@ -112,6 +126,8 @@ impl CliConfigFile {
             address,
             password,
             extra_yt_dlp_args: self.extra_yt_dlp_args,
+            album_art_order: self.album_art.order.into(),
+            album_art_cache: self.album_art.cache.into(),
         }
     }
 }
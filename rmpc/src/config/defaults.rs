@@ -36,10 +36,18 @@ pub fn u8<const V: u8>() -> u8 {
     V
 }
 
+pub fn u16<const V: u16>() -> u16 {
+    V
+}
+
 pub fn i32<const V: i32>() -> i32 {
     V
 }
 
+pub fn default_statistics_breakdowns() -> Vec<String> {
+    vec!["Genre".to_string(), "Date".to_string()]
+}
+
 pub fn default_playing_label() -> String {
     "Playing".to_string()
 }
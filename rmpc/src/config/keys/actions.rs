@@ -2,7 +2,11 @@ use std::{borrow::Cow, fmt::Write, ops::Range, sync::Arc};
 
 use anyhow::bail;
 use itertools::Itertools;
-use rmpc_mpd::{commands::Song, queue_position::QueuePosition};
+use rmpc_mpd::{
+    commands::{Song, replay_gain::ReplayGainMode, stickers::StickerType},
+    filter::Tag,
+    queue_position::QueuePosition,
+};
 use rmpc_shared::paths::utils::tilde_expand;
 use strum::{Display, EnumDiscriminants, VariantArray};
 
@@ -25,12 +29,22 @@ pub enum GlobalAction {
     ShowCurrentSongInfo,
     ShowOutputs,
     ShowDecoders,
+    ShowMounts,
     ShowDownloads,
+    FindDuplicates,
+    Snapshots,
+    Undo,
+    Redo,
     #[strum(to_string = "Partition({name:?})")]
     Partition {
         name: Option<String>,
         autocreate: bool,
     },
+    /// Sets the given replay gain mode or cycles through them when `None`
+    #[strum(to_string = "ReplayGain({mode:?})")]
+    ReplayGain {
+        mode: Option<ReplayGainMode>,
+    },
     AddRandom,
     NextTrack,
     PreviousTrack,
@@ -48,6 +62,10 @@ pub enum GlobalAction {
     VolumeDown,
     CrossfadeUp,
     CrossfadeDown,
+    MixRampDbUp,
+    MixRampDbDown,
+    MixRampDelayUp,
+    MixRampDelayDown,
     SeekForward,
     SeekBack,
     SeekToStart,
@@ -76,13 +94,22 @@ pub enum GlobalActionFile {
     ShowCurrentSongInfo,
     ShowOutputs,
     ShowDecoders,
+    ShowMounts,
     ShowDownloads,
+    FindDuplicates,
+    Snapshots,
+    Undo,
+    Redo,
     Partition {
         #[serde(default)]
         name: Option<String>,
         #[serde(default)]
         autocreate: bool,
     },
+    ReplayGain {
+        #[serde(default)]
+        mode: Option<ReplayGainMode>,
+    },
     NextTrack,
     PreviousTrack,
     Stop,
@@ -99,6 +126,10 @@ pub enum GlobalActionFile {
     VolumeDown,
     CrossfadeUp,
     CrossfadeDown,
+    MixRampDbUp,
+    MixRampDbDown,
+    MixRampDelayUp,
+    MixRampDelayDown,
     SeekForward,
     SeekBack,
     SeekToStart,
@@ -131,8 +162,13 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::Quit => GlobalAction::Quit,
             GlobalActionFile::ShowOutputs => GlobalAction::ShowOutputs,
             GlobalActionFile::ShowDecoders => GlobalAction::ShowDecoders,
+            GlobalActionFile::ShowMounts => GlobalAction::ShowMounts,
             GlobalActionFile::ShowCurrentSongInfo => GlobalAction::ShowCurrentSongInfo,
             GlobalActionFile::ShowDownloads => GlobalAction::ShowDownloads,
+            GlobalActionFile::FindDuplicates => GlobalAction::FindDuplicates,
+            GlobalActionFile::Snapshots => GlobalAction::Snapshots,
+            GlobalActionFile::Undo => GlobalAction::Undo,
+            GlobalActionFile::Redo => GlobalAction::Redo,
             GlobalActionFile::CommandMode => GlobalAction::CommandMode,
             GlobalActionFile::Command { command, description } => {
                 GlobalAction::Command { command, description }
@@ -156,6 +192,10 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::VolumeUp => GlobalAction::VolumeUp,
             GlobalActionFile::CrossfadeDown => GlobalAction::CrossfadeDown,
             GlobalActionFile::CrossfadeUp => GlobalAction::CrossfadeUp,
+            GlobalActionFile::MixRampDbDown => GlobalAction::MixRampDbDown,
+            GlobalActionFile::MixRampDbUp => GlobalAction::MixRampDbUp,
+            GlobalActionFile::MixRampDelayDown => GlobalAction::MixRampDelayDown,
+            GlobalActionFile::MixRampDelayUp => GlobalAction::MixRampDelayUp,
             GlobalActionFile::PreviousTab => GlobalAction::PreviousTab,
             GlobalActionFile::NextTab => GlobalAction::NextTab,
             GlobalActionFile::ToggleConsume => GlobalAction::ToggleConsume,
@@ -181,6 +221,7 @@ impl From<GlobalActionFile> for GlobalAction {
             GlobalActionFile::Partition { name, autocreate } => {
                 GlobalAction::Partition { name, autocreate }
             }
+            GlobalActionFile::ReplayGain { mode } => GlobalAction::ReplayGain { mode },
         }
     }
 }
@@ -191,10 +232,17 @@ impl ToDescription for GlobalAction {
             GlobalAction::Quit => "Exit rmpc".into(),
             GlobalAction::ShowOutputs => "Show MPD outputs config".into(),
             GlobalAction::ShowDecoders => "Show MPD decoder plugins".into(),
+            GlobalAction::ShowMounts => "Show mounted storages and discovered neighbors".into(),
             GlobalAction::ShowCurrentSongInfo => {
                 "Show metadata of the currently playing song in a modal popup".into()
             }
             GlobalAction::ShowDownloads => "Show current downloads".into(),
+            GlobalAction::FindDuplicates => {
+                "Find songs in the library which are likely the same recording".into()
+            }
+            GlobalAction::Snapshots => "Save, restore or delete snapshots of the queue".into(),
+            GlobalAction::Undo => "Undo the last change of the queue or a playlist".into(),
+            GlobalAction::Redo => "Redo the last undone change of the queue or a playlist".into(),
             GlobalAction::ToggleRepeat => "Toggle repeat".into(),
             GlobalAction::ToggleSingle => {
                 "Whether to stop playing after single track or repeat track/playlist when repeat is on".into()
@@ -209,6 +257,10 @@ impl ToDescription for GlobalAction {
             GlobalAction::VolumeDown => "Lower volume".into(),
             GlobalAction::CrossfadeUp => "Increase crossfade duration".into(),
             GlobalAction::CrossfadeDown => "Decrease crossfade duration".into(),
+            GlobalAction::MixRampDbUp => "Raise MixRamp overlap threshold".into(),
+            GlobalAction::MixRampDbDown => "Lower MixRamp overlap threshold".into(),
+            GlobalAction::MixRampDelayUp => "Increase MixRamp delay".into(),
+            GlobalAction::MixRampDelayDown => "Decrease MixRamp delay".into(),
             GlobalAction::NextTrack => "Play next track in the queue".into(),
             GlobalAction::PreviousTrack => "Play previous track in the queue".into(),
             GlobalAction::SeekForward => "Seek currently playing track forwards".into(),
@@ -232,6 +284,8 @@ impl ToDescription for GlobalAction {
             GlobalAction::ToggleConsumeOnOff => "Toggle consume mode on or off, skipping oneshot".into(),
             GlobalAction::Partition { name: Some(name), .. }=> format!("Switch to '{name}' partition").into(),
             GlobalAction::Partition { name: None, .. }=> "Open partition management modal".into(),
+            GlobalAction::ReplayGain { mode: Some(mode) } => format!("Set replay gain mode to '{mode}'").into(),
+            GlobalAction::ReplayGain { mode: None } => "Cycle replay gain mode between off, track, album and auto".into(),
         }
     }
 }
@@ -340,6 +394,9 @@ pub enum QueueActionsFile {
     Delete,
     DeleteAll,
     Play,
+    PlayFromPosition,
+    Filter,
+    PlayRange,
     #[deprecated]
     Save,
     #[deprecated]
@@ -353,6 +410,9 @@ pub enum QueueActionsFile {
         kind: SortFile,
     },
     SelectAlbum(),
+    Priority {
+        kind: PriorityKind,
+    },
 }
 
 #[derive(Debug, Display, Clone, EnumDiscriminants, PartialEq, Eq)]
@@ -361,12 +421,16 @@ pub enum QueueActions {
     Delete,
     DeleteAll,
     Play,
+    PlayFromPosition,
+    Filter,
+    PlayRange,
     JumpToCurrent,
     Shuffle,
     Unused,
     SelectAlbum,
     SortByColumn(usize),
     Sort { kind: Sort },
+    Priority { kind: PriorityKind },
 }
 
 impl TryFrom<QueueActionsFile> for QueueActions {
@@ -377,6 +441,9 @@ impl TryFrom<QueueActionsFile> for QueueActions {
             QueueActionsFile::Delete => Ok(QueueActions::Delete),
             QueueActionsFile::DeleteAll => Ok(QueueActions::DeleteAll),
             QueueActionsFile::Play => Ok(QueueActions::Play),
+            QueueActionsFile::PlayFromPosition => Ok(QueueActions::PlayFromPosition),
+            QueueActionsFile::Filter => Ok(QueueActions::Filter),
+            QueueActionsFile::PlayRange => Ok(QueueActions::PlayRange),
             QueueActionsFile::Save => Ok(QueueActions::Unused),
             QueueActionsFile::AddToPlaylist => Ok(QueueActions::Unused),
             QueueActionsFile::ShowInfo => Ok(QueueActions::Unused),
@@ -407,6 +474,7 @@ impl TryFrom<QueueActionsFile> for QueueActions {
 
                 Ok(QueueActions::Sort { kind })
             }
+            QueueActionsFile::Priority { kind } => Ok(QueueActions::Priority { kind }),
         }
     }
 }
@@ -417,6 +485,16 @@ impl ToDescription for QueueActions {
             QueueActions::Delete => "Remove song under cursor from the queue".into(),
             QueueActions::DeleteAll => "Clear current queue".into(),
             QueueActions::Play => "Play song under cursor".into(),
+            QueueActions::PlayFromPosition => {
+                "Prompt for a timestamp and play song under cursor from it".into()
+            }
+            QueueActions::Filter => {
+                "Show only songs in the queue matching a search filter, empty filter shows all"
+                    .into()
+            }
+            QueueActions::PlayRange => {
+                "Prompt for a range of the song under cursor which should be played".into()
+            }
             QueueActions::Unused => "unused".into(),
             QueueActions::JumpToCurrent => {
                 "Moves the cursor in Queue table to the currently playing song".into()
@@ -432,6 +510,18 @@ impl ToDescription for QueueActions {
                     format!("Sort the queue by [{}]", opts.tags.iter().join(", ")).into()
                 }
             },
+            QueueActions::Priority { kind } => match kind {
+                PriorityKind::Increase(step) => {
+                    format!("Raise priority of selected songs by {step}").into()
+                }
+                PriorityKind::Decrease(step) => {
+                    format!("Lower priority of selected songs by {step}").into()
+                }
+                PriorityKind::Set(value) => {
+                    format!("Set priority of selected songs to {value}").into()
+                }
+                PriorityKind::Reset() => "Reset priority of selected songs".into(),
+            },
         }
     }
 }
@@ -748,6 +838,33 @@ impl Default for RateKind {
     }
 }
 
+/// Object stickers are attached to. Album and artist stickers require MPD 0.24
+#[derive(
+    Debug, Default, Display, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Hash,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum StickerTarget {
+    #[default]
+    Song,
+    Album,
+    Artist,
+}
+
+impl StickerTarget {
+    /// Tag whose value the sticker is attached to, `None` for song stickers
+    pub fn tag(self) -> Option<Tag> {
+        match self {
+            StickerTarget::Song => None,
+            StickerTarget::Album => Some(Tag::Album),
+            StickerTarget::Artist => Some(Tag::Artist),
+        }
+    }
+
+    pub fn sticker_type(self) -> StickerType {
+        self.tag().map_or(StickerType::Song, StickerType::Tag)
+    }
+}
+
 #[derive(Debug, Default, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq)]
 pub enum DuplicateStrategy {
     All,
@@ -848,6 +965,25 @@ pub enum Sort {
     Tags(SortOpts),
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityKind {
+    Increase(u8),
+    Decrease(u8),
+    Set(u8),
+    Reset(),
+}
+
+impl PriorityKind {
+    pub fn apply(self, current: u8) -> u8 {
+        match self {
+            PriorityKind::Increase(step) => current.saturating_add(step),
+            PriorityKind::Decrease(step) => current.saturating_sub(step),
+            PriorityKind::Set(value) => value,
+            PriorityKind::Reset() => 0,
+        }
+    }
+}
+
 impl Default for CopyContentsKindFile {
     fn default() -> Self {
         Self::Modal(vec![
@@ -986,6 +1122,8 @@ pub enum CommonActionFile {
         min_rating: i32,
         #[serde(default = "crate::config::defaults::i32::<10>")]
         max_rating: i32,
+        #[serde(default)]
+        target: StickerTarget,
     },
     Save {
         #[serde(default)]
@@ -1045,6 +1183,7 @@ pub enum CommonAction {
         current: bool,
         min_rating: i32,
         max_rating: i32,
+        target: StickerTarget,
     },
     Save {
         kind: SaveKind,
@@ -1133,6 +1272,17 @@ impl ToDescription for CommonAction {
             },
             CommonAction::ShowInfo => "Show info about item under cursor in a modal popup".into(),
             CommonAction::ContextMenu => "Show context menu".into(),
+            CommonAction::Rate { kind, current, min_rating, max_rating, target: target @ (StickerTarget::Album | StickerTarget::Artist) } => {
+                let song_description = CommonAction::Rate {
+                    kind: kind.clone(),
+                    current: *current,
+                    min_rating: *min_rating,
+                    max_rating: *max_rating,
+                    target: StickerTarget::Song,
+                }
+                .to_description();
+                format!("{song_description}, applied to the {target} of the song").into()
+            }
             CommonAction::Rate { kind: RateKind::Modal { .. }, current, .. } => {
                 let mut buf = String::from("Open a modal popup with song rating options");
                 if *current {
@@ -1334,7 +1484,7 @@ impl TryFrom<CommonActionFile> for CommonAction {
             CommonActionFile::ShowInfo => CommonAction::ShowInfo,
             CommonActionFile::AddOptions { kind } => CommonAction::AddOptions { kind },
             CommonActionFile::ContextMenu {} => CommonAction::ContextMenu,
-            CommonActionFile::Rate { kind, current, min_rating, max_rating } => {
+            CommonActionFile::Rate { kind, current, min_rating, max_rating, target } => {
                 match &kind {
                     RateKind::Modal { values, custom, like } => {
                         if values.is_empty() && !custom && !like {
@@ -1361,7 +1511,7 @@ impl TryFrom<CommonActionFile> for CommonAction {
                     }
                     _ => {}
                 }
-                CommonAction::Rate { kind, current, min_rating, max_rating }
+                CommonAction::Rate { kind, current, min_rating, max_rating, target }
             }
             CommonActionFile::Save { kind, current } => CommonAction::Save { kind, current },
             CommonActionFile::DeleteFromPlaylist { kind } => {
@@ -29,6 +29,7 @@ use crate::config::keys::{
         DuplicateStrategy,
         RateKind,
         SaveKind,
+        StickerTarget,
     },
     key::KeySequence,
 };
@@ -84,8 +85,10 @@ impl Default for KeyConfigFile {
             (s().char('o').char('I'),             G::ShowCurrentSongInfo),
             (s().char('o').char('o'),             G::ShowOutputs),
             (s().char('o').char('p'),             G::ShowDecoders),
+            (s().char('o').char('m'),             G::ShowMounts),
             (s().char('o').char('d'),             G::ShowDownloads),
             (s().char('o').char('P'),             G::Partition { name: None, autocreate: false }),
+            (s().char('o').char('s'),             G::Snapshots),
             (s().char('z'),                       G::ToggleRepeat),
             (s().char('x'),                       G::ToggleRandom),
             (s().char('c'),                       G::ToggleConsume),
@@ -112,6 +115,8 @@ impl Default for KeyConfigFile {
             (s().char('u'),                       G::Update),
             (s().char('U'),                       G::Rescan),
             (s().char('R'),                       G::AddRandom),
+            (s().char('z').ctrl(),                G::Undo),
+            (s().char('y').ctrl(),                G::Redo),
         ]);
 
         let navigation = HashMap::from([
@@ -164,7 +169,7 @@ impl Default for KeyConfigFile {
             (s().char('s').ctrl().char('s'),      C::Save { kind: SaveKind::Modal { all: false, duplicates_strategy: DuplicateStrategy::Ask }, current: false }),
             (s().char('s').ctrl().char('a'),      C::Save { kind: SaveKind::Modal { all: true, duplicates_strategy: DuplicateStrategy::Ask }, current: false }),
             (s().char('s').ctrl().char('c'),      C::Save { kind: SaveKind::Modal { all: false, duplicates_strategy: DuplicateStrategy::Ask }, current: true }),
-            (s().char('r'),                       C::Rate { kind: RateKind::default(), current: false, min_rating: 0, max_rating: 10 }),
+            (s().char('r'),                       C::Rate { kind: RateKind::default(), current: false, min_rating: 0, max_rating: 10, target: StickerTarget::Song }),
         ]);
 
         let queue = HashMap::from([
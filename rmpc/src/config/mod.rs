@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use artists::{Artists, ArtistsFile};
 use cava::{Cava, CavaFile};
 use clap::Parser;
-use cli::{Args, OnOff, OnOffOneshot};
+use cli::{Args, OnOff, OnOffOneshot, ReplayGainMode};
 use itertools::Itertools;
 use rmpc_mpd::address::{MpdAddress, MpdPassword};
 use rmpc_shared::paths::utils::{absolute_env_var_expand_path, env_var_expand, tilde_expand};
@@ -25,6 +25,7 @@ pub mod keys;
 mod search;
 pub mod sort_mode;
 pub mod tabs;
+mod tag_types;
 pub mod theme;
 
 pub use search::{FilterKindFile, Search};
@@ -89,7 +90,9 @@ pub struct Config {
     pub extra_yt_dlp_args: Vec<String>,
     pub duration_format: DurationFormat,
     pub on_exit: Option<Arc<Vec<String>>>,
+    pub snapshot_on_exit: bool,
     pub quit_closes_modal: bool,
+    pub negotiate_tag_types: bool,
     pub queue_disable_current_item_style_timeout_ms: Option<Duration>,
 }
 
@@ -161,7 +164,9 @@ pub struct ConfigFile {
     pub auto_open_downloads: bool,
     pub duration_format: String,
     pub on_exit: Option<Vec<String>>,
+    pub snapshot_on_exit: bool,
     pub quit_closes_modal: bool,
+    pub negotiate_tag_types: bool,
     pub queue_disable_current_item_style_timeout_ms: Option<u64>,
 }
 
@@ -236,7 +241,9 @@ impl Default for ConfigFile {
             auto_open_downloads: true,
             duration_format: "%m:%S".to_string(),
             on_exit: None,
+            snapshot_on_exit: true,
             quit_closes_modal: false,
+            negotiate_tag_types: false,
             queue_disable_current_item_style_timeout_ms: None,
         }
     }
@@ -391,7 +398,9 @@ impl ConfigFile {
             on_exit: self.on_exit.map(|arr| {
                 Arc::new(arr.into_iter().map(|v| tilde_expand(&v).into_owned()).collect_vec())
             }),
+            snapshot_on_exit: self.snapshot_on_exit,
             quit_closes_modal: self.quit_closes_modal,
+            negotiate_tag_types: self.negotiate_tag_types,
             queue_disable_current_item_style_timeout_ms: self
                 .queue_disable_current_item_style_timeout_ms
                 .map(Duration::from_millis),
@@ -440,6 +449,17 @@ impl From<OnOffOneshot> for rmpc_mpd::commands::status::OnOffOneshot {
     }
 }
 
+impl From<ReplayGainMode> for rmpc_mpd::commands::replay_gain::ReplayGainMode {
+    fn from(value: ReplayGainMode) -> Self {
+        match value {
+            ReplayGainMode::Off => rmpc_mpd::commands::replay_gain::ReplayGainMode::Off,
+            ReplayGainMode::Track => rmpc_mpd::commands::replay_gain::ReplayGainMode::Track,
+            ReplayGainMode::Album => rmpc_mpd::commands::replay_gain::ReplayGainMode::Album,
+            ReplayGainMode::Auto => rmpc_mpd::commands::replay_gain::ReplayGainMode::Auto,
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
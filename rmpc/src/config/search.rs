@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use rmpc_mpd::filter::{Filter, FilterExpr, FilterKind};
 use serde::{Deserialize, Serialize};
 
 use super::defaults;
@@ -40,6 +41,41 @@ pub struct SearchableTagFile {
     value: String,
 }
 
+impl Search {
+    /// Parses a single line filter in the form of `[tag:]value` where tag is
+    /// either value or label of one of the searchable tags. Values starting
+    /// with `(` are parsed as a MPD filter expression.
+    pub fn parse_filter(&self, input: &str) -> anyhow::Result<Option<Filter<'static>>> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+
+        if input.starts_with('(') {
+            return Ok(Some(Filter::from(input.parse::<FilterExpr>()?)));
+        }
+
+        let (tag, value) = input
+            .split_once(':')
+            .and_then(|(key, value)| {
+                let key = key.trim();
+                self.tags
+                    .iter()
+                    .find(|tag| {
+                        tag.value.eq_ignore_ascii_case(key) || tag.label.eq_ignore_ascii_case(key)
+                    })
+                    .map(|tag| (tag.value.clone(), value.trim()))
+            })
+            .unwrap_or_else(|| ("any".to_owned(), input));
+
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Filter::new(tag, value.to_owned()).with_type(self.mode.into())))
+    }
+}
+
 impl TryFrom<SearchFile> for Search {
     type Error = anyhow::Error;
 
@@ -103,3 +139,76 @@ pub enum FilterKindFile {
     Regex,
     NotRegex,
 }
+
+impl From<FilterKindFile> for FilterKind {
+    fn from(value: FilterKindFile) -> Self {
+        match value {
+            FilterKindFile::Exact => FilterKind::Exact,
+            FilterKindFile::NotExact => FilterKind::NotExact,
+            FilterKindFile::StartsWith => FilterKind::StartsWith,
+            FilterKindFile::Contains => FilterKind::Contains,
+            FilterKindFile::Regex => FilterKind::Regex,
+            FilterKindFile::NotRegex => FilterKind::NotRegex,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use rmpc_mpd::filter::{FilterExpr, FilterKind, Tag, TagOperator};
+
+    use super::{FilterKindFile, Search, SearchFile};
+
+    fn search(mode: FilterKindFile) -> Search {
+        let mut search: Search = SearchFile::default().try_into().expect("default to be valid");
+        search.mode = mode;
+        search
+    }
+
+    #[test]
+    fn parse_filter_with_tag() {
+        let filter = search(FilterKindFile::Exact).parse_filter("Album Artist: Foo:Bar ");
+
+        let filter = filter.unwrap().expect("filter to be parsed");
+        assert_eq!(filter.tag, Tag::Custom("albumartist".to_owned()));
+        assert_eq!(filter.value, "Foo:Bar");
+        assert_eq!(filter.kind, FilterKind::Exact);
+    }
+
+    #[test]
+    fn parse_filter_without_known_tag_matches_any() {
+        let filter = search(FilterKindFile::Contains).parse_filter("foo: bar");
+
+        let filter = filter.unwrap().expect("filter to be parsed");
+        assert_eq!(filter.tag, Tag::Custom("any".to_owned()));
+        assert_eq!(filter.value, "foo: bar");
+        assert_eq!(filter.kind, FilterKind::Contains);
+    }
+
+    #[test]
+    fn parse_filter_custom_query() {
+        let filter = search(FilterKindFile::Contains).parse_filter("(artist == 'foo')");
+
+        let filter = filter.unwrap().expect("filter to be parsed");
+        assert_eq!(
+            filter.kind,
+            FilterKind::CustomQuery(FilterExpr::Tag {
+                tag: Tag::Artist,
+                op: TagOperator::Eq,
+                value: "foo".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_filter_invalid_custom_query() {
+        assert!(search(FilterKindFile::Contains).parse_filter("(artist == 'foo'").is_err());
+    }
+
+    #[test]
+    fn parse_filter_empty() {
+        assert!(search(FilterKindFile::Contains).parse_filter("  ").unwrap().is_none());
+        assert!(search(FilterKindFile::Contains).parse_filter("artist:").unwrap().is_none());
+    }
+}
@@ -22,6 +22,7 @@ use super::theme::{
 use crate::{
     config::{
         defaults,
+        keys::actions::StickerTarget,
         theme::{
             ConfigColor,
             StyleFile,
@@ -180,6 +181,8 @@ pub enum PaneTypeFile {
         limit: Option<u32>,
         #[serde(default)]
         sort: StickerPaneSort,
+        #[serde(default)]
+        target: StickerTarget,
     },
     Search,
     AlbumArt,
@@ -208,6 +211,20 @@ pub enum PaneTypeFile {
         levels: Vec<BrowserTagConfigFile>,
     },
     Cava,
+    Statistics {
+        /// Tags to show the number of songs and their playtime for, grouped
+        /// by the tag's values
+        #[serde(default = "defaults::default_statistics_breakdowns")]
+        breakdowns: Vec<String>,
+    },
+    AlbumGrid {
+        /// Width of a single album in the grid in columns
+        #[serde(default = "defaults::u16::<20>")]
+        width: u16,
+        /// Height of the album's cover in rows, its name is shown below it
+        #[serde(default = "defaults::u16::<9>")]
+        height: u16,
+    },
     Empty(),
 }
 
@@ -228,6 +245,7 @@ pub enum PaneType {
         format: Vec<Property<SongProperty>>,
         limit: Option<u32>,
         sort: StickerPaneSort,
+        target: StickerTarget,
     },
     Search,
     AlbumArt,
@@ -250,6 +268,13 @@ pub enum PaneType {
         levels: Vec<BrowserTagConfig>,
     },
     Cava,
+    Statistics {
+        breakdowns: Vec<String>,
+    },
+    AlbumGrid {
+        width: u16,
+        height: u16,
+    },
     Empty,
 }
 
@@ -307,7 +332,7 @@ impl TryFrom<PaneTypeFile> for PaneType {
             PaneTypeFile::AlbumArtists => PaneType::AlbumArtists,
             PaneTypeFile::Albums => PaneType::Albums,
             PaneTypeFile::Playlists => PaneType::Playlists,
-            PaneTypeFile::Sticker { sticker, format, limit, sort } => PaneType::Sticker {
+            PaneTypeFile::Sticker { sticker, format, limit, sort, target } => PaneType::Sticker {
                 sticker,
                 format: format
                     .unwrap_or_default()
@@ -316,6 +341,7 @@ impl TryFrom<PaneTypeFile> for PaneType {
                     .try_collect()?,
                 limit,
                 sort,
+                target,
             },
             PaneTypeFile::Search => PaneType::Search,
             PaneTypeFile::AlbumArt => PaneType::AlbumArt,
@@ -388,6 +414,8 @@ impl TryFrom<PaneTypeFile> for PaneType {
                 }
             }
             PaneTypeFile::Cava => PaneType::Cava,
+            PaneTypeFile::Statistics { breakdowns } => PaneType::Statistics { breakdowns },
+            PaneTypeFile::AlbumGrid { width, height } => PaneType::AlbumGrid { width, height },
             PaneTypeFile::Empty() => PaneType::Empty,
         })
     }
@@ -0,0 +1,171 @@
+use std::collections::BTreeSet;
+
+use rmpc_mpd::filter::Tag;
+
+use super::{
+    Config,
+    artists::AlbumDateTag,
+    sort_mode::SortMode,
+    tabs::PaneType,
+    theme::properties::{Property, PropertyKind, PropertyKindOrText, SongProperty, Transform},
+};
+
+/// Tags rmpc reads directly regardless of the config, for example when sorting
+/// songs, looking up lyrics or displaying streams
+const ALWAYS_USED_TAGS: [&str; 8] =
+    ["artist", "albumartist", "album", "title", "track", "disc", "genre", "name"];
+
+impl Config {
+    /// Tags which should be requested from MPD for songs, `None` when tag type
+    /// negotiation is disabled and MPD should send all of them
+    pub fn tag_types(&self) -> Option<Vec<String>> {
+        if !self.negotiate_tag_types {
+            return None;
+        }
+
+        let mut tags: BTreeSet<String> =
+            ALWAYS_USED_TAGS.iter().map(|tag| (*tag).to_owned()).collect();
+
+        for column in &self.theme.song_table_format {
+            visit_property(&column.prop, &mut |prop| add(&mut tags, prop));
+            visit_property(&column.label, &mut |prop| add(&mut tags, prop));
+        }
+        for prop in &self.theme.browser_song_format.0 {
+            visit_property(prop, &mut |prop| add(&mut tags, prop));
+        }
+        for prop in self
+            .theme
+            .header
+            .rows
+            .iter()
+            .flat_map(|row| row.left.iter().chain(&row.center).chain(&row.right))
+        {
+            visit_property(prop, &mut |prop| add_kind(&mut tags, prop));
+        }
+        for pane in self
+            .tabs
+            .tabs
+            .values()
+            .flat_map(|tab| tab.panes.panes_iter())
+            .chain(self.theme.layout.panes_iter())
+        {
+            for prop in &pane.border_title {
+                visit_property(prop, &mut |prop| add_kind(&mut tags, prop));
+            }
+        }
+        for pane in &self.active_panes {
+            match pane {
+                PaneType::Sticker { format, .. } => {
+                    for prop in format {
+                        visit_property(prop, &mut |prop| add(&mut tags, prop));
+                    }
+                }
+                PaneType::Property { content, .. } => {
+                    for prop in content {
+                        visit_property(prop, &mut |prop| add_kind(&mut tags, prop));
+                    }
+                }
+                PaneType::Browser { levels } => {
+                    for level in levels {
+                        level
+                            .group_by
+                            .iter()
+                            .chain(level.sort_by.iter().flatten())
+                            .flatten()
+                            .for_each(|prop| add(&mut tags, prop));
+                        for prop in &level.format {
+                            visit_property(prop, &mut |prop| add(&mut tags, prop));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let SortMode::Format(props) = &self.browser_song_sort.mode {
+            for prop in props {
+                add(&mut tags, prop);
+            }
+        }
+
+        tags.extend(self.search.tags.iter().map(|tag| tag.value.to_lowercase()));
+        tags.extend(self.artists.album_date_tags.iter().map(|tag| match tag {
+            AlbumDateTag::Date => "date".to_owned(),
+            AlbumDateTag::OriginalDate => "originaldate".to_owned(),
+        }));
+
+        Some(tags.into_iter().collect())
+    }
+}
+
+fn add(tags: &mut BTreeSet<String>, prop: &SongProperty) {
+    if let Ok(tag) = Tag::try_from(prop.clone()) {
+        tags.insert(tag.to_string().to_lowercase());
+    }
+}
+
+fn add_kind(tags: &mut BTreeSet<String>, prop: &PropertyKind) {
+    if let PropertyKind::Song(prop) = prop {
+        add(tags, prop);
+    }
+}
+
+fn visit_property<T>(prop: &Property<T>, cb: &mut impl FnMut(&T)) {
+    match &prop.kind {
+        PropertyKindOrText::Text(_) | PropertyKindOrText::Sticker(_) => {}
+        PropertyKindOrText::Property(value) => cb(value),
+        PropertyKindOrText::Group(items) => {
+            for item in items {
+                visit_property(item, cb);
+            }
+        }
+        PropertyKindOrText::Transform(Transform::Truncate { content, .. }) => {
+            visit_property(content, cb);
+        }
+        PropertyKindOrText::Transform(Transform::Replace { content, replacements }) => {
+            visit_property(content, cb);
+            for replacement in replacements.values() {
+                visit_property(replacement, cb);
+            }
+        }
+    }
+
+    if let Some(default) = &prop.default {
+        visit_property(default, cb);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{
+        Config,
+        theme::properties::{Property, PropertyKindOrText, SongProperty},
+    };
+
+    #[test]
+    fn tag_types_are_not_negotiated_by_default() {
+        let config = Config::default();
+
+        assert_eq!(config.tag_types(), None);
+    }
+
+    #[test]
+    fn tag_types_include_tags_used_by_the_config() {
+        let mut config = Config { negotiate_tag_types: true, ..Config::default() };
+        config.theme.browser_song_format.0.push(Property {
+            kind: PropertyKindOrText::Text("label".to_owned()),
+            style: None,
+            default: Some(Box::new(Property {
+                kind: PropertyKindOrText::Property(SongProperty::Other("Composer".to_owned())),
+                style: None,
+                default: None,
+            })),
+        });
+
+        let tags = config.tag_types().expect("negotiation to be enabled");
+
+        for tag in ["title", "artist", "album", "date", "composer"] {
+            assert!(tags.contains(&tag.to_owned()), "'{tag}' missing in {tags:?}");
+        }
+        assert!(!tags.contains(&"musicbrainz_trackid".to_owned()));
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use ratatui::style::{Color, Style};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    StyleFile,
+    style::{StringColor, ToConfigOr},
+};
+use crate::shared::album_palette::AlbumPalette;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlbumColorsConfig {
+    pub enabled: bool,
+    pub accent_fallback: Color,
+    pub secondary_fallback: Color,
+}
+
+impl AlbumColorsConfig {
+    /// Palette used when album colors are disabled or no colors could be
+    /// picked from the current album art
+    pub fn fallback(&self) -> AlbumPalette {
+        AlbumPalette { accent: self.accent_fallback, secondary: self.secondary_fallback }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AlbumColorsConfigFile {
+    pub(super) enabled: bool,
+    pub(super) accent_fallback: String,
+    pub(super) secondary_fallback: String,
+}
+
+impl Default for AlbumColorsConfigFile {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            accent_fallback: "blue".to_string(),
+            secondary_fallback: "cyan".to_string(),
+        }
+    }
+}
+
+impl TryFrom<AlbumColorsConfigFile> for AlbumColorsConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: AlbumColorsConfigFile) -> Result<Self> {
+        let fallback = |color: String| -> Result<Color> {
+            Ok(StringColor(Some(color)).to_color()?.unwrap_or_default())
+        };
+
+        Ok(AlbumColorsConfig {
+            enabled: value.enabled,
+            accent_fallback: fallback(value.accent_fallback)?,
+            secondary_fallback: fallback(value.secondary_fallback)?,
+        })
+    }
+}
+
+/// Color of the album palette a style color is set to by `"$album_accent"` or
+/// `"$album_secondary"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlbumColor {
+    Accent,
+    Secondary,
+}
+
+impl AlbumColor {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "$album_accent" => Some(Self::Accent),
+            "$album_secondary" => Some(Self::Secondary),
+            _ => None,
+        }
+    }
+
+    fn pick(self, palette: AlbumPalette) -> Color {
+        match self {
+            AlbumColor::Accent => palette.accent,
+            AlbumColor::Secondary => palette.secondary,
+        }
+    }
+}
+
+/// Theme styles which can use album colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlbumStyleField {
+    Borders,
+    HighlightBorder,
+    HighlightedItem,
+    CurrentItem,
+    TabBarActive,
+    TabBarInactive,
+    ProgressBarElapsed,
+    ProgressBarThumb,
+    ProgressBarTrack,
+}
+
+/// Colors of a style which are taken from the album palette
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AlbumStyleColors {
+    pub fg: Option<AlbumColor>,
+    pub bg: Option<AlbumColor>,
+}
+
+impl AlbumStyleColors {
+    pub(super) fn apply(self, style: &mut Style, palette: AlbumPalette) {
+        if let Some(color) = self.fg {
+            style.fg = Some(color.pick(palette));
+        }
+        if let Some(color) = self.bg {
+            style.bg = Some(color.pick(palette));
+        }
+    }
+}
+
+/// Theme styles which use album colors, remembered so that only those have to
+/// be updated when the palette changes
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AlbumStyles {
+    fallback: AlbumPalette,
+    fields: Vec<(AlbumStyleField, AlbumStyleColors)>,
+}
+
+impl AlbumStyles {
+    pub(super) fn new(fallback: AlbumPalette) -> Self {
+        Self { fallback, fields: Vec::new() }
+    }
+
+    /// Album colors used by the given field
+    pub(super) fn colors(&self, field: AlbumStyleField) -> AlbumStyleColors {
+        self.fields.iter().find(|(f, _)| *f == field).map(|(_, colors)| *colors).unwrap_or_default()
+    }
+
+    /// Converts the style of the given field with its album colors set to the
+    /// fallbacks. Colors which are not set fall back to the defaults,
+    /// `inherited` tells which of the defaults are album colors.
+    pub(super) fn convert(
+        &mut self,
+        field: AlbumStyleField,
+        style: Option<&StyleFile>,
+        default: (Option<Color>, Option<Color>),
+        inherited: AlbumStyleColors,
+    ) -> Result<Style> {
+        let album_color = |value: Option<&String>, inherited: Option<AlbumColor>| match value {
+            Some(value) => AlbumColor::parse(value),
+            None => inherited,
+        };
+        let colors = AlbumStyleColors {
+            fg: album_color(style.and_then(|s| s.fg.as_ref()), inherited.fg),
+            bg: album_color(style.and_then(|s| s.bg.as_ref()), inherited.bg),
+        };
+        let without_album_colors = style.map(|style| StyleFile {
+            fg: style.fg.clone().filter(|fg| AlbumColor::parse(fg).is_none()),
+            bg: style.bg.clone().filter(|bg| AlbumColor::parse(bg).is_none()),
+            modifiers: style.modifiers,
+        });
+
+        let mut result = without_album_colors.to_config_or(default.0, default.1)?;
+        if colors != AlbumStyleColors::default() {
+            colors.apply(&mut result, self.fallback);
+            self.fields.push((field, colors));
+        }
+
+        Ok(result)
+    }
+
+    /// The given palette or the fallback palette when there is none
+    pub(super) fn palette_or_fallback(&self, palette: Option<AlbumPalette>) -> AlbumPalette {
+        palette.unwrap_or(self.fallback)
+    }
+
+    pub(super) fn fields(&self) -> &[(AlbumStyleField, AlbumStyleColors)] {
+        &self.fields
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::AlbumColorsConfigFile;
+    use crate::{
+        config::theme::{StyleFile, TabBarFile, UiConfig, UiConfigFile},
+        shared::album_palette::AlbumPalette,
+    };
+
+    const PALETTE: AlbumPalette = AlbumPalette { accent: Color::Green, secondary: Color::Yellow };
+
+    fn theme(enabled: bool) -> UiConfigFile {
+        UiConfigFile {
+            highlighted_item_style: Some(
+                StyleFile::builder().fg("$album_accent".to_owned()).build(),
+            ),
+            current_item_style: Some(
+                StyleFile::builder().bg("$album_secondary".to_owned()).build(),
+            ),
+            album_colors: AlbumColorsConfigFile { enabled, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn patches_album_colors_in_styles() {
+        let mut config = UiConfig::try_from(theme(true)).unwrap();
+        assert_eq!(config.highlighted_item_style.fg, Some(Color::Blue));
+        assert_eq!(config.current_item_style.bg, Some(Color::Cyan));
+
+        config.apply_album_palette(Some(PALETTE));
+        assert_eq!(config.highlighted_item_style.fg, Some(Color::Green));
+        assert_eq!(config.current_item_style.bg, Some(Color::Yellow));
+
+        config.apply_album_palette(None);
+        assert_eq!(config.highlighted_item_style.fg, Some(Color::Blue));
+        assert_eq!(config.current_item_style.bg, Some(Color::Cyan));
+    }
+
+    #[test]
+    fn tab_bar_inherits_album_colors_of_current_item() {
+        let mut config = UiConfig::try_from(UiConfigFile {
+            tab_bar: TabBarFile { enabled: false, active_style: None, inactive_style: None },
+            ..theme(true)
+        })
+        .unwrap();
+
+        config.apply_album_palette(Some(PALETTE));
+
+        assert_eq!(config.tab_bar.active_style.bg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn uses_fallbacks_when_disabled() {
+        let mut config = UiConfig::try_from(theme(false)).unwrap();
+
+        config.apply_album_palette(Some(PALETTE));
+
+        assert_eq!(config.highlighted_item_style.fg, Some(Color::Blue));
+    }
+
+    #[test]
+    fn rejects_album_colors_outside_styles() {
+        let background = UiConfigFile {
+            background_color: Some("$album_accent".to_owned()),
+            ..Default::default()
+        };
+        let fallback = UiConfigFile {
+            album_colors: AlbumColorsConfigFile {
+                accent_fallback: "$album_secondary".to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(UiConfig::try_from(background).is_err());
+        assert!(UiConfig::try_from(fallback).is_err());
+    }
+}
@@ -11,6 +11,13 @@ use rmpc_mpd::commands::metadata_tag::MetadataTag;
 use rmpc_shared::paths::utils::{env_var_expand, tilde_expand};
 
 use self::{
+    album_colors::{
+        AlbumColorsConfig,
+        AlbumColorsConfigFile,
+        AlbumStyleColors,
+        AlbumStyleField,
+        AlbumStyles,
+    },
     header::{HeaderConfig, HeaderConfigFile},
     lyrics::{LyricsConfig, LyricsConfigFile},
     progress_bar::{ProgressBarConfig, ProgressBarConfigFile},
@@ -18,8 +25,12 @@ use self::{
     scrollbar::ScrollbarConfig,
     style::{StringColor, ToConfigOr},
 };
-use crate::config::theme::borders::{BorderSetLib, BorderSetLibFile};
+use crate::{
+    config::theme::borders::{BorderSetLib, BorderSetLibFile},
+    shared::album_palette::AlbumPalette,
+};
 
+pub mod album_colors;
 pub mod borders;
 pub mod cava;
 mod header;
@@ -79,6 +90,8 @@ pub struct UiConfig {
     pub lyrics: LyricsConfig,
     pub cava: CavaTheme,
     pub border_symbol_sets: BorderSetLib,
+    pub album_colors: AlbumColorsConfig,
+    pub album_styles: AlbumStyles,
 }
 
 impl Default for UiConfig {
@@ -126,6 +139,7 @@ pub struct UiConfigFile {
     pub(super) lyrics: LyricsConfigFile,
     pub(super) cava: CavaThemeFile,
     pub border_symbol_sets: BorderSetLibFile,
+    pub(super) album_colors: AlbumColorsConfigFile,
 }
 
 impl Default for UiConfigFile {
@@ -213,6 +227,7 @@ impl Default for UiConfigFile {
             lyrics: LyricsConfigFile::default(),
             cava: CavaThemeFile::default(),
             border_symbol_sets: BorderSetLibFile::default(),
+            album_colors: AlbumColorsConfigFile::default(),
         }
     }
 }
@@ -437,16 +452,57 @@ impl TryFrom<UiConfigFile> for UiConfig {
         let fg_color = StringColor(value.text_color.clone()).to_color()?;
         let bg_color = StringColor(value.background_color).to_color()?;
         let header_bg_color = StringColor(value.header_background_color).to_color()?.or(bg_color);
-        let border = value.borders_style.to_config_or(None, None)?;
+        let album_colors: AlbumColorsConfig = value.album_colors.try_into()?;
+        let mut album_styles = AlbumStyles::new(album_colors.fallback());
+        let none = AlbumStyleColors::default();
+        let border = album_styles.convert(
+            AlbumStyleField::Borders,
+            value.borders_style.as_ref(),
+            (None, None),
+            none,
+        )?;
+        let highlight_border = album_styles.convert(
+            AlbumStyleField::HighlightBorder,
+            value.highlight_border_style.as_ref(),
+            (border.fg, border.bg),
+            album_styles.colors(AlbumStyleField::Borders),
+        )?;
         let border_set_lib: BorderSetLib = value.border_symbol_sets.try_into()?;
         let components = convert_components(value.components, &border_set_lib)?;
-        let current_style = value.current_item_style.to_config_or(None, None)?;
-        let highlighted_style = value.highlighted_item_style.to_config_or(None, None)?;
+        let current_style = album_styles.convert(
+            AlbumStyleField::CurrentItem,
+            value.current_item_style.as_ref(),
+            (None, None),
+            none,
+        )?;
+        let highlighted_style = album_styles.convert(
+            AlbumStyleField::HighlightedItem,
+            value.highlighted_item_style.as_ref(),
+            (None, None),
+            none,
+        )?;
+        let tab_bar = TabBar {
+            active_style: album_styles.convert(
+                AlbumStyleField::TabBarActive,
+                value.tab_bar.active_style.as_ref(),
+                (current_style.fg.or(fg_color), current_style.bg),
+                album_styles.colors(AlbumStyleField::CurrentItem),
+            )?,
+            inactive_style: album_styles.convert(
+                AlbumStyleField::TabBarInactive,
+                value.tab_bar.inactive_style.as_ref(),
+                (fg_color, header_bg_color),
+                none,
+            )?,
+        };
+        let progress_bar = value.progress_bar.into_config(&mut album_styles)?;
 
         Ok(Self {
             layout: value.layout.convert(&components, &border_set_lib)?,
             components,
             cava: value.cava.into_config(bg_color)?,
+            album_colors,
+            album_styles,
             background_color: bg_color,
             draw_borders: value.draw_borders,
             format_tag_separator: value.format_tag_separator,
@@ -458,15 +514,13 @@ impl TryFrom<UiConfigFile> for UiConfig {
             text_color: fg_color,
             header_background_color: header_bg_color,
             borders_style: border,
-            highlight_border_style: value
-                .highlight_border_style
-                .to_config_or(border.fg, border.bg)?,
+            highlight_border_style: highlight_border,
             symbols: value.symbols.into(),
             scrollbar: value
                 .scrollbar
                 .map(|sc| sc.into_config(border.fg.unwrap_or_default()))
                 .transpose()?,
-            progress_bar: value.progress_bar.into_config()?,
+            progress_bar,
             song_table_format: TryInto::<QueueTableColumns>::try_into(value.song_table_format)?.0,
             song_table_album_separator: value.song_table_album_separator,
             header_column_widths: [
@@ -480,16 +534,7 @@ impl TryFrom<UiConfigFile> for UiConfig {
                 value.browser_column_widths[1],
                 value.browser_column_widths[2],
             ],
-            tab_bar: TabBar {
-                active_style: value
-                    .tab_bar
-                    .active_style
-                    .to_config_or(current_style.fg.or(fg_color), current_style.bg)?,
-                inactive_style: value
-                    .tab_bar
-                    .inactive_style
-                    .to_config_or(fg_color, header_bg_color)?,
-            },
+            tab_bar,
             highlighted_item_style: highlighted_style,
             current_item_style: current_style,
             default_album_art: value
@@ -511,3 +556,29 @@ impl TryFrom<UiConfigFile> for UiConfig {
         })
     }
 }
+
+impl UiConfig {
+    /// Sets the styles which use album colors to the colors of the palette, or
+    /// to the fallbacks when album colors are disabled or there is no palette
+    pub fn apply_album_palette(&mut self, palette: Option<AlbumPalette>) {
+        let palette =
+            self.album_styles.palette_or_fallback(palette.filter(|_| self.album_colors.enabled));
+        for (field, colors) in self.album_styles.fields().to_vec() {
+            colors.apply(self.album_style_mut(field), palette);
+        }
+    }
+
+    fn album_style_mut(&mut self, field: AlbumStyleField) -> &mut Style {
+        match field {
+            AlbumStyleField::Borders => &mut self.borders_style,
+            AlbumStyleField::HighlightBorder => &mut self.highlight_border_style,
+            AlbumStyleField::HighlightedItem => &mut self.highlighted_item_style,
+            AlbumStyleField::CurrentItem => &mut self.current_item_style,
+            AlbumStyleField::TabBarActive => &mut self.tab_bar.active_style,
+            AlbumStyleField::TabBarInactive => &mut self.tab_bar.inactive_style,
+            AlbumStyleField::ProgressBarElapsed => &mut self.progress_bar.elapsed_style,
+            AlbumStyleField::ProgressBarThumb => &mut self.progress_bar.thumb_style,
+            AlbumStyleField::ProgressBarTrack => &mut self.progress_bar.track_style,
+        }
+    }
+}
@@ -2,7 +2,10 @@ use anyhow::Result;
 use ratatui::style::{Color, Style};
 use serde::{Deserialize, Serialize};
 
-use super::{StyleFile, style::ToConfigOr};
+use super::{
+    StyleFile,
+    album_colors::{AlbumStyleColors, AlbumStyleField, AlbumStyles},
+};
 
 #[derive(Debug, Default, Clone)]
 pub struct ProgressBarConfig {
@@ -63,7 +66,10 @@ impl Default for ProgressBarConfigFile {
 }
 
 impl ProgressBarConfigFile {
-    pub(super) fn into_config(mut self) -> Result<ProgressBarConfig> {
+    pub(super) fn into_config(
+        mut self,
+        album_styles: &mut AlbumStyles,
+    ) -> Result<ProgressBarConfig> {
         if self.symbols.len() == 3 {
             self.symbols.resize(5, String::default());
             let s0 = self.symbols[0].clone();
@@ -84,9 +90,24 @@ impl ProgressBarConfigFile {
 
         Ok(ProgressBarConfig {
             symbols: [start, elapsed, thumb, track, end],
-            elapsed_style: self.elapsed_style.to_config_or(Some(Color::Blue), None)?,
-            thumb_style: self.thumb_style.to_config_or(Some(Color::Blue), None)?,
-            track_style: self.track_style.to_config_or(Some(Color::Black), None)?,
+            elapsed_style: album_styles.convert(
+                AlbumStyleField::ProgressBarElapsed,
+                self.elapsed_style.as_ref(),
+                (Some(Color::Blue), None),
+                AlbumStyleColors::default(),
+            )?,
+            thumb_style: album_styles.convert(
+                AlbumStyleField::ProgressBarThumb,
+                self.thumb_style.as_ref(),
+                (Some(Color::Blue), None),
+                AlbumStyleColors::default(),
+            )?,
+            track_style: album_styles.convert(
+                AlbumStyleField::ProgressBarTrack,
+                self.track_style.as_ref(),
+                (Some(Color::Black), None),
+                AlbumStyleColors::default(),
+            )?,
             use_track_when_empty: self.use_track_when_empty,
         })
     }
@@ -101,6 +122,7 @@ mod tests {
     use crate::config::theme::{
         Style,
         StyleFile,
+        album_colors::AlbumStyles,
         progress_bar::ProgressBarConfigFile,
         style::Modifiers,
     };
@@ -112,7 +134,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap().symbols;
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap().symbols;
 
         assert_eq!(result, [
             "a".to_owned(),
@@ -136,7 +158,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap().symbols;
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap().symbols;
 
         assert_eq!(result, [
             "a".to_owned(),
@@ -172,7 +194,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap();
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap();
 
         assert_eq!(result.elapsed_style, expected);
     }
@@ -202,7 +224,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap();
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap();
 
         assert_eq!(result.track_style, expected);
     }
@@ -232,7 +254,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap();
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap();
 
         assert_eq!(result.thumb_style, expected);
     }
@@ -249,7 +271,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap();
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap();
 
         assert_eq!(
             result.track_style.add_modifier,
@@ -269,7 +291,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap();
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap();
 
         assert_eq!(
             result.thumb_style.add_modifier,
@@ -289,7 +311,7 @@ mod tests {
             ..Default::default()
         };
 
-        let result = input.into_config().unwrap();
+        let result = input.into_config(&mut AlbumStyles::default()).unwrap();
 
         assert_eq!(
             result.elapsed_style.add_modifier,
@@ -30,6 +30,8 @@ pub enum SongPropertyFile {
     Channels(),
     Added(),
     LastModified(),
+    Priority(),
+    Range(),
     Other(String),
 }
 
@@ -51,6 +53,8 @@ pub enum SongProperty {
     Channels(),
     Added(),
     LastModified(),
+    Priority(),
+    Range(),
     #[strum(to_string = "Other(\"{0}\")")]
     Other(String),
 }
@@ -129,7 +133,10 @@ pub enum StatusPropertyFile {
     Elapsed,
     Duration,
     Crossfade,
+    MixRampDb,
+    MixRampDelay,
     Bitrate,
+    ReplayGain,
     QueueLength {
         #[serde(default = "defaults::default_thousands_separator")]
         thousands_separator: String,
@@ -193,7 +200,10 @@ pub enum StatusProperty {
     Elapsed,
     Duration,
     Crossfade,
+    MixRampDb,
+    MixRampDelay,
     Bitrate,
+    ReplayGain,
     QueueLength {
         thousands_separator: String,
     },
@@ -358,6 +368,8 @@ impl From<SongPropertyFile> for SongProperty {
             SongPropertyFile::Channels() => SongProperty::Channels(),
             SongPropertyFile::Added() => SongProperty::Added(),
             SongPropertyFile::LastModified() => SongProperty::LastModified(),
+            SongPropertyFile::Priority() => SongProperty::Priority(),
+            SongPropertyFile::Range() => SongProperty::Range(),
         }
     }
 }
@@ -412,6 +424,9 @@ impl TryFrom<StatusPropertyFile> for StatusProperty {
             StatusPropertyFile::Volume => StatusProperty::Volume,
             StatusPropertyFile::Bitrate => StatusProperty::Bitrate,
             StatusPropertyFile::Crossfade => StatusProperty::Crossfade,
+            StatusPropertyFile::MixRampDb => StatusProperty::MixRampDb,
+            StatusPropertyFile::MixRampDelay => StatusProperty::MixRampDelay,
+            StatusPropertyFile::ReplayGain => StatusProperty::ReplayGain,
             StatusPropertyFile::Repeat => StatusProperty::Repeat {
                 on_label: defaults::default_on_label(),
                 off_label: defaults::default_off_label(),
@@ -674,6 +689,8 @@ impl TryFrom<SongProperty> for Tag {
             SongProperty::Channels() => bail!("Cannot convert Channels to Tag"),
             SongProperty::Added() => bail!("Cannot convert Added to Tag"),
             SongProperty::LastModified() => bail!("Cannot convert LastModified to Tag"),
+            SongProperty::Priority() => bail!("Cannot convert Priority to Tag"),
+            SongProperty::Range() => bail!("Cannot convert Range to Tag"),
             SongProperty::Other(val) => Ok(Tag::Custom(val)),
         }
     }
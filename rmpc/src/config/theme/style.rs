@@ -165,6 +165,12 @@ impl TryFrom<&[u8]> for crate::config::ConfigColor {
             b"light_magenta" => Ok(Self::LightMagenta),
             b"light_cyan" => Ok(Self::LightCyan),
             b"white" => Ok(Self::White),
+            s @ (b"$album_accent" | b"$album_secondary") => {
+                bail!(
+                    "'{}' can only be used in the borders, highlighted item, current item, tab bar and progress bar styles",
+                    String::from_utf8_lossy(s)
+                )
+            }
             s if input.len() == 7 && input.first().is_some_and(|v| v == &b'#') => {
                 let res = std::str::from_utf8(s.strip_prefix(b"#").context("")?)?;
                 let res = u32::from_str_radix(res, 16).context("")?;
@@ -329,6 +335,12 @@ mod tests {
         assert_eq!(ConfigColor::try_from("white".as_bytes()).unwrap(), ConfigColor::White);
     }
 
+    #[test]
+    fn album_values_are_rejected() {
+        assert!(ConfigColor::try_from(b"$album_accent".as_slice()).is_err());
+        assert!(ConfigColor::try_from(b"$album_secondary".as_slice()).is_err());
+    }
+
     #[test]
     fn hex_value() {
         let input: &[u8] = b"#ff00ff";
@@ -1,7 +1,7 @@
 use std::{
     collections::BTreeSet,
     io::{BufRead, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
@@ -11,8 +11,8 @@ use itertools::Itertools;
 use rmpc_mpd::{
     client::Client,
     commands::{IdleEvent, State, mpd_config::MpdConfig, volume::Bound},
-    filter::{Filter, Tag},
-    mpd_client::{AlbumArtOrder, MpdClient, MpdCommand, StickerFindOptions, ValueChange},
+    filter::{Filter, FilterExpr, Tag},
+    mpd_client::{MpdClient, MpdCommand, StickerFindOptions, ValueChange},
     proto_client::ProtoClient,
     queue_position::QueuePosition,
     single_or_range::SingleOrRange,
@@ -21,17 +21,21 @@ use rmpc_shared::version::Version;
 
 use crate::{
     config::{
-        cli::{AddRandom, Command, Provider, StickerCmd},
+        cli::{AddRandom, Command, Provider, SnapshotCmd, StickerCmd},
         cli_config::CliConfig,
     },
     ctx::Ctx,
     shared::{
+        album_art_cache::AlbumArtCache,
         args,
+        duplicates,
+        duration_format::parse_duration,
         events::AppEvent,
         ext::duration::DurationExt,
         lrc::{LrcIndex, get_lrc_path},
         macros::status_error,
         mpd_client_ext::MpdClientExt,
+        snapshots::{Snapshot, Snapshots},
         ytdlp::{self, YtDlp, YtDlpHost},
     },
 };
@@ -133,10 +137,37 @@ impl Command {
                 result.into_files().for_each(|file| println!("{file}"));
                 Ok(())
             })),
-            Command::Play { position: None } => Ok(Box::new(|_, client| Ok(client.play()?))),
-            Command::Play { position: Some(pos) } => {
+            Command::Search { query, exact } => {
+                let filter = [Filter::from(query.parse::<FilterExpr>()?)];
+                Ok(Box::new(move |_, client| {
+                    let songs = if exact {
+                        client.find(&filter)?
+                    } else {
+                        client.search(&filter, false)?
+                    };
+                    println!("{}", serde_json::ser::to_string(&songs)?);
+                    Ok(())
+                }))
+            }
+            Command::Play { position: None, at: None } => {
+                Ok(Box::new(|_, client| Ok(client.play()?)))
+            }
+            Command::Play { position: Some(pos), at: None } => {
                 Ok(Box::new(move |_, client| Ok(client.play_pos(pos)?)))
             }
+            Command::Play { position, at: Some(at) } => {
+                let time = parse_duration(&at)?;
+                Ok(Box::new(move |_, client| {
+                    let pos = match position {
+                        Some(pos) => pos,
+                        None => client
+                            .get_status()?
+                            .song
+                            .ok_or_else(|| anyhow::anyhow!("No song is playing"))?,
+                    };
+                    Ok(client.seek(pos, time)?)
+                }))
+            }
             Command::Pause => Ok(Box::new(|_, client| Ok(client.pause()?))),
             Command::TogglePause => Ok(Box::new(|_, client| {
                 let status = client.get_status()?;
@@ -187,6 +218,19 @@ impl Command {
             Command::Consume { value } => {
                 Ok(Box::new(move |_, client| Ok(client.consume((value).into())?)))
             }
+            Command::ReplayGain { mode: Some(mode) } => {
+                Ok(Box::new(move |_, client| Ok(client.replay_gain_mode(mode.into())?)))
+            }
+            Command::ReplayGain { mode: None } => Ok(Box::new(|_, client| {
+                println!("{}", serde_json::ser::to_string(&client.replay_gain_status()?)?);
+                Ok(())
+            })),
+            Command::MixRampDb { value } => {
+                Ok(Box::new(move |_, client| Ok(client.mixramp_db(value)?)))
+            }
+            Command::MixRampDelay { value } => {
+                Ok(Box::new(move |_, client| Ok(client.mixramp_delay(value)?)))
+            }
             Command::ToggleRepeat => Ok(Box::new(move |_, client| {
                 let status = client.get_status()?;
                 Ok(client.repeat(!status.repeat)?)
@@ -257,6 +301,12 @@ impl Command {
                     {
                         files.reverse();
                     }
+                    let uris = files
+                        .iter()
+                        .filter(|file| !file.starts_with(&dir))
+                        .map(|file| file.to_string_lossy())
+                        .collect_vec();
+                    client.ensure_url_handlers(&uris.iter().map(AsRef::as_ref).collect_vec())?;
                     for file in files {
                         if file.starts_with(&dir) {
                             client.add(
@@ -278,6 +328,8 @@ impl Command {
                 if let Some(QueuePosition::Absolute(_) | QueuePosition::RelativeAdd(_)) = position {
                     files.reverse();
                 }
+                let uris = files.iter().map(|file| file.to_string_lossy()).collect_vec();
+                client.ensure_url_handlers(&uris.iter().map(AsRef::as_ref).collect_vec())?;
                 for file in files {
                     client.add(&file.to_string_lossy(), position)?;
                 }
@@ -316,9 +368,50 @@ impl Command {
 
                 Ok(())
             })),
+            Command::Priority { priority, positions, ids } => Ok(Box::new(move |_, client| {
+                if !ids.is_empty() {
+                    client.prio_id(priority, &ids)?;
+                    return Ok(());
+                }
+
+                let mut positions: BTreeSet<_> = positions.into_iter().collect();
+                let status = client.get_status()?;
+
+                if positions.is_empty() {
+                    positions = std::io::stdin()
+                        .lock()
+                        .lines()
+                        .map_ok(|line| line.parse())
+                        .flatten()
+                        .try_collect()?;
+                }
+
+                if positions.is_empty() {
+                    return Ok(());
+                }
+
+                if let Some(idx) =
+                    positions.iter().find(|idx| **idx >= status.playlistlength as usize)
+                {
+                    bail!(
+                        "Song index {idx} is out of bounds (queue length: {})",
+                        status.playlistlength
+                    );
+                }
+
+                let songs = positions.into_iter().map(SingleOrRange::single).collect_vec();
+                client.prio(priority, &songs)?;
+
+                Ok(())
+            })),
             Command::AddYt { url, position } => {
                 let config = config.clone();
                 Ok(Box::new(move |_, client| {
+                    // Fail early instead of after the download when MPD cannot play the
+                    // downloaded files
+                    if let Some(cache_dir) = config.cache_dir.as_deref().and_then(Path::to_str) {
+                        client.ensure_url_handlers(&[cache_dir])?;
+                    }
                     // Idle with message subsystem, the cli client is never subscribed to any
                     // channels so this will idle indefinitely
                     client.enter_idle(Some(IdleEvent::Message))?;
@@ -354,6 +447,9 @@ impl Command {
 
                 let config = config.clone();
                 Ok(Box::new(move |_, client| {
+                    if let Some(cache_dir) = config.cache_dir.as_deref().and_then(Path::to_str) {
+                        client.ensure_url_handlers(&[cache_dir])?;
+                    }
                     // Idle with message subsystem, the cli client is never subscribed to any
                     // channels so this will idle indefinitely
                     client.enter_idle(Some(IdleEvent::Message))?;
@@ -390,6 +486,15 @@ impl Command {
                 println!("{}", serde_json::ser::to_string(&client.decoders()?)?);
                 Ok(())
             })),
+            Command::Duplicates { path } => {
+                let cache_dir = config.cache_dir.clone();
+                Ok(Box::new(move |_, client| {
+                    let groups =
+                        duplicates::find_duplicates(client, path.as_deref(), cache_dir.as_deref())?;
+                    println!("{}", serde_json::ser::to_string(&groups)?);
+                    Ok(())
+                }))
+            }
             Command::Outputs => Ok(Box::new(|_, client| {
                 println!("{}", serde_json::ser::to_string(&client.outputs()?)?);
                 Ok(())
@@ -449,35 +554,64 @@ impl Command {
                 println!("{}", serde_json::ser::to_string(&client.list_mounts()?)?);
                 Ok(())
             })),
+            Command::ListNeighbors => Ok(Box::new(|_, client| {
+                println!("{}", serde_json::ser::to_string(&client.list_neighbors()?)?);
+                Ok(())
+            })),
             Command::ListPartitions => Ok(Box::new(|_, client| {
                 println!("{}", serde_json::ser::to_string(&client.list_partitions()?.0)?);
                 Ok(())
             })),
-            Command::AlbumArt { output } => Ok(Box::new(move |_, client| {
-                let Some(song) = client.get_current_song()? else {
-                    std::process::exit(3);
-                };
+            Command::AlbumArt { output, cache_clear, prefetch } => {
+                let order = config.album_art_order;
+                let cache =
+                    AlbumArtCache::new(config.cache_dir.as_deref(), &config.album_art_cache);
+                Ok(Box::new(move |_, client| {
+                    if cache_clear || prefetch {
+                        let Some(cache) = &cache else {
+                            bail!("Album art cache requires 'cache_dir' to be configured");
+                        };
+                        if cache_clear {
+                            cache.clear()?;
+                        }
+                        if prefetch {
+                            let summary = cache.prefetch(client, order)?;
+                            println!("{}", serde_json::ser::to_string(&summary)?);
+                        }
+                    }
 
-                let album_art = client.find_album_art(&song.file, AlbumArtOrder::EmbeddedFirst)?;
+                    let Some(output) = output else {
+                        return Ok(());
+                    };
 
-                let Some(album_art) = album_art else {
-                    std::process::exit(2);
-                };
+                    let Some(song) = client.get_current_song()? else {
+                        std::process::exit(3);
+                    };
 
-                if &output == "-" {
-                    std::io::stdout().write_all(&album_art)?;
-                    std::io::stdout().flush()?;
-                    Ok(())
-                } else {
-                    std::fs::OpenOptions::new()
-                        .write(true)
-                        .create(true)
-                        .truncate(true)
-                        .open(output)?
-                        .write_all(&album_art)?;
-                    Ok(())
-                }
-            })),
+                    let album_art = match &cache {
+                        Some(cache) => cache.find_album_art(client, &song, order)?,
+                        None => client.find_album_art(&song.file, order)?,
+                    };
+
+                    let Some(album_art) = album_art else {
+                        std::process::exit(2);
+                    };
+
+                    if &output == "-" {
+                        std::io::stdout().write_all(&album_art)?;
+                        std::io::stdout().flush()?;
+                        Ok(())
+                    } else {
+                        std::fs::OpenOptions::new()
+                            .write(true)
+                            .create(true)
+                            .truncate(true)
+                            .open(output)?
+                            .write_all(&album_art)?;
+                        Ok(())
+                    }
+                }))
+            }
             Command::Sticker { cmd: StickerCmd::Set { uri, key, value } } => {
                 Ok(Box::new(move |_, client| {
                     client.set_sticker(&uri, &key, &value)?;
@@ -522,6 +656,24 @@ impl Command {
                     Ok(())
                 }))
             }
+            Command::Snapshot { cmd: SnapshotCmd::Save { name } } => {
+                let snapshots = Snapshots::from_state_dir()?;
+                Ok(Box::new(move |_, client| {
+                    snapshots.save(&name, &Snapshot::capture(client)?)?;
+                    Ok(())
+                }))
+            }
+            Command::Snapshot { cmd: SnapshotCmd::Restore { name } } => {
+                let snapshot = Snapshots::from_state_dir()?.load(&name)?;
+                Ok(Box::new(move |_, client| snapshot.restore(client)))
+            }
+            Command::Snapshot { cmd: SnapshotCmd::List } => {
+                let snapshots = Snapshots::from_state_dir()?.list()?;
+                Ok(Box::new(move |_, _| {
+                    println!("{}", serde_json::ser::to_string(&snapshots)?);
+                    Ok(())
+                }))
+            }
             Command::SendMessage { channel, content } => Ok(Box::new(move |_, client| {
                 client.send_message(&channel, &content)?;
                 Ok(())
@@ -1,13 +1,16 @@
 use std::{
+    collections::HashMap,
     path::PathBuf,
     sync::{Arc, LazyLock},
     time::{Duration, Instant},
 };
 
+use anyhow::Result;
 use crossbeam::channel::{Receiver, RecvTimeoutError};
 use enum_map::{EnumMap, enum_map};
 use ratatui::{Terminal, layout::Rect, prelude::Backend};
 use rmpc_mpd::{
+    client::Client,
     commands::{IdleEvent, State},
     mpd_client::{MpdClient, SaveMode},
 };
@@ -18,6 +21,7 @@ use crate::{
     ctx::Ctx,
     shared::{
         album_art,
+        album_palette::{fetch_album_palette, refresh_album_palette},
         events::{AppEvent, LoadAlbumArtResult, WorkDone},
         ext::error::ErrorExt,
         id::{self, Id},
@@ -26,13 +30,18 @@ use crate::{
         mpd_client_ext::MpdClientExt,
         mpd_query::{
             EXTERNAL_COMMAND,
+            GLOBAL_ALBUM_PALETTE_UPDATE,
+            GLOBAL_HISTORY_UPDATE,
             GLOBAL_QUEUE_UPDATE,
+            GLOBAL_REPLAY_GAIN_UPDATE,
             GLOBAL_STATUS_UPDATE,
             GLOBAL_STICKERS_UPDATE,
             GLOBAL_VOLUME_UPDATE,
             MpdQueryResult,
             run_status_update,
         },
+        snapshots::{EXIT_SNAPSHOT, Snapshot, Snapshots},
+        undo,
     },
     ui::{
         KeyHandleResult,
@@ -188,7 +197,10 @@ fn main_task<B: Backend + std::io::Write>(
 
                     new_config.active_panes =
                         Config::calc_active_panes(&new_config.tabs.tabs, &new_config.theme.layout);
+                    let old_tag_types = ctx.config.tag_types();
                     ctx.config = Arc::new(*new_config);
+                    renegotiate_tag_types(old_tag_types.as_deref(), &mut ctx);
+                    refresh_album_palette(&mut ctx);
                     let max_fps = f64::from(ctx.config.max_fps);
                     min_frame_duration = Duration::from_secs_f64(1f64 / max_fps);
 
@@ -247,7 +259,10 @@ fn main_task<B: Backend + std::io::Write>(
 
                     config.active_panes =
                         Config::calc_active_panes(&config.tabs.tabs, &config.theme.layout);
+                    let old_tag_types = ctx.config.tag_types();
                     ctx.config = Arc::new(config);
+                    renegotiate_tag_types(old_tag_types.as_deref(), &mut ctx);
+                    refresh_album_palette(&mut ctx);
 
                     if let Err(err) = ui.on_event(UiEvent::ConfigChanged, &mut ctx) {
                         log::error!(error:? = err; "UI failed to handle config changed event");
@@ -286,6 +301,11 @@ fn main_task<B: Backend + std::io::Write>(
                                     log::error!(error:? = err; "Failed to run on_exit command");
                                 }
                             }
+                            if ctx.config.snapshot_on_exit
+                                && let Err(err) = save_exit_snapshot(&ctx)
+                            {
+                                log::error!(error:? = err; "Failed to save queue snapshot on exit");
+                            }
                             if let Err(err) = ui.on_event(UiEvent::Exit, &mut ctx) {
                                 log::error!(error:? = err; "UI failed to handle quit event");
                             }
@@ -487,6 +507,16 @@ fn main_task<B: Backend + std::io::Write>(
                             log::error!(error:? = err; "UI failed to handle image resized event");
                         }
                     }
+                    WorkDone::ThumbnailEncoded { key, data } => {
+                        let event = match data {
+                            Ok(data) => UiEvent::ThumbnailEncoded { key, data },
+                            Err(err) => UiEvent::ThumbnailEncodeFailed { key, err },
+                        };
+
+                        if let Err(err) = ui.on_event(event, &mut ctx) {
+                            log::error!(error:? = err; "UI failed to handle thumbnail encoded event");
+                        }
+                    }
                     WorkDone::LyricsIndexed { index } => {
                         ctx.lrc_index = index;
                         if let Err(err) = ui.on_event(UiEvent::LyricsIndexed, &mut ctx) {
@@ -506,6 +536,9 @@ fn main_task<B: Backend + std::io::Write>(
                             ctx.set_stickers(stickers);
                             render_wanted = true;
                         }
+                        (GLOBAL_HISTORY_UPDATE, None, MpdQueryResult::HistoryEdit(applied)) => {
+                            undo::finish(&ctx, applied);
+                        }
                         (
                             GLOBAL_STATUS_UPDATE,
                             None,
@@ -635,21 +668,35 @@ fn main_task<B: Backend + std::io::Write>(
                                 ctx.song_played = Some(Duration::ZERO);
                             }
 
-                            if song_changed
-                                && let Err(err) = ui.on_event(UiEvent::SongChanged, &mut ctx)
-                            {
-                                status_error!(error:? = err; "UI failed to handle idle event, error: '{}'", err.to_status());
+                            if song_changed {
+                                if let Err(err) = ui.on_event(UiEvent::SongChanged, &mut ctx) {
+                                    status_error!(error:? = err; "UI failed to handle idle event, error: '{}'", err.to_status());
+                                }
+                                fetch_album_palette(&mut ctx);
                             }
 
                             ctx.last_status_update = Instant::now();
                             render_wanted = true;
                         }
+                        (
+                            GLOBAL_ALBUM_PALETTE_UPDATE,
+                            None,
+                            MpdQueryResult::AlbumPalette(palette),
+                        ) => {
+                            ctx.set_album_palette(palette);
+                            render_wanted = true;
+                        }
                         (GLOBAL_VOLUME_UPDATE, None, MpdQueryResult::Volume(volume)) => {
                             ctx.status.volume = volume;
                             render_wanted = true;
                         }
-                        (GLOBAL_QUEUE_UPDATE, None, MpdQueryResult::Queue(queue)) => {
+                        (GLOBAL_REPLAY_GAIN_UPDATE, None, MpdQueryResult::ReplayGain(mode)) => {
+                            ctx.replay_gain_mode = Some(mode);
+                            render_wanted = true;
+                        }
+                        (GLOBAL_QUEUE_UPDATE, None, MpdQueryResult::Queue { queue, version }) => {
                             ctx.queue = queue.unwrap_or_default();
+                            ctx.queue_version = version;
                             ctx.cached_queue_time_total =
                                 ctx.queue.iter().filter_map(|s| s.duration).sum();
                             render_wanted = true;
@@ -658,6 +705,48 @@ fn main_task<B: Backend + std::io::Write>(
                                 status_error!(error:? = err; "Ui failed to handle queue changed event, error: '{}'", err.to_status());
                             }
                         }
+                        (GLOBAL_QUEUE_UPDATE, None, MpdQueryResult::QueueDiff(diff)) => {
+                            if ctx.queue_version.as_ref() == Some(&diff.from) {
+                                let version = diff.to.clone();
+                                if diff.apply(&mut ctx.queue) {
+                                    ctx.queue_version = Some(version);
+                                    ctx.cached_queue_time_total =
+                                        ctx.queue.iter().filter_map(|s| s.duration).sum();
+                                    render_wanted = true;
+                                    log::debug!(len = ctx.queue.len(); "Queue changes applied");
+                                    if let Err(err) = ui.on_event(UiEvent::QueueChanged, &mut ctx) {
+                                        status_error!(error:? = err; "Ui failed to handle queue changed event, error: '{}'", err.to_status());
+                                    }
+                                } else {
+                                    log::warn!(
+                                        "Failed to apply queue changes, fetching the whole queue"
+                                    );
+                                    ctx.queue_version = None;
+                                    handle_idle_event(IdleEvent::Playlist, &ctx);
+                                }
+                            } else if ctx.queue_version.as_ref() != Some(&diff.to) {
+                                // Queue was updated by another query in the meantime, ask for
+                                // changes since the current version
+                                log::debug!(diff:? = diff.from, current:? = ctx.queue_version; "Queue changes are outdated");
+                                handle_idle_event(IdleEvent::Playlist, &ctx);
+                            }
+                        }
+                        (
+                            album_art::ALBUM_ART,
+                            target,
+                            MpdQueryResult::AlbumArtWithPalette { data, palette },
+                        ) => {
+                            ctx.set_album_palette(palette);
+                            if let Err(err) = ui.on_command_finished(
+                                album_art::ALBUM_ART,
+                                target,
+                                MpdQueryResult::AlbumArt(data),
+                                &mut ctx,
+                            ) {
+                                log::error!(error:? = err; "UI failed to handle command finished event");
+                            }
+                            render_wanted = true;
+                        }
                         (
                             EXTERNAL_COMMAND,
                             None,
@@ -775,6 +864,8 @@ fn main_task<B: Backend + std::io::Write>(
                     }
                 }
                 AppEvent::Reconnected => {
+                    // MPD might have been restarted in the meantime which resets the queue version
+                    ctx.queue_version = None;
                     for ev in [IdleEvent::Player, IdleEvent::Playlist, IdleEvent::Options] {
                         handle_idle_event(ev, &ctx);
                     }
@@ -842,6 +933,31 @@ fn main_task<B: Backend + std::io::Write>(
     terminal
 }
 
+fn fetch_whole_queue(client: &mut Client<'_>) -> Result<MpdQueryResult> {
+    // Status is fetched first so the queue is at least as new as its version
+    let version = client.get_status()?.queue_version();
+    let queue = client.playlist_info()?;
+    Ok(MpdQueryResult::Queue { queue, version })
+}
+
+/// Sends the tag types used by the current config to MPD if they changed and
+/// refetches the songs so they contain the newly requested tags
+fn renegotiate_tag_types(old_tag_types: Option<&[String]>, ctx: &mut Ctx) {
+    let tag_types = ctx.config.tag_types();
+    if tag_types.as_deref() == old_tag_types {
+        return;
+    }
+
+    ctx.command(move |_, client| {
+        client.set_tag_types(tag_types)?;
+        Ok(())
+    });
+    ctx.queue_version = None;
+    for ev in [IdleEvent::Player, IdleEvent::Playlist] {
+        handle_idle_event(ev, ctx);
+    }
+}
+
 fn handle_idle_event(event: IdleEvent, ctx: &Ctx) {
     match event {
         IdleEvent::Mixer if ctx.supported_commands.contains("getvol") => {
@@ -869,6 +985,12 @@ fn handle_idle_event(event: IdleEvent, ctx: &Ctx) {
                     source_event: Some(IdleEvent::Options),
                 })
             });
+            // Replay gain mode is not part of the status response
+            if ctx.supported_commands.contains("replay_gain_status") {
+                ctx.query().id(GLOBAL_REPLAY_GAIN_UPDATE).replace_id("replay_gain").query(
+                    move |client| Ok(MpdQueryResult::ReplayGain(client.replay_gain_status()?.mode)),
+                );
+            }
         }
         IdleEvent::Player => {
             ctx.query().id(GLOBAL_STATUS_UPDATE).replace_id("status").query(move |client| {
@@ -881,10 +1003,18 @@ fn handle_idle_event(event: IdleEvent, ctx: &Ctx) {
             });
         }
         IdleEvent::Playlist => {
-            ctx.query()
-                .id(GLOBAL_QUEUE_UPDATE)
-                .replace_id("playlist")
-                .query(move |client| Ok(MpdQueryResult::Queue(client.playlist_info()?)));
+            if let Some(version) = ctx.queue_version.clone() {
+                let known_positions: HashMap<u32, usize> =
+                    ctx.queue.iter().enumerate().map(|(idx, song)| (song.id, idx)).collect();
+                ctx.query().id(GLOBAL_QUEUE_UPDATE).replace_id("playlist").query(move |client| {
+                    match client.queue_diff(&version, &known_positions)? {
+                        Some(diff) => Ok(MpdQueryResult::QueueDiff(diff)),
+                        None => fetch_whole_queue(client),
+                    }
+                });
+            } else {
+                ctx.query().id(GLOBAL_QUEUE_UPDATE).replace_id("playlist").query(fetch_whole_queue);
+            }
 
             // Do not replace because we want to update currently loaded playlist if any
             // Also have to query every time because the current song position may change
@@ -931,13 +1061,14 @@ fn handle_idle_event(event: IdleEvent, ctx: &Ctx) {
                 })
             });
         }
-        IdleEvent::Output => {}
-        IdleEvent::Partition
-        | IdleEvent::Subscription
-        | IdleEvent::Message
-        | IdleEvent::Neighbor
-        | IdleEvent::Mount => {
+        IdleEvent::Output | IdleEvent::Neighbor | IdleEvent::Mount => {}
+        IdleEvent::Partition | IdleEvent::Subscription | IdleEvent::Message => {
             log::warn!(event:?; "Received unhandled event");
         }
     }
 }
+
+fn save_exit_snapshot(ctx: &Ctx) -> Result<()> {
+    let snapshot = ctx.query_sync(Snapshot::capture)?;
+    Snapshots::from_state_dir()?.save(EXIT_SNAPSHOT, &snapshot)
+}
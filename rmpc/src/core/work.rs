@@ -62,6 +62,9 @@ fn handle_work_request(
             Ok(WorkDone::SingleLrcIndexed { path, metadata })
         }
         WorkRequest::ResizeImage(fn_once) => Ok(WorkDone::ImageResized { data: fn_once() }),
+        WorkRequest::EncodeThumbnail { key, encode } => {
+            Ok(WorkDone::ThumbnailEncoded { key, data: encode() })
+        }
         WorkRequest::SearchYt { query, kind, limit, interactive, position } => {
             if ytdlp.is_none() {
                 anyhow::bail!("Youtube support requires 'cache_dir' to be configured")
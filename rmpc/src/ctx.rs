@@ -11,7 +11,7 @@ use bon::bon;
 use crossbeam::channel::{SendError, Sender, bounded};
 use rmpc_mpd::{
     client::Client,
-    commands::{Song, State, Status},
+    commands::{QueueVersion, Song, State, Status, replay_gain::ReplayGainMode},
     mpd_client::MpdClient,
 };
 use rmpc_shared::version::Version;
@@ -29,6 +29,7 @@ use crate::{
     },
     core::scheduler::{Scheduler, time_provider::DefaultTimeProvider},
     shared::{
+        album_palette::AlbumPalette,
         events::ClientRequest,
         keys::KeyResolver,
         lrc::{Lrc, LrcIndex},
@@ -36,9 +37,10 @@ use crate::{
         mpd_client_ext::MpdClientExt,
         mpd_query::MpdQuerySync,
         ring_vec::RingVec,
+        undo::History,
         ytdlp::YtDlpManager,
     },
-    ui::{StatusMessage, input::InputManager},
+    ui::{StatusMessage, UiEvent, input::InputManager},
 };
 
 pub const FETCH_SONG_STICKERS: &str = "fetch_song_stickers";
@@ -55,6 +57,9 @@ pub struct Ctx {
     #[cfg(not(test))]
     current_song: Option<Song>,
     pub(crate) queue: Vec<Song>,
+    /// Version of [`Ctx::queue`], `None` when unknown and the next queue update
+    /// has to fetch the whole queue
+    pub(crate) queue_version: Option<QueueVersion>,
     #[cfg(test)]
     pub(crate) stickers: HashMap<String, HashMap<String, String>>,
     #[cfg(not(test))]
@@ -83,6 +88,17 @@ pub struct Ctx {
     pub(crate) key_resolver: KeyResolver,
     pub(crate) ytdlp_manager: YtDlpManager,
     pub(crate) cached_queue_time_total: Duration,
+    /// Current `replay_gain_mode`, `None` when MPD does not support it
+    pub(crate) replay_gain_mode: Option<ReplayGainMode>,
+    /// Queue and stored playlist changes which can be undone
+    pub(crate) history: RefCell<History>,
+    /// Colors picked from the current album art, `None` when they are not
+    /// available and the fallbacks should be used
+    pub(crate) album_palette: Option<AlbumPalette>,
+    /// File whose album art was last requested for the album art pane together
+    /// with its palette, so the album art is not fetched a second time
+    #[debug(skip)]
+    pub(crate) album_art_requested: Cell<Option<String>>,
 }
 
 #[bon]
@@ -103,10 +119,23 @@ impl Ctx {
         };
         log::debug!(supported_commands:? = supported_commands; "Supported commands by server");
 
+        if let Some(tag_types) = config.tag_types()
+            && let Err(err) = client.set_tag_types(Some(tag_types))
+        {
+            status_warn!(err:?; "Failed to negotiate tag types with MPD: {err}");
+        }
+
         let status = client.get_status()?;
+        // Status is fetched first so the queue is at least as new as its version
+        let queue_version = status.queue_version();
         let queue = client.playlist_info()?.unwrap_or_default();
         let current_song = client.get_current_song()?;
         let cached_queue_time_total = queue.iter().filter_map(|s| s.duration).sum();
+        let replay_gain_mode = if supported_commands.contains("replay_gain_status") {
+            Some(client.replay_gain_status()?.mode)
+        } else {
+            None
+        };
 
         if !supported_commands.contains("albumart") || !supported_commands.contains("readpicture") {
             config.album_art.method = ImageMethod::None;
@@ -126,6 +155,7 @@ impl Ctx {
             config: std::sync::Arc::new(config),
             status,
             queue,
+            queue_version,
             current_song,
             stickers: HashMap::new(),
             active_tab,
@@ -145,9 +175,30 @@ impl Ctx {
             input: InputManager::default(),
             key_resolver,
             cached_queue_time_total,
+            replay_gain_mode,
+            history: RefCell::new(History::default()),
+            album_palette: None,
+            album_art_requested: Cell::new(None),
         })
     }
 
+    /// Sets the colors picked from the current album art and updates the theme
+    /// styles which use them
+    pub(crate) fn set_album_palette(&mut self, palette: Option<AlbumPalette>) {
+        self.album_palette = palette;
+        if !self.config.theme.album_colors.enabled {
+            return;
+        }
+
+        // Only the first update after the config was loaded clones it, the other
+        // threads keep their own reference to it
+        std::sync::Arc::make_mut(&mut self.config).theme.apply_album_palette(palette);
+        if let Err(err) = self.app_event_sender.send(AppEvent::UiEvent(UiEvent::AlbumColorsChanged))
+        {
+            log::error!(error:? = err; "Failed to send album colors changed event");
+        }
+    }
+
     // TODO: Error comes from crossebeam, try to remove later if it gets solved
     // upstream
     #[allow(clippy::result_large_err)]
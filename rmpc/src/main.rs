@@ -14,6 +14,7 @@ use rmpc_mpd::{
     client::{Client, MIN_SUPPORTED_VERSION},
     mpd_client::MpdClient,
     proto_client::SocketClient,
+    recording::{Recorder, Recording, ReplayServer},
 };
 use rmpc_shared::{
     env::ENV,
@@ -52,6 +53,7 @@ use crate::{
 
 #[cfg(test)]
 mod tests {
+    mod cli_commands;
     mod cli_integration;
     pub mod fixtures;
     mod remote_ipc;
@@ -65,6 +67,18 @@ mod ui;
 
 fn main() -> Result<()> {
     let mut args = Args::parse();
+    let recorder = args
+        .record
+        .as_ref()
+        .map(Recorder::create)
+        .transpose()
+        .context("Failed to create the MPD protocol recording")?;
+    if let Some(path) = &args.replay {
+        let server = ReplayServer::start(Recording::read(path)?)
+            .context("Failed to start MPD replay server")?;
+        args.address = Some(server.addr().to_string());
+    }
+
     match args.command {
         Some(Command::Config { current: false }) => {
             std::io::stdout().write_all(include_bytes!("../../assets/example_config.ron"))?;
@@ -141,6 +155,7 @@ fn main() -> Result<()> {
                 None,
                 false,
                 false,
+                recorder.clone(),
             )?;
 
             client.set_read_timeout(Some(Duration::from_secs(3)))?;
@@ -233,12 +248,25 @@ fn main() -> Result<()> {
                 None,
                 false,
                 config.enable_keepalive,
+                recorder.clone(),
             )
             .and_then(|mut client| -> Result<_, _> {
                 let version = client.version();
                 let commands = client.commands().map(|c| c.0)?;
                 let not_commands = client.not_commands().map(|c| c.0)?;
-                Ok((version, commands, not_commands))
+                let mut protocol_features =
+                    client.protocol_features.iter().cloned().collect::<Vec<_>>();
+                protocol_features.sort();
+                let available_features = client.protocol_available().map(|c| c.0);
+                let url_handlers = client.url_handlers().map(|c| c.0)?;
+                Ok((
+                    version,
+                    commands,
+                    not_commands,
+                    protocol_features,
+                    available_features,
+                    url_handlers,
+                ))
             });
 
             let theme_path = config.theme_name.as_ref().and_then(|theme_name| {
@@ -261,11 +289,24 @@ fn main() -> Result<()> {
             println!("{:<20} {mpd_host}", "MPD_HOST");
             println!("{:<20} {mpd_port}", "MPD_PORT");
             match mpd_info {
-                Ok((version, commands, not_commands)) => {
+                Ok((
+                    version,
+                    commands,
+                    not_commands,
+                    protocol_features,
+                    available_features,
+                    url_handlers,
+                )) => {
                     println!("{:<20} Success", "Connection");
                     println!("{:<20} {version}", "Version");
                     println!("{:<20} {commands:?}", "Supported commands");
                     println!("{:<20} {not_commands:?}", "Unsupported commands");
+                    println!("{:<20} {protocol_features:?}", "Protocol features");
+                    match available_features {
+                        Ok(features) => println!("{:<20} {features:?}", "Available features"),
+                        Err(err) => println!("{:<20} {err}", "Available features"),
+                    }
+                    println!("{:<20} {url_handlers:?}", "URL handlers");
                 }
                 Err(err) => {
                     println!("{:<20} Error {err:?}", "Connection");
@@ -337,6 +378,7 @@ fn main() -> Result<()> {
                 args.partition.partition,
                 args.partition.autocreate,
                 false,
+                recorder.clone(),
             )?;
             client.set_read_timeout(None)?;
             let (sender, _receiver) = crossbeam::channel::unbounded();
@@ -411,6 +453,7 @@ fn main() -> Result<()> {
                 args.partition.partition,
                 args.partition.autocreate,
                 config.enable_keepalive,
+                recorder.clone(),
             )
             .context("Failed to connect to MPD")?;
 
@@ -1,7 +1,13 @@
 use crate::{
     config::{album_art::ImageMethod, tabs::PaneType},
     ctx::Ctx,
-    shared::{events::WorkRequest, mpd_client_ext::MpdClientExt as _, mpd_query::MpdQueryResult},
+    shared::{
+        album_art_cache::AlbumArtCache,
+        album_palette::pick_palette,
+        events::WorkRequest,
+        mpd_client_ext::MpdClientExt as _,
+        mpd_query::MpdQueryResult,
+    },
 };
 
 pub const ALBUM_ART: &str = "album_art";
@@ -39,12 +45,36 @@ pub fn fetch_album_art(ctx: &Ctx) -> Option<()> {
 
 pub fn request_album_art_from_mpd(file: String, ctx: &Ctx) {
     let order = ctx.config.album_art.order;
+    let cache = AlbumArtCache::new(ctx.config.cache_dir.as_deref(), &ctx.config.album_art.cache);
+    let song = ctx.current_song().filter(|song| song.file == file).cloned();
+    // The album colors are picked from the album art of the current song, so they
+    // come along with it instead of fetching the album art a second time
+    let palette_background = (ctx.config.theme.album_colors.enabled && song.is_some())
+        .then_some(ctx.config.theme.background_color);
+    if palette_background.is_some() {
+        ctx.album_art_requested.set(Some(file.clone()));
+    }
     ctx.query().id(ALBUM_ART).replace_id(ALBUM_ART).target(PaneType::AlbumArt).query(move |client| {
             let start = std::time::Instant::now();
             log::debug!(file = file.as_str(); "Searching for album art");
-            let result = client.find_album_art(&file, order)?;
-            log::debug!(elapsed:? = start.elapsed(), size = result.as_ref().map(|v|v.len()); "Found album art");
+            let result = match (cache, song) {
+                (Some(cache), Some(song)) => cache.find_album_art(client, &song, order),
+                _ => client.find_album_art(&file, order).map_err(Into::into),
+            };
+            let Some(background) = palette_background else {
+                let result = result?;
+                log::debug!(elapsed:? = start.elapsed(), size = result.as_ref().map(|v|v.len()); "Found album art");
+                return Ok(MpdQueryResult::AlbumArt(result));
+            };
+
+            // The album colors have to be reset even when the album art cannot be fetched
+            let data = result
+                .inspect_err(|err| log::error!(err:?; "Failed to fetch album art"))
+                .ok()
+                .flatten();
+            log::debug!(elapsed:? = start.elapsed(), size = data.as_ref().map(|v|v.len()); "Found album art");
+            let palette = pick_palette(data.as_deref(), background);
 
-            Ok(MpdQueryResult::AlbumArt(result))
+            Ok(MpdQueryResult::AlbumArtWithPalette { data, palette })
         });
 }
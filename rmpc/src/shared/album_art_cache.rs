@@ -0,0 +1,638 @@
+use std::{
+    collections::HashSet,
+    fs::{self, Metadata},
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use image::{ImageFormat, imageops::FilterType};
+use itertools::Itertools;
+use ratatui::layout::Rect;
+use rmpc_mpd::{
+    client::Client,
+    commands::{Song, lsinfo::LsInfoEntry},
+    errors::MpdError,
+    mpd_client::{AlbumArtOrder, MpdClient},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::{
+        Size,
+        album_art::{AlbumArtCacheConfig, AlbumArtCacheKey},
+    },
+    shared::{image::available_size_px, mpd_client_ext::MpdClientExt as _},
+};
+
+const ALBUM_ART_DIR: &str = "album_art";
+const KEYS_DIR: &str = "keys";
+const IMAGES_DIR: &str = "images";
+/// Content of the key file of an album without album art
+const MISSING: &str = "missing";
+/// Albums without album art are asked for again after this long in case the
+/// art was added since
+const MISSING_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// File whose modification time marks the last eviction
+const EVICTION_MARKER: &str = "last_eviction";
+/// Minimal time between evictions triggered by storing new album art
+const EVICTION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Album art stored in `cache_dir`. Images are stored under the hash of their
+/// content so albums with the same art share a single file, keys only point to
+/// them or mark the album as having no album art. Scaled down variants of the
+/// images are stored alongside them.
+#[derive(Debug, Clone)]
+pub struct AlbumArtCache {
+    dir: PathBuf,
+    key: AlbumArtCacheKey,
+    max_size: u64,
+    max_age: Option<Duration>,
+}
+
+/// What a key file points to
+#[derive(Debug, PartialEq, Eq)]
+enum Cached {
+    Image(PathBuf),
+    Missing,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct PrefetchSummary {
+    /// Album art fetched from MPD
+    pub fetched: usize,
+    /// Album art which was already cached
+    pub cached: usize,
+    /// Albums without album art
+    pub missing: usize,
+    /// Albums whose album art could not be fetched
+    pub failed: usize,
+}
+
+impl AlbumArtCache {
+    /// Returns none if the cache is disabled or `cache_dir` is not configured
+    pub fn new(cache_dir: Option<&Path>, config: &AlbumArtCacheConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+
+        Some(Self {
+            dir: cache_dir?.join(ALBUM_ART_DIR),
+            key: config.key,
+            max_size: config.max_size,
+            max_age: config.max_age,
+        })
+    }
+
+    /// Key under which the album art of the song is stored
+    pub fn key(&self, song: &Song) -> String {
+        let album = song.metadata.get("album").map(|album| album.first());
+        match (self.key, album) {
+            (AlbumArtCacheKey::Album, Some(album)) => {
+                let album_artist =
+                    song.metadata.get("albumartist").map_or("", |artist| artist.first());
+                format!("album\0{album_artist}\0{album}")
+            }
+            (AlbumArtCacheKey::Album | AlbumArtCacheKey::Directory, _) => {
+                format!("dir\0{}", directory(&song.file))
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let key_path = self.key_path(key);
+        let Some(Cached::Image(path)) = self.cached(&key_path) else {
+            return None;
+        };
+        match fs::read(&path) {
+            Ok(data) => {
+                touch(&key_path);
+                touch(&path);
+                Some(data)
+            }
+            Err(err) => {
+                log::debug!(err:?, path:?; "Cached album art is missing, removing its key");
+                let _ = fs::remove_file(&key_path);
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        matches!(self.cached(&self.key_path(key)), Some(Cached::Image(path)) if path.exists())
+    }
+
+    /// Whether the album was recently found to have no album art
+    pub fn is_missing(&self, key: &str) -> bool {
+        self.cached(&self.key_path(key)) == Some(Cached::Missing)
+    }
+
+    /// Stores the album art under the key. Does not evict anything, see
+    /// [`AlbumArtCache::evict`].
+    pub fn insert(&self, key: &str, data: &[u8]) -> Result<()> {
+        let hash = hash(data);
+        let path = self.dir.join(IMAGES_DIR).join(&hash);
+        if path.exists() {
+            touch(&path);
+        } else {
+            write_atomic(&path, data)?;
+        }
+
+        write_atomic(&self.key_path(key), hash.as_bytes())
+    }
+
+    /// Remembers that the album has no album art so it is not requested from
+    /// MPD again until [`MISSING_TTL`] passes
+    pub fn insert_missing(&self, key: &str) -> Result<()> {
+        write_atomic(&self.key_path(key), MISSING.as_bytes())
+    }
+
+    /// Album art of the song from the cache. It is requested from MPD and
+    /// stored in the cache if it is not cached yet.
+    pub fn find_album_art(
+        &self,
+        client: &mut Client<'_>,
+        song: &Song,
+        order: AlbumArtOrder,
+    ) -> Result<Option<Vec<u8>>> {
+        let key = self.key(song);
+        if let Some(data) = self.get(&key) {
+            log::debug!(file = song.file.as_str(); "Found album art in cache");
+            return Ok(Some(data));
+        }
+        if self.is_missing(&key) {
+            log::debug!(file = song.file.as_str(); "Album art is cached as missing");
+            return Ok(None);
+        }
+
+        let data = client.find_album_art(&song.file, order)?;
+        let result = match &data {
+            Some(data) => self.insert(&key, data).and_then(|()| self.evict_if_due()),
+            None => self.insert_missing(&key),
+        };
+        if let Err(err) = result {
+            log::warn!(err:?; "Failed to store album art in cache");
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the image scaled down to fit the area. The scaled down variant
+    /// is cached so it does not have to be resized again next time. Returns
+    /// the original image if it already fits, is animated or the variant
+    /// cannot be created.
+    pub fn fit(&self, data: Arc<Vec<u8>>, area: Rect, max_size_px: Size) -> Arc<Vec<u8>> {
+        match self.fitted_variant(&data, area, max_size_px) {
+            Ok(Some(variant)) => Arc::new(variant),
+            Ok(None) => data,
+            Err(err) => {
+                log::warn!(err:?; "Failed to create resized album art variant");
+                data
+            }
+        }
+    }
+
+    fn fitted_variant(
+        &self,
+        data: &[u8],
+        area: Rect,
+        max_size_px: Size,
+    ) -> Result<Option<Vec<u8>>> {
+        if data.starts_with(b"GIF") {
+            return Ok(None);
+        }
+        let Some(size) = available_size_px(area, max_size_px) else {
+            return Ok(None);
+        };
+        if size.width == 0 || size.height == 0 {
+            return Ok(None);
+        }
+
+        // Only the header is read to find out whether the image already fits
+        let (width, height) = image::ImageReader::new(Cursor::new(data))
+            .with_guessed_format()?
+            .into_dimensions()
+            .context("Unable to read image dimensions")?;
+        if width <= size.width.into() && height <= size.height.into() {
+            return Ok(None);
+        }
+
+        let path = self.dir.join(IMAGES_DIR).join(format!(
+            "{}-{}x{}.png",
+            hash(data),
+            size.width,
+            size.height
+        ));
+        if let Ok(variant) = fs::read(&path) {
+            touch(&path);
+            return Ok(Some(variant));
+        }
+
+        let image = image::load_from_memory(data).context("Unable to decode image")?;
+        let mut variant = Vec::new();
+        image
+            .resize(size.width.into(), size.height.into(), FilterType::Lanczos3)
+            .write_to(&mut Cursor::new(&mut variant), ImageFormat::Png)?;
+        write_atomic(&path, &variant)?;
+
+        Ok(Some(variant))
+    }
+
+    /// Removes images and keys not used for longer than `max_age` and then the
+    /// least recently used images until the cache fits into `max_size`
+    pub fn evict(&self) -> Result<()> {
+        let now = SystemTime::now();
+        let is_expired = |modified: SystemTime| {
+            self.max_age.is_some_and(|max_age| {
+                now.duration_since(modified).is_ok_and(|elapsed| elapsed > max_age)
+            })
+        };
+
+        for (path, metadata) in entries(&self.dir.join(KEYS_DIR))? {
+            if metadata.modified().is_ok_and(is_expired) {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        let mut total_size = 0;
+        for (path, metadata) in entries(&self.dir.join(IMAGES_DIR))?
+            .into_iter()
+            .sorted_by_key(|(_, metadata)| metadata.modified().ok())
+            .rev()
+        {
+            total_size += metadata.len();
+            if total_size > self.max_size || metadata.modified().is_ok_and(is_expired) {
+                log::debug!(path:?; "Evicting album art from cache");
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evicts unless the last eviction happened less than
+    /// [`EVICTION_INTERVAL`] ago so the cache directory is not scanned every
+    /// time new album art is stored
+    fn evict_if_due(&self) -> Result<()> {
+        let marker = self.dir.join(EVICTION_MARKER);
+        let is_recent =
+            fs::metadata(&marker).and_then(|metadata| metadata.modified()).is_ok_and(|modified| {
+                SystemTime::now()
+                    .duration_since(modified)
+                    .is_ok_and(|elapsed| elapsed < EVICTION_INTERVAL)
+            });
+        if is_recent {
+            return Ok(());
+        }
+
+        write_atomic(&marker, &[])?;
+        self.evict()
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir).with_context(|| {
+                format!("Failed to remove album art cache '{}'", self.dir.display())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Stores album art of all albums in the database which are not cached yet.
+    /// Albums MPD fails to provide the album art for are skipped.
+    pub fn prefetch(
+        &self,
+        client: &mut Client<'_>,
+        order: AlbumArtOrder,
+    ) -> Result<PrefetchSummary> {
+        let files = client
+            .list_all(None)?
+            .into_files()
+            .unique_by(|file| directory(file).to_owned())
+            .collect_vec();
+
+        let mut seen = HashSet::new();
+        let mut summary = PrefetchSummary::default();
+        for file in files {
+            let songs = match self.key {
+                AlbumArtCacheKey::Directory => vec![Song { file, ..Default::default() }],
+                AlbumArtCacheKey::Album => match client.lsinfo(Some(directory(&file))) {
+                    Ok(entries) => entries
+                        .into_iter()
+                        .filter_map(|entry| match entry {
+                            LsInfoEntry::File(song) => Some(song),
+                            LsInfoEntry::Dir(_) | LsInfoEntry::Playlist(_) => None,
+                        })
+                        .collect_vec(),
+                    Err(err @ MpdError::Mpd(_)) => {
+                        log::warn!(err:?, file = file.as_str(); "Failed to list songs for album art prefetch");
+                        summary.failed += 1;
+                        continue;
+                    }
+                    Err(err) => return Err(err.into()),
+                },
+            };
+
+            for song in songs {
+                let key = self.key(&song);
+                if !seen.insert(key.clone()) {
+                    continue;
+                }
+                if self.contains(&key) {
+                    summary.cached += 1;
+                    continue;
+                }
+                if self.is_missing(&key) {
+                    summary.missing += 1;
+                    continue;
+                }
+
+                match client.find_album_art(&song.file, order) {
+                    Ok(Some(data)) => {
+                        self.insert(&key, &data)?;
+                        summary.fetched += 1;
+                    }
+                    Ok(None) => {
+                        self.insert_missing(&key)?;
+                        summary.missing += 1;
+                    }
+                    // Only failures of the connection itself stop the prefetch
+                    Err(err @ MpdError::Mpd(_)) => {
+                        log::warn!(err:?, file = song.file.as_str(); "Failed to prefetch album art");
+                        summary.failed += 1;
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+
+        self.evict()?;
+        Ok(summary)
+    }
+
+    fn key_path(&self, key: &str) -> PathBuf {
+        self.dir.join(KEYS_DIR).join(hash(key.as_bytes()))
+    }
+
+    /// What the key file points to, none if there is no such key or the album
+    /// was found to have no album art too long ago
+    fn cached(&self, key_path: &Path) -> Option<Cached> {
+        let hash = fs::read_to_string(key_path).ok()?;
+        if hash == MISSING {
+            let modified = fs::metadata(key_path).and_then(|metadata| metadata.modified()).ok()?;
+            let elapsed = SystemTime::now().duration_since(modified).unwrap_or_default();
+            return (elapsed < MISSING_TTL).then_some(Cached::Missing);
+        }
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            log::warn!(key_path:?; "Invalid album art cache key");
+            return None;
+        }
+        Some(Cached::Image(self.dir.join(IMAGES_DIR).join(hash)))
+    }
+}
+
+/// Directory of the song or the song itself if it is in the root of the music
+/// directory
+fn directory(file: &str) -> &str {
+    file.rsplit_once('/').map_or(file, |(dir, _)| dir)
+}
+
+fn hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Marks the file as recently used
+fn touch(path: &Path) {
+    if let Err(err) =
+        fs::File::options().write(true).open(path).and_then(|f| f.set_modified(SystemTime::now()))
+    {
+        log::debug!(err:?, path:?; "Failed to update modification time of cached album art");
+    }
+}
+
+/// Writes into a temporary file first so readers never see a partially written
+/// file
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let dir = path.parent().context("Cache file has no parent directory")?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&tmp, data).with_context(|| format!("Failed to write '{}'", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to write '{}'", path.display()))?;
+    Ok(())
+}
+
+fn entries(dir: &Path) -> Result<Vec<(PathBuf, Metadata)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            Some((entry.path(), entry.metadata().ok()?))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::PathBuf,
+        time::{Duration, SystemTime},
+    };
+
+    use rmpc_mpd::commands::{Song, metadata_tag::MetadataTag};
+
+    use super::{AlbumArtCache, IMAGES_DIR, MISSING_TTL};
+    use crate::config::album_art::{AlbumArtCacheConfig, AlbumArtCacheKey};
+
+    fn cache(name: &str, config: &AlbumArtCacheConfig) -> (AlbumArtCache, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("rmpc-album-art-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        (AlbumArtCache::new(Some(&dir), config).unwrap(), dir)
+    }
+
+    fn song(file: &str, album: Option<&str>) -> Song {
+        Song {
+            file: file.to_owned(),
+            metadata: album
+                .map(|album| HashMap::from([("album".to_owned(), MetadataTag::from(album))]))
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn images(dir: &std::path::Path) -> usize {
+        std::fs::read_dir(dir.join("album_art").join(IMAGES_DIR)).unwrap().count()
+    }
+
+    #[test]
+    fn stores_and_loads() {
+        let (cache, dir) = cache("stores_and_loads", &AlbumArtCacheConfig::default());
+
+        cache.insert("key", b"image").unwrap();
+
+        assert_eq!(cache.get("key").unwrap(), b"image");
+        assert!(cache.get("other").is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remembers_missing_album_art() {
+        let (cache, dir) = cache("remembers_missing_album_art", &AlbumArtCacheConfig::default());
+
+        cache.insert_missing("key").unwrap();
+
+        assert!(cache.is_missing("key"));
+        assert!(!cache.contains("key"));
+        assert!(cache.get("key").is_none());
+        assert!(!cache.is_missing("other"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_album_art_expires() {
+        let (cache, dir) = cache("missing_album_art_expires", &AlbumArtCacheConfig::default());
+        cache.insert_missing("key").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(cache.key_path("key"))
+            .unwrap()
+            .set_modified(SystemTime::now() - MISSING_TTL - Duration::from_secs(1))
+            .unwrap();
+
+        assert!(!cache.is_missing("key"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn throttles_eviction() {
+        let config = AlbumArtCacheConfig { max_size: 0, ..Default::default() };
+        let (cache, dir) = cache("throttles_eviction", &config);
+        cache.insert("first", b"image").unwrap();
+        cache.evict_if_due().unwrap();
+        assert!(!cache.contains("first"));
+
+        cache.insert("second", b"image").unwrap();
+        cache.evict_if_due().unwrap();
+
+        assert!(cache.contains("second"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn shares_same_images() {
+        let (cache, dir) = cache("shares_same_images", &AlbumArtCacheConfig::default());
+
+        cache.insert("first", b"image").unwrap();
+        cache.insert("second", b"image").unwrap();
+
+        assert_eq!(images(&dir), 1);
+        assert_eq!(cache.get("second").unwrap(), b"image");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keys_by_directory() {
+        let config = AlbumArtCacheConfig { key: AlbumArtCacheKey::Directory, ..Default::default() };
+        let (cache, _) = cache("keys_by_directory", &config);
+
+        assert_eq!(
+            cache.key(&song("artist/album/01.flac", Some("a"))),
+            cache.key(&song("artist/album/02.flac", Some("b")))
+        );
+        assert_ne!(cache.key(&song("01.flac", None)), cache.key(&song("02.flac", None)));
+    }
+
+    #[test]
+    fn keys_by_album() {
+        let config = AlbumArtCacheConfig { key: AlbumArtCacheKey::Album, ..Default::default() };
+        let (cache, _) = cache("keys_by_album", &config);
+
+        assert_eq!(
+            cache.key(&song("album/cd1/01.flac", Some("album"))),
+            cache.key(&song("album/cd2/01.flac", Some("album")))
+        );
+        assert_ne!(
+            cache.key(&song("album/cd1/01.flac", None)),
+            cache.key(&song("album/cd2/01.flac", None))
+        );
+    }
+
+    #[test]
+    fn evicts_least_recently_used_over_max_size() {
+        let config = AlbumArtCacheConfig { max_size: 10, ..Default::default() };
+        let (cache, dir) = cache("evicts_least_recently_used_over_max_size", &config);
+        cache.insert("old", b"aaaaaa").unwrap();
+        cache.insert("new", b"bbbbbb").unwrap();
+        let path = dir.join("album_art").join(IMAGES_DIR).join(super::hash(b"aaaaaa"));
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+
+        cache.evict().unwrap();
+
+        assert!(cache.get("old").is_none());
+        assert_eq!(cache.get("new").unwrap(), b"bbbbbb");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn evicts_expired() {
+        let config =
+            AlbumArtCacheConfig { max_age: Some(Duration::from_secs(30)), ..Default::default() };
+        let (cache, dir) = cache("evicts_expired", &config);
+        cache.insert("old", b"image").unwrap();
+        for entry in walkdir::WalkDir::new(&dir).into_iter().filter_map(Result::ok) {
+            if entry.file_type().is_file() {
+                std::fs::File::options()
+                    .write(true)
+                    .open(entry.path())
+                    .unwrap()
+                    .set_modified(SystemTime::now() - Duration::from_secs(60))
+                    .unwrap();
+            }
+        }
+
+        cache.evict().unwrap();
+
+        assert!(!cache.contains("old"));
+        assert_eq!(images(&dir), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn clears() {
+        let (cache, dir) = cache("clears", &AlbumArtCacheConfig::default());
+        cache.insert("key", b"image").unwrap();
+
+        cache.clear().unwrap();
+
+        assert!(cache.get("key").is_none());
+        assert!(!dir.join("album_art").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn is_disabled_without_cache_dir() {
+        assert!(AlbumArtCache::new(None, &AlbumArtCacheConfig::default()).is_none());
+        assert!(
+            AlbumArtCache::new(Some(&std::env::temp_dir()), &AlbumArtCacheConfig {
+                enabled: false,
+                ..Default::default()
+            })
+            .is_none()
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::{Context, Result};
+use color_quant::NeuQuant;
+use ratatui::style::Color;
+
+use crate::{
+    ctx::Ctx,
+    shared::{
+        album_art_cache::AlbumArtCache,
+        mpd_client_ext::MpdClientExt as _,
+        mpd_query::{GLOBAL_ALBUM_PALETTE_UPDATE, MpdQueryResult},
+    },
+};
+
+/// Number of colors the album art is reduced to before picking the accents
+const PALETTE_SIZE: usize = 8;
+/// Minimal contrast ratio of the accents against the background, the WCAG
+/// recommendation for normal text
+const MIN_CONTRAST: f32 = 4.5;
+/// Minimal hue difference in degrees between the accent and secondary color
+const MIN_HUE_DISTANCE: f32 = 30.0;
+
+type Rgb = [u8; 3];
+
+/// Accent colors picked from the current album art
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AlbumPalette {
+    pub accent: Color,
+    pub secondary: Color,
+}
+
+impl AlbumPalette {
+    /// Picks the most prominent colorful colors of the image and adjusts them
+    /// to be readable on the background. A dark background is assumed when
+    /// none is given.
+    pub fn from_image(data: &[u8], background: Option<Color>) -> Result<Self> {
+        let image = image::load_from_memory(data)
+            .context("Unable to decode image")?
+            .thumbnail(64, 64)
+            .to_rgba8();
+
+        let quantized = NeuQuant::new(1, PALETTE_SIZE, image.as_raw());
+        let mut counts = [0usize; PALETTE_SIZE];
+        for pixel in image.pixels() {
+            counts[quantized.index_of(&pixel.0)] += 1;
+        }
+        let total = counts.iter().sum::<usize>().max(1) as f32;
+
+        let mut colors = quantized
+            .color_map_rgb()
+            .chunks_exact(3)
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|(rgb, count)| ([rgb[0], rgb[1], rgb[2]], count as f32 / total))
+            .collect::<Vec<_>>();
+        colors.sort_by(|(a, a_share), (b, b_share)| {
+            score(*b, *b_share).total_cmp(&score(*a, *a_share))
+        });
+
+        let (accent, _) = *colors.first().context("Image has no colors")?;
+        let secondary = colors
+            .iter()
+            .skip(1)
+            .find(|(color, _)| {
+                saturation(*color) > 0.15 && hue_distance(*color, accent) > MIN_HUE_DISTANCE
+            })
+            .or_else(|| colors.get(1))
+            .map_or(accent, |(color, _)| *color);
+
+        let background = to_rgb(background);
+        Ok(Self {
+            accent: to_color(with_contrast(accent, background)),
+            secondary: to_color(with_contrast(secondary, background)),
+        })
+    }
+}
+
+/// Requests the palette of the current song's album art. The album art is only
+/// fetched when the album art pane did not request it already, the palette is
+/// then picked from the pane's result. The palette is reset right away so the
+/// colors of the previous album are not kept when no palette can be picked for
+/// the new one.
+pub fn fetch_album_palette(ctx: &mut Ctx) {
+    let requested = ctx.album_art_requested.take();
+    ctx.album_palette = None;
+    if !ctx.config.theme.album_colors.enabled {
+        return;
+    }
+
+    let disabled_protos = &ctx.config.album_art.disabled_protocols;
+    let Some(song) = ctx
+        .current_song()
+        .filter(|song| !disabled_protos.iter().any(|proto| song.file.starts_with(proto)))
+        .cloned()
+    else {
+        ctx.set_album_palette(None);
+        return;
+    };
+    if requested.is_some_and(|file| file == song.file) {
+        return;
+    }
+
+    let order = ctx.config.album_art.order;
+    let background = ctx.config.theme.background_color;
+    let cache = AlbumArtCache::new(ctx.config.cache_dir.as_deref(), &ctx.config.album_art.cache);
+    ctx.query().id(GLOBAL_ALBUM_PALETTE_UPDATE).replace_id(GLOBAL_ALBUM_PALETTE_UPDATE).query(
+        move |client| {
+            let data = match cache {
+                Some(cache) => cache.find_album_art(client, &song, order),
+                None => client.find_album_art(&song.file, order).map_err(Into::into),
+            };
+            let data = data
+                .inspect_err(|err| log::warn!(err:?; "Failed to fetch album art for album colors"))
+                .ok()
+                .flatten();
+
+            Ok(MpdQueryResult::AlbumPalette(pick_palette(data.as_deref(), background)))
+        },
+    );
+}
+
+/// Applies the known palette to a newly loaded theme or requests it when it is
+/// not known yet
+pub fn refresh_album_palette(ctx: &mut Ctx) {
+    match ctx.album_palette {
+        Some(palette) => ctx.set_album_palette(Some(palette)),
+        None => fetch_album_palette(ctx),
+    }
+}
+
+/// Picks the palette from already fetched album art, failures are logged
+pub fn pick_palette(data: Option<&[u8]>, background: Option<Color>) -> Option<AlbumPalette> {
+    AlbumPalette::from_image(data?, background)
+        .inspect_err(|err| log::warn!(err:?; "Failed to pick colors from album art"))
+        .ok()
+}
+
+/// Prefers colorful colors covering a large part of the image, very dark and
+/// very light colors are penalized
+fn score(color: Rgb, share: f32) -> f32 {
+    let max = f32::from(*color.iter().max().unwrap_or(&0)) / 255.0;
+    let min = f32::from(*color.iter().min().unwrap_or(&0)) / 255.0;
+    let lightness = f32::midpoint(max, min);
+    let brightness = if max < 0.2 {
+        max
+    } else if lightness > 0.9 {
+        1.0 - lightness
+    } else {
+        1.0
+    };
+    share.sqrt() * (saturation(color) + 0.1) * brightness
+}
+
+fn saturation(color: Rgb) -> f32 {
+    let max = f32::from(*color.iter().max().unwrap_or(&0));
+    let min = f32::from(*color.iter().min().unwrap_or(&0));
+    if max == 0.0 { 0.0 } else { (max - min) / max }
+}
+
+fn hue(color: Rgb) -> f32 {
+    let [r, g, b] = color;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if max == min {
+        return 0.0;
+    }
+
+    let [r, g, b] = color.map(f32::from);
+    let delta = f32::from(max - min);
+    let hue = if max == color[0] {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == color[1] {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    hue * 60.0
+}
+
+fn hue_distance(a: Rgb, b: Rgb) -> f32 {
+    let distance = (hue(a) - hue(b)).abs();
+    distance.min(360.0 - distance)
+}
+
+/// Relative luminance as defined by WCAG
+fn luminance(color: Rgb) -> f32 {
+    let [r, g, b] = color.map(|c| {
+        let c = f32::from(c) / 255.0;
+        if c <= 0.039_28 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    });
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn contrast(a: Rgb, b: Rgb) -> f32 {
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Lightens the color on dark backgrounds and darkens it on light ones until
+/// it has enough contrast
+fn with_contrast(color: Rgb, background: Rgb) -> Rgb {
+    let target: f32 = if luminance(background) < 0.18 { 255.0 } else { 0.0 };
+    (0..=20)
+        .map(|step| {
+            let ratio = step as f32 / 20.0;
+            color.map(|c| (f32::from(c) + (target - f32::from(c)) * ratio).round() as u8)
+        })
+        .find(|candidate| contrast(*candidate, background) >= MIN_CONTRAST)
+        .unwrap_or(color)
+}
+
+fn to_color([r, g, b]: Rgb) -> Color {
+    Color::Rgb(r, g, b)
+}
+
+fn to_rgb(color: Option<Color>) -> Rgb {
+    let index = match color {
+        Some(Color::Rgb(r, g, b)) => return [r, g, b],
+        Some(Color::Indexed(index)) => index,
+        None | Some(Color::Reset | Color::Black) => 0,
+        Some(Color::Red) => 1,
+        Some(Color::Green) => 2,
+        Some(Color::Yellow) => 3,
+        Some(Color::Blue) => 4,
+        Some(Color::Magenta) => 5,
+        Some(Color::Cyan) => 6,
+        Some(Color::Gray) => 7,
+        Some(Color::DarkGray) => 8,
+        Some(Color::LightRed) => 9,
+        Some(Color::LightGreen) => 10,
+        Some(Color::LightYellow) => 11,
+        Some(Color::LightBlue) => 12,
+        Some(Color::LightMagenta) => 13,
+        Some(Color::LightCyan) => 14,
+        Some(Color::White) => 15,
+    };
+    let (r, g, b) = ansi_colours::rgb_from_ansi256(index);
+    [r, g, b]
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, Rgb, RgbImage};
+    use ratatui::style::Color;
+
+    use super::{AlbumPalette, MIN_CONTRAST, contrast, to_rgb};
+
+    fn image(colors: &[[u8; 3]]) -> Vec<u8> {
+        let width = u32::try_from(colors.len()).unwrap() * 8;
+        let image = RgbImage::from_fn(width, 8, |x, _| Rgb(colors[(x / 8) as usize]));
+        let mut data = Vec::new();
+        image.write_to(&mut Cursor::new(&mut data), ImageFormat::Png).unwrap();
+        data
+    }
+
+    fn rgb(color: Color) -> [u8; 3] {
+        let Color::Rgb(r, g, b) = color else { panic!("Expected rgb color, got {color:?}") };
+        [r, g, b]
+    }
+
+    #[test]
+    fn picks_colorful_colors() {
+        let data = image(&[[20, 20, 20], [20, 20, 20], [220, 40, 40], [40, 60, 220]]);
+
+        let palette = AlbumPalette::from_image(&data, Some(Color::White)).unwrap();
+
+        let [r, g, b] = rgb(palette.accent);
+        assert!(r > g && r > b, "accent {:?} should be red", palette.accent);
+        let [r, g, b] = rgb(palette.secondary);
+        assert!(b > r && b > g, "secondary {:?} should be blue", palette.secondary);
+    }
+
+    #[test]
+    fn penalizes_very_light_colors() {
+        let data = image(&[[255, 225, 235], [255, 225, 235], [255, 225, 235], [90, 110, 90]]);
+
+        let palette = AlbumPalette::from_image(&data, Some(Color::White)).unwrap();
+
+        let [r, g, b] = rgb(palette.accent);
+        assert!(g > r && g > b, "accent {:?} should be green", palette.accent);
+    }
+
+    #[test]
+    fn keeps_contrast_with_background() {
+        let data = image(&[[10, 10, 60], [10, 10, 60], [10, 10, 40]]);
+
+        for background in [None, Some(Color::White), Some(Color::Rgb(30, 30, 30))] {
+            let palette = AlbumPalette::from_image(&data, background).unwrap();
+
+            for color in [palette.accent, palette.secondary] {
+                assert!(contrast(rgb(color), to_rgb(background)) >= MIN_CONTRAST);
+            }
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use rmpc_mpd::{
+    commands::{Song, fingerprint::Fingerprint},
+    errors::MpdError,
+    filter::{Filter, FilterKind, Tag},
+    mpd_client::MpdClient,
+};
+use serde::{Deserialize, Serialize};
+
+/// How many songs are fingerprinted by a single query so that other queries
+/// are not blocked for too long while the library is being scanned
+pub const FINGERPRINT_BATCH_SIZE: usize = 10;
+
+const CACHE_FILE_NAME: &str = "fingerprints.json";
+/// Songs whose durations differ by more than this are never considered
+/// duplicates
+const MAX_DURATION_DIFFERENCE: Duration = Duration::from_secs(3);
+/// Maximum number of subfingerprints by which two fingerprints can be shifted
+/// against each other. One subfingerprint covers roughly 0.12 seconds.
+const MAX_OFFSET: usize = 8;
+/// Minimum number of overlapping subfingerprints for a comparison to be
+/// meaningful
+const MIN_OVERLAP: usize = 16;
+/// Fraction of matching bits above which two fingerprints are considered to
+/// be of the same recording. Unrelated recordings usually score around 0.5.
+const SIMILARITY_THRESHOLD: f64 = 0.85;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FingerprintCache {
+    entries: HashMap<String, CachedFingerprint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedFingerprint {
+    last_modified: DateTime<Utc>,
+    chromaprint: String,
+}
+
+impl FingerprintCache {
+    fn path(cache_dir: &Path) -> PathBuf {
+        cache_dir.join(CACHE_FILE_NAME)
+    }
+
+    /// Loads the cache from `cache_dir`. Starts with an empty cache when there
+    /// is no cache dir configured or the file cannot be read.
+    pub fn load(cache_dir: Option<&Path>) -> Self {
+        let Some(path) = cache_dir.map(Self::path) else {
+            return Self::default();
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+
+        std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+            .inspect_err(|err| log::error!(err:?, path:?; "Failed to read fingerprint cache"))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, cache_dir: Option<&Path>) -> Result<()> {
+        let Some(cache_dir) = cache_dir else {
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(cache_dir)?;
+        std::fs::write(Self::path(cache_dir), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Fingerprint of the song if it was cached and the file has not been
+    /// modified since
+    pub fn get(&self, song: &Song) -> Option<&str> {
+        self.entries
+            .get(&song.file)
+            .filter(|entry| entry.last_modified == song.last_modified)
+            .map(|entry| entry.chromaprint.as_str())
+    }
+
+    pub fn insert(&mut self, song: &Song, fingerprint: Fingerprint) {
+        self.entries.insert(song.file.clone(), CachedFingerprint {
+            last_modified: song.last_modified,
+            chromaprint: fingerprint.chromaprint,
+        });
+    }
+
+    /// Songs which have no up to date fingerprint in the cache
+    pub fn missing<'a>(&self, songs: &'a [Song]) -> impl Iterator<Item = &'a Song> {
+        songs.iter().filter(|song| self.get(song).is_none())
+    }
+}
+
+/// All songs in the database under `path`, or the whole database when `path`
+/// is `None`
+pub fn songs_to_scan(client: &mut impl MpdClient, path: Option<&str>) -> Result<Vec<Song>> {
+    let songs = client.find(&[Filter::new_with_kind(
+        Tag::File,
+        path.unwrap_or_default(),
+        FilterKind::StartsWith,
+    )])?;
+
+    Ok(songs.into_iter().filter(|song| song.duration.is_some()).collect())
+}
+
+/// Fingerprints the given songs. Songs MPD fails to fingerprint, for example
+/// because they cannot be decoded, are skipped.
+pub fn fetch_fingerprints(
+    client: &mut impl MpdClient,
+    songs: Vec<Song>,
+) -> Result<Vec<(Song, Fingerprint)>> {
+    let mut result = Vec::with_capacity(songs.len());
+    for song in songs {
+        match client.get_fingerprint(&song.file) {
+            Ok(fingerprint) => result.push((song, fingerprint)),
+            Err(MpdError::Mpd(err)) => {
+                log::warn!(err:?, file = song.file.as_str(); "Failed to fingerprint song");
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(result)
+}
+
+/// Fingerprints all songs under `path` which are not cached yet and returns
+/// groups of songs which are likely the same recording
+pub fn find_duplicates(
+    client: &mut impl MpdClient,
+    path: Option<&str>,
+    cache_dir: Option<&Path>,
+) -> Result<Vec<Vec<Song>>> {
+    let songs = songs_to_scan(client, path)?;
+    let mut cache = FingerprintCache::load(cache_dir);
+
+    let missing = cache.missing(&songs).cloned().collect_vec();
+    for (song, fingerprint) in fetch_fingerprints(client, missing)? {
+        cache.insert(&song, fingerprint);
+    }
+    cache.save(cache_dir)?;
+
+    Ok(group_duplicates(songs, &cache))
+}
+
+/// Groups songs with similar fingerprints. Only groups of at least two songs
+/// are returned, songs without a cached fingerprint are ignored.
+pub fn group_duplicates(songs: Vec<Song>, cache: &FingerprintCache) -> Vec<Vec<Song>> {
+    let mut songs = songs
+        .into_iter()
+        .filter_map(|song| {
+            let duration = song.duration?;
+            let fingerprint = cache.get(&song).and_then(decode_fingerprint)?;
+            Some((song, duration, fingerprint))
+        })
+        .collect_vec();
+    songs.sort_by_key(|(_, duration, _)| *duration);
+
+    let mut groups = DisjointSet::new(songs.len());
+    for (i, (_, duration, fingerprint)) in songs.iter().enumerate() {
+        for (j, (_, other_duration, other_fingerprint)) in songs.iter().enumerate().skip(i + 1) {
+            if other_duration.saturating_sub(*duration) > MAX_DURATION_DIFFERENCE {
+                break;
+            }
+            if similarity(fingerprint, other_fingerprint) >= SIMILARITY_THRESHOLD {
+                groups.union(i, j);
+            }
+        }
+    }
+
+    let mut result: HashMap<usize, Vec<Song>> = HashMap::new();
+    for (idx, (song, _, _)) in songs.into_iter().enumerate() {
+        result.entry(groups.find(idx)).or_default().push(song);
+    }
+
+    result
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| group.into_iter().sorted_by(|a, b| a.file.cmp(&b.file)).collect_vec())
+        .sorted_by(|a, b| a[0].file.cmp(&b[0].file))
+        .collect()
+}
+
+/// Decodes a compressed chromaprint fingerprint as returned by MPD into its
+/// subfingerprints
+fn decode_fingerprint(chromaprint: &str) -> Option<Vec<u32>> {
+    let data = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(chromaprint.trim_end_matches('='))
+        .ok()?;
+    if data.len() < 4 {
+        return None;
+    }
+
+    // The header consists of the algorithm id followed by a 24 bit big endian
+    // number of subfingerprints
+    let count = u32::from_be_bytes([0, data[1], data[2], data[3]]) as usize;
+    let mut reader = BitReader::new(&data[4..]);
+
+    // Every subfingerprint is stored as a list of 3 bit differences between the
+    // positions of its set bits, terminated by zero
+    let mut bits = Vec::new();
+    let mut terminators = 0;
+    while terminators < count {
+        let value = reader.read(3)?;
+        if value == 0 {
+            terminators += 1;
+        }
+        bits.push(value);
+    }
+
+    // Differences which do not fit into 3 bits are stored at the maximum value
+    // with the remainder in a following 5 bit section
+    reader.align_to_byte();
+    for bit in bits.iter_mut().filter(|bit| **bit == 7) {
+        *bit += reader.read(5)?;
+    }
+
+    let mut result = vec![0u32; count];
+    let mut idx = 0;
+    let mut last_bit = 0;
+    for bit in bits {
+        if bit == 0 {
+            // Each subfingerprint is stored XORed with the previous one
+            if idx > 0 {
+                result[idx] ^= result[idx - 1];
+            }
+            idx += 1;
+            last_bit = 0;
+        } else {
+            last_bit += bit;
+            if last_bit > 32 {
+                return None;
+            }
+            result[idx] |= 1 << (last_bit - 1);
+        }
+    }
+
+    Some(result)
+}
+
+/// Fraction of matching bits of the two fingerprints at their best alignment
+fn similarity(a: &[u32], b: &[u32]) -> f64 {
+    let compare = |a: &[u32], b: &[u32]| -> Option<f64> {
+        let overlap = a.len().min(b.len());
+        if overlap < MIN_OVERLAP {
+            return None;
+        }
+        let differing: u32 = a.iter().zip(b).map(|(a, b)| (a ^ b).count_ones()).sum();
+        Some(1.0 - f64::from(differing) / (overlap * 32) as f64)
+    };
+
+    (0..=MAX_OFFSET)
+        .flat_map(|offset| {
+            [
+                a.get(offset..).and_then(|a| compare(a, b)),
+                b.get(offset..).and_then(|b| compare(a, b)),
+            ]
+        })
+        .flatten()
+        .fold(0.0, f64::max)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Reads `bits` bits, least significant bit first
+    fn read(&mut self, bits: usize) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self.data.get(self.position / 8)?;
+            value |= u32::from((byte >> (self.position % 8)) & 1) << i;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self { parents: (0..len).collect() }
+    }
+
+    fn find(&mut self, idx: usize) -> usize {
+        let mut root = idx;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        let mut current = idx;
+        while self.parents[current] != root {
+            current = std::mem::replace(&mut self.parents[current], root);
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[b] = a;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::time::Duration;
+
+    use rmpc_mpd::commands::{Song, fingerprint::Fingerprint};
+
+    use super::*;
+
+    /// Compresses the fingerprint the same way chromaprint does
+    fn encode(fingerprint: &[u32]) -> String {
+        let mut normal = Vec::new();
+        let mut exceptional = Vec::new();
+        let mut previous = 0;
+        for value in fingerprint {
+            let mut bits = value ^ previous;
+            previous = *value;
+            let (mut bit, mut last_bit) = (1, 0);
+            while bits != 0 {
+                if bits & 1 == 1 {
+                    let diff = bit - last_bit;
+                    if diff >= 7 {
+                        normal.push(7);
+                        exceptional.push(diff - 7);
+                    } else {
+                        normal.push(diff);
+                    }
+                    last_bit = bit;
+                }
+                bits >>= 1;
+                bit += 1;
+            }
+            normal.push(0);
+        }
+
+        let pack = |values: &[u32], width: usize, out: &mut Vec<u8>| {
+            let start = out.len();
+            for (i, value) in values.iter().enumerate() {
+                for b in 0..width {
+                    let position = i * width + b;
+                    if position / 8 >= out.len() - start {
+                        out.push(0);
+                    }
+                    out[start + position / 8] |= (((value >> b) & 1) as u8) << (position % 8);
+                }
+            }
+        };
+
+        let len = fingerprint.len() as u32;
+        let mut data = vec![1, (len >> 16) as u8, (len >> 8) as u8, len as u8];
+        pack(&normal, 3, &mut data);
+        pack(&exceptional, 5, &mut data);
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
+    }
+
+    fn fingerprint(seed: u32, len: usize) -> Vec<u32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state
+            })
+            .collect()
+    }
+
+    fn song(file: &str, secs: u64) -> Song {
+        Song {
+            file: file.to_owned(),
+            duration: Some(Duration::from_secs(secs)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decodes_compressed_fingerprint() {
+        let input = vec![0, 1, 0x8000_0001, 0xFFFF_FFFF, 0x1234_5678, 0x0040_0000];
+
+        assert_eq!(decode_fingerprint(&encode(&input)).unwrap(), input);
+    }
+
+    #[test]
+    fn rejects_truncated_fingerprint() {
+        let mut encoded = encode(&fingerprint(1, 20));
+        encoded.truncate(encoded.len() / 2);
+
+        assert_eq!(decode_fingerprint(&encoded), None);
+    }
+
+    #[test]
+    fn shifted_fingerprint_is_similar() {
+        let original = fingerprint(1, 100);
+
+        assert!((similarity(&original, &original[3..]) - 1.0).abs() < f64::EPSILON);
+        assert!(similarity(&original, &fingerprint(2, 100)) < SIMILARITY_THRESHOLD);
+    }
+
+    #[test]
+    fn groups_songs_with_similar_fingerprints() {
+        let mut cache = FingerprintCache::default();
+        let mut noisy = fingerprint(1, 100);
+        for value in noisy.iter_mut().step_by(4) {
+            *value ^= 0b101;
+        }
+        let songs =
+            vec![song("a.flac", 200), song("b.mp3", 201), song("c.mp3", 200), song("d.mp3", 300)];
+        for (song, fp) in
+            songs.iter().zip([fingerprint(1, 100), noisy, fingerprint(2, 100), fingerprint(1, 100)])
+        {
+            cache.insert(song, Fingerprint { chromaprint: encode(&fp) });
+        }
+
+        let groups = group_duplicates(songs, &cache);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].iter().map(|song| song.file.as_str()).collect_vec(), vec![
+            "a.flac", "b.mp3"
+        ]);
+    }
+}
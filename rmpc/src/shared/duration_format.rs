@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use anyhow::Result;
+use rmpc_mpd::song_range::SongRange;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -172,6 +173,62 @@ impl DurationFormat {
     }
 }
 
+/// Parses a timestamp in the form of `[[[days:]hours:]minutes:]seconds` where
+/// seconds may contain a fractional part, ie. `83`, `1:23.5` or `1:02:03`
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    const MULTIPLIERS: [u64; 4] = [1, 60, 3600, 86400];
+
+    let input = input.trim();
+    let mut parts = input.rsplit(':');
+    let seconds = parts.next().unwrap_or_default();
+    let mut total = seconds
+        .parse()
+        .ok()
+        .and_then(|s: f64| Duration::try_from_secs_f64(s).ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid seconds '{seconds}' in timestamp '{input}'"))?;
+
+    for (idx, part) in parts.enumerate() {
+        let Some(multiplier) = MULTIPLIERS.get(idx + 1) else {
+            anyhow::bail!("Too many parts in timestamp '{input}'");
+        };
+        total = part
+            .parse::<u64>()
+            .ok()
+            .and_then(|value| value.checked_mul(*multiplier))
+            .and_then(|secs| total.checked_add(Duration::from_secs(secs)))
+            .ok_or_else(|| anyhow::anyhow!("Invalid value '{part}' in timestamp '{input}'"))?;
+    }
+
+    Ok(total)
+}
+
+/// Parses a play range in the form of `[start]-[end]` where both start and end
+/// are timestamps accepted by [`parse_duration`], ie. `0:30-2:15` or `1:00-`.
+/// Empty input means the whole song.
+pub fn parse_song_range(input: &str) -> Result<SongRange> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(SongRange::default());
+    }
+
+    let (start, end) = input
+        .split_once('-')
+        .ok_or_else(|| anyhow::anyhow!("Invalid range '{input}', expected 'start-end'"))?;
+    let parse = |value: &str| -> Result<Option<Duration>> {
+        let value = value.trim();
+        if value.is_empty() { Ok(None) } else { parse_duration(value).map(Some) }
+    };
+    let range = SongRange::new(parse(start)?, parse(end)?);
+
+    if let (Some(start), Some(end)) = (range.start, range.end)
+        && start >= end
+    {
+        anyhow::bail!("Start of the range '{input}' must be before its end");
+    }
+
+    Ok(range)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -212,6 +269,51 @@ mod tests {
         assert_eq!(fmt2.format(&Duration::from_secs(0)), "00:00");
     }
 
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("83").unwrap(), Duration::from_secs(83));
+        assert_eq!(parse_duration("1:23").unwrap(), Duration::from_secs(83));
+        assert_eq!(parse_duration("01:02:03").unwrap(), Duration::from_secs(3723));
+        assert_eq!(parse_duration("1:00:00:00").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_duration("0:01.5").unwrap(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_parse_invalid_duration() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("-5").is_err());
+        assert!(parse_duration("1.5:00").is_err());
+        assert!(parse_duration("1:1:1:1:1").is_err());
+    }
+
+    #[test]
+    fn test_parse_out_of_range_duration() {
+        assert!(parse_duration("1e300").is_err());
+        assert!(parse_duration("NaN").is_err());
+        assert!(parse_duration("999999999999999999:0").is_err());
+        assert!(parse_duration(&format!("{}:{}", u64::MAX / 60, u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_parse_song_range() {
+        assert_eq!(parse_song_range("").unwrap(), SongRange::default());
+        assert_eq!(
+            parse_song_range("0:30-2:15").unwrap(),
+            SongRange::new(Some(Duration::from_secs(30)), Some(Duration::from_secs(135)))
+        );
+        assert_eq!(
+            parse_song_range("1:00-").unwrap(),
+            SongRange::new(Some(Duration::from_secs(60)), None)
+        );
+        assert_eq!(
+            parse_song_range("-10").unwrap(),
+            SongRange::new(None, Some(Duration::from_secs(10)))
+        );
+        assert!(parse_song_range("10").is_err());
+        assert!(parse_song_range("20-10").is_err());
+    }
+
     #[test]
     fn test_invalid_format_token() {
         assert!(DurationFormat::parse("%z").is_err());
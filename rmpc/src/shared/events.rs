@@ -33,7 +33,11 @@ use crate::{
             YtDlpSearchItem,
         },
     },
-    ui::{UiAppEvent, UiEvent, image::facade::EncodeData},
+    ui::{
+        UiAppEvent,
+        UiEvent,
+        image::{facade::EncodeData, thumbnails::ThumbnailKey},
+    },
 };
 
 #[derive(Debug)]
@@ -69,6 +73,10 @@ pub(crate) enum WorkRequest {
     },
     Command(Command),
     ResizeImage(Box<dyn FnOnce() -> Result<EncodeData> + Send + Sync>),
+    EncodeThumbnail {
+        key: ThumbnailKey,
+        encode: Box<dyn FnOnce() -> Result<EncodeData> + Send + Sync>,
+    },
     LoadAlbumArt {
         file: String,
         loader: std::sync::Arc<Vec<String>>,
@@ -101,6 +109,10 @@ pub(crate) enum WorkDone {
     ImageResized {
         data: Result<EncodeData>,
     },
+    ThumbnailEncoded {
+        key: ThumbnailKey,
+        data: Result<EncodeData>,
+    },
     SearchYtResults {
         items: Vec<YtDlpSearchItem>,
         position: Option<QueuePosition>,
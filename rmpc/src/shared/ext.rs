@@ -199,6 +199,7 @@ pub mod error {
                     format!("Unsupported MPD version: {e}")
                 }
                 MpdError::TimedOut(_) => "Request to MPD timed out".to_string(),
+                MpdError::UnsupportedUrlScheme(_) => self.to_string(),
             }
         }
     }
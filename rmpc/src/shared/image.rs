@@ -162,6 +162,27 @@ pub fn create_aligned_area(
     result
 }
 
+/// Returns the size in pixels of [`available_area_cells`] constrained by
+/// [`max_size_px`] or none if terminal's size cannot be determined properly.
+pub fn available_size_px(available_area_cells: Rect, max_size_px: Size) -> Option<Size> {
+    let window_size = crossterm::terminal::window_size().ok()?;
+    if window_size.width == 0
+        || window_size.height == 0
+        || window_size.rows == 0
+        || window_size.columns == 0
+    {
+        return None;
+    }
+
+    let cell_width = window_size.width as f64 / window_size.columns as f64;
+    let cell_height = window_size.height as f64 / window_size.rows as f64;
+
+    Some(Size {
+        width: ((available_area_cells.width as f64 * cell_width) as u16).min(max_size_px.width),
+        height: ((available_area_cells.height as f64 * cell_height) as u16).min(max_size_px.height),
+    })
+}
+
 pub fn resize_image(
     image_data: &[u8],
     available_area: Rect,
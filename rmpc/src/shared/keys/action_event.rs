@@ -19,6 +19,13 @@ impl From<Arc<Vec<Actions>>> for ActionEvent {
     }
 }
 
+#[cfg(test)]
+impl From<CommonAction> for ActionEvent {
+    fn from(value: CommonAction) -> Self {
+        Arc::new(vec![value.into()]).into()
+    }
+}
+
 impl ActionEvent {
     pub fn abandon(&mut self) {
         self.already_handled = false;
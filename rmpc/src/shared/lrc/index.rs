@@ -220,6 +220,7 @@ mod tests {
             metadata,
             last_modified: DateTime::default(),
             added: Some(DateTime::default()),
+            prio: 0,
         }
     }
 
@@ -1,9 +1,12 @@
 pub mod album_art;
+pub mod album_art_cache;
+pub mod album_palette;
 pub mod args;
 pub mod clipboard;
 pub mod cmp;
 pub mod config_read;
 pub mod dependencies;
+pub mod duplicates;
 pub mod duration_format;
 pub mod events;
 pub mod exit_code;
@@ -21,7 +24,9 @@ pub mod mpd_client_ext;
 pub mod mpd_query;
 pub mod percent;
 pub mod ring_vec;
+pub mod snapshots;
 pub mod song_ext;
 pub mod terminal;
 pub mod tmux;
+pub mod undo;
 pub mod ytdlp;
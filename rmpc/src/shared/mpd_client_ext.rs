@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -7,7 +7,16 @@ use std::{
 use anyhow::Context;
 use itertools::Itertools;
 use rmpc_mpd::{
-    commands::{IdleEvent, Song, State, Status, outputs::Outputs, stickers::Stickers},
+    command_list::CommandList,
+    commands::{
+        IdleEvent,
+        Song,
+        State,
+        Status,
+        added_ids::AddedIds,
+        outputs::Outputs,
+        stickers::{StickerType, Stickers},
+    },
     errors::{ErrorCode, MpdError, MpdFailureResponse},
     filter::{Filter, FilterKind, Tag},
     mpd_client::{AlbumArtOrder, MpdClient, MpdCommand},
@@ -17,7 +26,7 @@ use rmpc_mpd::{
 };
 
 use crate::{
-    config::keys::actions::{AddOpts, AutoplayKind, Position},
+    config::keys::actions::{AddOpts, AutoplayKind, Position, StickerTarget},
     ctx::Ctx,
     shared::{
         ext::error::ErrorExt as _,
@@ -54,13 +63,16 @@ pub trait MpdClientExt {
         });
     }
     fn play_position_safe(&mut self, queue_len: usize) -> Result<(), MpdError>;
+    /// Returns ids of the added songs in the order they were placed in the
+    /// queue. Only songs enqueued as files are reported because MPD does not
+    /// return ids for whole directories or playlists.
     fn enqueue_multiple(
         &mut self,
         items: Vec<Enqueue>,
         autoplay_idx: Option<usize>,
         position: Option<QueuePosition>,
         replace: bool,
-    ) -> Result<(), MpdError>;
+    ) -> Result<Vec<u32>, MpdError>;
     fn delete_multiple(&mut self, items: Vec<MpdDelete>) -> Result<(), MpdError>;
     fn add_to_playlist_multiple(
         &mut self,
@@ -83,14 +95,32 @@ pub trait MpdClientExt {
         key: &str,
         value: String,
         items: Vec<Enqueue>,
+        target: StickerTarget,
     ) -> Result<(), MpdError>;
-    fn delete_sticker_multiple(&mut self, key: &str, items: Vec<Enqueue>) -> Result<(), MpdError>;
+    fn delete_sticker_multiple(
+        &mut self,
+        key: &str,
+        items: Vec<Enqueue>,
+        target: StickerTarget,
+    ) -> Result<(), MpdError>;
+    /// Uris of the objects the stickers of given items are attached to. These
+    /// are song files for song stickers or unique tag values otherwise.
+    fn sticker_uris(
+        &mut self,
+        items: Vec<Enqueue>,
+        target: StickerTarget,
+    ) -> Result<Vec<String>, MpdError>;
+    /// Fails with [`MpdError::UnsupportedUrlScheme`] when MPD has no handler
+    /// for one of the URIs. Absolute paths are treated as `file://` URIs,
+    /// paths relative to the music directory are always accepted.
+    fn ensure_url_handlers(&mut self, uris: &[&str]) -> Result<(), MpdError>;
+    /// Returns id of the added song
     fn add_downloaded_file_to_queue(
         &mut self,
         paths: PathBuf,
         cache_dir: Option<&Path>,
         position: Option<QueuePosition>,
-    ) -> Result<(), MpdError>;
+    ) -> Result<u32, MpdError>;
     fn find_album_art(
         &mut self,
         path: &str,
@@ -101,7 +131,7 @@ pub trait MpdClientExt {
 
 #[derive(Debug, Clone)]
 pub enum MpdDelete {
-    SongInPlaylist { playlist: Arc<str>, range: SingleOrRange },
+    SongInPlaylist { playlist: Arc<str>, position: usize, file: String },
     Playlist { name: String },
 }
 
@@ -137,9 +167,9 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
         autoplay_idx: Option<usize>,
         position: Option<QueuePosition>,
         replace: bool,
-    ) -> Result<(), MpdError> {
+    ) -> Result<Vec<u32>, MpdError> {
         if items.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
         let should_reverse = match position {
             Some(QueuePosition::RelativeAdd(_)) => true,
@@ -159,33 +189,45 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
         let items_len = items.len();
         let mut i = 0;
         let mut errors = 0;
+        let mut ids = Vec::new();
         while i < items_len {
-            self.send_start_cmd_list()?;
-            for item in &items[i..] {
-                match item {
-                    Enqueue::File { path } | Enqueue::Directory { path } => {
-                        self.send_add(path, position)
+            let mut list = CommandList::new();
+            let handles = items[i..]
+                .iter()
+                .map(|item| match item {
+                    Enqueue::File { path } => {
+                        list.push::<AddedIds>(move |c: &mut Self| c.send_add_id(path, position))
+                    }
+                    Enqueue::Directory { path } => {
+                        list.push::<AddedIds>(move |c: &mut Self| c.send_add(path, position))
                     }
-                    Enqueue::Playlist { name } => self.send_load_playlist(name, position),
-                    Enqueue::Find { filter } => self.send_find_add(
-                        &filter
-                            .iter()
-                            .map(|(tag, kind, value)| {
-                                Filter::new_with_kind(tag.clone(), value, kind.clone())
-                            })
-                            .collect_vec(),
-                        position,
-                    ),
-                }?;
+                    Enqueue::Playlist { name } => list
+                        .push::<AddedIds>(move |c: &mut Self| c.send_load_playlist(name, position)),
+                    Enqueue::Find { filter } => list.push::<AddedIds>(move |c: &mut Self| {
+                        c.send_find_add(
+                            &filter
+                                .iter()
+                                .map(|(tag, kind, value)| {
+                                    Filter::new_with_kind(tag.clone(), value, kind.clone())
+                                })
+                                .collect_vec(),
+                            position,
+                        )
+                    }),
+                })
+                .collect_vec();
+
+            let mut results = list.execute(self)?;
+            let failed_idx = results.failure().map(|(idx, _)| idx);
+            for handle in handles.into_iter().take_while(|h| Some(h.index()) != failed_idx) {
+                ids.extend(results.take(handle)?.0);
             }
-            self.send_execute_cmd_list()?;
-            match self.read_ok() {
-                Ok(()) => i = items_len,
-                Err(MpdError::Mpd(err)) => {
-                    i += 1 + err.command_list_index as usize;
+            match failed_idx {
+                Some(idx) => {
+                    i += 1 + idx;
                     errors += 1;
                 }
-                err => err?,
+                None => i = items_len,
             }
         }
         if errors == 0 {
@@ -210,7 +252,11 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
             self.play_position_safe(autoplay_idx)?;
         }
 
-        Ok(())
+        if should_reverse {
+            ids.reverse();
+        }
+
+        Ok(ids)
     }
 
     fn delete_multiple(&mut self, items: Vec<MpdDelete>) -> Result<(), MpdError> {
@@ -219,19 +265,20 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
             return Ok(());
         }
 
-        self.send_start_cmd_list()?;
+        let mut list = CommandList::new();
         for item in items.into_iter().rev() {
             match item {
-                MpdDelete::SongInPlaylist { playlist, range } => {
-                    self.send_delete_from_playlist(&playlist, &range)?;
+                MpdDelete::SongInPlaylist { playlist, position, .. } => {
+                    list.push_ok(move |c: &mut Self| {
+                        c.send_delete_from_playlist(&playlist, &SingleOrRange::single(position))
+                    });
                 }
                 MpdDelete::Playlist { name } => {
-                    self.send_delete_playlist(&name)?;
+                    list.push_ok(move |c: &mut Self| c.send_delete_playlist(&name));
                 }
             }
         }
-        self.send_execute_cmd_list()?;
-        self.read_ok()?;
+        list.execute(self)?.into_result()?;
 
         if items_len == 1 {
             status_info!("Deleted 1 item");
@@ -292,6 +339,7 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
                         PartitionedOutputKind::CurrentPartition
                     },
                     plugin: output.plugin,
+                    attributes: output.attributes,
                 })
                 .collect())
         } else {
@@ -323,6 +371,7 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
                         name: std::mem::take(&mut current.name),
                         enabled: current.enabled,
                         plugin: std::mem::take(&mut current.plugin),
+                        attributes: std::mem::take(&mut current.attributes),
                         kind: PartitionedOutputKind::CurrentPartition,
                     });
                 } else {
@@ -331,6 +380,7 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
                         name: output.name,
                         enabled: false,
                         plugin: output.plugin,
+                        attributes: output.attributes,
                         kind: PartitionedOutputKind::OtherPartition,
                     });
                 }
@@ -405,42 +455,38 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
             return Ok(HashMap::new());
         }
 
-        let mut list_ended_with_err = false;
         let mut i = 0;
         let mut result = HashMap::new();
 
         while i < song_uris.len() {
-            self.send_start_cmd_list_ok()?;
-            for uri in &song_uris[i..] {
-                self.send_list_stickers(uri)?;
-            }
-            self.send_execute_cmd_list()?;
-
-            for uri in &mut song_uris[i..] {
-                let res: Result<Stickers, _> = self.read_response();
-                match res {
+            let mut list = CommandList::new();
+            let handles = song_uris[i..]
+                .iter()
+                .map(|uri| {
+                    list.push::<Stickers>(move |c: &mut Self| {
+                        c.send_list_stickers(&StickerType::Song, uri)
+                    })
+                })
+                .collect_vec();
+            let mut results = list.execute(self)?;
+
+            let start = i;
+            for handle in handles {
+                let uri = std::mem::take(&mut song_uris[start + handle.index()]);
+                i += 1;
+                match results.take(handle) {
                     Ok(stickers) => {
-                        list_ended_with_err = false;
-                        result.insert(std::mem::take(uri), stickers.0);
-                        i += 1;
+                        result.insert(uri, stickers.0);
                     }
                     Err(error) => {
                         log::warn!(error:?, file = uri.as_str(); "Tried to find stickers but unexpected error occurred");
-                        result.insert(std::mem::take(uri), HashMap::new());
-                        list_ended_with_err = true;
-                        i += 1;
+                        result.insert(uri, HashMap::new());
                         break;
                     }
                 }
             }
         }
 
-        // In case the last sticker was fetched successfully we have to read an
-        // OK as an ack for the whole command list
-        if !list_ended_with_err {
-            self.read_ok()?;
-        }
-
         log::debug!(count = result.len(); "Fetched stickers for songs");
         Ok(result)
     }
@@ -450,67 +496,136 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
         key: &str,
         value: String,
         items: Vec<Enqueue>,
+        target: StickerTarget,
     ) -> Result<(), MpdError> {
-        let mut uris = Vec::new();
-        for item in items {
-            match item {
-                Enqueue::File { path } | Enqueue::Directory { path } => uris.push(path),
-                Enqueue::Playlist { name } => {
-                    let playlist = self.list_playlist(&name)?.0;
-                    uris.extend(playlist);
-                }
-                Enqueue::Find { filter } => {
-                    let songs = self.find(
-                        &filter
-                            .into_iter()
-                            .map(|(tag, kind, value)| Filter::new_with_kind(tag, value, kind))
-                            .collect_vec(),
-                    )?;
-                    uris.extend(songs.into_iter().map(|song| song.file));
-                }
-            }
-        }
+        let uris = self.sticker_uris(items, target)?;
+        let sticker_type = target.sticker_type();
 
-        self.send_start_cmd_list()?;
+        let mut list = CommandList::new();
         for uri in uris {
-            self.send_set_sticker(&uri, key, &value.clone())?;
+            let (sticker_type, value) = (&sticker_type, &value);
+            list.push_ok(move |c: &mut Self| c.send_set_sticker(sticker_type, &uri, key, value));
         }
-        self.send_execute_cmd_list()?;
-        self.read_ok()?;
+        list.execute(self)?.into_result()?;
 
         Ok(())
     }
 
-    fn delete_sticker_multiple(&mut self, key: &str, items: Vec<Enqueue>) -> Result<(), MpdError> {
-        let mut uris = Vec::new();
+    fn delete_sticker_multiple(
+        &mut self,
+        key: &str,
+        items: Vec<Enqueue>,
+        target: StickerTarget,
+    ) -> Result<(), MpdError> {
+        let uris = self.sticker_uris(items, target)?;
+        let sticker_type = target.sticker_type();
+
+        // Items without the sticker fail with NoExist which stops the command
+        // list, so the rest of the list is sent again after such failure
+        let mut i = 0;
+        while i < uris.len() {
+            let mut list = CommandList::new();
+            for uri in &uris[i..] {
+                let sticker_type = &sticker_type;
+                list.push_ok(move |c: &mut Self| c.send_delete_sticker(sticker_type, uri, key));
+            }
+
+            let results = list.execute(self)?;
+            match results.failure().map(|(idx, failure)| (idx, failure.is_no_exist())) {
+                Some((idx, true)) => i += idx + 1,
+                Some((_, false)) => {
+                    results.into_result()?;
+                }
+                None => i = uris.len(),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sticker_uris(
+        &mut self,
+        items: Vec<Enqueue>,
+        target: StickerTarget,
+    ) -> Result<Vec<String>, MpdError> {
+        let Some(tag) = target.tag() else {
+            let mut uris = Vec::new();
+            for item in items {
+                match item {
+                    Enqueue::File { path } | Enqueue::Directory { path } => uris.push(path),
+                    Enqueue::Playlist { name } => {
+                        let playlist = self.list_playlist(&name)?.0;
+                        uris.extend(playlist);
+                    }
+                    Enqueue::Find { filter } => {
+                        let songs = self.find(
+                            &filter
+                                .into_iter()
+                                .map(|(tag, kind, value)| Filter::new_with_kind(tag, value, kind))
+                                .collect_vec(),
+                        )?;
+                        uris.extend(songs.into_iter().map(|song| song.file));
+                    }
+                }
+            }
+            return Ok(uris);
+        };
+
+        // Tag stickers need the tags of the songs so they have to be fetched first
+        let mut songs = Vec::new();
         for item in items {
             match item {
-                Enqueue::File { path } | Enqueue::Directory { path } => uris.push(path),
+                Enqueue::File { path } => {
+                    songs.extend(self.find(&[Filter::new(Tag::File, path)])?);
+                }
+                Enqueue::Directory { path } => {
+                    let path = format!("{}/", path.trim_end_matches('/'));
+                    songs.extend(self.find(&[Filter::new_with_kind(
+                        Tag::File,
+                        path,
+                        FilterKind::StartsWith,
+                    )])?);
+                }
                 Enqueue::Playlist { name } => {
-                    let playlist = self.list_playlist(&name)?.0;
-                    uris.extend(playlist);
+                    songs.extend(self.list_playlist_info(&name, None)?);
                 }
                 Enqueue::Find { filter } => {
-                    let songs = self.find(
-                        &filter
-                            .into_iter()
-                            .map(|(tag, kind, value)| Filter::new_with_kind(tag, value, kind))
-                            .collect_vec(),
-                    )?;
-                    uris.extend(songs.into_iter().map(|song| song.file));
+                    songs.extend(
+                        self.find(
+                            &filter
+                                .into_iter()
+                                .map(|(tag, kind, value)| Filter::new_with_kind(tag, value, kind))
+                                .collect_vec(),
+                        )?,
+                    );
                 }
             }
         }
 
-        for uri in uris {
-            match self.delete_sticker(&uri, key) {
-                Ok(()) => {}
-                Err(MpdError::Mpd(MpdFailureResponse { code: ErrorCode::NoExist, .. })) => {}
-                err @ Err(_) => err?,
-            }
+        let key = tag.to_string();
+        Ok(songs
+            .iter()
+            .filter_map(|song| song.metadata.get(&key))
+            .flat_map(|values| values.iter())
+            .unique()
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+
+    fn ensure_url_handlers(&mut self, uris: &[&str]) -> Result<(), MpdError> {
+        let schemes = uris.iter().filter_map(|uri| url_scheme(uri)).unique().collect_vec();
+        if schemes.is_empty() {
+            return Ok(());
         }
 
-        Ok(())
+        let handlers = self.url_handlers()?.0;
+        match schemes
+            .into_iter()
+            .find(|scheme| !handlers.iter().any(|handler| handler.eq_ignore_ascii_case(scheme)))
+        {
+            Some(scheme) => Err(MpdError::UnsupportedUrlScheme(scheme)),
+            None => Ok(()),
+        }
     }
 
     fn add_downloaded_file_to_queue(
@@ -518,15 +633,14 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
         path: PathBuf,
         cache_dir: Option<&Path>,
         position: Option<QueuePosition>,
-    ) -> Result<(), MpdError> {
-        let result = self.add(
-            path.as_os_str().to_str().ok_or_else(|| {
-                MpdError::Generic(format!("Path '{}' is not valid UTF-8", path.display()))
-            })?,
-            position,
-        );
+    ) -> Result<u32, MpdError> {
+        let uri = path.as_os_str().to_str().ok_or_else(|| {
+            MpdError::Generic(format!("Path '{}' is not valid UTF-8", path.display()))
+        })?;
+        self.ensure_url_handlers(&[uri])?;
+        let result = self.add_id(uri, position);
         match result {
-            Ok(()) => {}
+            Ok(id) => Ok(id),
             Err(MpdError::Mpd(err)) if err.is_no_exist() => {
                 let Some(cache_dir) = cache_dir else {
                     // This should not happen, the download should only happen when
@@ -577,17 +691,15 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
                 }
 
                 log::debug!("Trying to add the downloaded files again");
-                self.add(
+                self.add_id(
                     path.as_os_str().to_str().ok_or_else(|| {
                         MpdError::Generic(format!("Path '{}' is not valid UTF-8", path.display()))
                     })?,
                     position,
-                )?;
+                )
             }
-            original @ Err(_) => original?,
+            original @ Err(_) => original,
         }
-
-        Ok(())
     }
 
     fn find_album_art(
@@ -637,16 +749,12 @@ impl<T: MpdClient + MpdCommand + ProtoClient> MpdClientExt for T {
     }
 
     fn get_status_and_current_song(&mut self) -> Result<(Status, Option<Song>), MpdError> {
-        self.send_start_cmd_list_ok()?;
-        self.send_get_status()?;
-        self.send_get_current_song()?;
-        self.send_execute_cmd_list()?;
-
-        let status = self.read_response()?;
-        let current_song = self.read_opt_response()?;
-        self.read_ok()?;
+        let mut list = CommandList::new();
+        let status = list.push::<Status>(|c: &mut Self| c.send_get_status());
+        let current_song = list.push_opt::<Song>(|c: &mut Self| c.send_get_current_song());
+        let mut results = list.execute(self)?;
 
-        Ok((status, current_song))
+        Ok((results.take(status)?, results.take(current_song)?))
     }
 }
 
@@ -658,6 +766,7 @@ pub struct PartitionedOutput {
     pub name: String,
     pub enabled: bool,
     pub plugin: String,
+    pub attributes: BTreeMap<String, String>,
     pub kind: PartitionedOutputKind,
 }
 
@@ -667,6 +776,19 @@ pub enum PartitionedOutputKind {
     CurrentPartition,
 }
 
+/// Scheme of the URI including `://` as reported by MPD's `urlhandlers`,
+/// `None` for paths relative to the music directory
+fn url_scheme(uri: &str) -> Option<String> {
+    if uri.starts_with('/') {
+        return Some("file://".to_owned());
+    }
+
+    let (scheme, _) = uri.split_once("://")?;
+    (!scheme.is_empty()
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')))
+    .then(|| format!("{}://", scheme.to_ascii_lowercase()))
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -679,6 +801,74 @@ mod tests {
         tests::fixtures::ctx,
     };
 
+    mod url_scheme {
+        use super::super::url_scheme;
+
+        #[test]
+        fn relative_path_has_no_scheme() {
+            assert_eq!(url_scheme("artist/album/song.flac"), None);
+            assert_eq!(url_scheme("weird ://name.flac"), None);
+        }
+
+        #[test]
+        fn absolute_path_is_a_local_file() {
+            assert_eq!(url_scheme("/home/user/song.flac"), Some("file://".to_owned()));
+        }
+
+        #[test]
+        fn scheme_is_lowercased() {
+            assert_eq!(url_scheme("HTTPS://example.com/stream"), Some("https://".to_owned()));
+            assert_eq!(url_scheme("file:///music/song.flac"), Some("file://".to_owned()));
+        }
+    }
+
+    mod sticker_uris {
+        use rmpc_mpd::{
+            commands::metadata_tag::MetadataTag,
+            fake_server::{FakeMpd, FakeServer},
+        };
+
+        use super::*;
+        use crate::{
+            config::keys::actions::StickerTarget,
+            shared::mpd_client_ext::{Enqueue, MpdClientExt},
+        };
+
+        #[test]
+        fn includes_all_values_of_multi_valued_tags() {
+            let songs = [("a.flac", vec!["Foo", "Bar"]), ("b.flac", vec!["Bar", "Baz"])].map(
+                |(file, artists)| Song {
+                    file: file.to_owned(),
+                    metadata: [(
+                        "artist".to_owned(),
+                        MetadataTag::from(
+                            artists.into_iter().map(ToOwned::to_owned).collect::<Vec<_>>(),
+                        ),
+                    )]
+                    .into(),
+                    ..Default::default()
+                },
+            );
+            let server = FakeServer::start_tcp(FakeMpd::new().with_songs(songs)).unwrap();
+            let mut client = rmpc_mpd::client::Client::init(
+                server.address(),
+                None,
+                "test",
+                None,
+                false,
+                false,
+                None,
+            )
+            .unwrap();
+            let items =
+                ["a.flac", "b.flac"].map(|path| Enqueue::File { path: path.to_owned() }).to_vec();
+
+            let uris = client.sticker_uris(items, StickerTarget::Artist).unwrap();
+
+            assert_eq!(uris, ["Foo", "Bar", "Baz"]);
+        }
+    }
+
     mod enqueue_multiple {
         use std::collections::HashMap;
 
@@ -6,10 +6,32 @@ use crossbeam::channel::Sender;
 use ratatui::{style::Style, widgets::ListItem};
 use rmpc_mpd::{
     client::Client,
-    commands::{Decoder, IdleEvent, Song, Status, Volume, list::MpdGroupedList},
+    commands::{
+        Decoder,
+        IdleEvent,
+        QueueDiff,
+        QueueVersion,
+        Song,
+        Stats,
+        Status,
+        Volume,
+        comments::Comments,
+        count::GroupedCounts,
+        fingerprint::Fingerprint,
+        list::MpdGroupedList,
+        list_mounts::Mount,
+        list_neighbors::Neighbor,
+        replay_gain::ReplayGainMode,
+    },
 };
 
-use super::{events::AppEvent, mpd_client_ext::PartitionedOutput};
+use super::{
+    album_palette::AlbumPalette,
+    duplicates::FingerprintCache,
+    events::AppEvent,
+    mpd_client_ext::PartitionedOutput,
+    undo::Applied,
+};
 use crate::{
     config::tabs::PaneType,
     shared::{events::ClientRequest, macros::try_skip, mpd_client_ext::MpdClientExt},
@@ -19,8 +41,11 @@ use crate::{
 pub const EXTERNAL_COMMAND: &str = "external_command";
 pub const GLOBAL_STATUS_UPDATE: &str = "global_status_update";
 pub const GLOBAL_VOLUME_UPDATE: &str = "global_volume_update";
+pub const GLOBAL_REPLAY_GAIN_UPDATE: &str = "global_replay_gain_update";
 pub const GLOBAL_QUEUE_UPDATE: &str = "global_queue_update";
 pub const GLOBAL_STICKERS_UPDATE: &str = "global_stickers_update";
+pub const GLOBAL_ALBUM_PALETTE_UPDATE: &str = "global_album_palette_update";
+pub const GLOBAL_HISTORY_UPDATE: &str = "global_history_update";
 
 #[derive(derive_more::Debug, Builder)]
 pub(crate) struct MpdQuery {
@@ -83,17 +108,30 @@ pub(crate) enum MpdQueryResult {
     LsInfo { data: Vec<String>, path: Option<Path> },
     DirOrSong { data: Vec<DirOrSong>, path: Option<Path> },
     SearchResult { data: Vec<Song> },
+    QueueFilter { query: String, ids: Vec<u32> },
     AddToPlaylist { playlists: Vec<String>, song_file: String },
     AddToPlaylistMultiple { playlists: Vec<String>, song_files: Vec<String> },
     AlbumArt(Option<Vec<u8>>),
+    AlbumThumbnail { album: String, data: Result<Option<Vec<u8>>> },
+    AlbumPalette(Option<AlbumPalette>),
+    AlbumArtWithPalette { data: Option<Vec<u8>>, palette: Option<AlbumPalette> },
     Status { status: Status, current_song: Option<Song>, source_event: Option<IdleEvent> },
-    Queue(Option<Vec<Song>>),
+    Queue { queue: Option<Vec<Song>>, version: Option<QueueVersion> },
+    QueueDiff(QueueDiff),
     Volume(Volume),
+    ReplayGain(ReplayGainMode),
     Outputs(Vec<PartitionedOutput>),
     Decoders(Vec<Decoder>),
+    Mounts { mounts: Vec<Mount>, neighbors: Vec<Neighbor> },
     ExternalCommand(Arc<Vec<String>>, Vec<String>, Vec<Song>),
     SongStickers(HashMap<String, HashMap<String, String>>),
     TagGroupedList { data: MpdGroupedList },
+    Statistics { stats: Stats, groups: Vec<(String, GroupedCounts)> },
+    Comments { file: String, comments: Comments },
+    Fingerprints(Vec<(Song, Fingerprint)>),
+    DuplicateCandidates { songs: Vec<Song>, cache: FingerprintCache },
+    DuplicateGroups(Vec<Vec<Song>>),
+    HistoryEdit(Applied),
     Any(Box<dyn Any + Send + Sync>),
 }
 
@@ -34,6 +34,10 @@ impl<const LEN: usize, T> RingVec<LEN, T> {
     pub fn last(&self) -> Option<&T> {
         self.inner.back()
     }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.inner.pop_back()
+    }
 }
 
 impl<const LEN: usize, T> IntoIterator for RingVec<LEN, T> {
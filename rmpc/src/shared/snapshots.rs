@@ -0,0 +1,323 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use rmpc_mpd::{
+    client::Client,
+    command_list::CommandList,
+    commands::{State, status::OnOffOneshot},
+    mpd_client::{MpdClient, MpdCommand},
+};
+use rmpc_shared::paths::rmpc_state_dir;
+use serde::{Deserialize, Serialize};
+
+/// Name of the snapshot taken automatically when rmpc exits
+pub const EXIT_SNAPSHOT: &str = "exit";
+const SNAPSHOTS_DIR: &str = "snapshots";
+const EXTENSION: &str = "json";
+/// Number of songs added to the queue in a single command list
+const ADD_BATCH_SIZE: usize = 500;
+
+/// State of the queue and playback which can be restored later
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub created: DateTime<Utc>,
+    pub songs: Vec<String>,
+    /// Position of the current song in the queue
+    pub current: Option<usize>,
+    pub elapsed: Duration,
+    pub state: State,
+    pub repeat: bool,
+    pub random: bool,
+    pub single: OnOffOneshot,
+    pub consume: OnOffOneshot,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NamedSnapshot {
+    pub name: String,
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
+}
+
+impl Snapshot {
+    pub fn capture(client: &mut Client<'_>) -> Result<Self> {
+        let status = client.get_status()?;
+        let songs = client.playlist_info()?.unwrap_or_default();
+
+        Ok(Self {
+            created: Utc::now(),
+            songs: songs.into_iter().map(|song| song.file).collect(),
+            current: status.song,
+            elapsed: status.elapsed,
+            state: status.state,
+            repeat: status.repeat,
+            random: status.random,
+            single: status.single,
+            consume: status.consume,
+        })
+    }
+
+    /// Replaces the queue with the songs from the snapshot and restores the
+    /// playback. Songs which no longer exist are skipped. The queue is left
+    /// empty when the restore fails midway.
+    pub fn restore(&self, client: &mut Client<'_>) -> Result<()> {
+        let missing = self.add_songs(client).inspect_err(|_| {
+            if let Err(err) = client.clear() {
+                log::error!(err:?; "Failed to clear partially restored queue");
+            }
+        })?;
+        let current = self
+            .current
+            .filter(|pos| !missing.contains(pos))
+            .map(|pos| pos - missing.iter().filter(|missing| **missing < pos).count());
+
+        // Seek and pause are sent in a single command list so MPD pauses the
+        // playback before any audio of a paused snapshot gets to the outputs
+        let mut list = CommandList::new();
+        list.push_ok(|c: &mut Client<'_>| c.send_repeat(self.repeat));
+        list.push_ok(|c: &mut Client<'_>| c.send_random(self.random));
+        list.push_ok(|c: &mut Client<'_>| c.send_single(self.single));
+        list.push_ok(|c: &mut Client<'_>| c.send_consume(self.consume));
+        if let Some(pos) = current {
+            match self.state {
+                State::Play => {
+                    list.push_ok(move |c: &mut Client<'_>| c.send_seek(pos, self.elapsed));
+                }
+                State::Pause => {
+                    list.push_ok(move |c: &mut Client<'_>| c.send_seek(pos, self.elapsed));
+                    list.push_ok(|c: &mut Client<'_>| c.send_pause());
+                }
+                State::Stop => {}
+            }
+        }
+        list.execute(client)?.into_result()?;
+
+        if !missing.is_empty() {
+            bail!("{} song(s) from the snapshot no longer exist", missing.len());
+        }
+
+        Ok(())
+    }
+
+    /// Clears the queue and adds the songs in batches of command lists.
+    /// Returns positions of the songs which no longer exist.
+    fn add_songs(&self, client: &mut Client<'_>) -> Result<Vec<usize>> {
+        client.clear()?;
+
+        // A song which no longer exists fails with NoExist which stops the
+        // command list, so the rest of the batch is sent again after it
+        let mut missing = Vec::new();
+        let mut i = 0;
+        while i < self.songs.len() {
+            let batch = &self.songs[i..self.songs.len().min(i + ADD_BATCH_SIZE)];
+            let mut list = CommandList::new();
+            for uri in batch {
+                list.push_ok(move |c: &mut Client<'_>| c.send_add(uri, None));
+            }
+
+            let results = list.execute(client)?;
+            match results.failure().map(|(idx, failure)| (idx, failure.is_no_exist())) {
+                Some((idx, true)) => {
+                    log::warn!(uri:? = batch[idx]; "Song from snapshot no longer exists, skipping");
+                    missing.push(i + idx);
+                    i += idx + 1;
+                }
+                Some((_, false)) => {
+                    results.into_result()?;
+                }
+                None => i += batch.len(),
+            }
+        }
+
+        Ok(missing)
+    }
+}
+
+/// Snapshots stored as json files in a directory
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Snapshots stored in rmpc's state directory
+    pub fn from_state_dir() -> Result<Self> {
+        let Some(dir) = rmpc_state_dir() else {
+            bail!("Could not determine state directory");
+        };
+
+        Ok(Self::new(dir.join(SNAPSHOTS_DIR)))
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name.starts_with('.') || name.contains(std::path::is_separator) {
+            bail!("Invalid snapshot name '{name}'");
+        }
+
+        Ok(self.dir.join(format!("{name}.{EXTENSION}")))
+    }
+
+    pub fn save(&self, name: &str, snapshot: &Snapshot) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create directory '{}'", self.dir.display()))?;
+        std::fs::write(&path, serde_json::to_string(snapshot)?)
+            .with_context(|| format!("Failed to write snapshot '{}'", path.display()))?;
+        Ok(())
+    }
+
+    pub fn load(&self, name: &str) -> Result<Snapshot> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            bail!("Snapshot '{name}' does not exist");
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read snapshot '{}'", path.display()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete snapshot '{}'", path.display()))?;
+        Ok(())
+    }
+
+    /// All stored snapshots, the most recent first. Files which cannot be read
+    /// are skipped.
+    pub fn list(&self) -> Result<Vec<NamedSnapshot>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let snapshots = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                    return None;
+                }
+                let name = path.file_stem()?.to_str()?.to_owned();
+                self.load(&name)
+                    .inspect_err(|err| log::error!(err:?, path:?; "Failed to read snapshot"))
+                    .ok()
+                    .map(|snapshot| NamedSnapshot { name, snapshot })
+            })
+            .sorted_by(|a, b| b.snapshot.created.cmp(&a.snapshot.created))
+            .collect();
+
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use chrono::{TimeZone, Utc};
+    use rmpc_mpd::{
+        client::Client,
+        commands::{Song, State, status::OnOffOneshot},
+        fake_server::{FakeMpd, FakeServer},
+    };
+
+    use super::{Snapshot, Snapshots};
+
+    fn snapshots(name: &str) -> (Snapshots, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("rmpc-snapshots-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        (Snapshots::new(&dir), dir)
+    }
+
+    fn snapshot(created: i64) -> Snapshot {
+        Snapshot {
+            created: Utc.timestamp_opt(created, 0).unwrap(),
+            songs: vec!["a.mp3".to_owned(), "b.mp3".to_owned()],
+            current: Some(1),
+            elapsed: Duration::from_millis(1500),
+            state: State::Pause,
+            repeat: true,
+            random: false,
+            single: OnOffOneshot::Oneshot,
+            consume: OnOffOneshot::Off,
+        }
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let (snapshots, dir) = snapshots("saves_and_loads");
+
+        snapshots.save("work", &snapshot(0)).unwrap();
+
+        assert_eq!(snapshots.load("work").unwrap(), snapshot(0));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lists_most_recent_first() {
+        let (snapshots, dir) = snapshots("lists_most_recent_first");
+        snapshots.save("old", &snapshot(0)).unwrap();
+        snapshots.save("new", &snapshot(100)).unwrap();
+        std::fs::write(dir.join("garbage.json"), "{").unwrap();
+
+        let names = snapshots.list().unwrap().into_iter().map(|s| s.name).collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["new", "old"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn deletes() {
+        let (snapshots, dir) = snapshots("deletes");
+        snapshots.save("party", &snapshot(0)).unwrap();
+
+        snapshots.delete("party").unwrap();
+
+        assert!(snapshots.load("party").is_err());
+        assert!(snapshots.list().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restores_paused_playback_without_missing_songs() {
+        let songs =
+            ["a.mp3", "b.mp3"].map(|file| Song { file: file.to_owned(), ..Default::default() });
+        let server = FakeServer::start_tcp(FakeMpd::new().with_songs(songs)).unwrap();
+        let mut client =
+            Client::init(server.address(), None, "test", None, false, false, None).unwrap();
+        let snapshot = Snapshot {
+            songs: vec!["a.mp3".to_owned(), "missing.mp3".to_owned(), "b.mp3".to_owned()],
+            current: Some(2),
+            ..snapshot(0)
+        };
+
+        assert!(snapshot.restore(&mut client).is_err());
+
+        server.with_state(|mpd| {
+            assert_eq!(mpd.queue_uris(), ["a.mp3", "b.mp3"]);
+            assert_eq!(mpd.playback.current, Some(1));
+            assert_eq!(mpd.playback.state, State::Pause);
+            assert_eq!(mpd.playback.elapsed, Duration::from_millis(1500));
+            assert!(mpd.playback.repeat);
+            assert_eq!(mpd.playback.single, OnOffOneshot::Oneshot);
+        });
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let (snapshots, _) = snapshots("rejects_invalid_names");
+
+        assert!(snapshots.save("", &snapshot(0)).is_err());
+        assert!(snapshots.save("../escape", &snapshot(0)).is_err());
+        assert!(snapshots.save(".hidden", &snapshot(0)).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+use anyhow::{Result, bail};
+use itertools::Itertools;
+use rmpc_mpd::{
+    client::Client,
+    commands::Song,
+    mpd_client::{MpdClient, MpdCommand},
+    proto_client::ProtoClient,
+    queue_position::QueuePosition,
+    single_or_range::SingleOrRange,
+};
+
+use crate::{
+    ctx::Ctx,
+    shared::{
+        macros::{status_error, status_info},
+        mpd_client_ext::MpdDelete,
+        mpd_query::{GLOBAL_HISTORY_UPDATE, MpdQueryResult},
+        ring_vec::RingVec,
+    },
+};
+
+/// Maximum number of changes which can be undone
+const HISTORY_LEN: usize = 100;
+
+/// Edit of the queue or a stored playlist. The history stores edits which
+/// revert the changes made by the user, applying an edit yields the edit
+/// reverting it in turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// Add the songs to the queue, positions are in ascending order
+    QueueAdd(Vec<(usize, String)>),
+    /// Remove the songs at the positions from the queue
+    QueueRemove(Vec<(usize, String)>),
+    /// Reorder the queue so the songs are in the given order
+    QueueOrder(Vec<String>),
+    /// Replace the whole queue with the songs
+    QueueReplace(Vec<String>),
+    /// Add the songs to the stored playlist, positions are in ascending order
+    PlaylistAdd { playlist: String, songs: Vec<(usize, String)> },
+    /// Remove the songs at the positions from the stored playlist
+    PlaylistRemove { playlist: String, songs: Vec<(usize, String)> },
+    /// Reorder the stored playlist from the first order to the second one
+    PlaylistOrder { playlist: String, from: Vec<String>, to: Vec<String> },
+}
+
+type Commands = Box<dyn FnOnce(&mut Client<'_>) -> Result<()> + Send>;
+
+/// Stack of the [`History`] an edit was taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stack {
+    Undo,
+    Redo,
+}
+
+/// Outcome of an edit taken from the history, the inverse edit when its
+/// commands succeeded
+#[derive(Debug)]
+pub(crate) struct Applied {
+    stack: Stack,
+    edit: Edit,
+    result: Result<Edit>,
+}
+
+/// Undo and redo stacks of the changes made from rmpc. Lives in [`Ctx`] so it
+/// is kept across reconnects for the whole session.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: RingVec<HISTORY_LEN, Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// Records the edit reverting a change made by the user. Changes which
+    /// were undone can no longer be redone afterwards.
+    pub fn record(&mut self, edit: Edit) {
+        self.undo.push(edit);
+        self.redo.clear();
+    }
+}
+
+impl Edit {
+    /// Edit adding back the songs of the queue which are about to be removed
+    pub fn restore_queue_songs(
+        queue: &[Song],
+        mut removed: impl FnMut(usize, &Song) -> bool,
+    ) -> Option<Edit> {
+        let songs = queue
+            .iter()
+            .enumerate()
+            .filter(|(idx, song)| removed(*idx, song))
+            .map(|(idx, song)| (idx, song.file.clone()))
+            .collect_vec();
+
+        (!songs.is_empty()).then_some(Edit::QueueAdd(songs))
+    }
+
+    /// Edit restoring the current order of the queue
+    pub fn restore_queue_order(queue: &[Song]) -> Edit {
+        Edit::QueueOrder(queue.iter().map(|song| song.file.clone()).collect())
+    }
+
+    /// Edit restoring the current content of the queue after it is replaced
+    pub fn restore_queue(queue: &[Song]) -> Edit {
+        Edit::QueueReplace(queue.iter().map(|song| song.file.clone()).collect())
+    }
+
+    /// Edit adding back the songs in stored playlists which are about to be
+    /// deleted. Returns `None` when whole playlists are deleted as their
+    /// content is not known.
+    pub fn restore_deleted(items: &[MpdDelete]) -> Option<Edit> {
+        let mut playlist = None;
+        let mut songs = Vec::new();
+        for item in items {
+            match item {
+                MpdDelete::SongInPlaylist { playlist: name, position, file } => {
+                    if playlist.is_some_and(|p: &str| p != name.as_ref()) {
+                        return None;
+                    }
+                    playlist = Some(name.as_ref());
+                    songs.push((*position, file.clone()));
+                }
+                MpdDelete::Playlist { .. } => return None,
+            }
+        }
+        songs.sort_by_key(|(pos, _)| *pos);
+
+        Some(Edit::PlaylistAdd { playlist: playlist?.to_owned(), songs })
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Edit::QueueAdd(songs) => format!("Added {} song(s) to the queue", songs.len()),
+            Edit::QueueRemove(songs) => format!("Removed {} song(s) from the queue", songs.len()),
+            Edit::QueueOrder(_) => "Restored order of the queue".to_owned(),
+            Edit::QueueReplace(songs) => format!("Replaced the queue with {} song(s)", songs.len()),
+            Edit::PlaylistAdd { playlist, songs } => {
+                format!("Added {} song(s) to playlist \"{playlist}\"", songs.len())
+            }
+            Edit::PlaylistRemove { playlist, songs } => {
+                format!("Removed {} song(s) from playlist \"{playlist}\"", songs.len())
+            }
+            Edit::PlaylistOrder { playlist, .. } => {
+                format!("Restored order of playlist \"{playlist}\"")
+            }
+        }
+    }
+
+    /// Sends the commands performing the edit, the edit reverting it is pushed
+    /// to the other stack of the history once MPD has applied them. Fails when
+    /// the queue or the playlist has changed in a way the edit can no longer
+    /// be applied.
+    fn apply(self, stack: Stack, ctx: &Ctx) -> Result<()> {
+        let edit = self.clone();
+        let (inverse, commands) = match self {
+            Edit::QueueAdd(songs) => {
+                if !fits(ctx.queue.len(), &songs) {
+                    bail!("The queue has changed, songs can no longer be added back");
+                }
+                let inverse = Edit::QueueRemove(songs.clone());
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    for (pos, uri) in songs {
+                        client.send_add(&uri, Some(QueuePosition::Absolute(pos)))?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+            Edit::QueueRemove(songs) => {
+                let current = ctx.queue.iter().map(|song| song.file.as_str()).collect_vec();
+                if !contains(&current, &songs) {
+                    bail!("The queue has changed, songs can no longer be removed");
+                }
+                let inverse = Edit::QueueAdd(songs.clone());
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    for (pos, _) in songs.iter().rev() {
+                        client.send_delete_from_queue(SingleOrRange::single(*pos))?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+            Edit::QueueOrder(order) => {
+                let inverse = Edit::restore_queue_order(&ctx.queue);
+                let current = ctx.queue.iter().map(|song| song.id).collect_vec();
+                let target = queue_order(&ctx.queue, &order);
+                let moves = moves(&current, &target);
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    for (from, to) in moves {
+                        client.send_move_in_queue(
+                            SingleOrRange::single(from),
+                            QueuePosition::Absolute(to),
+                        )?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+            Edit::QueueReplace(songs) => {
+                let inverse = Edit::restore_queue(&ctx.queue);
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    client.send_clear()?;
+                    for uri in songs {
+                        client.send_add(&uri, None)?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+            Edit::PlaylistAdd { playlist, songs } => {
+                let current = playlist_content(&playlist, ctx)?;
+                if !fits(current.len(), &songs) {
+                    bail!("Playlist \"{playlist}\" has changed, songs can no longer be added back");
+                }
+                let inverse =
+                    Edit::PlaylistRemove { playlist: playlist.clone(), songs: songs.clone() };
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    for (pos, uri) in songs {
+                        client.send_add_to_playlist(&playlist, &uri, Some(pos))?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+            Edit::PlaylistRemove { playlist, songs } => {
+                let current = playlist_content(&playlist, ctx)?;
+                if !contains(&current, &songs) {
+                    bail!("Playlist \"{playlist}\" has changed, songs can no longer be removed");
+                }
+                let inverse =
+                    Edit::PlaylistAdd { playlist: playlist.clone(), songs: songs.clone() };
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    for (pos, _) in songs.iter().rev() {
+                        client
+                            .send_delete_from_playlist(&playlist, &SingleOrRange::single(*pos))?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+            Edit::PlaylistOrder { playlist, from, to } => {
+                if playlist_content(&playlist, ctx)? != from {
+                    bail!(
+                        "Playlist \"{playlist}\" has changed, its order can no longer be restored"
+                    );
+                }
+                let moves = moves(&from, &to);
+                let inverse =
+                    Edit::PlaylistOrder { playlist: playlist.clone(), from: to, to: from };
+                let commands: Commands = Box::new(move |client| {
+                    client.send_start_cmd_list()?;
+                    for (from, to) in moves {
+                        client.send_move_in_playlist(
+                            &playlist,
+                            &SingleOrRange::single(from),
+                            to,
+                        )?;
+                    }
+                    client.send_execute_cmd_list()?;
+                    client.read_ok()?;
+                    Ok(())
+                });
+                (inverse, commands)
+            }
+        };
+
+        ctx.query().id(GLOBAL_HISTORY_UPDATE).query(move |client| {
+            let result = commands(client).map(|()| inverse);
+            Ok(MpdQueryResult::HistoryEdit(Applied { stack, edit, result }))
+        });
+
+        Ok(())
+    }
+}
+
+/// Records the edit reverting a change which is about to be made, does nothing
+/// when there is nothing to revert
+pub fn record(ctx: &Ctx, edit: impl Into<Option<Edit>>) {
+    if let Some(edit) = edit.into() {
+        ctx.history.borrow_mut().record(edit);
+    }
+}
+
+/// Reverts the last change recorded in the history
+pub fn undo(ctx: &Ctx) {
+    let Some(edit) = ctx.history.borrow_mut().undo.pop() else {
+        status_info!("Nothing to undo");
+        return;
+    };
+
+    if let Err(err) = edit.apply(Stack::Undo, ctx) {
+        status_error!("Failed to undo: {err}");
+    }
+}
+
+/// Applies again the last change reverted by [`undo`]
+pub fn redo(ctx: &Ctx) {
+    let Some(edit) = ctx.history.borrow_mut().redo.pop() else {
+        status_info!("Nothing to redo");
+        return;
+    };
+
+    if let Err(err) = edit.apply(Stack::Redo, ctx) {
+        status_error!("Failed to redo: {err}");
+    }
+}
+
+/// Updates the history once the commands of an undone or redone edit
+/// finished. The inverse goes to the other stack on success, otherwise the
+/// edit is put back so it can be tried again.
+pub(crate) fn finish(ctx: &Ctx, applied: Applied) {
+    let Applied { stack, edit, result } = applied;
+    let mut history = ctx.history.borrow_mut();
+    match (stack, result) {
+        (Stack::Undo, Ok(inverse)) => {
+            status_info!("{}", edit.describe());
+            history.redo.push(inverse);
+        }
+        (Stack::Redo, Ok(inverse)) => {
+            status_info!("{}", edit.describe());
+            history.undo.push(inverse);
+        }
+        (Stack::Undo, Err(err)) => {
+            status_error!("Failed to undo: {err}");
+            history.undo.push(edit);
+        }
+        (Stack::Redo, Err(err)) => {
+            status_error!("Failed to redo: {err}");
+            history.redo.push(edit);
+        }
+    }
+}
+
+fn playlist_content(playlist: &str, ctx: &Ctx) -> Result<Vec<String>> {
+    let playlist = playlist.to_owned();
+    ctx.query_sync(move |client| Ok(client.list_playlist(&playlist)?.0))
+}
+
+/// Whether songs can be inserted at their positions one after another into a
+/// list of the given length
+fn fits(len: usize, songs: &[(usize, String)]) -> bool {
+    songs.iter().enumerate().all(|(inserted, (pos, _))| *pos <= len + inserted)
+}
+
+/// Whether the songs are at their positions in the list
+fn contains(list: &[impl AsRef<str>], songs: &[(usize, String)]) -> bool {
+    songs.iter().all(|(pos, uri)| list.get(*pos).is_some_and(|item| item.as_ref() == uri))
+}
+
+/// Ids of the songs in the queue sorted to match the order of the uris as
+/// closely as possible. Songs are matched by their uris so the order survives
+/// songs being removed and added back with different ids, songs not present
+/// in the order are kept at the end.
+fn queue_order(queue: &[Song], order: &[String]) -> Vec<u32> {
+    let mut remaining = queue.iter().collect_vec();
+    let mut result = Vec::with_capacity(queue.len());
+    for uri in order {
+        if let Some(idx) = remaining.iter().position(|song| &song.file == uri) {
+            result.push(remaining.remove(idx).id);
+        }
+    }
+    result.extend(remaining.into_iter().map(|song| song.id));
+
+    result
+}
+
+/// Single item moves, applied one after another, which turn `current` into
+/// `target`. Both lists have to contain the same items.
+fn moves<T: PartialEq + Clone>(current: &[T], target: &[T]) -> Vec<(usize, usize)> {
+    let mut current = current.to_vec();
+    let mut result = Vec::new();
+    for (to, item) in target.iter().enumerate() {
+        let Some(from) = current.iter().skip(to).position(|v| v == item).map(|idx| idx + to) else {
+            continue;
+        };
+        if from != to {
+            let item = current.remove(from);
+            current.insert(to, item);
+            result.push((from, to));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam::channel::{Receiver, Sender};
+    use rmpc_mpd::commands::Song;
+    use rstest::rstest;
+
+    use super::{Applied, Edit, History, Stack, contains, finish, fits, moves, queue_order};
+    use crate::{
+        shared::{
+            events::{AppEvent, ClientRequest, WorkRequest},
+            mpd_client_ext::MpdDelete,
+        },
+        tests::fixtures::{app_event_channel, client_request_channel, ctx, work_request_channel},
+    };
+
+    fn song(id: u32, file: &str) -> Song {
+        Song { id, file: file.to_owned(), ..Default::default() }
+    }
+
+    fn apply_moves<T: Clone>(list: &[T], moves: &[(usize, usize)]) -> Vec<T> {
+        let mut list = list.to_vec();
+        for (from, to) in moves {
+            let item = list.remove(*from);
+            list.insert(*to, item);
+        }
+        list
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = History::default();
+        for i in 0..150 {
+            history.record(Edit::QueueOrder(vec![i.to_string()]));
+        }
+
+        assert_eq!(history.undo.iter().count(), 100);
+        assert_eq!(history.undo.pop(), Some(Edit::QueueOrder(vec!["149".to_owned()])));
+    }
+
+    #[test]
+    fn recording_clears_redo() {
+        let mut history = History::default();
+        history.redo.push(Edit::QueueOrder(Vec::new()));
+
+        history.record(Edit::QueueOrder(Vec::new()));
+
+        assert!(history.redo.is_empty());
+    }
+
+    #[rstest]
+    fn updates_history_once_edit_finished(
+        app_event_channel: (Sender<AppEvent>, Receiver<AppEvent>),
+        work_request_channel: (Sender<WorkRequest>, Receiver<WorkRequest>),
+        client_request_channel: (Sender<ClientRequest>, Receiver<ClientRequest>),
+    ) {
+        let ctx = ctx(app_event_channel, work_request_channel, client_request_channel);
+        let edit = Edit::QueueOrder(vec!["a".to_owned()]);
+        let inverse = Edit::QueueOrder(vec!["b".to_owned()]);
+
+        let result = Err(anyhow::anyhow!("connection lost"));
+        finish(&ctx, Applied { stack: Stack::Undo, edit: edit.clone(), result });
+        assert_eq!(ctx.history.borrow_mut().undo.pop(), Some(edit.clone()));
+
+        let result = Ok(inverse.clone());
+        finish(&ctx, Applied { stack: Stack::Undo, edit, result });
+        assert_eq!(ctx.history.borrow_mut().undo.pop(), None);
+        assert_eq!(ctx.history.borrow().redo, vec![inverse]);
+    }
+
+    #[test]
+    fn restores_removed_queue_songs() {
+        let queue = vec![song(1, "a"), song(2, "b"), song(3, "c")];
+
+        let edit = Edit::restore_queue_songs(&queue, |_, song| song.id != 2);
+
+        assert_eq!(edit, Some(Edit::QueueAdd(vec![(0, "a".to_owned()), (2, "c".to_owned())])));
+        assert_eq!(Edit::restore_queue_songs(&queue, |_, _| false), None);
+    }
+
+    #[test]
+    fn restores_replaced_queue() {
+        let queue = vec![song(1, "a"), song(2, "b")];
+
+        assert_eq!(
+            Edit::restore_queue(&queue),
+            Edit::QueueReplace(vec!["a".to_owned(), "b".to_owned()])
+        );
+    }
+
+    #[test]
+    fn restores_songs_deleted_from_playlist() {
+        let items = vec![
+            MpdDelete::SongInPlaylist { playlist: "pl".into(), position: 3, file: "b".into() },
+            MpdDelete::SongInPlaylist { playlist: "pl".into(), position: 1, file: "a".into() },
+        ];
+
+        assert_eq!(
+            Edit::restore_deleted(&items),
+            Some(Edit::PlaylistAdd {
+                playlist: "pl".to_owned(),
+                songs: vec![(1, "a".to_owned()), (3, "b".to_owned())]
+            })
+        );
+        assert_eq!(Edit::restore_deleted(&[MpdDelete::Playlist { name: "pl".into() }]), None);
+    }
+
+    #[test]
+    fn checks_positions() {
+        let songs = vec![(0, "a".to_owned()), (2, "c".to_owned())];
+
+        assert!(fits(1, &songs));
+        assert!(!fits(0, &songs));
+        assert!(contains(&["a", "b", "c"], &songs));
+        assert!(!contains(&["a", "c"], &songs));
+    }
+
+    #[test]
+    fn orders_queue_by_uris() {
+        let queue = vec![song(1, "a"), song(2, "b"), song(3, "a"), song(4, "d")];
+        let order = ["b", "a", "c", "a"].map(ToOwned::to_owned);
+
+        assert_eq!(queue_order(&queue, &order), vec![2, 1, 3, 4]);
+    }
+
+    #[test]
+    fn moves_to_target_order() {
+        let current = ["a", "b", "c", "d", "e"];
+        let target = ["e", "c", "a", "d", "b"];
+
+        let moves = moves(&current, &target);
+
+        assert_eq!(apply_moves(&current, &moves), target);
+    }
+
+    #[test]
+    fn moves_nothing_when_in_order() {
+        let current = [1, 2, 3];
+
+        assert!(moves(&current, &current).is_empty());
+    }
+}
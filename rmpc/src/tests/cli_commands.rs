@@ -0,0 +1,164 @@
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod cli_commands_tests {
+    use anyhow::{Context, Result};
+    use clap::Parser;
+    use rmpc_mpd::{
+        client::Client,
+        commands::{Song, State, status::OnOffOneshot},
+        fake_server::{FakeMpd, FakeServer},
+    };
+    use rmpc_shared::env::ENV;
+
+    use crate::config::{cli::Args, cli_config::CliConfig};
+
+    fn server() -> FakeServer {
+        let songs = ["music/a.flac", "music/b.flac", "music/c.flac"]
+            .map(|file| Song { file: file.to_owned(), ..Default::default() });
+        FakeServer::start_tcp(FakeMpd::new().with_songs(songs)).unwrap()
+    }
+
+    fn run(server: &FakeServer, args: &[&str]) -> Result<()> {
+        run_with_config(server, args, &CliConfig::default())
+    }
+
+    fn run_with_config(server: &FakeServer, args: &[&str], config: &CliConfig) -> Result<()> {
+        let command = Args::try_parse_from(std::iter::once("rmpc").chain(args.iter().copied()))?
+            .command
+            .context("No command given")?;
+        let mut client = Client::init(server.address(), None, "test", None, false, false, None)?;
+        let (tx, _rx) = crossbeam::channel::unbounded();
+        command.execute(config)?(&tx, &mut client)
+    }
+
+    #[test]
+    fn add_and_play() {
+        let server = server();
+
+        run(&server, &["add", "music/a.flac", "music/b.flac"]).unwrap();
+        run(&server, &["play", "1"]).unwrap();
+        run(&server, &["prev"]).unwrap();
+
+        server.with_state(|mpd| {
+            assert_eq!(mpd.queue_uris(), ["music/a.flac", "music/b.flac"]);
+            assert_eq!(mpd.playback.state, State::Play);
+            assert_eq!(mpd.playback.current, Some(0));
+        });
+    }
+
+    #[test]
+    fn add_missing_song_fails() {
+        let server = server();
+
+        assert!(run(&server, &["add", "music/missing.flac"]).is_err());
+        server.with_state(|mpd| assert!(mpd.queue.is_empty()));
+    }
+
+    #[test]
+    fn play_at_without_current_song_fails() {
+        let server = server();
+
+        run(&server, &["add", "music/a.flac"]).unwrap();
+
+        assert!(run(&server, &["play", "--at", "0:30"]).is_err());
+        server.with_state(|mpd| assert_eq!(mpd.playback.state, State::Stop));
+    }
+
+    #[test]
+    fn change_options() {
+        let server = server();
+
+        run(&server, &["volume", "-20"]).unwrap();
+        run(&server, &["repeat", "on"]).unwrap();
+        run(&server, &["single", "oneshot"]).unwrap();
+
+        server.with_state(|mpd| {
+            assert_eq!(mpd.playback.volume, 80);
+            assert!(mpd.playback.repeat);
+            assert_eq!(mpd.playback.single, OnOffOneshot::Oneshot);
+        });
+    }
+
+    #[test]
+    fn save_and_load_playlist() {
+        let server = server();
+
+        run(&server, &["add", "music"]).unwrap();
+        run(&server, &["save", "all"]).unwrap();
+        run(&server, &["clear"]).unwrap();
+        run(&server, &["load", "all"]).unwrap();
+
+        server.with_state(|mpd| {
+            assert_eq!(mpd.playlists["all"].songs, [
+                "music/a.flac",
+                "music/b.flac",
+                "music/c.flac"
+            ]);
+            assert_eq!(mpd.queue_uris(), ["music/a.flac", "music/b.flac", "music/c.flac"]);
+        });
+    }
+
+    #[test]
+    fn set_sticker() {
+        let server = server();
+
+        run(&server, &["sticker", "set", "music/c.flac", "rating", "7"]).unwrap();
+
+        server.with_state(|mpd| {
+            assert_eq!(
+                mpd.stickers[&("song".to_owned(), "music/c.flac".to_owned())]["rating"],
+                "7"
+            );
+        });
+    }
+
+    #[test]
+    fn save_and_restore_snapshot() {
+        let _lock = ENV.lock();
+        let state_dir = std::env::temp_dir().join(format!("rmpc-state-{}", std::process::id()));
+        ENV.set("XDG_STATE_HOME", state_dir.to_string_lossy());
+        let server = server();
+
+        run(&server, &["add", "music/a.flac", "music/c.flac"]).unwrap();
+        run(&server, &["play", "1"]).unwrap();
+        run(&server, &["random", "on"]).unwrap();
+        run(&server, &["snapshot", "save", "evening"]).unwrap();
+        run(&server, &["clear"]).unwrap();
+        run(&server, &["random", "off"]).unwrap();
+        run(&server, &["snapshot", "restore", "evening"]).unwrap();
+
+        server.with_state(|mpd| {
+            assert_eq!(mpd.queue_uris(), ["music/a.flac", "music/c.flac"]);
+            assert_eq!(mpd.playback.current, Some(1));
+            assert_eq!(mpd.playback.state, State::Play);
+            assert!(mpd.playback.random);
+        });
+        assert!(run(&server, &["snapshot", "restore", "missing"]).is_err());
+        ENV.remove("XDG_STATE_HOME");
+        std::fs::remove_dir_all(state_dir).unwrap();
+    }
+
+    #[test]
+    fn prefetch_and_clear_album_art_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("rmpc-cache-{}", std::process::id()));
+        let config = CliConfig { cache_dir: Some(cache_dir.clone()), ..Default::default() };
+        let server = FakeServer::start_tcp(
+            FakeMpd::new()
+                .with_songs(
+                    ["music/a.flac", "music/b.flac", "other/c.flac"]
+                        .map(|file| Song { file: file.to_owned(), ..Default::default() }),
+                )
+                .with_picture("music", b"cover".to_vec()),
+        )
+        .unwrap();
+        let images = cache_dir.join("album_art").join("images");
+
+        run_with_config(&server, &["albumart", "--prefetch"], &config).unwrap();
+        assert_eq!(std::fs::read_dir(&images).unwrap().count(), 1);
+
+        run_with_config(&server, &["albumart", "--cache-clear"], &config).unwrap();
+        assert!(!images.exists());
+        assert!(run(&server, &["albumart", "--prefetch"]).is_err());
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+}
@@ -21,6 +21,7 @@ use crate::{
         keys::KeyResolver,
         lrc::LrcIndex,
         ring_vec::RingVec,
+        undo::History,
         ytdlp::YtDlpManager,
     },
     ui::input::InputManager,
@@ -69,6 +70,7 @@ pub fn ctx(
         status: Status::default(),
         config: std::sync::Arc::new(config),
         queue: Vec::default(),
+        queue_version: None,
         stickers: HashMap::new(),
         active_tab: TabName::from("test_tab"),
         app_event_sender: app_event_channel.0.clone(),
@@ -88,7 +90,11 @@ pub fn ctx(
         input: InputManager::default(),
         key_resolver,
         cached_queue_time_total: Duration::default(),
+        replay_gain_mode: None,
         current_song: None,
+        history: RefCell::new(History::default()),
+        album_palette: None,
+        album_art_requested: Cell::new(None),
     }
 }
 
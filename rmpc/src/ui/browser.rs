@@ -31,6 +31,7 @@ use crate::{
         mouse_event::{MouseEvent, MouseEventKind, calculate_scrollbar_position},
         mpd_client_ext::{Enqueue, MpdClientExt, MpdDelete},
         mpd_query::EXTERNAL_COMMAND,
+        undo::{self, Edit},
     },
     ui::{
         dirstack::{DirStack, DirStackItem, WalkDirStackItem},
@@ -555,18 +556,22 @@ where
                 let items = self.delete_items(false);
                 if !items.is_empty() {
                     let len = items.len();
+                    let edit = Edit::restore_deleted(&items);
+                    let mut message =
+                        vec![format!("Are you sure you want to delete {} items?", len)];
+                    if edit.is_none() {
+                        message.push("This action cannot be undone.".into());
+                    }
                     modal!(
                         ctx,
                         ConfirmModal::builder()
                             .ctx(ctx)
-                            .message(vec![
-                                format!("Are you sure you want to delete {} items?", len),
-                                "This action cannot be undone.".into()
-                            ])
+                            .message(message)
                             .action(Action::Single {
                                 confirm_label: Some("Delete"),
                                 cancel_label: None,
                                 on_confirm: Box::new(move |ctx| {
+                                    undo::record(ctx, edit);
                                     ctx.command(move |_, client| {
                                         client.delete_multiple(items)?;
                                         Ok(())
@@ -647,10 +652,16 @@ where
                 current: false,
                 min_rating: _,
                 max_rating: _,
+                target,
             } => {
                 let items = self.enqueue(self.items(false).map(|(_, i)| i)).0;
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(RATING_STICKER, value.to_string(), items)?;
+                    client.set_sticker_multiple(
+                        RATING_STICKER,
+                        value.to_string(),
+                        items,
+                        target,
+                    )?;
                     Ok(())
                 });
             }
@@ -659,10 +670,11 @@ where
                 current: false,
                 min_rating: _,
                 max_rating: _,
+                target,
             } => {
                 let items = self.enqueue(self.items(false).map(|(_, i)| i)).0;
                 ctx.command(move |_, client| {
-                    client.delete_sticker_multiple(RATING_STICKER, items)?;
+                    client.delete_sticker_multiple(RATING_STICKER, items, target)?;
                     Ok(())
                 });
             }
@@ -671,12 +683,14 @@ where
                 current: false,
                 min_rating,
                 max_rating,
+                target,
             } => {
                 let items = self.enqueue(self.items(false).map(|(_, i)| i)).0;
                 modal!(
                     ctx,
                     create_rating_modal(
                         items,
+                        target,
                         values.as_slice(),
                         min_rating,
                         max_rating,
@@ -686,28 +700,28 @@ where
                     )
                 );
             }
-            CommonAction::Rate { kind: RateKind::Like(), current: false, .. } => {
+            CommonAction::Rate { kind: RateKind::Like(), current: false, target, .. } => {
                 let items = self.enqueue(self.items(false).map(|(_, i)| i)).0;
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(LIKE_STICKER, "2".to_string(), items)?;
+                    client.set_sticker_multiple(LIKE_STICKER, "2".to_string(), items, target)?;
                     Ok(())
                 });
             }
-            CommonAction::Rate { kind: RateKind::Neutral(), current: false, .. } => {
+            CommonAction::Rate { kind: RateKind::Neutral(), current: false, target, .. } => {
                 let items = self.enqueue(self.items(false).map(|(_, i)| i)).0;
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(LIKE_STICKER, "1".to_string(), items)?;
+                    client.set_sticker_multiple(LIKE_STICKER, "1".to_string(), items, target)?;
                     Ok(())
                 });
             }
-            CommonAction::Rate { kind: RateKind::Dislike(), current: false, .. } => {
+            CommonAction::Rate { kind: RateKind::Dislike(), current: false, target, .. } => {
                 let items = self.enqueue(self.items(false).map(|(_, i)| i)).0;
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(LIKE_STICKER, "0".to_string(), items)?;
+                    client.set_sticker_multiple(LIKE_STICKER, "0".to_string(), items, target)?;
                     Ok(())
                 });
             }
-            CommonAction::Rate { kind: _, current: true, min_rating: _, max_rating: _ } => {
+            CommonAction::Rate { current: true, .. } => {
                 event.abandon();
             }
             CommonAction::Save { kind: _, current: true } => {
@@ -446,6 +446,11 @@ impl CmpByProp {
             SongProperty::LastModified() => {
                 CmpByProp::cmp(Some(a.last_modified), Some(b.last_modified))
             }
+            SongProperty::Priority() => CmpByProp::cmp(Some(a.prio), Some(b.prio)),
+            SongProperty::Range() => CmpByProp::cmp(
+                a.range().map(|r| (r.start, r.end)),
+                b.range().map(|r| (r.start, r.end)),
+            ),
         }
     }
 }
@@ -493,6 +498,7 @@ mod ordtest {
                 .collect(),
             last_modified: mtime.parse().unwrap(),
             added: None,
+            prio: 0,
         })
     }
 
@@ -988,6 +994,7 @@ mod ordtest {
                 )]),
                 last_modified: NOW.to_rfc3339().parse().unwrap(),
                 added: None,
+                prio: 0,
             }
         }
 
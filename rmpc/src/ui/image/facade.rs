@@ -15,7 +15,12 @@ use super::{
 use crate::{
     config::album_art::ImageMethod,
     ctx::Ctx,
-    shared::{events::WorkRequest, macros::status_error, terminal::TERMINAL},
+    shared::{
+        album_art_cache::AlbumArtCache,
+        events::WorkRequest,
+        macros::status_error,
+        terminal::TERMINAL,
+    },
 };
 
 #[derive(Debug)]
@@ -55,7 +60,7 @@ impl AlbumArtFacade {
     pub fn new(ctx: &Ctx) -> Self {
         let config = ctx.config.as_ref();
         let image_backend = match config.album_art.method {
-            ImageMethod::Kitty => ImageBackend::Kitty(Kitty),
+            ImageMethod::Kitty => ImageBackend::Kitty(Kitty::default()),
             ImageMethod::UeberzugWayland => ImageBackend::Ueberzug(Ueberzug::new(Layer::Wayland)),
             ImageMethod::UeberzugX11 => ImageBackend::Ueberzug(Ueberzug::new(Layer::X11)),
             ImageMethod::Iterm2 => ImageBackend::Iterm2(Iterm2),
@@ -108,18 +113,33 @@ impl AlbumArtFacade {
             return Ok(());
         }
 
+        let cache =
+            AlbumArtCache::new(ctx.config.cache_dir.as_deref(), &ctx.config.album_art.cache);
+        let data = move || match cache {
+            Some(cache) => cache.fit(data, size, max_size),
+            None => data,
+        };
+
         match &mut self.image_backend {
             ImageBackend::Kitty(_kitty) => {
                 self.work_tx.send(WorkRequest::ResizeImage(Box::new(move || {
                     Ok(EncodeData::Kitty(Kitty::create_data(
-                        &data, size, max_size, halign, valign,
+                        &data(),
+                        size,
+                        max_size,
+                        halign,
+                        valign,
                     )?))
                 })))?;
             }
             ImageBackend::Iterm2(_iterm2) => {
                 self.work_tx.send(WorkRequest::ResizeImage(Box::new(move || {
                     Ok(EncodeData::Iterm2(Iterm2::create_data(
-                        &data, size, max_size, halign, valign,
+                        &data(),
+                        size,
+                        max_size,
+                        halign,
+                        valign,
                     )?))
                 })))?;
             }
@@ -127,21 +147,33 @@ impl AlbumArtFacade {
                 log::debug!("Sending sixel image encode request");
                 self.work_tx.send(WorkRequest::ResizeImage(Box::new(move || {
                     Ok(EncodeData::Sixel(Sixel::create_data(
-                        &data, size, max_size, halign, valign,
+                        &data(),
+                        size,
+                        max_size,
+                        halign,
+                        valign,
                     )?))
                 })))?;
             }
             ImageBackend::Block(_block) => {
                 self.work_tx.send(WorkRequest::ResizeImage(Box::new(move || {
                     Ok(EncodeData::Block(Block::create_data(
-                        &data, size, max_size, halign, valign,
+                        &data(),
+                        size,
+                        max_size,
+                        halign,
+                        valign,
                     )?))
                 })))?;
             }
             ImageBackend::Ueberzug(_ueberzug) => {
                 self.work_tx.send(WorkRequest::ResizeImage(Box::new(move || {
                     Ok(EncodeData::Ueberzug(Ueberzug::create_data(
-                        &data, size, max_size, halign, valign,
+                        &data(),
+                        size,
+                        max_size,
+                        halign,
+                        valign,
                     )?))
                 })))?;
             }
@@ -24,8 +24,27 @@ use crate::{
     try_skip,
 };
 
+/// Id of the image used to display album art of the current song
+const ALBUM_ART_ID: u32 = 1;
+
 #[derive(Debug)]
-pub struct Kitty;
+pub struct Kitty {
+    id: u32,
+}
+
+impl Kitty {
+    /// Backend which manages only the image with the given id so multiple
+    /// images can be displayed at once
+    pub fn new(id: u32) -> Self {
+        Self { id }
+    }
+}
+
+impl Default for Kitty {
+    fn default() -> Self {
+        Self::new(ALBUM_ART_ID)
+    }
+}
 
 impl Backend for Kitty {
     type EncodedData = Data;
@@ -37,7 +56,7 @@ impl Backend for Kitty {
         bg_color: Option<crossterm::style::Color>,
     ) -> Result<()> {
         super::clear_area(w, bg_color, area)?;
-        tmux_write!(w, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        tmux_write!(w, "\x1b_Ga=d,d=I,i={id},q=2\x1b\\", id = self.id)?;
 
         Ok(())
     }
@@ -46,13 +65,14 @@ impl Backend for Kitty {
         match data {
             Data::ImageData(data) => {
                 try_skip!(
-                    transfer_image_data(w, &data.content, data.img_width, data.img_height),
+                    transfer_image_data(w, self.id, &data.content, data.img_width, data.img_height),
                     "Failed to transfer image data"
                 );
 
                 try_skip!(
                     create_unicode_placeholder_grid(
                         w,
+                        self.id,
                         ctx.config.theme.background_color.map(|c| c.into_crossterm()),
                         data.aligned_area
                     ),
@@ -61,10 +81,14 @@ impl Backend for Kitty {
             }
             Data::AnimationData(data) => {
                 let aligned_area = data.aligned_area;
-                try_skip!(transfer_animation_data(w, data), "Failed to transfer animation data");
+                try_skip!(
+                    transfer_animation_data(w, self.id, data),
+                    "Failed to transfer animation data"
+                );
                 try_skip!(
                     create_unicode_placeholder_grid(
                         w,
+                        self.id,
                         ctx.config.theme.background_color.map(|c| c.into_crossterm()),
                         aligned_area
                     ),
@@ -134,6 +158,7 @@ impl Backend for Kitty {
 
 fn create_unicode_placeholder_grid(
     w: &mut impl Write,
+    id: u32,
     bg_color: Option<crossterm::style::Color>,
     area: Rect,
 ) -> Result<()> {
@@ -142,7 +167,12 @@ fn create_unicode_placeholder_grid(
     execute!(buf, SetColors(colors))?;
     for y in 0..area.height {
         csi_move!(buf, area.left(), area.top() + y)?;
-        write!(buf, "\x1b[38;5;1m")?;
+        // Placeholders reference the image by their foreground color
+        if id < 256 {
+            write!(buf, "\x1b[38;5;{id}m")?;
+        } else {
+            write!(buf, "\x1b[38;2;{};{};{}m", (id >> 16) & 0xFF, (id >> 8) & 0xFF, id & 0xFF)?;
+        }
 
         for x in 0..area.width {
             write!(buf, "{DELIM}{row}{col}", row = GRID[y as usize], col = GRID[x as usize])?;
@@ -156,7 +186,7 @@ fn create_unicode_placeholder_grid(
     Ok(())
 }
 
-fn transfer_animation_data(w: &mut impl Write, data: AnimationData) -> Result<()> {
+fn transfer_animation_data(w: &mut impl Write, id: u32, data: AnimationData) -> Result<()> {
     let start_time = Instant::now();
     let AnimationData { frames, is_compressed, img_width, img_height, aligned_area } = data;
 
@@ -176,7 +206,7 @@ fn transfer_animation_data(w: &mut impl Write, data: AnimationData) -> Result<()
     // Create image and transfer first frame
     tmux_write!(
         w,
-        "\x1b_Gi=1,f=32,U=1,a=T,t=d,m={m},z={delay},q=2,s={img_width},v={img_height},c={cols},r={rows}{compression};{chunk}\x1b\\",
+        "\x1b_Gi={id},f=32,U=1,a=T,t=d,m={m},z={delay},q=2,s={img_width},v={img_height},c={cols},r={rows}{compression};{chunk}\x1b\\",
         compression = if is_compressed { ",o=z" } else { "" },
         cols = aligned_area.width,
         rows = aligned_area.height
@@ -186,7 +216,7 @@ fn transfer_animation_data(w: &mut impl Write, data: AnimationData) -> Result<()
     while first_frame_iter.peek().is_some() {
         let chunk: String = first_frame_iter.by_ref().take(4096).collect();
         let m = i32::from(first_frame_iter.peek().is_some());
-        tmux_write!(w, "\x1b_Gi=1,m={m};{chunk}\x1b\\")?;
+        tmux_write!(w, "\x1b_Gi={id},m={m};{chunk}\x1b\\")?;
     }
 
     // Transfer rest of the frames, skip first because it was already
@@ -197,19 +227,19 @@ fn transfer_animation_data(w: &mut impl Write, data: AnimationData) -> Result<()
 
         tmux_write!(
             w,
-            "\x1b_Gi=1,a=f,t=d,m={m},z={delay},q=2,s={img_width},v={img_height}{compression};{chunk}\x1b\\",
+            "\x1b_Gi={id},a=f,t=d,m={m},z={delay},q=2,s={img_width},v={img_height}{compression};{chunk}\x1b\\",
             compression = if is_compressed { ",o=z" } else { "" }
         )?;
 
         while frame_iter.peek().is_some() {
             let chunk: String = frame_iter.by_ref().take(4096).collect();
             let m = i32::from(frame_iter.peek().is_some());
-            tmux_write!(w, "\x1b_Ga=f,i=1,m={m};{chunk}\x1b\\")?;
+            tmux_write!(w, "\x1b_Ga=f,i={id},m={m};{chunk}\x1b\\")?;
         }
     }
 
     // Run the animation
-    tmux_write!(w, "\x1b_Ga=a,i=1,s=3\x1b\\")?;
+    tmux_write!(w, "\x1b_Ga=a,i={id},s=3\x1b\\")?;
     log::debug!(duration:? = start_time.elapsed(); "Transfer finished");
 
     Ok(())
@@ -217,6 +247,7 @@ fn transfer_animation_data(w: &mut impl Write, data: AnimationData) -> Result<()
 
 fn transfer_image_data(
     w: &mut impl Write,
+    id: u32,
     content: &str,
     img_width: u32,
     img_height: u32,
@@ -229,7 +260,7 @@ fn transfer_image_data(
     let m = i32::from(iter.peek().is_some());
     tmux_write!(
         w,
-        "\x1b_Gi=1,f=32,U=1,t=d,a=T,m={m},q=2,o=z,s={img_width},v={img_height};{first}\x1b\\"
+        "\x1b_Gi={id},f=32,U=1,t=d,a=T,m={m},q=2,o=z,s={img_width},v={img_height};{first}\x1b\\"
     )?;
 
     while iter.peek().is_some() {
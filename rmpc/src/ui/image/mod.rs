@@ -22,6 +22,7 @@ pub mod facade;
 pub mod iterm2;
 pub mod kitty;
 pub mod sixel;
+pub mod thumbnails;
 pub mod ueberzug;
 
 #[allow(unused)]
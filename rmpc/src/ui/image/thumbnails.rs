@@ -0,0 +1,210 @@
+use std::{io::Write, sync::Arc};
+
+use anyhow::Result;
+use crossbeam::channel::Sender;
+use ratatui::{layout::Rect, prelude::IntoCrossterm};
+
+use super::{
+    Backend,
+    block::Block,
+    clear_area,
+    facade::EncodeData,
+    iterm2::Iterm2,
+    kitty::Kitty,
+    sixel::Sixel,
+    ueberzug::{Layer, Ueberzug},
+};
+use crate::{
+    config::album_art::{HorizontalAlign, ImageMethod, VerticalAlign},
+    ctx::Ctx,
+    shared::{
+        album_art_cache::AlbumArtCache,
+        events::WorkRequest,
+        id::{self, Id},
+        terminal::TERMINAL,
+    },
+};
+
+/// Kitty image ids used for the thumbnails start here, the ones below are
+/// reserved for the album art
+const FIRST_KITTY_ID: u32 = 2;
+
+/// Identifies an image encoded for a [`Thumbnails`] slot. Results for an
+/// outdated generation are discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailKey {
+    owner: Id,
+    generation: u64,
+    slot: usize,
+}
+
+/// Displays multiple images at once, each in its own slot, using the
+/// configured image backend
+#[derive(Debug)]
+pub struct Thumbnails {
+    backend: ThumbnailBackend,
+    work_tx: Sender<WorkRequest>,
+    id: Id,
+    generation: u64,
+    shown: Vec<(usize, Rect)>,
+}
+
+#[derive(Debug, Default)]
+enum ThumbnailBackend {
+    Kitty,
+    Ueberzug(Ueberzug),
+    Iterm2,
+    Sixel,
+    Block,
+    #[default]
+    None,
+}
+
+impl Thumbnails {
+    pub fn new(ctx: &Ctx) -> Self {
+        let id = id::new();
+        let backend = match ctx.config.album_art.method {
+            ImageMethod::Kitty => ThumbnailBackend::Kitty,
+            ImageMethod::UeberzugWayland => {
+                ThumbnailBackend::Ueberzug(Ueberzug::new_thumbnails(Layer::Wayland, id))
+            }
+            ImageMethod::UeberzugX11 => {
+                ThumbnailBackend::Ueberzug(Ueberzug::new_thumbnails(Layer::X11, id))
+            }
+            ImageMethod::Iterm2 => ThumbnailBackend::Iterm2,
+            ImageMethod::Sixel => ThumbnailBackend::Sixel,
+            ImageMethod::Block => ThumbnailBackend::Block,
+            ImageMethod::None => ThumbnailBackend::None,
+        };
+
+        Self { backend, work_tx: ctx.work_sender.clone(), id, generation: 0, shown: Vec::new() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self.backend, ThumbnailBackend::None)
+    }
+
+    /// Requests the image to be encoded for the given area. It is displayed
+    /// once [`Thumbnails::display`] receives the encoded data.
+    pub fn show(&mut self, slot: usize, data: Arc<Vec<u8>>, area: Rect, ctx: &Ctx) -> Result<()> {
+        let max_size = ctx.config.album_art.max_size_px;
+        let (halign, valign) = (HorizontalAlign::Center, VerticalAlign::Center);
+        let key = ThumbnailKey { owner: self.id, generation: self.generation, slot };
+
+        let cache =
+            AlbumArtCache::new(ctx.config.cache_dir.as_deref(), &ctx.config.album_art.cache);
+        let data = move || match cache {
+            Some(cache) => cache.fit(data, area, max_size),
+            None => data,
+        };
+
+        let encode: Box<dyn FnOnce() -> Result<EncodeData> + Send + Sync> = match &self.backend {
+            ThumbnailBackend::Kitty => Box::new(move || {
+                Ok(EncodeData::Kitty(Kitty::create_data(&data(), area, max_size, halign, valign)?))
+            }),
+            ThumbnailBackend::Ueberzug(_) => Box::new(move || {
+                Ok(EncodeData::Ueberzug(Ueberzug::create_thumbnail_data(&data(), area, slot)?))
+            }),
+            ThumbnailBackend::Iterm2 => Box::new(move || {
+                Ok(EncodeData::Iterm2(Iterm2::create_data(
+                    &data(),
+                    area,
+                    max_size,
+                    halign,
+                    valign,
+                )?))
+            }),
+            ThumbnailBackend::Sixel => Box::new(move || {
+                Ok(EncodeData::Sixel(Sixel::create_data(&data(), area, max_size, halign, valign)?))
+            }),
+            ThumbnailBackend::Block => Box::new(move || {
+                Ok(EncodeData::Block(Block::create_data(&data(), area, max_size, halign, valign)?))
+            }),
+            ThumbnailBackend::None => return Ok(()),
+        };
+
+        self.shown.push((slot, area));
+        self.work_tx.send(WorkRequest::EncodeThumbnail { key, encode })?;
+
+        Ok(())
+    }
+
+    /// Displays the encoded image, returns false if it does not belong to
+    /// the currently shown thumbnails
+    pub fn display(&mut self, key: ThumbnailKey, data: EncodeData, ctx: &Ctx) -> Result<bool> {
+        if !self.owns(key) {
+            return Ok(false);
+        }
+
+        let w = TERMINAL.writer();
+        let mut w = w.lock();
+        let w = w.by_ref();
+
+        match (&mut self.backend, data) {
+            (ThumbnailBackend::Kitty, EncodeData::Kitty(data)) => {
+                Kitty::new(kitty_id(key.slot)).display(w, data, ctx)?;
+            }
+            (ThumbnailBackend::Ueberzug(ueberzug), EncodeData::Ueberzug(data)) => {
+                ueberzug.display(w, data, ctx)?;
+            }
+            (ThumbnailBackend::Iterm2, EncodeData::Iterm2(data)) => {
+                Iterm2.display(w, data, ctx)?;
+            }
+            (ThumbnailBackend::Sixel, EncodeData::Sixel(data)) => {
+                Sixel.display(w, data, ctx)?;
+            }
+            (ThumbnailBackend::Block, EncodeData::Block(data)) => {
+                Block.display(w, data, ctx)?;
+            }
+            (_, data) => {
+                log::warn!(data:?; "Received thumbnail data for a different backend than the one in use");
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Whether the encoded image belongs to the currently shown thumbnails
+    pub fn owns(&self, key: ThumbnailKey) -> bool {
+        key.owner == self.id && key.generation == self.generation
+    }
+
+    /// Hides all shown thumbnails and discards the ones still being encoded
+    pub fn hide(&mut self, ctx: &Ctx) -> Result<()> {
+        self.generation += 1;
+        if self.shown.is_empty() {
+            return Ok(());
+        }
+
+        let w = TERMINAL.writer();
+        let mut w = w.lock();
+        let w = w.by_ref();
+        let c = ctx.config.theme.background_color.map(|c| c.into_crossterm());
+
+        for (slot, area) in self.shown.drain(..) {
+            match &mut self.backend {
+                ThumbnailBackend::Kitty => Kitty::new(kitty_id(slot)).hide(w, area, c)?,
+                ThumbnailBackend::Ueberzug(ueberzug) => ueberzug.remove_thumbnail(slot)?,
+                ThumbnailBackend::Iterm2 | ThumbnailBackend::Sixel | ThumbnailBackend::Block => {
+                    clear_area(w, c, area)?;
+                }
+                ThumbnailBackend::None => {}
+            }
+        }
+        w.flush()?;
+
+        Ok(())
+    }
+
+    pub fn cleanup(&mut self) -> Result<()> {
+        self.shown.clear();
+        match std::mem::take(&mut self.backend) {
+            ThumbnailBackend::Ueberzug(ueberzug) => Box::new(ueberzug).cleanup(Rect::default()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn kitty_id(slot: usize) -> u32 {
+    FIRST_KITTY_ID + u32::try_from(slot).unwrap_or(u32::MAX - FIRST_KITTY_ID)
+}
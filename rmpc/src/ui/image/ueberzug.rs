@@ -19,7 +19,7 @@ use crate::{
         album_art::{HorizontalAlign, VerticalAlign},
     },
     ctx::Ctx,
-    shared::{macros::try_skip, tmux},
+    shared::{id::Id, macros::try_skip, tmux},
 };
 
 #[derive(Debug)]
@@ -49,6 +49,8 @@ pub enum Layer {
 #[derive(derive_more::Debug)]
 pub struct Data {
     area: Rect,
+    identifier: String,
+    path: String,
 }
 
 impl Layer {
@@ -69,7 +71,7 @@ impl Backend for Ueberzug {
         _: Rect,
         _bg_color: Option<crossterm::style::Color>,
     ) -> Result<()> {
-        self.daemon.remove_image()?;
+        self.daemon.remove_image(IDENTIFIER)?;
         Ok(())
     }
 
@@ -84,7 +86,7 @@ impl Backend for Ueberzug {
         let Rect { x, y, width, height } = data.area;
 
         self.daemon.spawn_daemon_if_needed()?;
-        self.daemon.show_image(UEBERZUG_ALBUM_ART_PATH, x, y, width, height)?;
+        self.daemon.show_image(&data.identifier, &data.path, x, y, width, height)?;
         Ok(())
     }
 
@@ -95,41 +97,60 @@ impl Backend for Ueberzug {
         _halign: HorizontalAlign,
         _valign: VerticalAlign,
     ) -> Result<Self::EncodedData> {
-        std::fs::create_dir_all(UEBERZUG_ALBUM_ART_DIR)?;
-        let mut file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(UEBERZUG_ALBUM_ART_PATH)?;
+        write_image(UEBERZUG_ALBUM_ART_PATH, image_data)?;
 
-        file.write_all(image_data)?;
-
-        Ok(Data { area })
+        Ok(Data {
+            area,
+            identifier: IDENTIFIER.to_owned(),
+            path: UEBERZUG_ALBUM_ART_PATH.to_owned(),
+        })
     }
 }
 
+fn write_image(path: &str, image_data: &[u8]) -> Result<()> {
+    std::fs::create_dir_all(UEBERZUG_ALBUM_ART_DIR)?;
+    let mut file =
+        std::fs::OpenOptions::new().write(true).create(true).truncate(true).open(path)?;
+
+    file.write_all(image_data)?;
+    Ok(())
+}
+
+fn thumbnail_identifier(slot: usize) -> String {
+    format!("{IDENTIFIER}-thumbnail-{slot}")
+}
+
 impl Ueberzug {
     pub fn new(layer: Layer) -> Self {
-        let pid_file_path = std::env::temp_dir()
-            .join("rmpc")
-            .join(format!("ueberzug-{}.pid", std::process::id()))
-            .to_string_lossy()
-            .into_owned();
-        let _ = std::fs::create_dir_all(std::env::temp_dir().join("rmpc"));
-
-        let mut daemon =
-            UeberzugDaemon { pid: None, pid_file: pid_file_path, ueberzug_process: None, layer };
-        if let Ok(pid) = daemon.spawn_daemon_if_needed() {
-            daemon.pid = Some(pid);
-        }
+        let mut daemon = UeberzugDaemon::new(layer, "ueberzug");
+        try_skip!(daemon.spawn_daemon_if_needed(), "Failed to spawn ueberzugpp daemon");
 
         Self { daemon }
     }
+
+    /// Ueberzug with its own daemon used to display thumbnails. The daemon is
+    /// spawned once the first thumbnail is displayed.
+    pub fn new_thumbnails(layer: Layer, id: Id) -> Self {
+        Self { daemon: UeberzugDaemon::new(layer, &format!("ueberzug-thumbnails-{}", *id)) }
+    }
+
+    /// Same as [`Backend::create_data`] but the image is stored separately
+    /// for each slot so multiple thumbnails can be displayed at once
+    pub fn create_thumbnail_data(image_data: &[u8], area: Rect, slot: usize) -> Result<Data> {
+        let path = format!("{UEBERZUG_ALBUM_ART_DIR}/thumbnail-{slot}");
+        write_image(&path, image_data)?;
+
+        Ok(Data { area, identifier: thumbnail_identifier(slot), path })
+    }
+
+    pub fn remove_thumbnail(&mut self, slot: usize) -> Result<()> {
+        self.daemon.remove_image(&thumbnail_identifier(slot))
+    }
 }
 
 impl std::ops::Drop for UeberzugDaemon {
     fn drop(&mut self) {
-        try_skip!(self.remove_image(), "Failed to send remove request to ueberzugpp");
+        try_skip!(self.remove_image(IDENTIFIER), "Failed to send remove request to ueberzugpp");
 
         if let Some(ref mut proc) = self.ueberzug_process {
             try_skip!(proc.kill(), "Failed to kill ueberzugpp process");
@@ -150,9 +171,21 @@ impl std::ops::Drop for UeberzugDaemon {
 }
 
 impl UeberzugDaemon {
+    /// The pid file is named after the daemon and rmpc's pid so multiple
+    /// daemons and rmpc instances do not clash
+    fn new(layer: Layer, name: &str) -> Self {
+        let dir = std::env::temp_dir().join("rmpc");
+        let _ = std::fs::create_dir_all(&dir);
+        let pid_file =
+            dir.join(format!("{name}-{}.pid", std::process::id())).to_string_lossy().into_owned();
+
+        Self { pid: None, pid_file, ueberzug_process: None, layer }
+    }
+
     fn show_image(
         &self,
-        path: &'static str,
+        identifier: &str,
+        path: &str,
         x: u16,
         y: u16,
         width: u16,
@@ -164,18 +197,18 @@ impl UeberzugDaemon {
 
         let mut socket = UeberzugSocket::connect(pid)?;
 
-        socket.add_image(pid, CreateData { path, width, height, x, y })?;
+        socket.add_image(pid, identifier, CreateData { path, width, height, x, y })?;
 
         Ok(())
     }
 
-    fn remove_image(&self) -> Result<()> {
+    fn remove_image(&self, identifier: &str) -> Result<()> {
         let Some(pid) = self.pid else {
             return Ok(());
         };
 
         let mut socket = UeberzugSocket::connect(pid)?;
-        socket.remove_image(pid)
+        socket.remove_image(pid, identifier)
     }
 
     fn is_daemon_running(pid: Pid) -> bool {
@@ -207,18 +240,16 @@ impl UeberzugDaemon {
     }
 
     fn spawn_daemon_if_needed(&mut self) -> Result<Pid> {
-        let Some(pid) = self.pid else {
-            let (pid, child) = self.spawn_daemon()?;
-            self.ueberzug_process = Some(child);
+        if let Some(pid) = self.pid
+            && Self::is_daemon_running(pid)
+        {
             return Ok(pid);
-        };
-        if Self::is_daemon_running(pid) {
-            Ok(pid)
-        } else {
-            let (pid, child) = self.spawn_daemon()?;
-            self.ueberzug_process = Some(child);
-            Ok(pid)
         }
+
+        let (pid, child) = self.spawn_daemon()?;
+        self.pid = Some(pid);
+        self.ueberzug_process = Some(child);
+        Ok(pid)
     }
 
     fn read_pid(&self) -> Result<Pid> {
@@ -247,9 +278,9 @@ impl UeberzugSocket {
         ))
     }
 
-    fn remove_image(&mut self, pid: Pid) -> Result<()> {
+    fn remove_image(&mut self, pid: Pid, identifier: &str) -> Result<()> {
         self.0.write_all(
-            format!(r#"{{"action":"remove","identifier":"{IDENTIFIER}-{pid}"}}"#).as_bytes(),
+            format!(r#"{{"action":"remove","identifier":"{identifier}-{pid}"}}"#).as_bytes(),
         )?;
         self.0.write_all(b"\n")?;
         Ok(())
@@ -258,9 +289,10 @@ impl UeberzugSocket {
     fn add_image(
         &mut self,
         pid: Pid,
+        identifier: &str,
         CreateData { x, y, width, height, path }: CreateData,
     ) -> Result<()> {
-        self.0.write_all(format!(r#"{{"action":"add","identifier":"{IDENTIFIER}-{pid}","max_height":{height},"max_width":{width},"path":"{path}","x":{x},"y":{y}}}"#)
+        self.0.write_all(format!(r#"{{"action":"add","identifier":"{identifier}-{pid}","max_height":{height},"max_width":{width},"path":"{path}","x":{x},"y":{y}}}"#)
             .as_bytes(),
         )?;
         self.0.write_all(b"\n")?;
@@ -9,6 +9,7 @@ use modals::{
     input_modal::InputModal,
     keybinds::KeybindsModal,
     menu::modal::MenuModal,
+    mounts::{self, MountsModal},
     outputs::OutputsModal,
 };
 use panes::{PaneContainer, Panes, pane_call};
@@ -55,15 +56,21 @@ use crate::{
         macros::{modal, status_error, status_info, status_warn},
         mouse_event::MouseEvent,
         mpd_client_ext::{Enqueue, MpdClientExt},
+        undo,
         ytdlp::YtDlpHost,
     },
     ui::{
-        image::facade::EncodeData,
+        image::{facade::EncodeData, thumbnails::ThumbnailKey},
         input::{InputEvent, InputResultEvent},
         modals::{
             downloads::DownloadsModal,
-            info_list_modal::SongCtx,
-            menu::{add_to_playlist_or_show_modal, create_rating_modal, create_save_modal},
+            duplicates::DuplicatesModal,
+            menu::{
+                add_to_playlist_or_show_modal,
+                create_rating_modal,
+                create_save_modal,
+                create_snapshots_modal,
+            },
         },
     },
 };
@@ -98,6 +105,7 @@ pub struct Ui<'ui> {
 
 const OPEN_DECODERS_MODAL: &str = "open_decoders_modal";
 const OPEN_OUTPUTS_MODAL: &str = "open_outputs_modal";
+const OPEN_MOUNTS_MODAL: &str = "open_mounts_modal";
 
 pub const FILTER_PREFIX: &str = "[FILTER]:";
 
@@ -595,6 +603,35 @@ impl<'ui> Ui<'ui> {
                         Ok(())
                     });
                 }
+                GlobalAction::MixRampDbUp => {
+                    // 0dB is the normalized maximum volume
+                    let new_db = (ctx.status.mixramp_db().unwrap_or(0.0) + 1.0).min(0.0);
+                    ctx.command(move |_, client| {
+                        client.mixramp_db(new_db)?;
+                        Ok(())
+                    });
+                }
+                GlobalAction::MixRampDbDown => {
+                    let new_db = ctx.status.mixramp_db().unwrap_or(0.0) - 1.0;
+                    ctx.command(move |_, client| {
+                        client.mixramp_db(new_db)?;
+                        Ok(())
+                    });
+                }
+                GlobalAction::MixRampDelayUp => {
+                    let new_delay = ctx.status.mixramp_delay().unwrap_or(0.0) + 1.0;
+                    ctx.command(move |_, client| {
+                        client.mixramp_delay(new_delay)?;
+                        Ok(())
+                    });
+                }
+                GlobalAction::MixRampDelayDown => {
+                    let new_delay = (ctx.status.mixramp_delay().unwrap_or(0.0) - 1.0).max(0.0);
+                    ctx.command(move |_, client| {
+                        client.mixramp_delay(new_delay)?;
+                        Ok(())
+                    });
+                }
                 GlobalAction::SeekForward
                     if matches!(ctx.status.state, State::Play | State::Pause) =>
                 {
@@ -700,16 +737,15 @@ impl<'ui> Ui<'ui> {
                         .replace_id(OPEN_DECODERS_MODAL)
                         .query(|client| Ok(MpdQueryResult::Decoders(client.decoders()?.0)));
                 }
+                GlobalAction::ShowMounts => {
+                    ctx.query()
+                        .id(OPEN_MOUNTS_MODAL)
+                        .replace_id(OPEN_MOUNTS_MODAL)
+                        .query(mounts::fetch_mounts);
+                }
                 GlobalAction::ShowCurrentSongInfo => {
                     if let Some(current_song) = &ctx.current_song() {
-                        modal!(
-                            ctx,
-                            InfoListModal::builder()
-                                .items(SongCtx(current_song, ctx))
-                                .title("Song info")
-                                .column_widths(&[30, 70])
-                                .build()
-                        );
+                        InfoListModal::open_song_info(current_song, ctx);
                     } else {
                         status_info!("No song is currently playing");
                     }
@@ -720,6 +756,30 @@ impl<'ui> Ui<'ui> {
                 GlobalAction::ShowDownloads => {
                     modal!(ctx, DownloadsModal::new(ctx));
                 }
+                GlobalAction::FindDuplicates => {
+                    if ctx.supported_commands.contains("getfingerprint") {
+                        modal!(ctx, DuplicatesModal::new(ctx));
+                    } else {
+                        status_error!("MPD does not support fingerprinting songs");
+                    }
+                }
+                GlobalAction::Snapshots => {
+                    modal!(ctx, create_snapshots_modal(ctx)?);
+                }
+                GlobalAction::Undo => undo::undo(ctx),
+                GlobalAction::Redo => undo::redo(ctx),
+                GlobalAction::ReplayGain { mode } => {
+                    let Some(current) = ctx.replay_gain_mode else {
+                        status_error!("MPD does not support replay gain");
+                        return Ok(KeyHandleResult::None);
+                    };
+                    let mode = mode.unwrap_or_else(|| current.cycle());
+                    ctx.command(move |_, client| {
+                        client.replay_gain_mode(mode)?;
+                        status_info!("Replay gain mode set to '{mode}'");
+                        Ok(())
+                    });
+                }
             }
         } else if let Some(action) = key.claim_common() {
             #[allow(
@@ -727,15 +787,17 @@ impl<'ui> Ui<'ui> {
                 reason = "Future expansion, remove when adding other actions"
             )]
             match action {
-                CommonAction::Rate { kind, current: true, min_rating, max_rating } => {
+                CommonAction::Rate { kind, current: true, min_rating, max_rating, target } => {
                     if let Some(song) = ctx.current_song() {
+                        let items = vec![Enqueue::File { path: song.file.clone() }];
+                        let target = *target;
                         match kind {
                             RateKind::Modal { values, custom, like } => {
-                                let items = vec![Enqueue::File { path: song.file.clone() }];
                                 modal!(
                                     ctx,
                                     create_rating_modal(
                                         items,
+                                        target,
                                         values.as_slice(),
                                         *min_rating,
                                         *max_rating,
@@ -746,38 +808,57 @@ impl<'ui> Ui<'ui> {
                                 );
                             }
                             RateKind::Value(value) => {
-                                let uri = song.file.clone();
                                 let value = value.to_string();
                                 ctx.command(move |_, client| {
-                                    client.set_sticker(&uri, RATING_STICKER, &value)?;
+                                    client.set_sticker_multiple(
+                                        RATING_STICKER,
+                                        value,
+                                        items,
+                                        target,
+                                    )?;
                                     Ok(())
                                 });
                             }
                             RateKind::ClearRating() => {
-                                let uri = song.file.clone();
                                 ctx.command(move |_, client| {
-                                    client.delete_sticker(&uri, RATING_STICKER)?;
+                                    client.delete_sticker_multiple(
+                                        RATING_STICKER,
+                                        items,
+                                        target,
+                                    )?;
                                     Ok(())
                                 });
                             }
                             RateKind::Like() => {
-                                let uri = song.file.clone();
                                 ctx.command(move |_, client| {
-                                    client.set_sticker(&uri, LIKE_STICKER, "2")?;
+                                    client.set_sticker_multiple(
+                                        LIKE_STICKER,
+                                        "2".to_owned(),
+                                        items,
+                                        target,
+                                    )?;
                                     Ok(())
                                 });
                             }
                             RateKind::Dislike() => {
-                                let uri = song.file.clone();
                                 ctx.command(move |_, client| {
-                                    client.set_sticker(&uri, LIKE_STICKER, "0")?;
+                                    client.set_sticker_multiple(
+                                        LIKE_STICKER,
+                                        "0".to_owned(),
+                                        items,
+                                        target,
+                                    )?;
                                     Ok(())
                                 });
                             }
                             RateKind::Neutral() => {
-                                let uri = song.file.clone();
                                 ctx.command(move |_, client| {
-                                    client.set_sticker(&uri, LIKE_STICKER, "1")?;
+                                    client.set_sticker_multiple(
+                                        LIKE_STICKER,
+                                        "1".to_owned(),
+                                        items,
+                                        target,
+                                    )?;
                                     Ok(())
                                 });
                             }
@@ -1099,6 +1180,9 @@ impl<'ui> Ui<'ui> {
                 (OPEN_DECODERS_MODAL, MpdQueryResult::Decoders(decoders)) => {
                     modal!(ctx, DecodersModal::new(decoders));
                 }
+                (OPEN_MOUNTS_MODAL, MpdQueryResult::Mounts { mounts, neighbors }) => {
+                    modal!(ctx, MountsModal::new(mounts, neighbors));
+                }
                 (FETCH_SONG_STICKERS, MpdQueryResult::SongStickers(stickers)) => {
                     for (k, v) in stickers {
                         // Assume all stickers were fetched for each song so simple replace is
@@ -1137,6 +1221,8 @@ pub enum UiEvent {
     Output,
     StoredPlaylist,
     Sticker,
+    Mount,
+    Neighbor,
     LogAdded(Vec<u8>),
     ModalOpened,
     ModalClosed,
@@ -1151,8 +1237,11 @@ pub enum UiEvent {
     PlaybackStateChanged,
     ImageEncoded { data: EncodeData },
     ImageEncodeFailed { err: anyhow::Error },
+    ThumbnailEncoded { key: ThumbnailKey, data: EncodeData },
+    ThumbnailEncodeFailed { key: ThumbnailKey, err: anyhow::Error },
     DownloadsUpdated,
     DisableQueueHighlight,
+    AlbumColorsChanged,
 }
 
 impl TryFrom<IdleEvent> for UiEvent {
@@ -1165,6 +1254,8 @@ impl TryFrom<IdleEvent> for UiEvent {
             IdleEvent::StoredPlaylist => UiEvent::StoredPlaylist,
             IdleEvent::Output => UiEvent::Output,
             IdleEvent::Sticker => UiEvent::Sticker,
+            IdleEvent::Mount => UiEvent::Mount,
+            IdleEvent::Neighbor => UiEvent::Neighbor,
             _ => return Err(()),
         })
     }
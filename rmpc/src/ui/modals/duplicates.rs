@@ -0,0 +1,393 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    macros::{constraint, constraints},
+    style::Style,
+    symbols::border,
+    widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
+};
+use rmpc_mpd::{client::Client, commands::Song};
+
+use super::Modal;
+use crate::{
+    MpdQueryResult,
+    config::keys::{
+        CommonAction,
+        actions::{AutoplayKind, Position},
+    },
+    ctx::Ctx,
+    shared::{
+        clipboard::Clipboard,
+        duplicates::{self, FINGERPRINT_BATCH_SIZE, FingerprintCache},
+        ext::{duration::DurationExt, rect::RectExt},
+        id::{self, Id},
+        keys::ActionEvent,
+        macros::{modal, status_error},
+        mouse_event::{MouseEvent, MouseEventKind},
+        mpd_client_ext::{Enqueue, MpdClientExt},
+    },
+    ui::{
+        dirstack::DirState,
+        modals::menu::{create_delete_modal, modal::MenuModal},
+    },
+};
+
+const LIST_SONGS: &str = "duplicates_list_songs";
+const FINGERPRINT: &str = "duplicates_fingerprint";
+const GROUP: &str = "duplicates_group";
+
+#[derive(Debug)]
+enum ScanState {
+    Listing,
+    Fingerprinting { done: usize, total: usize },
+    Grouping,
+    Done,
+}
+
+#[derive(Debug)]
+pub struct DuplicatesModal {
+    id: Id,
+    state: ScanState,
+    cache: FingerprintCache,
+    songs: Vec<Song>,
+    /// Songs which still need to be fingerprinted by MPD
+    pending: Vec<Song>,
+    groups: Vec<Vec<Song>>,
+    /// Group index and index of the song in the group for every row
+    rows: Vec<(usize, usize)>,
+    scrolling_state: DirState<TableState>,
+    table_area: Rect,
+}
+
+impl DuplicatesModal {
+    pub fn new(ctx: &Ctx) -> Self {
+        let cache_dir = ctx.config.cache_dir.clone();
+        ctx.query().id(LIST_SONGS).replace_id(LIST_SONGS).query(move |client| {
+            Ok(MpdQueryResult::DuplicateCandidates {
+                songs: duplicates::songs_to_scan(client, None)?,
+                cache: FingerprintCache::load(cache_dir.as_deref()),
+            })
+        });
+
+        Self {
+            id: id::new(),
+            state: ScanState::Listing,
+            cache: FingerprintCache::default(),
+            songs: Vec::new(),
+            pending: Vec::new(),
+            groups: Vec::new(),
+            rows: Vec::new(),
+            scrolling_state: DirState::default(),
+            table_area: Rect::default(),
+        }
+    }
+
+    /// Requests fingerprints of the next batch of songs or groups the songs
+    /// once all of them are fingerprinted
+    fn fingerprint_next(&mut self, ctx: &Ctx) {
+        if self.pending.is_empty() {
+            self.finish(ctx);
+            return;
+        }
+
+        let batch =
+            self.pending.split_off(self.pending.len().saturating_sub(FINGERPRINT_BATCH_SIZE));
+        ctx.query().id(FINGERPRINT).query(move |client| {
+            Ok(MpdQueryResult::Fingerprints(duplicates::fetch_fingerprints(client, batch)?))
+        });
+    }
+
+    /// Saves the fingerprints and groups the songs on the worker because
+    /// comparing the fingerprints takes a while on large libraries
+    fn finish(&mut self, ctx: &Ctx) {
+        let songs = std::mem::take(&mut self.songs);
+        let cache = std::mem::take(&mut self.cache);
+        let cache_dir = ctx.config.cache_dir.clone();
+        self.state = ScanState::Grouping;
+
+        ctx.query().id(GROUP).replace_id(GROUP).query(move |_client| {
+            if let Err(err) = cache.save(cache_dir.as_deref()) {
+                status_error!(err:?; "Failed to save fingerprint cache");
+            }
+            Ok(MpdQueryResult::DuplicateGroups(duplicates::group_duplicates(songs, &cache)))
+        });
+    }
+
+    fn show_groups(&mut self, groups: Vec<Vec<Song>>) {
+        self.groups = groups;
+        self.rows = self
+            .groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group_idx, group)| (0..group.len()).map(move |idx| (group_idx, idx)))
+            .collect();
+        self.state = ScanState::Done;
+
+        self.scrolling_state.set_content_len(Some(self.rows.len()));
+        self.scrolling_state.first();
+    }
+
+    fn selected(&self) -> Option<(&Song, &[Song])> {
+        let (group_idx, idx) = self.rows.get(self.scrolling_state.get_selected()?)?;
+        let group = &self.groups[*group_idx];
+        Some((&group[*idx], group))
+    }
+
+    fn title(&self) -> String {
+        match self.state {
+            ScanState::Listing => "Duplicates - listing songs".to_owned(),
+            ScanState::Fingerprinting { done, total } => {
+                format!("Duplicates - fingerprinting {done}/{total}")
+            }
+            ScanState::Grouping => "Duplicates - comparing fingerprints".to_owned(),
+            ScanState::Done => format!("Duplicates - {} groups found", self.groups.len()),
+        }
+    }
+
+    fn create_menu(&self, ctx: &mut Ctx) {
+        let Some((song, group)) = self.selected() else {
+            return;
+        };
+        let file = song.file.clone();
+        let group_files = group.iter().map(|song| song.file.clone()).collect_vec();
+        let (file2, group_files2) = (file.clone(), group_files.clone());
+        let (file3, group_files3) = (file.clone(), group_files.clone());
+
+        let modal = MenuModal::new(ctx)
+            .list_section(ctx, |section| {
+                Some(
+                    section
+                        .item("Add to queue", move |ctx| {
+                            enqueue(ctx, vec![file]);
+                            Ok(())
+                        })
+                        .item("Add group to queue", move |ctx| {
+                            enqueue(ctx, group_files);
+                            Ok(())
+                        })
+                        .item("Delete from playlist", move |ctx| {
+                            open_delete_modal(ctx, HashSet::from([file2]));
+                            Ok(())
+                        })
+                        .item("Delete group from playlist", move |ctx| {
+                            open_delete_modal(ctx, group_files2.into_iter().collect());
+                            Ok(())
+                        })
+                        .item("Copy path", move |_ctx| {
+                            Clipboard::from(file3).write_with_status();
+                            Ok(())
+                        })
+                        .item("Copy group paths", move |_ctx| {
+                            Clipboard::from(group_files3.join("\n")).write_with_status();
+                            Ok(())
+                        }),
+                )
+            })
+            .list_section(ctx, |section| Some(section.item("Cancel", |_ctx| Ok(()))))
+            .build();
+
+        modal!(ctx, modal);
+    }
+}
+
+fn enqueue(ctx: &Ctx, files: Vec<String>) {
+    let items = files.into_iter().map(|path| Enqueue::File { path }).collect();
+    Client::resolve_and_enqueue(ctx, items, Position::EndOfQueue, AutoplayKind::None, None, None);
+}
+
+fn open_delete_modal(ctx: &Ctx, files: HashSet<String>) {
+    match create_delete_modal(files, true, ctx) {
+        Ok(modal) => modal!(ctx, modal),
+        Err(err) => status_error!(err:?; "Failed to list playlists"),
+    }
+}
+
+impl Modal for DuplicatesModal {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn render(&mut self, frame: &mut Frame, ctx: &mut Ctx) -> Result<()> {
+        let popup_area = frame.area().centered(constraint!(==80%), constraint!(==80%));
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = ctx.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(ctx.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title(self.title());
+
+        let table_area = block.inner(popup_area).inner(Margin { horizontal: 1, vertical: 0 });
+
+        let text_style =
+            ctx.config.theme.text_color.map(|c| Style::default().fg(c)).unwrap_or_default();
+        let rows = self
+            .rows
+            .iter()
+            .map(|(group_idx, idx)| {
+                let song = &self.groups[*group_idx][*idx];
+                let group = if *idx == 0 { (group_idx + 1).to_string() } else { String::new() };
+                Row::new([
+                    Cell::from(group),
+                    Cell::from(song.file.clone()),
+                    Cell::from(song.duration.map(|d| d.to_string()).unwrap_or_default()),
+                ])
+            })
+            .collect_vec();
+        let item_count = rows.len();
+        let table = Table::new(rows, constraints![==6, >=10, ==10])
+            .column_spacing(1)
+            .style(ctx.config.as_text_style())
+            .row_highlight_style(ctx.config.theme.current_item_style)
+            .header(Row::new(["Group", "File", "Duration"]).style(text_style));
+
+        self.scrolling_state.set_content_and_viewport_len(item_count, table_area.height as usize);
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+        frame.render_widget(block, popup_area);
+        if let Some(scrollbar) = ctx.config.as_styled_scrollbar()
+            && item_count > table_area.height.saturating_sub(1) as usize
+        {
+            frame.render_stateful_widget(
+                scrollbar,
+                popup_area.inner(Margin { horizontal: 0, vertical: 1 }),
+                self.scrolling_state.as_scrollbar_state_ref(),
+            );
+        }
+
+        self.table_area = table_area.shrink_from_top(1); // Subtract header height
+
+        Ok(())
+    }
+
+    fn destroy(&mut self, ctx: &Ctx) -> Result<()> {
+        // Keep the fingerprints computed so far when the scan is cancelled
+        if matches!(self.state, ScanState::Fingerprinting { .. }) {
+            let cache = std::mem::take(&mut self.cache);
+            let cache_dir = ctx.config.cache_dir.clone();
+            ctx.command(move |_tx, _client| cache.save(cache_dir.as_deref()));
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut ActionEvent, ctx: &mut Ctx) -> Result<()> {
+        if let Some(action) = key.claim_common() {
+            match action {
+                CommonAction::Down => {
+                    self.scrolling_state.next(ctx.config.scrolloff, ctx.config.wrap_navigation);
+                    ctx.render()?;
+                }
+                CommonAction::Up => {
+                    self.scrolling_state.prev(ctx.config.scrolloff, ctx.config.wrap_navigation);
+                    ctx.render()?;
+                }
+                CommonAction::DownHalf => {
+                    self.scrolling_state.next_half_viewport(ctx.config.scrolloff);
+                    ctx.render()?;
+                }
+                CommonAction::UpHalf => {
+                    self.scrolling_state.prev_half_viewport(ctx.config.scrolloff);
+                    ctx.render()?;
+                }
+                CommonAction::PageUp => {
+                    self.scrolling_state.prev_viewport(ctx.config.scrolloff);
+                    ctx.render()?;
+                }
+                CommonAction::PageDown => {
+                    self.scrolling_state.next_viewport(ctx.config.scrolloff);
+                    ctx.render()?;
+                }
+                CommonAction::Top => {
+                    self.scrolling_state.first();
+                    ctx.render()?;
+                }
+                CommonAction::Bottom => {
+                    self.scrolling_state.last();
+                    ctx.render()?;
+                }
+                CommonAction::Confirm => {
+                    self.create_menu(ctx);
+                }
+                CommonAction::Close => {
+                    self.hide(ctx)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEvent, ctx: &mut Ctx) -> Result<()> {
+        if !self.table_area.contains(event.into()) {
+            return Ok(());
+        }
+
+        let clicked_row: usize = event.y.saturating_sub(self.table_area.y).into();
+        let Some(idx) = self.scrolling_state.get_at_rendered_row(clicked_row) else {
+            return Ok(());
+        };
+
+        match event.kind {
+            MouseEventKind::LeftClick => {
+                self.scrolling_state.select(Some(idx), ctx.config.scrolloff);
+                ctx.render()?;
+            }
+            MouseEventKind::DoubleClick | MouseEventKind::RightClick => {
+                self.scrolling_state.select(Some(idx), ctx.config.scrolloff);
+                self.create_menu(ctx);
+                ctx.render()?;
+            }
+            MouseEventKind::ScrollDown => {
+                self.scrolling_state.scroll_down(ctx.config.scroll_amount, ctx.config.scrolloff);
+                ctx.render()?;
+            }
+            MouseEventKind::ScrollUp => {
+                self.scrolling_state.scroll_up(ctx.config.scroll_amount, ctx.config.scrolloff);
+                ctx.render()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_query_finished(
+        &mut self,
+        id: &'static str,
+        data: &mut MpdQueryResult,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        match (id, data) {
+            (LIST_SONGS, MpdQueryResult::DuplicateCandidates { songs, cache }) => {
+                self.songs = std::mem::take(songs);
+                self.cache = std::mem::take(cache);
+                self.pending = self.cache.missing(&self.songs).cloned().collect();
+                self.state = ScanState::Fingerprinting { done: 0, total: self.pending.len() };
+                self.fingerprint_next(ctx);
+                ctx.render()?;
+            }
+            (FINGERPRINT, MpdQueryResult::Fingerprints(fingerprints)) => {
+                for (song, fingerprint) in std::mem::take(fingerprints) {
+                    self.cache.insert(&song, fingerprint);
+                }
+                if let ScanState::Fingerprinting { done, total } = &mut self.state {
+                    *done = *total - self.pending.len();
+                }
+                self.fingerprint_next(ctx);
+                ctx.render()?;
+            }
+            (GROUP, MpdQueryResult::DuplicateGroups(groups)) => {
+                self.show_groups(std::mem::take(groups));
+                ctx.render()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
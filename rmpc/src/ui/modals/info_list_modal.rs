@@ -12,32 +12,52 @@ use ratatui::{
     text::Text,
     widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
 };
-use rmpc_mpd::commands::Song;
+use rmpc_mpd::{
+    commands::{Song, comments::Comments},
+    errors::MpdError,
+    mpd_client::MpdClient,
+};
 
 use super::Modal;
 use crate::{
-    config::keys::CommonAction,
+    MpdQueryResult,
+    config::keys::{CommonAction, actions::CopyContentsKind},
     ctx::Ctx,
     shared::{
+        clipboard::Clipboard,
         ext::duration::DurationExt,
         id::{self, Id},
         keys::ActionEvent,
+        macros::modal,
         mouse_event::{MouseEvent, MouseEventKind},
     },
     ui::{dirstack::DirState, song_ext::SongExt as _},
 };
 
+const READ_COMMENTS: &str = "info_list_modal_read_comments";
+
 #[derive(Debug)]
 pub struct InfoListModal {
     id: Id,
     scrolling_state: DirState<TableState>,
     table_area: Rect,
     items: KeyValues,
+    /// Raw tags of `comments_file`, shown in a separate section once fetched
+    comments: Option<KeyValues>,
+    comments_file: Option<String>,
+    /// Item shown on each rendered row, wrapped items span multiple rows
+    rendered_items: Vec<Option<ItemRef>>,
     column_widths: &'static [u16],
     title: &'static str,
     size: (u16, u16),
 }
 
+#[derive(Debug, Clone, Copy)]
+enum ItemRef {
+    Item(usize),
+    Comment(usize),
+}
+
 #[derive(Debug)]
 pub struct KeyValues(Vec<KeyValue>);
 #[derive(Debug)]
@@ -61,6 +81,9 @@ impl InfoListModal {
             id: id::new(),
             scrolling_state,
             items: items.into(),
+            comments: None,
+            comments_file: None,
+            rendered_items: Vec::new(),
             table_area: Rect::default(),
             title,
             column_widths,
@@ -68,6 +91,64 @@ impl InfoListModal {
         }
     }
 
+    /// Opens info about the song. Raw tags of its file are added once they
+    /// are read from MPD.
+    pub fn open_song_info(song: &Song, ctx: &Ctx) {
+        let mut modal = Self::builder()
+            .items(SongCtx(song, ctx))
+            .title("Song info")
+            .column_widths(&[30, 70])
+            .build();
+
+        let read_comments = ctx.supported_commands.contains("readcomments");
+        if read_comments {
+            modal.comments_file = Some(song.file.clone());
+        }
+        modal!(ctx, modal);
+
+        if read_comments {
+            let file = song.file.clone();
+            ctx.query().id(READ_COMMENTS).query(move |client| {
+                // Not every file or stream supports reading comments, show the info
+                // without them in that case
+                let comments = client.read_comments(&file).or_else(|err| match err {
+                    MpdError::Mpd(_) => Ok(Comments::default()),
+                    err => Err(err),
+                })?;
+                Ok(MpdQueryResult::Comments { file, comments })
+            });
+        }
+    }
+
+    fn item(&self, item: ItemRef) -> Option<&KeyValue> {
+        match item {
+            ItemRef::Item(idx) => self.items.0.get(idx),
+            ItemRef::Comment(idx) => self.comments.as_ref().and_then(|c| c.0.get(idx)),
+        }
+    }
+
+    fn copy_to_clipboard(&self, all: bool) {
+        let content = if all {
+            self.items
+                .0
+                .iter()
+                .chain(self.comments.iter().flat_map(|c| c.0.iter()))
+                .map(|item| format!("{}: {}", item.key, item.value))
+                .join("\n")
+        } else if let Some(item) = self
+            .scrolling_state
+            .get_selected()
+            .and_then(|idx| self.rendered_items.get(idx).copied().flatten())
+            .and_then(|item| self.item(item))
+        {
+            item.value.clone()
+        } else {
+            return;
+        };
+
+        Clipboard::from(content).write_with_status();
+    }
+
     fn row<'a>(
         key: &'a str,
         key_width: u16,
@@ -122,19 +203,32 @@ impl Modal for InfoListModal {
             self.column_widths.iter().map(|w| Constraint::Percentage(*w)).collect_vec();
         let column_areas = Layout::horizontal(&column_constraints).spacing(1).split(table_area);
 
-        let rows = self
-            .items
-            .0
-            .iter()
-            .flat_map(|item| {
-                InfoListModal::row(
-                    &item.key,
-                    column_areas[0].width,
-                    &item.value,
-                    column_areas[1].width,
-                )
-            })
-            .collect_vec();
+        let mut rows = Vec::new();
+        self.rendered_items.clear();
+        for (idx, item) in self.items.0.iter().enumerate() {
+            for row in
+                Self::row(&item.key, column_areas[0].width, &item.value, column_areas[1].width)
+            {
+                rows.push(row);
+                self.rendered_items.push(Some(ItemRef::Item(idx)));
+            }
+        }
+        if let Some(comments) = self.comments.as_ref().filter(|c| !c.0.is_empty()) {
+            rows.push(Row::default());
+            rows.push(
+                Row::new([Cell::from("Comments")])
+                    .style(ctx.config.theme.preview_metadata_group_style),
+            );
+            self.rendered_items.extend([None, None]);
+            for (idx, item) in comments.0.iter().enumerate() {
+                for row in
+                    Self::row(&item.key, column_areas[0].width, &item.value, column_areas[1].width)
+                {
+                    rows.push(row);
+                    self.rendered_items.push(Some(ItemRef::Comment(idx)));
+                }
+            }
+        }
 
         self.scrolling_state.set_content_and_viewport_len(rows.len(), table_area.height.into());
 
@@ -217,6 +311,13 @@ impl Modal for InfoListModal {
 
                     ctx.render()?;
                 }
+                CommonAction::CopyToClipboard { kind } => {
+                    let all = match kind {
+                        CopyContentsKind::Content(content) => content.all,
+                        CopyContentsKind::Modal(_) => false,
+                    };
+                    self.copy_to_clipboard(all);
+                }
                 CommonAction::Close => {
                     self.hide(ctx)?;
                 }
@@ -227,6 +328,22 @@ impl Modal for InfoListModal {
         Ok(())
     }
 
+    fn on_query_finished(
+        &mut self,
+        id: &'static str,
+        data: &mut MpdQueryResult,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        if let (READ_COMMENTS, MpdQueryResult::Comments { file, comments }) = (id, data)
+            && self.comments_file.as_ref() == Some(file)
+        {
+            self.comments = Some(std::mem::take(comments).into());
+            ctx.render()?;
+        }
+
+        Ok(())
+    }
+
     fn handle_mouse_event(&mut self, event: MouseEvent, ctx: &mut Ctx) -> Result<()> {
         if !self.table_area.contains(event.into()) {
             return Ok(());
@@ -291,6 +408,18 @@ impl From<Vec<Song>> for KeyValues {
     }
 }
 
+impl From<Comments> for KeyValues {
+    fn from(value: Comments) -> Self {
+        KeyValues(
+            value
+                .0
+                .into_iter()
+                .map(|comment| KeyValue { key: comment.name, value: comment.value })
+                .collect(),
+        )
+    }
+}
+
 pub struct SongCtx<'a>(pub &'a Song, pub &'a Ctx);
 impl From<SongCtx<'_>> for KeyValues {
     fn from(SongCtx(song, ctx): SongCtx) -> Self {
@@ -20,7 +20,7 @@ use rmpc_mpd::{
 
 use crate::{
     config::{
-        keys::actions::{AddOpts, CopyContent, CopyContents, DuplicateStrategy},
+        keys::actions::{AddOpts, CopyContent, CopyContents, DuplicateStrategy, StickerTarget},
         theme::properties::{Property, SongProperty},
     },
     ctx::{Ctx, LIKE_STICKER, RATING_STICKER},
@@ -29,6 +29,8 @@ use crate::{
         cmp::StringCompare,
         macros::{modal, status_error, status_info, status_warn},
         mpd_client_ext::{Enqueue, MpdClientExt as _},
+        snapshots::{Snapshot, Snapshots},
+        undo::{self, Edit},
     },
     ui::{
         dirstack::DirStackItem,
@@ -229,8 +231,10 @@ impl Section for SectionType<'_> {
     }
 }
 
+#[allow(clippy::too_many_arguments, reason = "Arguments mirror the fields of the Rate action")]
 pub fn create_rating_modal<'a>(
     items: Vec<Enqueue>,
+    target: StickerTarget,
     values: &[i32],
     min_rating: i32,
     max_rating: i32,
@@ -266,7 +270,7 @@ pub fn create_rating_modal<'a>(
 
                 if !value.trim().is_empty() {
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(RATING_STICKER, value, clone2)?;
+                        client.set_sticker_multiple(RATING_STICKER, value, clone2, target)?;
                         Ok(())
                     });
                 }
@@ -285,7 +289,7 @@ pub fn create_rating_modal<'a>(
 
             section.action(move |ctx, value| {
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(RATING_STICKER, value, clone)?;
+                    client.set_sticker_multiple(RATING_STICKER, value, clone, target)?;
                     Ok(())
                 });
                 Ok(())
@@ -298,25 +302,25 @@ pub fn create_rating_modal<'a>(
                 return None;
             }
             let clone = items.clone();
-            let section = section.item("Like", |ctx| {
+            let section = section.item("Like", move |ctx| {
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(LIKE_STICKER, "2".to_string(), clone)?;
+                    client.set_sticker_multiple(LIKE_STICKER, "2".to_string(), clone, target)?;
                     Ok(())
                 });
                 Ok(())
             });
             let clone = items.clone();
-            let section = section.item("Neutral", |ctx| {
+            let section = section.item("Neutral", move |ctx| {
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(LIKE_STICKER, "1".to_string(), clone)?;
+                    client.set_sticker_multiple(LIKE_STICKER, "1".to_string(), clone, target)?;
                     Ok(())
                 });
                 Ok(())
             });
             let clone = items.clone();
-            let section = section.item("Dislike", |ctx| {
+            let section = section.item("Dislike", move |ctx| {
                 ctx.command(move |_, client| {
-                    client.set_sticker_multiple(LIKE_STICKER, "0".to_string(), clone)?;
+                    client.set_sticker_multiple(LIKE_STICKER, "0".to_string(), clone, target)?;
                     Ok(())
                 });
                 Ok(())
@@ -325,18 +329,18 @@ pub fn create_rating_modal<'a>(
         })
         .list_section(ctx, |mut section| {
             if custom || !values.is_empty() {
-                section.add_item("Clear rating", |ctx| {
+                section.add_item("Clear rating", move |ctx| {
                     ctx.command(move |_, client| {
-                        client.delete_sticker_multiple(RATING_STICKER, clone3)?;
+                        client.delete_sticker_multiple(RATING_STICKER, clone3, target)?;
                         Ok(())
                     });
                     Ok(())
                 });
             }
             if like {
-                section.add_item("Clear like state", |ctx| {
+                section.add_item("Clear like state", move |ctx| {
                     ctx.command(move |_, client| {
-                        client.delete_sticker_multiple(LIKE_STICKER, items)?;
+                        client.delete_sticker_multiple(LIKE_STICKER, items, target)?;
                         Ok(())
                     });
                     Ok(())
@@ -610,6 +614,10 @@ pub fn delete_from_playlist_or_show_confirmation(
         format!("Remove {songs_to_remove} song(s) from playlist \"{playlist_name}\"?");
 
     let delete_songs = move |ctx: &Ctx| {
+        undo::record(ctx, Edit::PlaylistAdd {
+            playlist: playlist_name.clone(),
+            songs: songs_to_remove_in_playlist.clone(),
+        });
         ctx.command(move |_, client| {
             client.send_start_cmd_list()?;
             for (idx, _path) in songs_to_remove_in_playlist.iter().rev() {
@@ -689,3 +697,54 @@ where
         .list_section(ctx, |section| Some(section.item("Cancel", |_ctx| Ok(()))))
         .build()
 }
+
+pub fn create_snapshots_modal<'a>(ctx: &Ctx) -> Result<MenuModal<'a>> {
+    let snapshots = Snapshots::from_state_dir()?;
+    let names = snapshots.list()?.into_iter().map(|s| s.name).collect_vec();
+    let restore_from = snapshots.clone();
+    let delete_from = snapshots.clone();
+
+    Ok(MenuModal::new(ctx)
+        .width(60)
+        .multi_section(ctx, move |section| {
+            if names.is_empty() {
+                return None;
+            }
+
+            let mut section = section
+                .add_action("Restore", move |ctx, name| match restore_from.load(&name) {
+                    Ok(snapshot) => {
+                        undo::record(ctx, Edit::restore_queue(&ctx.queue));
+                        ctx.command(move |_, client| {
+                            snapshot.restore(client)?;
+                            status_info!("Restored snapshot '{name}'");
+                            Ok(())
+                        });
+                    }
+                    Err(err) => status_error!("Failed to load snapshot '{name}': {err}"),
+                })
+                .add_action("Delete", move |_, name| match delete_from.delete(&name) {
+                    Ok(()) => status_info!("Deleted snapshot '{name}'"),
+                    Err(err) => status_error!("{err}"),
+                });
+            for name in names {
+                section = section.add_item(name, ctx);
+            }
+            Some(section)
+        })
+        .input_section(ctx, "Save as:", move |section| {
+            Some(section.action(move |ctx, name| {
+                if name.is_empty() {
+                    return;
+                }
+                ctx.command(move |_, client| {
+                    let snapshot = Snapshot::capture(client)?;
+                    snapshots.save(&name, &snapshot)?;
+                    status_info!("Saved snapshot '{name}'");
+                    Ok(())
+                });
+            }))
+        })
+        .list_section(ctx, |section| Some(section.item("Cancel", |_ctx| Ok(()))))
+        .build())
+}
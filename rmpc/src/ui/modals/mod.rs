@@ -15,11 +15,13 @@ pub mod add_random_modal;
 pub mod confirm_modal;
 pub mod decoders;
 pub mod downloads;
+pub mod duplicates;
 pub mod info_list_modal;
 pub mod info_modal;
 pub mod input_modal;
 pub mod keybinds;
 pub mod menu;
+pub mod mounts;
 pub mod outputs;
 pub mod select_modal;
 
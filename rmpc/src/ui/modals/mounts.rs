@@ -0,0 +1,331 @@
+use anyhow::Result;
+use ratatui::{
+    layout::{Constraint, Margin, Rect},
+    macros::constraint,
+    style::Style,
+    symbols::border,
+    widgets::{Block, Borders, Cell, Clear, Row, Table, TableState},
+};
+use rmpc_mpd::{
+    client::Client,
+    commands::{list_mounts::Mount, list_neighbors::Neighbor},
+    errors::MpdError,
+    mpd_client::MpdClient,
+};
+
+use super::Modal;
+use crate::{
+    MpdQueryResult,
+    config::keys::CommonAction,
+    ctx::Ctx,
+    shared::{
+        id::{self, Id},
+        keys::ActionEvent,
+        macros::{modal, status_info},
+        mouse_event::{MouseEvent, MouseEventKind},
+    },
+    ui::{
+        UiEvent,
+        dirstack::DirState,
+        modals::{input_modal::InputModal, menu::modal::MenuModal},
+    },
+};
+
+const REFRESH_MOUNTS: &str = "refresh_mounts";
+
+#[derive(Debug)]
+enum Entry {
+    Mount(Mount),
+    Neighbor { neighbor: Neighbor, mounted: bool },
+}
+
+#[derive(Debug)]
+pub struct MountsModal {
+    id: Id,
+    scrolling_state: DirState<TableState>,
+    table_area: Rect,
+    entries: Vec<Entry>,
+}
+
+/// Fetches current mounts together with the neighbors. Neighbors are empty
+/// when MPD has no neighbor plugin configured.
+pub fn fetch_mounts(client: &mut Client<'_>) -> Result<MpdQueryResult> {
+    let mounts = client.list_mounts()?.0;
+    let neighbors = match client.list_neighbors() {
+        Ok(neighbors) => neighbors.0,
+        Err(MpdError::Mpd(_)) => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(MpdQueryResult::Mounts { mounts, neighbors })
+}
+
+impl MountsModal {
+    pub fn new(mounts: Vec<Mount>, neighbors: Vec<Neighbor>) -> Self {
+        let mut result = Self {
+            id: id::new(),
+            scrolling_state: DirState::default(),
+            table_area: Rect::default(),
+            entries: Vec::new(),
+        };
+        result.set_entries(mounts, neighbors);
+
+        result
+    }
+
+    fn set_entries(&mut self, mounts: Vec<Mount>, neighbors: Vec<Neighbor>) {
+        let neighbors = neighbors
+            .into_iter()
+            .map(|neighbor| {
+                let mounted = mounts.iter().any(|mount| mount.storage == neighbor.neighbor);
+                Entry::Neighbor { neighbor, mounted }
+            })
+            .collect::<Vec<_>>();
+        self.entries = mounts.into_iter().map(Entry::Mount).chain(neighbors).collect();
+
+        self.scrolling_state.set_content_len(Some(self.entries.len()));
+        if self.scrolling_state.get_selected().is_none_or(|idx| idx >= self.entries.len()) {
+            self.scrolling_state.first();
+        }
+    }
+
+    fn refresh(ctx: &Ctx) {
+        ctx.query().id(REFRESH_MOUNTS).replace_id(REFRESH_MOUNTS).query(fetch_mounts);
+    }
+
+    fn create_menu(&self, ctx: &mut Ctx) {
+        let Some(entry) = self.scrolling_state.get_selected().and_then(|idx| self.entries.get(idx))
+        else {
+            return;
+        };
+
+        let modal = match entry {
+            Entry::Mount(mount) => {
+                let name = mount.mount.clone();
+                let name2 = mount.mount.clone();
+                let is_root = name.is_empty();
+                MenuModal::new(ctx)
+                    .list_section(ctx, |section| {
+                        let section = section.item("Update", move |ctx| {
+                            ctx.command(move |_, client| {
+                                client.update(Some(&name))?;
+                                status_info!("Update of '/{name}' started");
+                                Ok(())
+                            });
+                            Ok(())
+                        });
+                        // The root mount is the music directory and cannot be unmounted
+                        if is_root {
+                            return Some(section);
+                        }
+                        Some(section.item("Unmount", move |ctx| {
+                            ctx.command(move |_, client| {
+                                client.unmount(&name2)?;
+                                Ok(())
+                            });
+                            Ok(())
+                        }))
+                    })
+                    .list_section(ctx, |section| Some(section.item("Cancel", |_ctx| Ok(()))))
+                    .build()
+            }
+            Entry::Neighbor { neighbor, .. } => {
+                let uri = neighbor.neighbor.clone();
+                let initial_name = neighbor.name.clone();
+                MenuModal::new(ctx)
+                    .list_section(ctx, |section| {
+                        Some(section.item("Mount", move |ctx| {
+                            modal!(
+                                ctx,
+                                InputModal::new(ctx)
+                                    .title("Mount neighbor")
+                                    .confirm_label("Mount")
+                                    .input_label("Mount name:")
+                                    .initial_value(initial_name)
+                                    .on_confirm(move |ctx, value| {
+                                        let name = value.to_owned();
+                                        ctx.command(move |_, client| {
+                                            client.mount(&name, &uri)?;
+                                            Ok(())
+                                        });
+                                        Ok(())
+                                    })
+                            );
+                            Ok(())
+                        }))
+                    })
+                    .list_section(ctx, |section| Some(section.item("Cancel", |_ctx| Ok(()))))
+                    .build()
+            }
+        };
+
+        modal!(ctx, modal);
+    }
+}
+
+impl Modal for MountsModal {
+    fn id(&self) -> Id {
+        self.id
+    }
+
+    fn render(&mut self, frame: &mut ratatui::Frame, ctx: &mut Ctx) -> Result<()> {
+        let popup_area = frame.area().centered(constraint!(==80), constraint!(==12));
+        frame.render_widget(Clear, popup_area);
+        if let Some(bg_color) = ctx.config.theme.modal_background_color {
+            frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(ctx.config.as_border_style())
+            .title_alignment(ratatui::prelude::Alignment::Center)
+            .title("Mounts");
+
+        let table_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+
+        let rows = self.entries.iter().map(|entry| match entry {
+            Entry::Mount(mount) => Row::new([
+                Cell::new(format!("/{}", mount.mount)),
+                Cell::new(mount.storage.as_str()),
+                Cell::new("mounted"),
+            ]),
+            Entry::Neighbor { neighbor, mounted } => Row::new([
+                Cell::new(neighbor.name.as_str()),
+                Cell::new(neighbor.neighbor.as_str()),
+                Cell::new(if *mounted { "neighbor, mounted" } else { "neighbor" }),
+            ]),
+        });
+
+        self.scrolling_state
+            .set_content_and_viewport_len(self.entries.len(), table_area.height.into());
+
+        let table =
+            Table::new(rows, [
+                Constraint::Percentage(30),
+                Constraint::Percentage(70),
+                Constraint::Length(18),
+            ])
+            .column_spacing(1)
+            .style(ctx.config.as_text_style())
+            .header(Row::new(["Name", "Storage", "State"]).style(
+                ctx.config.theme.text_color.map(|c| Style::default().fg(c)).unwrap_or_default(),
+            ))
+            .row_highlight_style(ctx.config.theme.current_item_style);
+
+        let table_area = table_area.inner(Margin { horizontal: 1, vertical: 0 });
+        self.table_area = table_area;
+
+        frame.render_widget(block, popup_area);
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+        if let Some(scrollbar) = ctx.config.as_styled_scrollbar() {
+            frame.render_stateful_widget(
+                scrollbar,
+                popup_area.inner(Margin { horizontal: 0, vertical: 1 }),
+                self.scrolling_state.as_scrollbar_state_ref(),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn on_query_finished(
+        &mut self,
+        id: &'static str,
+        data: &mut MpdQueryResult,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        match (id, data) {
+            (REFRESH_MOUNTS, MpdQueryResult::Mounts { mounts, neighbors }) => {
+                self.set_entries(std::mem::take(mounts), std::mem::take(neighbors));
+                ctx.render()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, event: &mut UiEvent, ctx: &Ctx) -> Result<()> {
+        match event {
+            UiEvent::Mount | UiEvent::Neighbor => Self::refresh(ctx),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: &mut ActionEvent, ctx: &mut Ctx) -> Result<()> {
+        if let Some(action) = key.claim_common() {
+            match action {
+                CommonAction::DownHalf => {
+                    self.scrolling_state.next_half_viewport(ctx.config.scrolloff);
+
+                    ctx.render()?;
+                }
+                CommonAction::UpHalf => {
+                    self.scrolling_state.prev_half_viewport(ctx.config.scrolloff);
+
+                    ctx.render()?;
+                }
+                CommonAction::Up => {
+                    self.scrolling_state.prev(ctx.config.scrolloff, ctx.config.wrap_navigation);
+
+                    ctx.render()?;
+                }
+                CommonAction::Down => {
+                    self.scrolling_state.next(ctx.config.scrolloff, ctx.config.wrap_navigation);
+
+                    ctx.render()?;
+                }
+                CommonAction::Bottom => {
+                    self.scrolling_state.last();
+
+                    ctx.render()?;
+                }
+                CommonAction::Top => {
+                    self.scrolling_state.first();
+
+                    ctx.render()?;
+                }
+                CommonAction::Confirm => {
+                    self.create_menu(ctx);
+                }
+                CommonAction::Close => {
+                    self.hide(ctx)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEvent, ctx: &mut Ctx) -> Result<()> {
+        if !self.table_area.contains(event.into()) {
+            return Ok(());
+        }
+
+        match event.kind {
+            MouseEventKind::LeftClick | MouseEventKind::DoubleClick => {
+                let y: usize = event.y.saturating_sub(self.table_area.y).into();
+                let y = y.saturating_sub(1); // Subtract one to account for table header
+                if let Some(idx) = self.scrolling_state.get_at_rendered_row(y) {
+                    self.scrolling_state.select(Some(idx), ctx.config.scrolloff);
+                    if matches!(event.kind, MouseEventKind::DoubleClick) {
+                        self.create_menu(ctx);
+                    }
+                    ctx.render()?;
+                }
+            }
+            MouseEventKind::ScrollDown => {
+                self.scrolling_state.scroll_down(ctx.config.scroll_amount, ctx.config.scrolloff);
+                ctx.render()?;
+            }
+            MouseEventKind::ScrollUp => {
+                self.scrolling_state.scroll_up(ctx.config.scroll_amount, ctx.config.scrolloff);
+                ctx.render()?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
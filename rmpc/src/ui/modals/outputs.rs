@@ -1,4 +1,5 @@
 use anyhow::Result;
+use itertools::Itertools;
 use ratatui::{
     layout::{Constraint, Margin, Rect},
     macros::constraint,
@@ -16,10 +17,11 @@ use crate::{
     shared::{
         id::{self, Id},
         keys::ActionEvent,
+        macros::{modal, status_info},
         mouse_event::{MouseEvent, MouseEventKind},
         mpd_client_ext::{MpdClientExt, PartitionedOutput, PartitionedOutputKind},
     },
-    ui::{UiEvent, dirstack::DirState},
+    ui::{UiEvent, dirstack::DirState, modals::menu::modal::MenuModal},
 };
 
 #[derive(Debug)]
@@ -76,6 +78,45 @@ impl OutputsModal {
         });
     }
 
+    fn edit_selected_attributes(&self, ctx: &mut Ctx) {
+        let Some(output) =
+            self.scrolling_state.get_selected().and_then(|idx| self.outputs.get(idx))
+        else {
+            return;
+        };
+        if matches!(output.kind, PartitionedOutputKind::OtherPartition) {
+            status_info!("Output '{}' has to be enabled on this partition first", output.name);
+            return;
+        }
+        if output.attributes.is_empty() {
+            status_info!("Output '{}' has no attributes", output.name);
+            return;
+        }
+
+        let id = output.id;
+        let mut modal = MenuModal::new(ctx).width(60);
+        for (name, current_value) in &output.attributes {
+            let name = name.clone();
+            let current_value = current_value.clone();
+            modal = modal.input_section(ctx, format!("{name}:"), |mut section| {
+                section.add_initial_value(current_value.clone(), ctx);
+                section.add_action(move |ctx, value| {
+                    if value != current_value {
+                        ctx.command(move |_, client| {
+                            client.output_set(id, &name, &value)?;
+                            Ok(())
+                        });
+                    }
+                });
+                Some(section)
+            });
+        }
+        let modal =
+            modal.list_section(ctx, |section| Some(section.item("Cancel", |_ctx| Ok(())))).build();
+
+        modal!(ctx, modal);
+    }
+
     fn refresh_outputs(&mut self, ctx: &Ctx) {
         let current_partition = ctx.status.partition.clone();
         ctx.query().id("refresh_outputs").replace_id("refresh_outputs").query(move |client| {
@@ -91,7 +132,7 @@ impl Modal for OutputsModal {
     }
 
     fn render(&mut self, frame: &mut ratatui::Frame, ctx: &mut Ctx) -> anyhow::Result<()> {
-        let popup_area = frame.area().centered(constraint!(==70), constraint!(==10));
+        let popup_area = frame.area().centered(constraint!(==100), constraint!(==10));
         frame.render_widget(Clear, popup_area);
         if let Some(bg_color) = ctx.config.theme.modal_background_color {
             frame.render_widget(Block::default().style(Style::default().bg(bg_color)), popup_area);
@@ -112,12 +153,14 @@ impl Modal for OutputsModal {
                 Cell::new("-"),
                 Cell::new("no"),
                 Cell::new("other"),
+                Cell::new(format_attributes(output)),
             ]),
             PartitionedOutputKind::CurrentPartition => Row::new([
                 Cell::new(output.name.as_str()),
                 Cell::new(output.plugin.as_str()),
                 Cell::new(if output.enabled { "yes" } else { "no" }),
                 Cell::new("current"),
+                Cell::new(format_attributes(output)),
             ]),
         });
 
@@ -126,14 +169,15 @@ impl Modal for OutputsModal {
 
         let table =
             Table::new(rows, [
-                Constraint::Percentage(80),
-                Constraint::Percentage(20),
+                Constraint::Percentage(40),
+                Constraint::Percentage(15),
+                Constraint::Length(10),
                 Constraint::Length(10),
-                Constraint::Length(9),
+                Constraint::Percentage(45),
             ])
             .column_spacing(0)
             .style(ctx.config.as_text_style())
-            .header(Row::new(["Name", "Plugin", "Enabled", "Partition"]).style(
+            .header(Row::new(["Name", "Plugin", "Enabled", "Partition", "Attributes"]).style(
                 ctx.config.theme.text_color.map(|c| Style::default().fg(c)).unwrap_or_default(),
             ))
             .row_highlight_style(ctx.config.theme.current_item_style);
@@ -229,6 +273,9 @@ impl Modal for OutputsModal {
                 CommonAction::Confirm => {
                     self.toggle_selected_output(ctx);
                 }
+                CommonAction::ContextMenu => {
+                    self.edit_selected_attributes(ctx);
+                }
                 CommonAction::Close => {
                     self.hide(ctx)?;
                 }
@@ -253,6 +300,15 @@ impl Modal for OutputsModal {
                 ctx.render()?;
             }
             MouseEventKind::MiddleClick => {}
+            MouseEventKind::RightClick if self.outputs_table_area.contains(event.into()) => {
+                let y: usize = event.y.saturating_sub(self.outputs_table_area.y).into();
+                let y = y.saturating_sub(1); // Subtract one to account for table header
+                if let Some(idx) = self.scrolling_state.get_at_rendered_row(y) {
+                    self.scrolling_state.select(Some(idx), ctx.config.scrolloff);
+                    self.edit_selected_attributes(ctx);
+                    ctx.render()?;
+                }
+            }
             MouseEventKind::RightClick => {}
             MouseEventKind::ScrollDown if self.outputs_table_area.contains(event.into()) => {
                 self.scrolling_state.scroll_down(ctx.config.scroll_amount, ctx.config.scrolloff);
@@ -272,3 +328,7 @@ impl Modal for OutputsModal {
         Ok(())
     }
 }
+
+fn format_attributes(output: &PartitionedOutput) -> String {
+    output.attributes.iter().map(|(name, value)| format!("{name}={value}")).join(", ")
+}
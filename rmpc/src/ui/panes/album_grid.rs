@@ -0,0 +1,669 @@
+use std::{collections::HashMap, ops::Range, sync::Arc};
+
+use anyhow::Result;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    text::Line,
+    widgets::Paragraph,
+};
+use rmpc_mpd::{
+    client::Client,
+    filter::{Filter, FilterKind, Tag},
+    mpd_client::MpdClient,
+};
+
+use super::Pane;
+use crate::{
+    MpdQueryResult,
+    config::{
+        keys::{
+            CommonAction,
+            actions::{AddKind, AutoplayKind, Position},
+        },
+        tabs::PaneType,
+    },
+    ctx::Ctx,
+    shared::{
+        album_art_cache::AlbumArtCache,
+        keys::ActionEvent,
+        macros::modal,
+        mouse_event::{MouseEvent, MouseEventKind},
+        mpd_client_ext::{Enqueue, MpdClientExt as _},
+    },
+    ui::{UiEvent, image::thumbnails::Thumbnails, modals::menu::create_add_modal},
+};
+
+const INIT: &str = "album_grid_init";
+const COVER: &str = "album_grid_cover";
+/// Covers which are not visible are dropped once more than this many are
+/// loaded
+const MAX_LOADED_COVERS: usize = 256;
+
+#[derive(Debug)]
+enum Cover {
+    Loading,
+    Missing,
+    /// The default cover is shown and the cover is requested again the next
+    /// time the pane is displayed
+    Failed,
+    Loaded(Arc<Vec<u8>>),
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug)]
+pub struct AlbumGridPane {
+    target_pane: PaneType,
+    cell_width: u16,
+    cover_height: u16,
+    albums: Vec<String>,
+    selected: usize,
+    /// First visible row of the grid
+    offset: usize,
+    area: Rect,
+    covers: HashMap<String, Cover>,
+    default_cover: Arc<Vec<u8>>,
+    thumbnails: Thumbnails,
+    /// Albums whose covers are displayed, the position in the list is their
+    /// thumbnail slot
+    shown: Vec<(usize, Rect)>,
+    /// Covers are requested after the next render so they are not drawn over
+    /// by stale content
+    show_pending: bool,
+    is_modal_open: bool,
+    initialized: bool,
+    needs_refresh: bool,
+}
+
+impl AlbumGridPane {
+    pub fn new(width: u16, height: u16, target_pane: PaneType, ctx: &Ctx) -> Self {
+        Self {
+            target_pane,
+            cell_width: width.max(4),
+            cover_height: height.max(2),
+            albums: Vec::new(),
+            selected: 0,
+            offset: 0,
+            area: Rect::default(),
+            covers: HashMap::new(),
+            default_cover: Arc::new(ctx.config.theme.default_album_art.to_vec()),
+            thumbnails: Thumbnails::new(ctx),
+            shown: Vec::new(),
+            show_pending: false,
+            is_modal_open: false,
+            initialized: false,
+            needs_refresh: false,
+        }
+    }
+
+    fn fetch(&self, ctx: &Ctx) {
+        ctx.query().id(INIT).replace_id(INIT).target(self.target_pane.clone()).query(
+            move |client| {
+                let albums = client.list_tag(Tag::Album, None)?;
+                Ok(MpdQueryResult::LsInfo { data: albums.0, path: None })
+            },
+        );
+    }
+
+    fn fetch_cover(&self, album: String, ctx: &Ctx) {
+        let order = ctx.config.album_art.order;
+        let disabled_protos = ctx.config.album_art.disabled_protocols.clone();
+        let cache =
+            AlbumArtCache::new(ctx.config.cache_dir.as_deref(), &ctx.config.album_art.cache);
+        ctx.query().id(COVER).target(self.target_pane.clone()).query(move |client| {
+            // Errors are passed to the pane, otherwise the cover would stay loading
+            let find_cover = || -> Result<Option<Vec<u8>>> {
+                let song = client.find_one(&[Filter::new(Tag::Album, album.as_str())])?;
+                Ok(match (song, cache) {
+                    (Some(song), _)
+                        if disabled_protos.iter().any(|proto| song.file.starts_with(proto)) =>
+                    {
+                        None
+                    }
+                    (Some(song), Some(cache)) => cache.find_album_art(client, &song, order)?,
+                    (Some(song), None) => client.find_album_art(&song.file, order)?,
+                    (None, _) => None,
+                })
+            };
+            let data = find_cover();
+
+            Ok(MpdQueryResult::AlbumThumbnail { album, data })
+        });
+    }
+
+    fn cell_height(&self) -> u16 {
+        self.cover_height + 1
+    }
+
+    fn columns(&self) -> usize {
+        usize::from((self.area.width / self.cell_width).max(1))
+    }
+
+    fn visible_rows(&self) -> usize {
+        usize::from((self.area.height / self.cell_height()).max(1))
+    }
+
+    /// Area of the album at the given index, none if it is scrolled out of
+    /// view
+    fn cell(&self, idx: usize) -> Option<Rect> {
+        let columns = self.columns();
+        let (row, column) = (idx / columns, idx % columns);
+        if idx >= self.albums.len() || row < self.offset || row >= self.offset + self.visible_rows()
+        {
+            return None;
+        }
+
+        let padding = self.area.width.saturating_sub(self.cell_width * columns as u16) / 2;
+        let cell = Rect {
+            x: self.area.x + padding + self.cell_width * column as u16,
+            y: self.area.y + self.cell_height() * (row - self.offset) as u16,
+            width: self.cell_width.saturating_sub(1).min(self.area.width),
+            height: self.cell_height(),
+        };
+
+        Some(cell.intersection(self.area))
+    }
+
+    fn cover_area(&self, cell: Rect) -> Rect {
+        Rect { height: cell.height.saturating_sub(1), ..cell }
+    }
+
+    fn visible_range(&self) -> Range<usize> {
+        let first = self.offset * self.columns();
+        let last = first + self.columns() * self.visible_rows();
+        first.min(self.albums.len())..last.min(self.albums.len())
+    }
+
+    fn visible_covers(&self) -> Vec<(usize, Rect)> {
+        self.visible_range()
+            .filter_map(|idx| self.cell(idx).map(|cell| (idx, self.cover_area(cell))))
+            .filter(|(_, area)| !area.is_empty())
+            .collect()
+    }
+
+    fn select(&mut self, idx: usize, ctx: &Ctx) -> Result<()> {
+        if self.albums.is_empty() {
+            return Ok(());
+        }
+        self.selected = idx.min(self.albums.len() - 1);
+        self.scroll_to_selected();
+
+        self.refresh(ctx)?;
+        ctx.render()?;
+        Ok(())
+    }
+
+    fn scroll_to_selected(&mut self) {
+        let row = self.selected / self.columns();
+        let last_row = self.albums.len().saturating_sub(1) / self.columns();
+        self.offset = self.offset.min((last_row + 1).saturating_sub(self.visible_rows()));
+        if row < self.offset {
+            self.offset = row;
+        } else if row >= self.offset + self.visible_rows() {
+            self.offset = row + 1 - self.visible_rows();
+        }
+    }
+
+    /// Hides the covers which moved or went out of view and schedules the
+    /// newly visible ones to be shown
+    fn refresh(&mut self, ctx: &Ctx) -> Result<()> {
+        if self.is_modal_open || !self.thumbnails.is_enabled() {
+            return Ok(());
+        }
+
+        let visible = self.visible_covers();
+        if visible == self.shown {
+            return Ok(());
+        }
+
+        self.thumbnails.hide(ctx)?;
+        self.shown = visible;
+        self.show_pending = true;
+        Ok(())
+    }
+
+    fn redisplay(&mut self, ctx: &Ctx) -> Result<()> {
+        self.covers.retain(|_, cover| !matches!(cover, Cover::Failed));
+        self.shown.clear();
+        self.thumbnails.hide(ctx)?;
+        self.refresh(ctx)?;
+        ctx.render()?;
+        Ok(())
+    }
+
+    fn show_covers(&mut self, ctx: &Ctx) -> Result<()> {
+        for slot in 0..self.shown.len() {
+            let (idx, area) = self.shown[slot];
+            let album = &self.albums[idx];
+            match self.covers.get(album) {
+                Some(Cover::Loaded(data)) => {
+                    self.thumbnails.show(slot, Arc::clone(data), area, ctx)?;
+                }
+                Some(Cover::Missing | Cover::Failed) => {
+                    self.thumbnails.show(slot, Arc::clone(&self.default_cover), area, ctx)?;
+                }
+                Some(Cover::Loading) => {}
+                None => {
+                    self.covers.insert(album.clone(), Cover::Loading);
+                    self.fetch_cover(album.clone(), ctx);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn cover_loaded(
+        &mut self,
+        album: String,
+        data: Result<Option<Vec<u8>>>,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        let (data, failed) = match data {
+            Ok(data) => (data, false),
+            Err(err) => {
+                log::error!(err:?, album = album.as_str(); "Failed to load album cover");
+                (None, true)
+            }
+        };
+        let data = data.map_or_else(|| Arc::clone(&self.default_cover), Arc::new);
+        let slot = self.shown.iter().position(|(idx, _)| self.albums.get(*idx) == Some(&album));
+        if let Some(slot) = slot
+            && !self.is_modal_open
+        {
+            self.thumbnails.show(slot, Arc::clone(&data), self.shown[slot].1, ctx)?;
+        }
+
+        let cover = if failed {
+            Cover::Failed
+        } else if Arc::ptr_eq(&data, &self.default_cover) {
+            Cover::Missing
+        } else {
+            Cover::Loaded(data)
+        };
+        self.covers.insert(album, cover);
+
+        if self.covers.values().filter(|cover| matches!(cover, Cover::Loaded(_))).count()
+            > MAX_LOADED_COVERS
+        {
+            let visible = self.shown.iter().map(|(idx, _)| &self.albums[*idx]).collect_vec();
+            self.covers.retain(|album, cover| {
+                !matches!(cover, Cover::Loaded(_)) || visible.contains(&album)
+            });
+        }
+
+        Ok(())
+    }
+
+    fn enqueue(&self) -> Vec<Enqueue> {
+        self.albums
+            .get(self.selected)
+            .map(|album| {
+                vec![Enqueue::Find { filter: vec![(Tag::Album, FilterKind::Exact, album.clone())] }]
+            })
+            .unwrap_or_default()
+    }
+
+    fn add(&self, position: Position, autoplay: AutoplayKind, ctx: &Ctx) {
+        let enqueue = self.enqueue();
+        if !enqueue.is_empty() {
+            Client::resolve_and_enqueue(
+                ctx,
+                enqueue,
+                position,
+                autoplay,
+                ctx.current_song_index(),
+                Some(0),
+            );
+        }
+    }
+
+    fn album_at(&self, event: MouseEvent) -> Option<usize> {
+        self.visible_range()
+            .find(|idx| self.cell(*idx).is_some_and(|cell| cell.contains(event.into())))
+    }
+}
+
+impl Pane for AlbumGridPane {
+    fn render(&mut self, frame: &mut Frame, area: Rect, ctx: &Ctx) -> Result<()> {
+        self.area = area;
+
+        if self.initialized && self.albums.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No albums found")
+                    .style(ctx.config.as_text_style())
+                    .alignment(Alignment::Center),
+                area,
+            );
+            return Ok(());
+        }
+
+        for idx in self.visible_range() {
+            let Some(cell) = self.cell(idx) else {
+                continue;
+            };
+            let label_area = Rect { y: cell.bottom().saturating_sub(1), height: 1, ..cell };
+            let label = if self.albums[idx].is_empty() { "<no album>" } else { &self.albums[idx] };
+            let style = if idx == self.selected {
+                ctx.config.theme.current_item_style
+            } else {
+                ctx.config.as_text_style()
+            };
+
+            frame.render_widget(
+                Paragraph::new(Line::from(label)).style(style).alignment(Alignment::Center),
+                label_area,
+            );
+        }
+
+        if self.show_pending {
+            self.show_pending = false;
+            self.show_covers(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn calculate_areas(&mut self, area: Rect, _ctx: &Ctx) -> Result<()> {
+        self.area = area;
+        Ok(())
+    }
+
+    fn resize(&mut self, area: Rect, ctx: &Ctx) -> Result<()> {
+        self.area = area;
+        self.scroll_to_selected();
+        self.redisplay(ctx)
+    }
+
+    fn before_show(&mut self, ctx: &Ctx) -> Result<()> {
+        if !self.initialized || self.needs_refresh {
+            self.fetch(ctx);
+            self.initialized = true;
+            self.needs_refresh = false;
+        }
+        self.redisplay(ctx)
+    }
+
+    fn on_hide(&mut self, ctx: &Ctx) -> Result<()> {
+        self.shown.clear();
+        self.show_pending = false;
+        self.thumbnails.hide(ctx)
+    }
+
+    fn on_event(&mut self, event: &mut UiEvent, is_visible: bool, ctx: &Ctx) -> Result<()> {
+        match event {
+            UiEvent::Database => {
+                self.covers.clear();
+                if is_visible {
+                    self.fetch(ctx);
+                } else {
+                    self.needs_refresh = true;
+                }
+            }
+            UiEvent::Reconnected => {
+                self.initialized = false;
+                self.needs_refresh = false;
+                if is_visible {
+                    self.before_show(ctx)?;
+                }
+            }
+            UiEvent::Displayed | UiEvent::ConfigChanged if is_visible && !self.is_modal_open => {
+                self.redisplay(ctx)?;
+            }
+            UiEvent::ModalOpened if is_visible => {
+                if !self.is_modal_open {
+                    self.on_hide(ctx)?;
+                }
+                self.is_modal_open = true;
+            }
+            UiEvent::ModalClosed if is_visible => {
+                self.is_modal_open = false;
+                self.redisplay(ctx)?;
+            }
+            UiEvent::ThumbnailEncoded { key, data } if self.thumbnails.owns(*key) => {
+                self.thumbnails.display(*key, std::mem::take(data), ctx)?;
+            }
+            UiEvent::ThumbnailEncodeFailed { key, err } if self.thumbnails.owns(*key) => {
+                log::error!(err:?; "Failed to encode album cover");
+            }
+            UiEvent::Exit => {
+                self.thumbnails.cleanup()?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn on_query_finished(
+        &mut self,
+        id: &'static str,
+        data: MpdQueryResult,
+        is_visible: bool,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        match (id, data) {
+            (INIT, MpdQueryResult::LsInfo { data, .. }) => {
+                self.albums = data;
+                self.selected = self.selected.min(self.albums.len().saturating_sub(1));
+                self.scroll_to_selected();
+                if is_visible {
+                    self.redisplay(ctx)?;
+                } else {
+                    self.shown.clear();
+                }
+            }
+            (COVER, MpdQueryResult::AlbumThumbnail { album, data }) => {
+                self.cover_loaded(album, data, ctx)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEvent, ctx: &Ctx) -> Result<()> {
+        if !self.area.contains(event.into()) {
+            return Ok(());
+        }
+
+        let rows = ctx.config.scroll_amount * self.columns();
+        match event.kind {
+            MouseEventKind::LeftClick => {
+                if let Some(idx) = self.album_at(event) {
+                    self.select(idx, ctx)?;
+                }
+            }
+            MouseEventKind::DoubleClick => {
+                if let Some(idx) = self.album_at(event) {
+                    self.select(idx, ctx)?;
+                    self.add(Position::EndOfQueue, AutoplayKind::First, ctx);
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                self.select(self.selected.saturating_sub(rows), ctx)?;
+            }
+            MouseEventKind::ScrollDown => {
+                self.select(self.selected + rows, ctx)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_action(&mut self, event: &mut ActionEvent, ctx: &mut Ctx) -> Result<()> {
+        let Some(action) = event.claim_common() else {
+            return Ok(());
+        };
+
+        let columns = self.columns();
+        let last_row = self.albums.len().saturating_sub(1) / columns;
+        let page = columns * self.visible_rows();
+        let half_page = columns * (self.visible_rows() / 2).max(1);
+        match action {
+            CommonAction::Left => self.select(self.selected.saturating_sub(1), ctx)?,
+            CommonAction::Right => self.select(self.selected + 1, ctx)?,
+            CommonAction::Up if self.selected >= columns => {
+                self.select(self.selected - columns, ctx)?;
+            }
+            CommonAction::Down if self.selected / columns < last_row => {
+                self.select(self.selected + columns, ctx)?;
+            }
+            CommonAction::UpHalf => self.select(self.selected.saturating_sub(half_page), ctx)?,
+            CommonAction::DownHalf => self.select(self.selected + half_page, ctx)?,
+            CommonAction::PageUp => self.select(self.selected.saturating_sub(page), ctx)?,
+            CommonAction::PageDown => self.select(self.selected + page, ctx)?,
+            CommonAction::Top => self.select(0, ctx)?,
+            CommonAction::Bottom => self.select(self.albums.len().saturating_sub(1), ctx)?,
+            CommonAction::Confirm => self.add(Position::EndOfQueue, AutoplayKind::First, ctx),
+            CommonAction::AddOptions { kind: AddKind::Action(options) } => {
+                self.add(options.position, options.autoplay, ctx);
+            }
+            CommonAction::AddOptions { kind: AddKind::Modal(items) } => {
+                let enqueue = self.enqueue();
+                let opts = items
+                    .iter()
+                    .map(|(label, options)| (label.clone(), *options, (enqueue.clone(), Some(0))))
+                    .collect_vec();
+                modal!(ctx, create_add_modal(opts, ctx));
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use crossbeam::channel::{Receiver, Sender};
+    use ratatui::layout::Rect;
+    use rstest::rstest;
+
+    use super::{AlbumGridPane, COVER, Cover, INIT};
+    use crate::{
+        MpdQueryResult,
+        config::{Config, album_art::ImageMethod, keys::CommonAction, tabs::PaneType},
+        ctx::Ctx,
+        shared::{
+            events::{AppEvent, ClientRequest, WorkRequest},
+            keys::ActionEvent,
+        },
+        tests::fixtures::{app_event_channel, client_request_channel, ctx, work_request_channel},
+        ui::panes::Pane,
+    };
+
+    fn pane(albums: usize, ctx: &mut Ctx) -> AlbumGridPane {
+        let mut config = Config::default();
+        config.album_art.method = ImageMethod::None;
+        ctx.config = Arc::new(config);
+
+        let mut pane = AlbumGridPane::new(20, 9, PaneType::AlbumGrid { width: 20, height: 9 }, ctx);
+        // 3 columns and 2 rows
+        pane.calculate_areas(Rect::new(0, 0, 60, 20), ctx).unwrap();
+        let data = (0..albums).map(|idx| format!("album {idx}")).collect();
+        pane.on_query_finished(INIT, MpdQueryResult::LsInfo { data, path: None }, false, ctx)
+            .unwrap();
+        pane
+    }
+
+    fn act(pane: &mut AlbumGridPane, action: CommonAction, ctx: &mut Ctx) {
+        let mut event = ActionEvent::from(action);
+        pane.handle_action(&mut event, ctx).unwrap();
+    }
+
+    #[rstest]
+    fn lays_out_albums_in_grid(
+        app_event_channel: (Sender<AppEvent>, Receiver<AppEvent>),
+        work_request_channel: (Sender<WorkRequest>, Receiver<WorkRequest>),
+        client_request_channel: (Sender<ClientRequest>, Receiver<ClientRequest>),
+    ) {
+        let mut ctx = ctx(app_event_channel, work_request_channel, client_request_channel);
+        let pane = pane(7, &mut ctx);
+
+        assert_eq!(pane.cell(0), Some(Rect::new(0, 0, 19, 10)));
+        assert_eq!(pane.cell(4), Some(Rect::new(20, 10, 19, 10)));
+        assert_eq!(pane.cell(6), None);
+        assert_eq!(
+            pane.visible_covers().into_iter().map(|(idx, _)| idx).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+    }
+
+    #[rstest]
+    fn navigates_across_rows_and_columns(
+        app_event_channel: (Sender<AppEvent>, Receiver<AppEvent>),
+        work_request_channel: (Sender<WorkRequest>, Receiver<WorkRequest>),
+        client_request_channel: (Sender<ClientRequest>, Receiver<ClientRequest>),
+    ) {
+        let mut ctx = ctx(app_event_channel, work_request_channel, client_request_channel);
+        let mut pane = pane(7, &mut ctx);
+
+        act(&mut pane, CommonAction::Right, &mut ctx);
+        assert_eq!(pane.selected, 1);
+        act(&mut pane, CommonAction::Down, &mut ctx);
+        assert_eq!(pane.selected, 4);
+        act(&mut pane, CommonAction::Down, &mut ctx);
+        assert_eq!(pane.selected, 6);
+        act(&mut pane, CommonAction::Down, &mut ctx);
+        assert_eq!(pane.selected, 6);
+        act(&mut pane, CommonAction::Up, &mut ctx);
+        assert_eq!(pane.selected, 3);
+        act(&mut pane, CommonAction::Left, &mut ctx);
+        assert_eq!(pane.selected, 2);
+    }
+
+    #[rstest]
+    fn scrolls_selected_album_into_view(
+        app_event_channel: (Sender<AppEvent>, Receiver<AppEvent>),
+        work_request_channel: (Sender<WorkRequest>, Receiver<WorkRequest>),
+        client_request_channel: (Sender<ClientRequest>, Receiver<ClientRequest>),
+    ) {
+        let mut ctx = ctx(app_event_channel, work_request_channel, client_request_channel);
+        let mut pane = pane(7, &mut ctx);
+
+        act(&mut pane, CommonAction::Bottom, &mut ctx);
+
+        assert_eq!(pane.offset, 1);
+        assert_eq!(pane.cell(6), Some(Rect::new(0, 10, 19, 10)));
+        assert_eq!(
+            pane.visible_covers().into_iter().map(|(idx, _)| idx).collect::<Vec<_>>(),
+            vec![3, 4, 5, 6]
+        );
+
+        act(&mut pane, CommonAction::Top, &mut ctx);
+
+        assert_eq!(pane.offset, 0);
+    }
+
+    #[rstest]
+    fn retries_failed_cover_when_displayed_again(
+        app_event_channel: (Sender<AppEvent>, Receiver<AppEvent>),
+        work_request_channel: (Sender<WorkRequest>, Receiver<WorkRequest>),
+        client_request_channel: (Sender<ClientRequest>, Receiver<ClientRequest>),
+    ) {
+        let mut ctx = ctx(app_event_channel, work_request_channel, client_request_channel);
+        let mut pane = pane(1, &mut ctx);
+        let album = "album 0".to_owned();
+        pane.covers.insert(album.clone(), Cover::Loading);
+
+        let data = Err(anyhow::anyhow!("connection lost"));
+        pane.on_query_finished(
+            COVER,
+            MpdQueryResult::AlbumThumbnail { album: album.clone(), data },
+            false,
+            &ctx,
+        )
+        .unwrap();
+        assert!(matches!(pane.covers.get(&album), Some(Cover::Failed)));
+
+        pane.redisplay(&ctx).unwrap();
+
+        assert!(!pane.covers.contains_key(&album));
+    }
+}
@@ -355,6 +355,7 @@ mod tests {
             )]),
             last_modified: chrono::Utc::now(),
             added: None,
+            prio: 0,
         })
     }
 
@@ -4,6 +4,7 @@ use std::{
 };
 
 use album_art::AlbumArtPane;
+use album_grid::AlbumGridPane;
 use anyhow::{Context, Result};
 use cava::CavaPane;
 use directories::DirectoriesPane;
@@ -19,6 +20,7 @@ use ratatui::{Frame, layout::Layout, prelude::Rect, style::Color, text::Span, wi
 use recently_played::StickerPane;
 use rmpc_mpd::commands::{Song, State, status::OnOffOneshot, volume::Bound};
 use search::SearchPane;
+use statistics::StatisticsPane;
 use strum::{Display, IntoDiscriminant};
 use tabs::TabsPane;
 use tag_browser::TagBrowserPane;
@@ -71,6 +73,7 @@ use crate::{
 };
 
 pub mod album_art;
+pub mod album_grid;
 pub mod cava;
 pub mod directories;
 pub mod empty;
@@ -87,6 +90,7 @@ pub mod queue;
 pub mod queue_header;
 pub mod recently_played;
 pub mod search;
+pub mod statistics;
 pub mod tabs;
 pub mod tag_browser;
 pub mod volume;
@@ -296,17 +300,28 @@ impl<'panes> PaneContainer<'panes> {
                     pane.pane.clone(),
                     Box::new(VolumePane::new(kind.clone())) as Box<dyn BoxedPane>,
                 )),
-                PaneType::Sticker { sticker, format, limit, sort } => Some((
+                PaneType::Sticker { sticker, format, limit, sort, target } => Some((
                     pane.pane.clone(),
                     Box::new(StickerPane::new(
                         sticker.clone(),
                         *sort,
+                        *target,
                         pane.pane.clone(),
                         format.clone(),
                         *limit,
                         ctx,
                     )) as Box<dyn BoxedPane>,
                 )),
+                PaneType::Statistics { breakdowns } => Some((
+                    pane.pane.clone(),
+                    Box::new(StatisticsPane::new(breakdowns.clone(), pane.pane.clone()))
+                        as Box<dyn BoxedPane>,
+                )),
+                PaneType::AlbumGrid { width, height } => Some((
+                    pane.pane.clone(),
+                    Box::new(AlbumGridPane::new(*width, *height, pane.pane.clone(), ctx))
+                        as Box<dyn BoxedPane>,
+                )),
                 _ => None,
             })
     }
@@ -353,6 +368,16 @@ impl<'panes> PaneContainer<'panes> {
                     .get_mut(pane)
                     .with_context(|| format!("expected pane to be defined {p:?}"))?,
             )),
+            p @ PaneType::Statistics { .. } => Ok(Panes::Others(
+                self.others
+                    .get_mut(pane)
+                    .with_context(|| format!("expected pane to be defined {p:?}"))?,
+            )),
+            p @ PaneType::AlbumGrid { .. } => Ok(Panes::Others(
+                self.others
+                    .get_mut(pane)
+                    .with_context(|| format!("expected pane to be defined {p:?}"))?,
+            )),
             PaneType::Cava => Ok(Panes::Cava(&mut self.cava)),
             PaneType::Empty => Ok(Panes::Empty(&mut self.empty)),
         }
@@ -663,6 +688,18 @@ impl Property<PropertyKind> {
                     || self.default_as_span(song, ctx, tag_separator, strategy),
                     |v| Some(Either::Left(Span::styled(v.to_string(), style))),
                 ),
+                StatusProperty::MixRampDb => status.mixramp_db().map_or_else(
+                    || self.default_as_span(song, ctx, tag_separator, strategy),
+                    |v| Some(Either::Left(Span::styled(v.to_string(), style))),
+                ),
+                StatusProperty::MixRampDelay => status.mixramp_delay().map_or_else(
+                    || self.default_as_span(song, ctx, tag_separator, strategy),
+                    |v| Some(Either::Left(Span::styled(v.to_string(), style))),
+                ),
+                StatusProperty::ReplayGain => ctx.replay_gain_mode.as_ref().map_or_else(
+                    || self.default_as_span(song, ctx, tag_separator, strategy),
+                    |v| Some(Either::Left(Span::styled(v.to_string(), style))),
+                ),
                 StatusProperty::QueueLength { thousands_separator } => {
                     Some(Either::Left(Span::styled(
                         ctx.queue.len().with_thousands_separator(thousands_separator),
@@ -955,7 +992,14 @@ mod format_tests {
 
     use either::Either;
     use ratatui::{style::Style, text::Span};
-    use rmpc_mpd::commands::{Song, State, Status, Volume, status::OnOffOneshot};
+    use rmpc_mpd::commands::{
+        Song,
+        State,
+        Status,
+        Volume,
+        replay_gain::ReplayGainMode,
+        status::OnOffOneshot,
+    };
     use rstest::rstest;
 
     use crate::{
@@ -1468,6 +1512,7 @@ mod format_tests {
                 ]),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             };
 
             let result = format.as_string(Some(&song), "", TagResolutionStrategy::All, &ctx);
@@ -1481,6 +1526,9 @@ mod format_tests {
         #[case(StatusProperty::Duration, "2:03")]
         #[case(StatusProperty::Crossfade, "3")]
         #[case(StatusProperty::Bitrate, "123")]
+        #[case(StatusProperty::MixRampDb, "-17.5")]
+        #[case(StatusProperty::MixRampDelay, "2")]
+        #[case(StatusProperty::ReplayGain, "album")]
         fn status_property_resolves_correctly(
             mut ctx: Ctx,
             #[case] prop: StatusProperty,
@@ -1504,6 +1552,7 @@ mod format_tests {
                 ]),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             };
             ctx.status = Status {
                 volume: Volume::new(123),
@@ -1515,9 +1564,12 @@ mod format_tests {
                 elapsed: Duration::from_secs(123),
                 duration: Duration::from_secs(123),
                 xfade: Some(3),
+                mixrampdb: Some("-17.500000".to_string()),
+                mixrampdelay: Some("2.000000".to_string()),
                 state: State::Play,
                 ..Default::default()
             };
+            ctx.replay_gain_mode = Some(ReplayGainMode::Album);
 
             let result = format.as_span(Some(&song), &ctx, "", TagResolutionStrategy::All);
 
@@ -1563,6 +1615,7 @@ mod format_tests {
                 ]),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             };
 
             // Set up the app context with a fake queue and status
@@ -1574,6 +1627,7 @@ mod format_tests {
                 metadata: HashMap::from([("title".to_string(), "Song 1".into())]),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             });
             queue.push(Song {
                 id: 2,
@@ -1582,6 +1636,7 @@ mod format_tests {
                 metadata: HashMap::from([("title".to_string(), "Song 2".into())]),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             });
 
             ctx.queue = queue;
@@ -1656,6 +1711,7 @@ mod format_tests {
                 metadata: HashMap::from([("title".to_string(), "No Duration".into())]),
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
             };
 
             ctx.queue = vec![song_no_duration.clone()];
@@ -23,6 +23,7 @@ use crate::{
         macros::{modal, status_info},
         mouse_event::MouseEvent,
         mpd_client_ext::MpdDelete,
+        undo::{self, Edit},
     },
     status_warn,
     ui::{
@@ -55,6 +56,19 @@ impl PlaylistsPane {
     pub fn new(_ctx: &Ctx) -> Self {
         Self { stack: DirStack::default(), browser: Browser::new(), initialized: false }
     }
+
+    /// Files of the songs in the currently open playlist
+    fn song_files(&self) -> Vec<String> {
+        self.stack()
+            .current()
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                DirOrSong::Song(song) => Some(song.file.clone()),
+                DirOrSong::Dir { .. } => None,
+            })
+            .collect()
+    }
 }
 
 impl Pane for PlaylistsPane {
@@ -368,9 +382,10 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
                 items
                     .filter_map(|(idx, item)| match item {
                         DirOrSong::Dir { .. } => None,
-                        DirOrSong::Song(_) => Some(MpdDelete::SongInPlaylist {
+                        DirOrSong::Song(song) => Some(MpdDelete::SongInPlaylist {
                             playlist: Arc::clone(&playlist),
-                            range: SingleOrRange::single(idx),
+                            position: idx,
+                            file: song.file.clone(),
                         }),
                     })
                     .collect_vec()
@@ -442,11 +457,19 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
                 MoveDirection::Down => (idx + 1).min(self.stack().current().items.len() - 1),
             };
 
-            let playlist = playlist.clone();
+            let name = playlist.clone();
             ctx.query_sync(move |client| {
-                client.move_in_playlist(&playlist, &SingleOrRange::single(idx), new_idx)?;
+                client.move_in_playlist(&name, &SingleOrRange::single(idx), new_idx)?;
                 Ok(())
             })?;
+            let before = self.song_files();
+            let mut after = before.clone();
+            after.swap(idx, new_idx);
+            undo::record(ctx, Edit::PlaylistOrder {
+                playlist: playlist.clone(),
+                from: after,
+                to: before,
+            });
             self.stack_mut().current_mut().items.swap(idx, new_idx);
             self.stack_mut().current_mut().select_idx(new_idx, ctx.config.scrolloff);
         } else {
@@ -465,21 +488,41 @@ impl BrowserPane<DirOrSong> for PlaylistsPane {
                 }
             }
 
-            let playlist = playlist.clone();
-            let ranges = self.stack().current().marked().ranges().collect_vec();
-
-            ctx.query_sync(move |client| {
-                for range in ranges {
-                    let idx = range.start();
+            let name = playlist.clone();
+            let moves = self
+                .stack()
+                .current()
+                .marked()
+                .ranges()
+                .map(|range| {
+                    let idx = *range.start();
                     let new_idx = match direction {
                         MoveDirection::Up => idx.saturating_sub(1),
                         MoveDirection::Down => idx + 1,
                     };
-                    client.move_in_playlist(&playlist, &(range.into()), new_idx)?;
+                    (range, new_idx)
+                })
+                .collect_vec();
+
+            let before = self.song_files();
+            let mut after = before.clone();
+            for (range, new_idx) in &moves {
+                let songs = after.drain(range.clone()).collect_vec();
+                after.splice(*new_idx..*new_idx, songs);
+            }
+
+            ctx.query_sync(move |client| {
+                for (range, new_idx) in moves {
+                    client.move_in_playlist(&name, &(range.into()), new_idx)?;
                 }
 
                 Ok(())
             })?;
+            undo::record(ctx, Edit::PlaylistOrder {
+                playlist: playlist.clone(),
+                from: after,
+                to: before,
+            });
 
             let mut new_marked = BTreeSet::new();
             for marked in self.stack().current().marked() {
@@ -532,6 +532,7 @@ fn song(name: &str) -> Song {
         metadata: HashMap::new(),
         last_modified: *NOW,
         added: None,
+        prio: 0,
     }
 }
 
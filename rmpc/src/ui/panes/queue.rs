@@ -41,6 +41,7 @@ use crate::{
             },
         },
         sort_mode::{SortMode, SortOptions},
+        tabs::PaneType,
         theme::{
             AlbumSeparator,
             properties::{Property, SongProperty},
@@ -51,6 +52,7 @@ use crate::{
     shared::{
         args,
         clipboard::Clipboard,
+        duration_format::{parse_duration, parse_song_range},
         events::AppEvent,
         ext::{btreeset_ranges::BTreeSetRanges, rect::RectExt},
         id::{self, Id},
@@ -59,6 +61,7 @@ use crate::{
         mouse_event::{MouseEvent, MouseEventKind, calculate_scrollbar_position},
         mpd_client_ext::{Enqueue, MpdClientExt},
         song_ext::SongsExt,
+        undo::{self, Edit},
     },
     ui::{
         UiEvent,
@@ -66,7 +69,7 @@ use crate::{
         input::InputResultEvent,
         modals::{
             confirm_modal::{Action, ConfirmModal},
-            info_list_modal::{InfoListModal, SongCtx},
+            info_list_modal::InfoListModal,
             input_modal::InputModal,
             menu::{
                 add_to_playlist_or_show_modal,
@@ -81,7 +84,7 @@ use crate::{
             select_modal::SelectModal,
         },
         panes::queue_header::QueueHeaderPane,
-        song_ext::SongExt as _,
+        song_ext::SongExt,
         widgets::virtualized_table::VirtualizedTable,
     },
 };
@@ -95,6 +98,15 @@ pub struct QueuePane {
     should_center_cursor_on_current: bool,
     highlight_id: Id,
     highlight_enabled: bool,
+    queue_filter: Option<QueueFilter>,
+}
+
+/// Filter evaluated by MPD which narrows the displayed queue down to the
+/// matching songs
+#[derive(Debug)]
+struct QueueFilter {
+    query: String,
+    ids: HashSet<u32>,
 }
 
 #[derive(Debug, Enum)]
@@ -106,6 +118,8 @@ enum Areas {
 
 const ADD_TO_PLAYLIST: &str = "add_to_playlist";
 const ADD_TO_PLAYLIST_MULTIPLE: &str = "add_to_playlist_multiple";
+const QUEUE_FILTER: &str = "queue_filter";
+const REFRESH_QUEUE_FILTER: &str = "refresh_queue_filter";
 
 impl QueuePane {
     pub fn new(ctx: &Ctx) -> Self {
@@ -121,6 +135,7 @@ impl QueuePane {
             should_center_cursor_on_current: ctx.config.center_current_song_on_change,
             highlight_id: id::new(),
             highlight_enabled: true,
+            queue_filter: None,
         };
 
         s.highlight_timeout(ctx);
@@ -151,6 +166,89 @@ impl QueuePane {
         }
     }
 
+    /// Index of the currently playing song in the displayed, possibly
+    /// filtered, queue
+    fn current_song_idx(&self, ctx: &Ctx) -> Option<usize> {
+        if self.queue_filter.is_none() {
+            return ctx.current_song_index();
+        }
+        let id = ctx.current_song_index().and(ctx.status.songid)?;
+        self.queue.items.iter().position(|song| song.id == id)
+    }
+
+    fn refresh_items(&mut self, ctx: &Ctx) {
+        if let Some(filter) = &self.queue_filter {
+            self.queue.items =
+                ctx.queue.iter().filter(|song| filter.ids.contains(&song.id)).cloned().collect();
+        } else {
+            self.queue.items.clone_from(&ctx.queue);
+        }
+    }
+
+    fn set_queue_filter(&mut self, filter: Option<QueueFilter>, ctx: &Ctx) {
+        self.queue_filter = filter;
+        self.refresh_items(ctx);
+        self.queue.unmark_all();
+        let to_select = self.current_song_idx(ctx).or(Some(0));
+        self.queue.select_idx_opt(to_select, ctx.config.scrolloff);
+    }
+
+    /// Evaluates the filter by MPD, empty query clears the current filter
+    fn run_queue_filter(id: &'static str, query: String, ctx: &Ctx) {
+        let filter = match ctx.config.search.parse_filter(&query) {
+            Ok(filter) => filter,
+            Err(err) => {
+                status_error!("Invalid queue filter: {err}");
+                return;
+            }
+        };
+        let case_sensitive = ctx.config.search.case_sensitive;
+        ctx.query().id(id).replace_id(id).target(PaneType::Queue).query(move |client| {
+            let ids = match filter {
+                Some(filter) if case_sensitive => client.playlist_find(&[filter])?,
+                Some(filter) => client.playlist_search(&[filter])?,
+                None => Vec::new(),
+            };
+            let ids = ids.into_iter().map(|song| song.id).collect();
+
+            Ok(MpdQueryResult::QueueFilter { query, ids })
+        });
+    }
+
+    fn open_queue_filter(&self, ctx: &Ctx) {
+        let initial_value =
+            self.queue_filter.as_ref().map(|filter| filter.query.clone()).unwrap_or_default();
+        modal!(
+            ctx,
+            InputModal::new(ctx)
+                .title("Filter queue")
+                .confirm_label("Filter")
+                .input_label("Filter (ie. artist:value):")
+                .initial_value(initial_value)
+                .on_confirm(|ctx, value| {
+                    Self::run_queue_filter(QUEUE_FILTER, value.trim().to_owned(), ctx);
+                    Ok(())
+                })
+        );
+    }
+
+    fn delete_ids(ids: Vec<u32>, ctx: &Ctx) {
+        let deleted: HashSet<u32> = ids.iter().copied().collect();
+        undo::record(
+            ctx,
+            Edit::restore_queue_songs(&ctx.queue, |_, song| deleted.contains(&song.id)),
+        );
+        ctx.command(move |_, client| {
+            client.send_start_cmd_list()?;
+            for id in ids {
+                client.send_delete_id(id)?;
+            }
+            client.send_execute_cmd_list()?;
+            client.read_ok()?;
+            Ok(())
+        });
+    }
+
     fn enqueue_items(&self, all: bool) -> (Vec<Enqueue>, Option<usize>) {
         let hovered = self.queue.selected().map(|s| s.file.as_str());
         self.items(all).fold((Vec::new(), None), |mut acc, (idx, song)| {
@@ -194,16 +292,22 @@ impl QueuePane {
                     }
                     Ok(())
                 });
+                section.add_item("Play from position…", move |ctx| {
+                    if let Some(id) = selected_song_id {
+                        Self::open_play_from_position(id, ctx);
+                    }
+                    Ok(())
+                });
+                let song = selected_song.clone();
+                section.add_item("Set play range…", move |ctx| {
+                    if let Some(song) = song {
+                        Self::open_play_range(&song, ctx);
+                    }
+                    Ok(())
+                });
                 section.add_item("Show info", move |ctx| {
                     if let Some(song) = selected_song {
-                        modal!(
-                            ctx,
-                            InfoListModal::builder()
-                                .items(SongCtx(&song, ctx))
-                                .title("Song info")
-                                .column_widths(&[30, 70])
-                                .build()
-                        );
+                        InfoListModal::open_song_info(&song, ctx);
                     }
                     Ok(())
                 });
@@ -259,14 +363,12 @@ impl QueuePane {
                 let section = section
                     .item("Remove", move |ctx| {
                         if let Some(id) = selected_song_id {
-                            ctx.command(move |_, client| {
-                                client.delete_id(id)?;
-                                Ok(())
-                            });
+                            Self::delete_ids(vec![id], ctx);
                         }
                         Ok(())
                     })
                     .item("Clear queue", |ctx| {
+                        undo::record(ctx, Edit::restore_queue_songs(&ctx.queue, |_, _| true));
                         ctx.command(|_, client| {
                             client.clear()?;
                             Ok(())
@@ -284,6 +386,64 @@ impl QueuePane {
         modal!(ctx, modal);
     }
 
+    fn open_play_from_position(id: u32, ctx: &Ctx) {
+        modal!(
+            ctx,
+            InputModal::new(ctx)
+                .title("Play from position")
+                .confirm_label("Play")
+                .input_label("Timestamp (ie. 1:23):")
+                .on_confirm(move |ctx, value| {
+                    let time = match parse_duration(value) {
+                        Ok(time) => time,
+                        Err(err) => {
+                            status_error!("{err}");
+                            return Ok(());
+                        }
+                    };
+                    ctx.command(move |_, client| {
+                        client.seek_id(id, time)?;
+                        Ok(())
+                    });
+                    Ok(())
+                })
+        );
+    }
+
+    fn open_play_range(song: &Song, ctx: &Ctx) {
+        let id = song.id;
+        let initial_value = SongExt::format(
+            song,
+            &SongProperty::Range(),
+            &ctx.config.theme.format_tag_separator,
+            ctx.config.theme.multiple_tag_resolution_strategy,
+        )
+        .map(|range| range.into_owned())
+        .unwrap_or_default();
+        modal!(
+            ctx,
+            InputModal::new(ctx)
+                .title("Set play range")
+                .confirm_label("Set")
+                .input_label("Range (ie. 0:30-2:15), empty to play whole song:")
+                .initial_value(initial_value)
+                .on_confirm(move |ctx, value| {
+                    let range = match parse_song_range(value) {
+                        Ok(range) => range,
+                        Err(err) => {
+                            status_error!("{err}");
+                            return Ok(());
+                        }
+                    };
+                    ctx.command(move |_, client| {
+                        client.range_id(id, range)?;
+                        Ok(())
+                    });
+                    Ok(())
+                })
+        );
+    }
+
     fn sort(opts: SortOpts, ctx: &Ctx) -> Result<()> {
         let opts = SortOptions {
             mode: SortMode::Format(opts.tags),
@@ -306,6 +466,7 @@ impl QueuePane {
 
         let swaps = QueueHeaderPane::calculate_swaps(evald.as_slice(), ctx)?;
 
+        undo::record(ctx, Edit::restore_queue_order(&ctx.queue));
         ctx.command(move |_, client| {
             client.send_start_cmd_list()?;
             for swap in swaps {
@@ -334,6 +495,13 @@ impl Pane for QueuePane {
                 && let Some(ref title) = filter_text
             {
                 b = b.title(title.clone());
+            } else if let Some(filter) = &self.queue_filter {
+                b = b.title(format!(
+                    "[Filter: {}] {} of {} songs",
+                    filter.query,
+                    self.queue.len(),
+                    ctx.queue.len()
+                ));
             }
             b
         };
@@ -505,11 +673,11 @@ impl Pane for QueuePane {
         );
 
         if self.should_center_cursor_on_current {
-            let to_select = ctx.current_song_index().or(self.queue.selected_idx()).or(Some(0));
+            let to_select = self.current_song_idx(ctx).or(self.queue.selected_idx()).or(Some(0));
             self.queue.select_idx_opt(to_select, usize::MAX);
             self.should_center_cursor_on_current = false;
         } else {
-            let to_select = self.queue.selected_idx().or(ctx.current_song_index()).or(Some(0));
+            let to_select = self.queue.selected_idx().or(self.current_song_idx(ctx)).or(Some(0));
             self.queue.select_idx_opt(to_select, usize::MAX);
         }
 
@@ -523,7 +691,7 @@ impl Pane for QueuePane {
             self.queue.len(),
             self.areas[Areas::Table].height as usize,
         );
-        let to_select = self.queue.selected_idx().or(ctx.current_song_index()).or(Some(0));
+        let to_select = self.queue.selected_idx().or(self.current_song_idx(ctx)).or(Some(0));
         self.queue.select_idx_opt(to_select, ctx.config.scrolloff);
         ctx.render()?;
         Ok(())
@@ -533,14 +701,18 @@ impl Pane for QueuePane {
         match event {
             UiEvent::Database => {
                 self.queue.filter_active = false;
-                self.queue.items.clone_from(&ctx.queue);
+                self.refresh_items(ctx);
                 self.queue.unmark_all();
             }
             UiEvent::QueueChanged => {
-                self.queue.items.clone_from(&ctx.queue);
+                self.refresh_items(ctx);
+                // Songs added to the queue might match the filter as well
+                if let Some(filter) = &self.queue_filter {
+                    Self::run_queue_filter(REFRESH_QUEUE_FILTER, filter.query.clone(), ctx);
+                }
             }
             UiEvent::SongChanged => {
-                if let Some(idx) = ctx.current_song_index()
+                if let Some(idx) = self.current_song_idx(ctx)
                     && ctx.config.select_current_song_on_change
                 {
                     match (is_visible, ctx.config.center_current_song_on_change) {
@@ -630,11 +802,7 @@ impl Pane for QueuePane {
                     .get_at_rendered_row(clicked_row)
                     .and_then(|idx| self.queue.items.get(idx))
                 {
-                    let id = selected_song.id;
-                    ctx.command(move |_, client| {
-                        client.delete_id(id)?;
-                        Ok(())
-                    });
+                    Self::delete_ids(vec![selected_song.id], ctx);
                 }
             }
             MouseEventKind::MiddleClick => {}
@@ -674,6 +842,22 @@ impl Pane for QueuePane {
         ctx: &Ctx,
     ) -> Result<()> {
         match (id, data) {
+            (QUEUE_FILTER, MpdQueryResult::QueueFilter { query, ids }) => {
+                let filter = (!query.is_empty())
+                    .then(|| QueueFilter { query, ids: ids.into_iter().collect() });
+                self.set_queue_filter(filter, ctx);
+                ctx.render()?;
+            }
+            (REFRESH_QUEUE_FILTER, MpdQueryResult::QueueFilter { query, ids }) => {
+                // The filter might have been changed or cleared in the meantime
+                if let Some(filter) = &mut self.queue_filter
+                    && filter.query == query
+                {
+                    filter.ids = ids.into_iter().collect();
+                    self.refresh_items(ctx);
+                    ctx.render()?;
+                }
+            }
             (ADD_TO_PLAYLIST, MpdQueryResult::AddToPlaylist { playlists, song_file }) => {
                 modal!(
                     ctx,
@@ -765,7 +949,22 @@ impl Pane for QueuePane {
     fn handle_action(&mut self, event: &mut ActionEvent, ctx: &mut Ctx) -> Result<()> {
         if let Some(action) = event.claim_queue() {
             match action {
+                QueueActions::Delete if self.queue_filter.is_some() => {
+                    let ids = self.items(false).map(|(_, song)| song.id).collect_vec();
+                    if ids.is_empty() {
+                        status_error!("No song selected");
+                    } else {
+                        Self::delete_ids(ids, ctx);
+                        self.queue.marked_mut().clear();
+                        ctx.render()?;
+                    }
+                }
                 QueueActions::Delete if !self.queue.marked().is_empty() => {
+                    let marked = self.queue.marked();
+                    undo::record(
+                        ctx,
+                        Edit::restore_queue_songs(&ctx.queue, |idx, _| marked.contains(&idx)),
+                    );
                     for range in self.queue.marked().ranges().rev() {
                         ctx.command(move |_, client| {
                             client.delete_from_queue(range.into())?;
@@ -778,6 +977,7 @@ impl Pane for QueuePane {
                 }
                 QueueActions::Delete => {
                     if let Some((idx, _)) = self.queue.selected_with_idx() {
+                        undo::record(ctx, Edit::restore_queue_songs(&ctx.queue, |i, _| i == idx));
                         ctx.command(move |_, client| {
                             client.delete_from_queue(SingleOrRange::single(idx))?;
                             Ok(())
@@ -786,17 +986,37 @@ impl Pane for QueuePane {
                         status_error!("No song selected");
                     }
                 }
+                QueueActions::DeleteAll if self.queue_filter.is_some() => {
+                    let ids = self.queue.items.iter().map(|song| song.id).collect_vec();
+                    modal!(
+                        ctx,
+                        ConfirmModal::builder()
+                            .ctx(ctx)
+                            .message(vec!["Are you sure you want to remove all filtered songs?"])
+                            .action(Action::Single {
+                                on_confirm: Box::new(move |ctx| {
+                                    Self::delete_ids(ids, ctx);
+                                    Ok(())
+                                }),
+                                confirm_label: Some("Remove"),
+                                cancel_label: None,
+                            })
+                            .size((45, 6))
+                            .build()
+                    );
+                }
                 QueueActions::DeleteAll => {
                     modal!(
                         ctx,
                         ConfirmModal::builder()
                             .ctx(ctx)
-                            .message(vec![
-                                "Are you sure you want to clear the queue?",
-                                "This action cannot be undone."
-                            ])
+                            .message(vec!["Are you sure you want to clear the queue?"])
                             .action(Action::Single {
                                 on_confirm: Box::new(|ctx| {
+                                    undo::record(
+                                        ctx,
+                                        Edit::restore_queue_songs(&ctx.queue, |_, _| true),
+                                    );
                                     ctx.command(|_, client| Ok(client.clear()?));
                                     Ok(())
                                 }),
@@ -816,6 +1036,21 @@ impl Pane for QueuePane {
                         });
                     }
                 }
+                QueueActions::PlayFromPosition => {
+                    if let Some(selected_song) = self.queue.selected() {
+                        Self::open_play_from_position(selected_song.id, ctx);
+                    }
+                }
+                QueueActions::Filter => {
+                    self.open_queue_filter(ctx);
+                }
+                QueueActions::PlayRange => {
+                    if let Some(selected_song) = self.queue.selected() {
+                        Self::open_play_range(selected_song, ctx);
+                    } else {
+                        status_error!("No song selected");
+                    }
+                }
                 QueueActions::JumpToCurrent => {
                     if let Some((idx, _)) = ctx.status.songid.and_then(|id| {
                         self.queue.items.iter().enumerate().find(|(_, song)| song.id == id)
@@ -846,7 +1081,15 @@ impl Pane for QueuePane {
                     }
                 }
 
+                QueueActions::Shuffle
+                | QueueActions::SortByColumn(_)
+                | QueueActions::Sort { .. }
+                    if self.queue_filter.is_some() =>
+                {
+                    status_warn!("Reordering is not supported while the queue is filtered");
+                }
                 QueueActions::Shuffle if !self.queue.marked().is_empty() => {
+                    undo::record(ctx, Edit::restore_queue_order(&ctx.queue));
                     for range in self.queue.marked().ranges().rev() {
                         ctx.command(move |_, client| {
                             client.shuffle(Some(range.into()))?;
@@ -856,6 +1099,7 @@ impl Pane for QueuePane {
                     status_info!("Shuffled selected songs");
                 }
                 QueueActions::Shuffle => {
+                    undo::record(ctx, Edit::restore_queue_order(&ctx.queue));
                     ctx.command(move |_, client| {
                         client.shuffle(None)?;
                         Ok(())
@@ -897,6 +1141,27 @@ impl Pane for QueuePane {
                     Self::sort(opts.clone(), ctx)?;
                     ctx.render()?;
                 }
+                QueueActions::Priority { kind } => {
+                    let kind = *kind;
+                    let changes = self
+                        .items(false)
+                        .map(|(_, song)| (kind.apply(song.prio), song.id))
+                        .into_group_map();
+
+                    if changes.is_empty() {
+                        status_error!("No song selected");
+                    } else {
+                        ctx.command(move |_, client| {
+                            client.send_start_cmd_list()?;
+                            for (prio, ids) in changes {
+                                client.send_prio_id(prio, &ids)?;
+                            }
+                            client.send_execute_cmd_list()?;
+                            client.read_ok()?;
+                            Ok(())
+                        });
+                    }
+                }
                 QueueActions::Unused => {}
             }
         } else if let Some(action) = event.claim_common().map(|v| v.to_owned()) {
@@ -915,6 +1180,9 @@ impl Pane for QueuePane {
 
                     ctx.render()?;
                 }
+                CommonAction::MoveUp | CommonAction::MoveDown if self.queue_filter.is_some() => {
+                    status_warn!("Reordering is not supported while the queue is filtered");
+                }
                 CommonAction::MoveUp if !self.queue.marked().is_empty() => {
                     if self.queue.is_empty() {
                         return Ok(());
@@ -924,6 +1192,7 @@ impl Pane for QueuePane {
                         return Ok(());
                     }
 
+                    undo::record(ctx, Edit::restore_queue_order(&self.queue.items));
                     let ranges = self.queue.marked().ranges().collect_vec();
                     for range in ranges {
                         for idx in range.clone() {
@@ -967,6 +1236,7 @@ impl Pane for QueuePane {
                         return Ok(());
                     }
 
+                    undo::record(ctx, Edit::restore_queue_order(&self.queue.items));
                     let ranges = self.queue.marked().ranges().rev().collect_vec();
                     for range in ranges {
                         for idx in range.clone().rev() {
@@ -1013,6 +1283,7 @@ impl Pane for QueuePane {
                     }
 
                     let new_idx = idx.saturating_sub(1);
+                    undo::record(ctx, Edit::restore_queue_order(&self.queue.items));
                     ctx.app_event_sender.send(AppEvent::IgnoreIdleEvent(IdleEvent::Playlist))?;
                     ctx.command(move |tx, client| {
                         let result = client.move_in_queue(
@@ -1036,6 +1307,7 @@ impl Pane for QueuePane {
                     };
 
                     let new_idx = (idx + 1).min(self.queue.len() - 1);
+                    undo::record(ctx, Edit::restore_queue_order(&self.queue.items));
                     ctx.app_event_sender.send(AppEvent::IgnoreIdleEvent(IdleEvent::Playlist))?;
                     ctx.command(move |tx, client| {
                         let result = client.move_in_queue(
@@ -1183,6 +1455,10 @@ impl Pane for QueuePane {
                     self.queue.marked_mut().clear();
                     ctx.render()?;
                 }
+                CommonAction::Close if self.queue_filter.is_some() => {
+                    self.set_queue_filter(None, ctx);
+                    ctx.render()?;
+                }
                 CommonAction::AddOptions { kind: AddKind::Action(options) } => {
                     let (enqueue, _hovered_song_idx) = self.enqueue_items(options.all);
 
@@ -1213,14 +1489,7 @@ impl Pane for QueuePane {
                 }
                 CommonAction::ShowInfo => {
                     if let Some(selected_song) = self.queue.selected() {
-                        modal!(
-                            ctx,
-                            InfoListModal::builder()
-                                .items(SongCtx(selected_song, ctx))
-                                .title("Song info")
-                                .column_widths(&[30, 70])
-                                .build()
-                        );
+                        InfoListModal::open_song_info(selected_song, ctx);
                     } else {
                         status_error!("No song selected");
                     }
@@ -1243,10 +1512,16 @@ impl Pane for QueuePane {
                     current: false,
                     min_rating: _,
                     max_rating: _,
+                    target,
                 } => {
                     let items = self.enqueue_items(false).0;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(RATING_STICKER, value.to_string(), items)?;
+                        client.set_sticker_multiple(
+                            RATING_STICKER,
+                            value.to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
@@ -1255,10 +1530,11 @@ impl Pane for QueuePane {
                     current: false,
                     min_rating: _,
                     max_rating: _,
+                    target,
                 } => {
                     let items = self.enqueue_items(false).0;
                     ctx.command(move |_, client| {
-                        client.delete_sticker_multiple(RATING_STICKER, items)?;
+                        client.delete_sticker_multiple(RATING_STICKER, items, target)?;
                         Ok(())
                     });
                 }
@@ -1267,12 +1543,14 @@ impl Pane for QueuePane {
                     current: false,
                     min_rating,
                     max_rating,
+                    target,
                 } => {
                     let items = self.enqueue_items(false).0;
                     modal!(
                         ctx,
                         create_rating_modal(
                             items,
+                            target,
                             values.as_slice(),
                             min_rating,
                             max_rating,
@@ -1282,28 +1560,47 @@ impl Pane for QueuePane {
                         )
                     );
                 }
-                CommonAction::Rate { kind: RateKind::Like(), current: false, .. } => {
+                CommonAction::Rate { kind: RateKind::Like(), current: false, target, .. } => {
                     let items = self.enqueue_items(false).0;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(LIKE_STICKER, "2".to_string(), items)?;
+                        client.set_sticker_multiple(
+                            LIKE_STICKER,
+                            "2".to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
-                CommonAction::Rate { kind: RateKind::Neutral(), current: false, .. } => {
+                CommonAction::Rate {
+                    kind: RateKind::Neutral(), current: false, target, ..
+                } => {
                     let items = self.enqueue_items(false).0;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(LIKE_STICKER, "1".to_string(), items)?;
+                        client.set_sticker_multiple(
+                            LIKE_STICKER,
+                            "1".to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
-                CommonAction::Rate { kind: RateKind::Dislike(), current: false, .. } => {
+                CommonAction::Rate {
+                    kind: RateKind::Dislike(), current: false, target, ..
+                } => {
                     let items = self.enqueue_items(false).0;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(LIKE_STICKER, "0".to_string(), items)?;
+                        client.set_sticker_multiple(
+                            LIKE_STICKER,
+                            "0".to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
-                CommonAction::Rate { kind: _, current: true, min_rating: _, max_rating: _ } => {
+                CommonAction::Rate { current: true, .. } => {
                     event.abandon();
                 }
                 CommonAction::Save { kind: _, current: true } => {
@@ -16,6 +16,7 @@ use crate::{
         cmp::StringCompare,
         keys::ActionEvent,
         mouse_event::{MouseEvent, MouseEventKind},
+        undo::{self, Edit},
     },
     ui::{
         UiEvent,
@@ -86,6 +87,7 @@ impl QueueHeaderPane {
 
             let swaps = Self::calculate_swaps(evald.as_slice(), ctx)?;
 
+            undo::record(ctx, Edit::restore_queue_order(&ctx.queue));
             ctx.command(move |_, client| {
                 client.send_start_cmd_list()?;
                 for swap in swaps {
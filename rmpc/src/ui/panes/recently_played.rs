@@ -6,6 +6,7 @@ use ratatui::{Frame, prelude::Rect, widgets::ListState};
 use rmpc_mpd::{
     client::Client,
     commands::Song,
+    filter::Filter,
     mpd_client::{MpdClient, MpdCommand, StickerFindOptions, StickerSort},
     proto_client::ProtoClient,
 };
@@ -14,6 +15,7 @@ use super::Pane;
 use crate::{
     MpdQueryResult,
     config::{
+        keys::actions::StickerTarget,
         tabs::{PaneType, StickerPaneSort},
         theme::properties::{Property, SongProperty},
     },
@@ -22,7 +24,7 @@ use crate::{
         events::ClientRequest,
         id::{self, Id},
         keys::ActionEvent,
-        macros::{modal, try_skip},
+        macros::try_skip,
         mouse_event::MouseEvent,
         mpd_query::MpdQuery,
     },
@@ -32,7 +34,7 @@ use crate::{
         dir_or_song::DirOrSong,
         dirstack::DirStack,
         input::InputResultEvent,
-        modals::info_list_modal::{InfoListModal, SongCtx},
+        modals::info_list_modal::InfoListModal,
         widgets::browser::{Browser, BrowserArea},
     },
 };
@@ -44,6 +46,7 @@ const DEBOUNCE_DELAY: Duration = Duration::from_millis(50);
 pub struct StickerPane {
     sticker: String,
     sort: StickerPaneSort,
+    target: StickerTarget,
     stack: DirStack<DirOrSong, ListState>,
     browser: Browser<DirOrSong>,
     target_pane: PaneType,
@@ -57,6 +60,7 @@ impl StickerPane {
     pub fn new(
         sticker: String,
         sort: StickerPaneSort,
+        target: StickerTarget,
         target_pane: PaneType,
         format: Vec<Property<SongProperty>>,
         limit: Option<u32>,
@@ -70,6 +74,7 @@ impl StickerPane {
         Self {
             sticker,
             sort,
+            target,
             stack: DirStack::default(),
             browser,
             target_pane,
@@ -85,7 +90,7 @@ impl StickerPane {
             .id(INIT)
             .replace_id(INIT)
             .target(self.target_pane.clone())
-            .query(make_fetch_callback(self.sticker.clone(), self.sort, self.limit));
+            .query(make_fetch_callback(self.sticker.clone(), self.sort, self.target, self.limit));
     }
 
     fn fetch_debounced(&self, ctx: &Ctx) {
@@ -93,13 +98,14 @@ impl StickerPane {
         let limit = self.limit;
         let sticker = self.sticker.clone();
         let sort = self.sort;
+        let sticker_target = self.target;
         ctx.scheduler.schedule_replace(self.debounce_id, DEBOUNCE_DELAY, move |(_, client_tx)| {
             try_skip!(
                 client_tx.send(ClientRequest::Query(MpdQuery {
                     id: INIT,
                     replace_id: Some(INIT),
                     target: Some(target),
-                    callback: Box::new(make_fetch_callback(sticker, sort, limit)),
+                    callback: Box::new(make_fetch_callback(sticker, sort, sticker_target, limit)),
                 })),
                 "Failed to send recently played debounce query"
             );
@@ -214,14 +220,7 @@ impl BrowserPane<DirOrSong> for StickerPane {
         let DirOrSong::Song(song) = item else {
             return Ok(());
         };
-        modal!(
-            ctx,
-            InfoListModal::builder()
-                .items(SongCtx(song, ctx))
-                .title("Song info")
-                .column_widths(&[30, 70])
-                .build()
-        );
+        InfoListModal::open_song_info(song, ctx);
         Ok(())
     }
 }
@@ -242,11 +241,12 @@ impl From<StickerPaneSort> for StickerSort {
 fn make_fetch_callback(
     sticker: String,
     sort: StickerPaneSort,
+    target: StickerTarget,
     limit: Option<u32>,
 ) -> impl FnOnce(&mut Client<'_>) -> Result<MpdQueryResult> + Send + 'static {
     move |client| {
         let uris: Vec<String> = client
-            .find_stickers("", &sticker, StickerFindOptions {
+            .find_typed_stickers(&target.sticker_type(), "", &sticker, StickerFindOptions {
                 filter: None,
                 sort: Some(sort.into()),
                 window: limit.map(|l| (0, l)),
@@ -260,6 +260,16 @@ fn make_fetch_callback(
             return Ok(MpdQueryResult::SongsList { data: Vec::new(), path: None });
         }
 
+        // Tag stickers are listed as songs of the matching albums or artists in
+        // the order of the stickers
+        if let Some(tag) = target.tag() {
+            let mut songs = Vec::new();
+            for uri in uris {
+                songs.extend(client.find(&[Filter::new(tag.clone(), uri)])?);
+            }
+            return Ok(MpdQueryResult::SongsList { data: songs, path: None });
+        }
+
         client.send_start_cmd_list()?;
         for uri in &uris {
             client.send_lsinfo(Some(uri.as_str()))?;
@@ -11,7 +11,7 @@ use ratatui::{
 use rmpc_mpd::{
     client::Client,
     commands::Song,
-    filter::{Filter, FilterKind},
+    filter::{Filter, FilterExpr},
     mpd_client::{MpdClient, MpdCommand, StickerFindOptions},
     proto_client::ProtoClient,
 };
@@ -202,7 +202,13 @@ impl SearchPane {
         let filter = if let Some(custom_query) = self.inputs.custom_query(ctx)
             && !custom_query.is_empty()
         {
-            vec![Filter::new(String::new(), "").with_type(FilterKind::CustomQuery(custom_query))]
+            match custom_query.parse::<FilterExpr>() {
+                Ok(expr) => vec![Filter::from(expr)],
+                Err(err) => {
+                    status_error!("Invalid custom query: {err}");
+                    return;
+                }
+            }
         } else {
             self.inputs
                 .inputs
@@ -475,7 +481,7 @@ impl SearchPane {
                 CommonAction::PaneLeft => {}
                 CommonAction::ShowInfo => {}
                 CommonAction::ContextMenu => {}
-                CommonAction::Rate { kind: _, min_rating: _, max_rating: _, current: true } => {
+                CommonAction::Rate { current: true, .. } => {
                     event.abandon();
                 }
                 CommonAction::Rate { .. } => {}
@@ -785,10 +791,16 @@ impl SearchPane {
                     current: false,
                     min_rating: _,
                     max_rating: _,
+                    target,
                 } => {
                     let items = self.enqueue(false).1;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(RATING_STICKER, value.to_string(), items)?;
+                        client.set_sticker_multiple(
+                            RATING_STICKER,
+                            value.to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
@@ -797,10 +809,11 @@ impl SearchPane {
                     current: false,
                     min_rating: _,
                     max_rating: _,
+                    target,
                 } => {
                     let items = self.enqueue(false).1;
                     ctx.command(move |_, client| {
-                        client.delete_sticker_multiple(RATING_STICKER, items)?;
+                        client.delete_sticker_multiple(RATING_STICKER, items, target)?;
                         Ok(())
                     });
                 }
@@ -809,12 +822,14 @@ impl SearchPane {
                     current: false,
                     min_rating,
                     max_rating,
+                    target,
                 } => {
                     let items = self.enqueue(false).1;
                     modal!(
                         ctx,
                         create_rating_modal(
                             items,
+                            target,
                             values.as_slice(),
                             min_rating,
                             max_rating,
@@ -824,28 +839,47 @@ impl SearchPane {
                         )
                     );
                 }
-                CommonAction::Rate { kind: RateKind::Like(), current: false, .. } => {
+                CommonAction::Rate { kind: RateKind::Like(), current: false, target, .. } => {
                     let items = self.enqueue(false).1;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(LIKE_STICKER, "2".to_string(), items)?;
+                        client.set_sticker_multiple(
+                            LIKE_STICKER,
+                            "2".to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
-                CommonAction::Rate { kind: RateKind::Neutral(), current: false, .. } => {
+                CommonAction::Rate {
+                    kind: RateKind::Neutral(), current: false, target, ..
+                } => {
                     let items = self.enqueue(false).1;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(LIKE_STICKER, "1".to_string(), items)?;
+                        client.set_sticker_multiple(
+                            LIKE_STICKER,
+                            "1".to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
-                CommonAction::Rate { kind: RateKind::Dislike(), current: false, .. } => {
+                CommonAction::Rate {
+                    kind: RateKind::Dislike(), current: false, target, ..
+                } => {
                     let items = self.enqueue(false).1;
                     ctx.command(move |_, client| {
-                        client.set_sticker_multiple(LIKE_STICKER, "0".to_string(), items)?;
+                        client.set_sticker_multiple(
+                            LIKE_STICKER,
+                            "0".to_string(),
+                            items,
+                            target,
+                        )?;
                         Ok(())
                     });
                 }
-                CommonAction::Rate { kind: _, current: true, min_rating: _, max_rating: _ } => {
+                CommonAction::Rate { current: true, .. } => {
                     event.abandon();
                 }
                 CommonAction::Save { kind: _, current: true } => {
@@ -0,0 +1,283 @@
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout},
+    prelude::Rect,
+    widgets::{Cell, Row, Table, TableState},
+};
+use rmpc_mpd::{
+    client::Client,
+    commands::{Stats, count::GroupedCounts},
+    filter::Tag,
+    mpd_client::MpdClient,
+};
+
+use super::Pane;
+use crate::{
+    MpdQueryResult,
+    config::{keys::CommonAction, tabs::PaneType},
+    ctx::Ctx,
+    shared::{
+        ext::duration::DurationExt,
+        keys::ActionEvent,
+        mouse_event::{MouseEvent, MouseEventKind},
+    },
+    ui::{UiEvent, dirstack::DirState},
+};
+
+const INIT: &str = "statistics_init";
+
+#[derive(Debug)]
+pub struct StatisticsPane {
+    breakdowns: Vec<String>,
+    target_pane: PaneType,
+    stats: Option<Stats>,
+    groups: Vec<(String, GroupedCounts)>,
+    scrolling_state: DirState<TableState>,
+    table_area: Rect,
+    initialized: bool,
+    needs_refresh: bool,
+}
+
+impl StatisticsPane {
+    pub fn new(breakdowns: Vec<String>, target_pane: PaneType) -> Self {
+        Self {
+            breakdowns,
+            target_pane,
+            stats: None,
+            groups: Vec::new(),
+            scrolling_state: DirState::default(),
+            table_area: Rect::default(),
+            initialized: false,
+            needs_refresh: false,
+        }
+    }
+
+    fn fetch(&self, ctx: &Ctx) {
+        let breakdowns = self.breakdowns.clone();
+        ctx.query().id(INIT).replace_id(INIT).target(self.target_pane.clone()).query(
+            move |client| {
+                let stats = client.stats()?;
+                let groups = fetch_groups(client, breakdowns)?;
+                Ok(MpdQueryResult::Statistics { stats, groups })
+            },
+        );
+    }
+
+    fn rows(&self, ctx: &Ctx) -> Vec<Row<'static>> {
+        let label_style = ctx.config.theme.preview_label_style;
+        let group_style = ctx.config.theme.preview_metadata_group_style;
+        let mut rows = Vec::new();
+
+        if let Some(stats) = &self.stats {
+            let last_update = stats.db_update.map_or_else(
+                || "never".to_owned(),
+                |time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+            );
+
+            rows.push(Row::new([Cell::from("Library")]).style(group_style));
+            for (label, value) in [
+                ("Artists", stats.artists.to_string()),
+                ("Albums", stats.albums.to_string()),
+                ("Songs", stats.songs.to_string()),
+                ("Playtime", stats.db_playtime.format_to_duration(" ")),
+                ("Last database update", last_update),
+                ("MPD uptime", stats.uptime.format_to_duration(" ")),
+            ] {
+                rows.push(Row::new([
+                    Cell::from(label).style(label_style),
+                    Cell::from(value),
+                    Cell::default(),
+                ]));
+            }
+        }
+
+        for (tag, counts) in &self.groups {
+            rows.push(Row::default());
+            rows.push(
+                Row::new([
+                    Cell::from(format!("Playtime by {tag}")),
+                    Cell::from("Songs"),
+                    Cell::from("Playtime"),
+                ])
+                .style(group_style),
+            );
+            for group in &counts.0 {
+                let value = if group.value.is_empty() {
+                    format!("<no {tag}>")
+                } else {
+                    group.value.clone()
+                };
+                rows.push(Row::new([
+                    Cell::from(value).style(label_style),
+                    Cell::from(group.count.songs.to_string()),
+                    Cell::from(group.count.playtime.format_to_duration(" ")),
+                ]));
+            }
+        }
+
+        rows
+    }
+}
+
+fn fetch_groups(
+    client: &mut Client<'_>,
+    breakdowns: Vec<String>,
+) -> Result<Vec<(String, GroupedCounts)>> {
+    breakdowns
+        .into_iter()
+        .map(|tag| {
+            let mut counts = client.count_grouped(Tag::Custom(tag.clone()), None)?;
+            counts.0.sort_by_key(|group| std::cmp::Reverse(group.count.playtime));
+            Ok((tag, counts))
+        })
+        .collect()
+}
+
+impl Pane for StatisticsPane {
+    fn render(&mut self, frame: &mut Frame, area: Rect, ctx: &Ctx) -> Result<()> {
+        let scrollbar_area_width: u16 = ctx.config.theme.scrollbar.is_some().into();
+        let [table_area, scrollbar_area] = Layout::horizontal([
+            Constraint::Percentage(100),
+            Constraint::Min(scrollbar_area_width),
+        ])
+        .areas(area);
+        self.table_area = table_area;
+
+        let rows = self.rows(ctx);
+        self.scrolling_state.set_content_and_viewport_len(rows.len(), table_area.height.into());
+        if self.scrolling_state.get_selected().is_none() {
+            self.scrolling_state.first();
+        }
+
+        let table = Table::new(rows, [
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+        ])
+        .column_spacing(1)
+        .style(ctx.config.as_text_style())
+        .row_highlight_style(ctx.config.theme.current_item_style);
+
+        if let Some(scrollbar) = ctx.config.as_styled_scrollbar() {
+            frame.render_stateful_widget(
+                scrollbar,
+                scrollbar_area,
+                self.scrolling_state.as_scrollbar_state_ref(),
+            );
+        }
+        frame.render_stateful_widget(table, table_area, self.scrolling_state.as_render_state_ref());
+
+        Ok(())
+    }
+
+    fn before_show(&mut self, ctx: &Ctx) -> Result<()> {
+        if !self.initialized || self.needs_refresh {
+            self.fetch(ctx);
+            self.initialized = true;
+            self.needs_refresh = false;
+        }
+        Ok(())
+    }
+
+    fn on_event(&mut self, event: &mut UiEvent, is_visible: bool, ctx: &Ctx) -> Result<()> {
+        match event {
+            UiEvent::Database => {
+                if is_visible {
+                    self.fetch(ctx);
+                } else {
+                    self.needs_refresh = true;
+                }
+            }
+            UiEvent::Reconnected => {
+                self.initialized = false;
+                self.needs_refresh = false;
+                if is_visible {
+                    self.before_show(ctx)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn on_query_finished(
+        &mut self,
+        id: &'static str,
+        data: MpdQueryResult,
+        is_visible: bool,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        if let (INIT, MpdQueryResult::Statistics { stats, groups }) = (id, data) {
+            self.stats = Some(stats);
+            self.groups = groups;
+            if is_visible {
+                ctx.render()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEvent, ctx: &Ctx) -> Result<()> {
+        if !self.table_area.contains(event.into()) {
+            return Ok(());
+        }
+
+        match event.kind {
+            MouseEventKind::ScrollUp => {
+                self.scrolling_state.scroll_up(ctx.config.scroll_amount, ctx.config.scrolloff);
+
+                ctx.render()?;
+            }
+            MouseEventKind::ScrollDown => {
+                self.scrolling_state.scroll_down(ctx.config.scroll_amount, ctx.config.scrolloff);
+
+                ctx.render()?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_action(&mut self, event: &mut ActionEvent, ctx: &mut Ctx) -> Result<()> {
+        let config = &ctx.config;
+        if let Some(action) = event.claim_common() {
+            match action {
+                CommonAction::DownHalf => {
+                    self.scrolling_state.next_half_viewport(config.scrolloff);
+
+                    ctx.render()?;
+                }
+                CommonAction::UpHalf => {
+                    self.scrolling_state.prev_half_viewport(config.scrolloff);
+
+                    ctx.render()?;
+                }
+                CommonAction::Up => {
+                    self.scrolling_state.prev(config.scrolloff, config.wrap_navigation);
+
+                    ctx.render()?;
+                }
+                CommonAction::Down => {
+                    self.scrolling_state.next(config.scrolloff, config.wrap_navigation);
+
+                    ctx.render()?;
+                }
+                CommonAction::Bottom => {
+                    self.scrolling_state.last();
+
+                    ctx.render()?;
+                }
+                CommonAction::Top => {
+                    self.scrolling_state.first();
+
+                    ctx.render()?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -98,6 +98,9 @@ impl Pane for TabsPane<'_> {
 
                 self.active_tab = new_active_tab;
             }
+            UiEvent::AlbumColorsChanged => {
+                self.tabs = Self::init_tabs(Self::init_tab_names(ctx), ctx);
+            }
             _ => {}
         }
         Ok(())
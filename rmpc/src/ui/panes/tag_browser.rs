@@ -218,6 +218,7 @@ impl TagBrowserPane {
                 duration: None,
                 last_modified: chrono::Utc::now(),
                 added: None,
+                prio: 0,
                 metadata: record
                     .into_iter()
                     .filter(|(_, v)| !v.is_empty())
@@ -474,6 +475,7 @@ mod tests {
             ]),
             last_modified: chrono::Utc::now(),
             added: None,
+            prio: 0,
         }
     }
 
@@ -493,6 +495,7 @@ mod tests {
             ]),
             last_modified: chrono::Utc::now(),
             added: None,
+            prio: 0,
         }
     }
 
@@ -514,6 +517,7 @@ mod tests {
             ]),
             last_modified: chrono::Utc::now(),
             added: None,
+            prio: 0,
         }
     }
 
@@ -354,6 +354,14 @@ impl SongExt for Song {
             SongProperty::Channels() => self.channels().map(|v| Cow::Owned(v.to_string())),
             SongProperty::Added() => self.added.map(|d| Cow::Owned(d.to_string())),
             SongProperty::LastModified() => Some(Cow::Owned(self.last_modified.to_string())),
+            SongProperty::Priority() => (self.prio > 0).then(|| Cow::Owned(self.prio.to_string())),
+            SongProperty::Range() => self.range().filter(|range| !range.is_full()).map(|range| {
+                Cow::Owned(format!(
+                    "{}-{}",
+                    range.start.unwrap_or_default().to_string(),
+                    range.end.map(|end| end.to_string()).unwrap_or_default()
+                ))
+            }),
         }
     }
 
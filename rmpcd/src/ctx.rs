@@ -1,9 +1,10 @@
-use rmpc_mpd::commands::{Song, Status};
+use rmpc_mpd::commands::{QueueVersion, Song, Status};
 
 pub struct Ctx {
     pub current_song: Option<Song>,
     pub status: Status,
     pub queue: Vec<Song>,
+    pub queue_version: Option<QueueVersion>,
     pub album_art: Option<Vec<u8>>,
     pub last_written_album_art_song_uri: Option<String>,
 }
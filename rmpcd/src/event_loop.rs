@@ -1,12 +1,15 @@
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering},
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use anyhow::Result;
 use rmpc_mpd::{
-    commands::{IdleEvent, State},
-    mpd_client::{AlbumArtOrder, MpdClient},
+    commands::{IdleEvent, QueueDiff, State},
+    mpd_client::AlbumArtOrder,
 };
 use tokio::{
     select,
@@ -19,21 +22,21 @@ use tracing::{error, info, trace, warn};
 
 use crate::{
     AppEvent,
-    async_client::AsyncClient,
     ctx::Ctx,
     ext::SenderExt,
     lua::{
         lualib::mpd::types::Song,
         plugin::{self, LuaPlugin, PluginStore, PluginsEvent},
     },
-    mpd_ext::MpdExt,
+    mpd::Mpd,
+    mpd_ext::find_album_art,
     mpris::Change,
 };
 
 static IS_PLAYING: AtomicBool = AtomicBool::new(false);
 
 pub async fn init(
-    client: Arc<AsyncClient>,
+    client: Arc<Mpd>,
     ctx: Arc<RwLock<Ctx>>,
     mut app_ev_rx: UnboundedReceiver<AppEvent>,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
@@ -71,7 +74,7 @@ pub async fn init(
 
         match ev {
             AppEvent::StatusUpdate(new_status) => {
-                let song = client.run(|c| c.get_current_song()).await?;
+                let song = client.run(|c| c.current_song()).await?;
                 let mut album_art_changed = false;
                 let mut album_art = None;
 
@@ -83,7 +86,9 @@ pub async fn init(
                         let uri = s.file.clone();
                         album_art_changed = true;
                         client
-                            .run(move |c| c.find_album_art(&uri, AlbumArtOrder::EmbeddedFirst))
+                            .run(|c| async move {
+                                find_album_art(c, &uri, AlbumArtOrder::EmbeddedFirst).await
+                            })
                             .await?
                     } else {
                         None
@@ -130,72 +135,67 @@ pub async fn init(
                     }
                 }
             }
-            AppEvent::Idle(events) => {
-                for ev in events {
-                    match ev {
-                        IdleEvent::Player => {
-                            let new_status = client.run(|c| c.get_status()).await?;
-                            app_ev_tx.send_safe(AppEvent::StatusUpdate(new_status));
-                        }
-                        IdleEvent::Options => {
-                            let new_status = client.run(|c| c.get_status()).await?;
-
-                            let (old_repeat, old_single, old_random) = {
-                                let mut state = ctx.write().await;
-                                let old =
-                                    (state.status.repeat, state.status.single, state.status.random);
-                                state.status.repeat = new_status.repeat;
-                                state.status.single = new_status.single;
-                                state.status.random = new_status.random;
-                                state.status.consume = new_status.consume;
-                                state.status.xfade = new_status.xfade;
-                                old
-                            };
-
-                            if let Some(tx) = &mpris_tx {
-                                if old_repeat != new_status.repeat
-                                    || old_single != new_status.single
-                                {
-                                    tx.send_safe(Change::LoopStatus);
-                                }
-                                if old_random != new_status.random {
-                                    tx.send_safe(Change::Shuffle);
-                                }
+            AppEvent::Idle(ev) => {
+                match ev {
+                    IdleEvent::Player => {
+                        let new_status = client.run(|c| c.status()).await?;
+                        app_ev_tx.send_safe(AppEvent::StatusUpdate(new_status));
+                    }
+                    IdleEvent::Options => {
+                        let new_status = client.run(|c| c.status()).await?;
+
+                        let (old_repeat, old_single, old_random) = {
+                            let mut state = ctx.write().await;
+                            let old =
+                                (state.status.repeat, state.status.single, state.status.random);
+                            state.status.repeat = new_status.repeat;
+                            state.status.single = new_status.single;
+                            state.status.random = new_status.random;
+                            state.status.consume = new_status.consume;
+                            state.status.xfade = new_status.xfade;
+                            old
+                        };
+
+                        if let Some(tx) = &mpris_tx {
+                            if old_repeat != new_status.repeat || old_single != new_status.single {
+                                tx.send_safe(Change::LoopStatus);
                             }
-                        }
-                        IdleEvent::Mixer => {
-                            let new_status = client.run(|c| c.get_status()).await?;
-                            let old_volume = ctx.read().await.status.volume;
-                            ctx.write().await.status.volume = new_status.volume;
-
-                            if old_volume != new_status.volume
-                                && let Some(tx) = &mpris_tx
-                            {
-                                tx.send_safe(Change::Volume);
+                            if old_random != new_status.random {
+                                tx.send_safe(Change::Shuffle);
                             }
                         }
-                        IdleEvent::Playlist => {
-                            let new_queue = client.run(|c| c.playlist_info()).await?;
-                            ctx.write().await.queue = new_queue.unwrap_or_default();
-                            if let Some(tx) = &mpris_tx {
-                                tx.send_safe(Change::Queue);
-                            }
-                        }
-                        IdleEvent::Message => {
-                            let messages =
-                                client.run(|c| c.read_messages()).await?.0.into_iter().collect();
-                            tx.send_safe(PluginsEvent::Messages { messages });
+                    }
+                    IdleEvent::Mixer => {
+                        let new_status = client.run(|c| c.status()).await?;
+                        let old_volume = ctx.read().await.status.volume;
+                        ctx.write().await.status.volume = new_status.volume;
+
+                        if old_volume != new_status.volume
+                            && let Some(tx) = &mpris_tx
+                        {
+                            tx.send_safe(Change::Volume);
                         }
-                        ev => {
-                            trace!(?ev, "Event currently not supported");
-                            client.skip_to_idle();
+                    }
+                    IdleEvent::Playlist => {
+                        let change = update_queue(&client, &ctx).await?;
+                        if let Some(tx) = &mpris_tx {
+                            tx.send_safe(change);
                         }
                     }
-
-                    tx.send_safe(PluginsEvent::Idle { event: ev });
+                    IdleEvent::Message => {
+                        let messages = client.client()?.read_messages().0.into_iter().collect();
+                        tx.send_safe(PluginsEvent::Messages { messages });
+                    }
+                    ev => {
+                        trace!(?ev, "Event currently not supported");
+                    }
                 }
+
+                tx.send_safe(PluginsEvent::Idle { event: ev });
             }
             AppEvent::Reconnected => {
+                // MPD might have been restarted which resets the queue version
+                ctx.write().await.queue_version = None;
                 tx.send_safe(PluginsEvent::Reconnect);
             }
         }
@@ -208,7 +208,7 @@ pub async fn init(
     Ok(())
 }
 
-pub fn start_update_loop(client: Arc<AsyncClient>, tx: UnboundedSender<AppEvent>) {
+pub fn start_update_loop(client: Arc<Mpd>, tx: UnboundedSender<AppEvent>) {
     IS_PLAYING.store(true, Ordering::Relaxed);
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
@@ -219,7 +219,7 @@ pub fn start_update_loop(client: Arc<AsyncClient>, tx: UnboundedSender<AppEvent>
             }
             trace!("Tick: checking status...");
 
-            match client.run(|c| c.get_status()).await {
+            match client.run(|c| c.status()).await {
                 Ok(s) => tx.send_safe(AppEvent::StatusUpdate(s.clone())),
                 Err(err) => {
                     error!(err = ?err, "Failed to get status in tick");
@@ -229,3 +229,67 @@ pub fn start_update_loop(client: Arc<AsyncClient>, tx: UnboundedSender<AppEvent>
         }
     });
 }
+
+/// Applies changes made to the queue since its last known version or fetches
+/// the whole queue when they cannot be determined. Returns the change which
+/// should be announced over MPRIS.
+async fn update_queue(client: &Mpd, ctx: &RwLock<Ctx>) -> Result<Change> {
+    let known = {
+        let state = ctx.read().await;
+        state.queue_version.clone().map(|version| {
+            let positions: HashMap<u32, usize> =
+                state.queue.iter().enumerate().map(|(idx, song)| (song.id, idx)).collect();
+            (version, positions)
+        })
+    };
+
+    if let Some((version, positions)) = known
+        && let Some(diff) =
+            client.run(|c| async move { c.queue_diff(&version, &positions).await }).await?
+    {
+        let mut state = ctx.write().await;
+        let change = queue_change(&diff, &state.queue);
+        let version = diff.to.clone();
+        if diff.apply(&mut state.queue) {
+            state.queue_version = Some(version);
+            return Ok(change);
+        }
+        warn!("Failed to apply queue changes, fetching the whole queue");
+    }
+
+    let (queue, version) = client
+        .run(|c| async move {
+            // Status is fetched first so the queue is at least as new as its version
+            let version = c.status().await?.queue_version();
+            Ok((c.playlist_info().await?, version))
+        })
+        .await?;
+
+    let mut state = ctx.write().await;
+    state.queue = queue.unwrap_or_default();
+    state.queue_version = version;
+
+    Ok(Change::Queue)
+}
+
+fn queue_change(diff: &QueueDiff, queue: &[rmpc_mpd::commands::Song]) -> Change {
+    if !diff.moved.is_empty() {
+        return Change::Queue;
+    }
+
+    let ids = || diff.songs.iter().map(|song| song.id).collect();
+
+    if diff.length == queue.len()
+        && diff.songs.iter().all(|song| {
+            song.pos().and_then(|pos| queue.get(pos)).is_some_and(|old| old.id == song.id)
+        })
+    {
+        Change::TracksMetadata(ids())
+    } else if diff.length == queue.len() + diff.songs.len()
+        && diff.songs.iter().all(|song| song.pos().is_some_and(|pos| pos >= queue.len()))
+    {
+        Change::TracksAdded(ids())
+    } else {
+        Change::Queue
+    }
+}
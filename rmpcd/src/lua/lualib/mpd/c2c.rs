@@ -2,17 +2,20 @@ use std::{collections::HashMap, sync::Arc};
 
 use anyhow::Result;
 use mlua::{IntoLua, IntoLuaMulti, Lua, Table, Value};
-use rmpc_mpd::{commands::messages::Messages as MpdMessages, mpd_client::MpdClient};
+use rmpc_mpd::commands::messages::Messages as MpdMessages;
 
-use crate::async_client::AsyncClient;
+use crate::mpd::Mpd;
 
-pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
+pub fn init(lua: &Lua, mpd: &Table, client: &Arc<Mpd>) -> Result<()> {
     let c = Arc::clone(client);
     let subscribe = lua.create_async_function(move |lua, channel: String| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.subscribe(&channel)).await {
-                Ok(()) => true.into_lua_multi(&lua),
+            match client.client() {
+                Ok(c) => {
+                    c.subscribe(&channel);
+                    true.into_lua_multi(&lua)
+                }
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to subscribe to a channel");
                     (false, err.to_string()).into_lua_multi(&lua)
@@ -25,8 +28,11 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let unsubscribe = lua.create_async_function(move |lua, channel: String| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.unsubscribe(&channel)).await {
-                Ok(()) => true.into_lua_multi(&lua),
+            match client.client() {
+                Ok(c) => {
+                    c.unsubscribe(&channel);
+                    true.into_lua_multi(&lua)
+                }
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to unsubscribe from a channel");
                     (false, err.to_string()).into_lua_multi(&lua)
@@ -39,7 +45,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let channels = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.channels()).await {
+            match client.run(|c| c.channels()).await {
                 Ok(channels) => channels.0.into_lua(&lua).into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get subscribed channels");
@@ -54,7 +60,8 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
         lua.create_async_function(move |lua, (channel, message): (String, String)| {
             let client = Arc::clone(&c);
             async move {
-                match client.run(move |c| c.send_message(&channel, &message)).await {
+                match client.run(|c| async move { c.send_message(&channel, &message).await }).await
+                {
                     Ok(()) => true.into_lua_multi(&lua),
                     Err(err) => {
                         tracing::error!(err = ?err, "Failed to send message to a channel");
@@ -68,9 +75,9 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let read_messages = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.read_messages()).await {
-                Ok(messages) => {
-                    let res = Messages::from(messages);
+            match client.client() {
+                Ok(c) => {
+                    let res = Messages::from(c.read_messages());
                     res.0.into_lua(&lua).into_lua_multi(&lua)
                 }
                 Err(err) => {
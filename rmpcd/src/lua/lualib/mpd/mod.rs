@@ -2,14 +2,11 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use mlua::{IntoLuaMulti, Lua, LuaSerdeExt, Table, Value};
-use rmpc_mpd::{
-    filter::{Filter, Tag},
-    mpd_client::MpdClient,
-};
+use rmpc_mpd::filter::{Filter, FilterExpr, Tag};
 
 use crate::{
-    async_client::AsyncClient,
     lua::lualib::mpd::types::{Song, Status},
+    mpd::Mpd,
 };
 
 mod c2c;
@@ -17,7 +14,7 @@ mod playback;
 mod sticker;
 pub mod types;
 
-pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
+pub fn create(lua: &Lua, client: &Arc<Mpd>) -> Result<Table> {
     let mpd = lua.create_table()?;
 
     sticker::init(lua, &mpd, client)?;
@@ -28,7 +25,7 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
     let get_status = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.get_status()).await {
+            match client.run(|c| c.status()).await {
                 Ok(status) => lua.to_value(&Status::from(status)).into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get MPD status");
@@ -42,7 +39,7 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
     let album_art = lua.create_async_function(move |lua, uri: String| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.albumart(&uri)).await {
+            match client.run(|c| async move { c.albumart(&uri).await }).await {
                 Ok(data) => data.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get album art");
@@ -56,7 +53,7 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
     let read_picture = lua.create_async_function(move |lua, uri: String| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.read_picture(&uri)).await {
+            match client.run(|c| async move { c.read_picture(&uri).await }).await {
                 Ok(data) => data.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to read picture");
@@ -70,7 +67,10 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
     let get_song = lua.create_async_function(move |lua, uri: String| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.find_one(&[Filter::new(Tag::File, uri.as_str())])).await {
+            match client
+                .run(|c| async move { c.find_one(&[Filter::new(Tag::File, uri.as_str())]).await })
+                .await
+            {
                 Ok(song) => song.map(Song::from).into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get song by uri");
@@ -84,7 +84,7 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
     let get_song_by_id = lua.create_async_function(move |lua, id: u32| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.playlist_id(id)).await {
+            match client.run(|c| c.playlist_id(id)).await {
                 Ok(song) => song.map(Song::from).into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get song by id");
@@ -98,7 +98,7 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
     let get_current_song = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.get_current_song()).await {
+            match client.run(|c| c.current_song()).await {
                 Ok(song) => song.map(Song::from).into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get current song");
@@ -108,12 +108,143 @@ pub fn create(lua: &Lua, client: &Arc<AsyncClient>) -> Result<Table> {
         }
     })?;
 
+    let c = Arc::clone(client);
+    let find = lua.create_async_function(move |lua, query: String| {
+        let client = Arc::clone(&c);
+        async move {
+            let expr = match query.parse::<FilterExpr>() {
+                Ok(expr) => expr,
+                Err(err) => return (Value::Nil, Some(err.to_string())).into_lua_multi(&lua),
+            };
+            match client.run(|c| async move { c.find(&[Filter::from(expr)]).await }).await {
+                Ok(songs) => {
+                    songs.into_iter().map(Song::from).collect::<Vec<_>>().into_lua_multi(&lua)
+                }
+                Err(err) => {
+                    tracing::error!(err = ?err, "Failed to find songs");
+                    (Value::Nil, Some(err.to_string())).into_lua_multi(&lua)
+                }
+            }
+        }
+    })?;
+
+    let c = Arc::clone(client);
+    let search = lua.create_async_function(move |lua, query: String| {
+        let client = Arc::clone(&c);
+        async move {
+            let expr = match query.parse::<FilterExpr>() {
+                Ok(expr) => expr,
+                Err(err) => return (Value::Nil, Some(err.to_string())).into_lua_multi(&lua),
+            };
+            match client.run(|c| async move { c.search(&[Filter::from(expr)]).await }).await {
+                Ok(songs) => {
+                    songs.into_iter().map(Song::from).collect::<Vec<_>>().into_lua_multi(&lua)
+                }
+                Err(err) => {
+                    tracing::error!(err = ?err, "Failed to search songs");
+                    (Value::Nil, Some(err.to_string())).into_lua_multi(&lua)
+                }
+            }
+        }
+    })?;
+
     mpd.raw_set("get_status", get_status)?;
     mpd.raw_set("album_art", album_art)?;
     mpd.raw_set("read_picture", read_picture)?;
     mpd.raw_set("get_song", get_song)?;
     mpd.raw_set("get_song_by_id", get_song_by_id)?;
     mpd.raw_set("get_current_song", get_current_song)?;
+    mpd.raw_set("find", find)?;
+    mpd.raw_set("search", search)?;
 
     Ok(mpd)
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use rmpc_mpd::{
+        commands::{IdleEvent, Song, metadata_tag::MetadataTag},
+        fake_server::{FakeMpd, FakeServer},
+    };
+    use rmpc_shared::version::Version;
+
+    use crate::{AppEvent, lua, mpd::Mpd};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_lua_against_mpd() {
+        let songs = [("a.flac", "Foo"), ("b.flac", "Bar")].map(|(file, artist)| Song {
+            file: file.to_owned(),
+            metadata: HashMap::from([("artist".to_owned(), MetadataTag::from(artist))]),
+            ..Default::default()
+        });
+        let server = FakeServer::start_tcp(FakeMpd::new().with_songs(songs)).unwrap();
+        let client = Arc::new(Mpd::default());
+        client.connect(server.address(), None, false).await.unwrap();
+        let lua = lua::create(None, &client, None).unwrap();
+
+        let (file, volume): (String, u32) = lua
+            .load(
+                r#"
+                local songs = assert(mpd.find("(Artist == 'Foo')"))
+                assert(mpd.inc_song_sticker(songs[1].file, "playcount"))
+                assert(mpd.set_volume(30))
+                return songs[1].file, mpd.get_volume()
+                "#,
+            )
+            .eval_async()
+            .await
+            .unwrap();
+
+        assert_eq!((file.as_str(), volume), ("a.flac", 30));
+        server.with_state(|mpd| {
+            assert_eq!(mpd.stickers[&("song".to_owned(), "a.flac".to_owned())]["playcount"], "1");
+        });
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn increments_sticker_without_sticker_inc() {
+        let mpd = FakeMpd::new()
+            .with_version(Version::new(0, 23, 5))
+            .with_song(Song { file: "a.flac".to_owned(), ..Default::default() })
+            .with_sticker("a.flac", "playCount", "2");
+        let server = FakeServer::start_tcp(mpd).unwrap();
+        let client = Arc::new(Mpd::default());
+        client.connect(server.address(), None, false).await.unwrap();
+        let lua = lua::create(None, &client, None).unwrap();
+
+        lua.load(r#"assert(mpd.inc_song_sticker("a.flac", "playCount", 2))"#)
+            .exec_async()
+            .await
+            .unwrap();
+
+        server.with_state(|mpd| {
+            assert_eq!(mpd.stickers[&("song".to_owned(), "a.flac".to_owned())]["playCount"], "4");
+        });
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reads_messages_received_by_idle() {
+        let server = FakeServer::start_tcp(FakeMpd::new()).unwrap();
+        let client = Arc::new(Mpd::default());
+        client.connect(server.address(), None, false).await.unwrap();
+        client.client().unwrap().subscribe("rmpcd");
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let idle = client.spawn_idle(tx);
+        let lua = lua::create(None, &client, None).unwrap();
+
+        while client.run(|c| c.channels()).await.unwrap().0.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        client.run(|c| c.send_message("rmpcd", "hello")).await.unwrap();
+        // subscribing also reports a subscription change
+        while !matches!(rx.recv().await.unwrap(), AppEvent::Idle(IdleEvent::Message)) {}
+        let message: String =
+            lua.load(r"return mpd.read_messages().rmpcd[1]").eval_async().await.unwrap();
+
+        assert_eq!(message, "hello");
+        idle.abort();
+    }
+}
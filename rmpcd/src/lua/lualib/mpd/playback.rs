@@ -4,13 +4,16 @@ use anyhow::Result;
 use mlua::{IntoLuaMulti, Lua, LuaSerdeExt, Table, Value};
 use rmpc_mpd::{
     commands::{Volume, volume::Bound},
-    mpd_client::{MpdClient, ValueChange as MpdValueChange},
+    mpd_client::ValueChange as MpdValueChange,
 };
 use serde_with::DeserializeFromStr;
 
-use crate::{async_client::AsyncClient, lua::lualib::mpd::types::OnOffOneshot};
+use crate::{
+    lua::lualib::mpd::types::{OnOffOneshot, ReplayGainMode},
+    mpd::Mpd,
+};
 
-pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
+pub fn init(lua: &Lua, mpd: &Table, client: &Arc<Mpd>) -> Result<()> {
     let c = Arc::clone(client);
     let consume = lua.create_async_function(move |lua, value: Value| {
         let client = Arc::clone(&c);
@@ -21,7 +24,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
                     .into_lua_multi(&lua);
             };
 
-            match client.run(move |c| c.consume(value.into())).await {
+            match client.run(|c| c.consume(value.into())).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set consume");
@@ -35,7 +38,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let crossfade = lua.create_async_function(move |lua, seconds: u32| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.crossfade(seconds)).await {
+            match client.run(|c| c.crossfade(seconds)).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set crossfade");
@@ -45,11 +48,50 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
         }
     })?;
 
+    let c = Arc::clone(client);
+    let get_replay_gain_mode = lua.create_async_function(move |lua, ()| {
+        let client = Arc::clone(&c);
+        async move {
+            match client.run(|c| c.replay_gain_status()).await {
+                Ok(status) => {
+                    lua.to_value(&ReplayGainMode::from(status.mode))?.into_lua_multi(&lua)
+                }
+                Err(err) => {
+                    tracing::error!(err = ?err, "Failed to get replay gain mode");
+                    (Value::Nil, Some(err.to_string())).into_lua_multi(&lua)
+                }
+            }
+        }
+    })?;
+
+    let c = Arc::clone(client);
+    let set_replay_gain_mode = lua.create_async_function(move |lua, value: Value| {
+        let client = Arc::clone(&c);
+        async move {
+            let Ok(value): mlua::Result<ReplayGainMode> = lua.from_value(value) else {
+                tracing::error!("Failed to parse value for replay gain mode");
+                return (
+                    false,
+                    "Invalid replay gain mode, expected 'off', 'track', 'album' or 'auto'",
+                )
+                    .into_lua_multi(&lua);
+            };
+
+            match client.run(|c| c.replay_gain_mode(value.into())).await {
+                Ok(()) => true.into_lua_multi(&lua),
+                Err(err) => {
+                    tracing::error!(err = ?err, "Failed to set replay gain mode");
+                    (false, Some(err.to_string())).into_lua_multi(&lua)
+                }
+            }
+        }
+    })?;
+
     let c = Arc::clone(client);
     let random = lua.create_async_function(move |lua, value: bool| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.random(value)).await {
+            match client.run(|c| c.random(value)).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set random");
@@ -63,7 +105,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let repeat = lua.create_async_function(move |lua, value: bool| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.repeat(value)).await {
+            match client.run(|c| c.repeat(value)).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set repeat");
@@ -83,7 +125,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
                     .into_lua_multi(&lua);
             };
 
-            match client.run(move |c| c.single(value.into())).await {
+            match client.run(|c| c.single(value.into())).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set single");
@@ -97,7 +139,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let get_volume = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.get_volume()).await {
+            match client.run(|c| c.get_volume()).await {
                 Ok(vol) => vol.value().into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get volume");
@@ -111,7 +153,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let set_volume = lua.create_async_function(move |lua, volume: u32| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.set_volume(Volume::new(volume))).await {
+            match client.run(|c| c.set_volume(Volume::new(volume))).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set volume");
@@ -129,7 +171,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
                 tracing::error!("Failed to parse volume value");
                 return (false, "Invalid volume value").into_lua_multi(&lua);
             };
-            match client.run(move |c| c.volume(value.into())).await {
+            match client.run(|c| c.volume(value.into())).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to set volume");
@@ -143,7 +185,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let prev = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.prev()).await {
+            match client.run(|c| c.prev()).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to go to previous song");
@@ -157,7 +199,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let next = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.next()).await {
+            match client.run(|c| c.next()).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to go to next song");
@@ -175,7 +217,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
                 tracing::error!("Failed to parse seek value");
                 return (false, "Invalid seek value").into_lua_multi(&lua);
             };
-            match client.run(move |c| c.seek_current(value.into())).await {
+            match client.run(|c| c.seek_current(value.into())).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to seek current song");
@@ -189,7 +231,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let play = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.play()).await {
+            match client.run(|c| c.play()).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to start playback");
@@ -203,7 +245,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let pause = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.pause()).await {
+            match client.run(|c| c.pause()).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to pause playback");
@@ -217,7 +259,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let toggle_pause = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.pause_toggle()).await {
+            match client.run(|c| c.pause_toggle()).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to toggle pause");
@@ -231,7 +273,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let stop = lua.create_async_function(move |lua, ()| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.stop()).await {
+            match client.run(|c| c.stop()).await {
                 Ok(()) => true.into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to stop playback");
@@ -243,6 +285,8 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
 
     mpd.raw_set("set_consume", consume)?;
     mpd.raw_set("set_crossfade", crossfade)?;
+    mpd.raw_set("get_replay_gain_mode", get_replay_gain_mode)?;
+    mpd.raw_set("set_replay_gain_mode", set_replay_gain_mode)?;
     mpd.raw_set("set_random", random)?;
     mpd.raw_set("set_repeat", repeat)?;
     mpd.raw_set("set_single", single)?;
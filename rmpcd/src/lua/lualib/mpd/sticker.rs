@@ -2,17 +2,19 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use mlua::{IntoLuaMulti, Lua, Table, Value};
-use rmpc_mpd::mpd_client::MpdClient;
+use rmpc_mpd::{commands::stickers::StickerType, errors::MpdError, tokio_client::AsyncClient};
+use rmpc_shared::version::Version;
 
-use crate::async_client::AsyncClient;
+use crate::mpd::Mpd;
 
-pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
+pub fn init(lua: &Lua, mpd: &Table, client: &Arc<Mpd>) -> Result<()> {
     let c = Arc::clone(client);
     let set_sticker =
         lua.create_async_function(move |lua, (uri, name, value): (String, String, String)| {
             let client = Arc::clone(&c);
             async move {
-                match client.run(move |c| c.set_sticker(&uri, &name, &value)).await {
+                match client.run(|c| async move { c.set_sticker(&uri, &name, &value).await }).await
+                {
                     Ok(()) => true.into_lua_multi(&lua),
                     Err(err) => {
                         tracing::error!(err = ?err, "Failed to set sticker");
@@ -26,7 +28,7 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
     let get_sticker = lua.create_async_function(move |lua, (uri, name): (String, String)| {
         let client = Arc::clone(&c);
         async move {
-            match client.run(move |c| c.sticker(&uri, &name)).await {
+            match client.run(|c| async move { c.sticker(&uri, &name).await }).await {
                 Ok(value) => (value.map(|s| s.value), Value::Nil).into_lua_multi(&lua),
                 Err(err) => {
                     tracing::error!(err = ?err, "Failed to get sticker");
@@ -36,8 +38,51 @@ pub fn init(lua: &Lua, mpd: &Table, client: &Arc<AsyncClient>) -> Result<()> {
         }
     })?;
 
+    let c = Arc::clone(client);
+    let inc_sticker = lua.create_async_function(
+        move |lua, (uri, name, value): (String, String, Option<u32>)| {
+            let client = Arc::clone(&c);
+            async move {
+                match client
+                    .run(|c| async move {
+                        inc_song_sticker(c, &uri, &name, value.unwrap_or(1)).await
+                    })
+                    .await
+                {
+                    Ok(()) => true.into_lua_multi(&lua),
+                    Err(err) => {
+                        tracing::error!(err = ?err, "Failed to increment sticker");
+                        (false, err.to_string()).into_lua_multi(&lua)
+                    }
+                }
+            }
+        },
+    )?;
+
     mpd.raw_set("set_song_sticker", set_sticker)?;
     mpd.raw_set("get_song_sticker", get_sticker)?;
+    mpd.raw_set("inc_song_sticker", inc_sticker)?;
 
     Ok(())
 }
+
+/// Increments the sticker atomically when MPD supports it, which is since 0.24.
+/// Older versions get and set it instead, which is decided by the version from
+/// the handshake so the unsupported command is never sent.
+async fn inc_song_sticker(
+    client: &AsyncClient,
+    uri: &str,
+    name: &str,
+    value: u32,
+) -> Result<(), MpdError> {
+    if client.version() >= Version::new(0, 24, 0) {
+        return client.inc_sticker(&StickerType::Song, uri, name, value).await;
+    }
+
+    let current = client
+        .sticker(uri, name)
+        .await?
+        .and_then(|sticker| sticker.value.parse::<u32>().ok())
+        .unwrap_or_default();
+    client.set_sticker(uri, name, &(current + value).to_string()).await
+}
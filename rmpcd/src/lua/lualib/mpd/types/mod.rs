@@ -1,9 +1,11 @@
 mod metadata_tag;
 mod on_off_oneshot;
+mod replay_gain_mode;
 mod song;
 mod status;
 
 pub use metadata_tag::{MetadataTag, MetadataTagExt};
 pub use on_off_oneshot::OnOffOneshot;
+pub use replay_gain_mode::ReplayGainMode;
 pub use song::Song;
 pub use status::{State, Status};
@@ -0,0 +1,33 @@
+use rmpc_mpd::commands::replay_gain::ReplayGainMode as MpdReplayGainMode;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayGainMode {
+    Off,
+    Track,
+    Album,
+    Auto,
+}
+
+impl From<ReplayGainMode> for MpdReplayGainMode {
+    fn from(value: ReplayGainMode) -> Self {
+        match value {
+            ReplayGainMode::Off => MpdReplayGainMode::Off,
+            ReplayGainMode::Track => MpdReplayGainMode::Track,
+            ReplayGainMode::Album => MpdReplayGainMode::Album,
+            ReplayGainMode::Auto => MpdReplayGainMode::Auto,
+        }
+    }
+}
+
+impl From<MpdReplayGainMode> for ReplayGainMode {
+    fn from(value: MpdReplayGainMode) -> Self {
+        match value {
+            MpdReplayGainMode::Off => ReplayGainMode::Off,
+            MpdReplayGainMode::Track => ReplayGainMode::Track,
+            MpdReplayGainMode::Album => ReplayGainMode::Album,
+            MpdReplayGainMode::Auto => ReplayGainMode::Auto,
+        }
+    }
+}
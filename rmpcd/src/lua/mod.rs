@@ -5,8 +5,8 @@ use mlua::{Lua, Table};
 use tokio::sync::RwLock;
 
 use crate::{
-    async_client::AsyncClient,
     lua::plugin::{LuaPluginSpec, PluginEvent},
+    mpd::Mpd,
     paths::Paths,
 };
 
@@ -16,7 +16,7 @@ pub mod type_def_eject;
 
 pub fn create(
     additional_package_path: Option<&Path>,
-    client: &Arc<AsyncClient>,
+    client: &Arc<Mpd>,
     plugins: Option<&Arc<RwLock<Vec<Arc<RwLock<LuaPluginSpec>>>>>>,
 ) -> Result<Lua> {
     let lua = Lua::new();
@@ -49,9 +49,9 @@ pub fn create(
 }
 
 pub async fn eval_config(
-    mpd: Option<Arc<AsyncClient>>,
+    mpd: Option<Arc<Mpd>>,
 ) -> Result<(Lua, mlua::Table, Arc<RwLock<Vec<Arc<RwLock<LuaPluginSpec>>>>>)> {
-    let mpd = mpd.unwrap_or(Arc::new(AsyncClient::new(|_| {}, || {})));
+    let mpd = mpd.unwrap_or_default();
 
     let cfg_dir = Paths::config_dir();
 
@@ -67,7 +67,7 @@ pub async fn eval_config(
 
 pub fn install_lib(
     lua: &Lua,
-    client: &Arc<AsyncClient>,
+    client: &Arc<Mpd>,
     plugins: Option<&Arc<RwLock<Vec<Arc<RwLock<LuaPluginSpec>>>>>>,
 ) -> mlua::Result<()> {
     macro_rules! install_lib {
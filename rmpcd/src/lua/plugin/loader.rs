@@ -5,7 +5,6 @@ use mlua::{LuaSerdeExt, Table};
 use tokio::sync::RwLock;
 
 use crate::{
-    async_client::AsyncClient,
     lua::{
         self,
         lualib::plugin::{ON_IDLE, ON_MESSAGE, ON_SHUTDOWN, ON_SONG_CHANGE, ON_STATE_CHANGE},
@@ -17,6 +16,7 @@ use crate::{
             triggers::Triggers,
         },
     },
+    mpd::Mpd,
     pkg::{AddResult, Lockfile, run_add},
 };
 
@@ -28,7 +28,7 @@ const LYRICS: &str = include_str!("../builtin/lyrics.lua");
 pub async fn load(
     cfg_dir: &Path,
     plugin: &Arc<RwLock<LuaPluginSpec>>,
-    client: &Arc<AsyncClient>,
+    client: &Arc<Mpd>,
     lockfile: &mut Lockfile,
 ) -> Result<LuaPlugin> {
     match &*plugin.read().await {
@@ -38,7 +38,7 @@ pub async fn load(
     }
 }
 
-async fn load_builtin(spec: &BuiltinPluginSpec, client: &Arc<AsyncClient>) -> Result<LuaPlugin> {
+async fn load_builtin(spec: &BuiltinPluginSpec, client: &Arc<Mpd>) -> Result<LuaPlugin> {
     let content = match spec.name.as_str() {
         "lastfm" => LASTFM,
         "notify" => NOTIFY,
@@ -53,7 +53,7 @@ async fn load_builtin(spec: &BuiltinPluginSpec, client: &Arc<AsyncClient>) -> Re
 
 async fn load_remote(
     spec: &RemotePluginSpec,
-    client: &Arc<AsyncClient>,
+    client: &Arc<Mpd>,
     lockfile: &mut Lockfile,
 ) -> Result<LuaPlugin> {
     let AddResult { manifest, plugin_dir } = run_add(spec, lockfile).await?;
@@ -83,7 +83,7 @@ async fn load_remote(
 async fn load_local(
     spec: &LocalPluginSpec,
     cfg_dir: &Path,
-    client: &Arc<AsyncClient>,
+    client: &Arc<Mpd>,
 ) -> Result<LuaPlugin> {
     let mut plugin_path = spec.path.clone();
     plugin_path.set_extension("lua");
@@ -107,7 +107,7 @@ async fn load_single(
     name: String,
     args: &str,
     additional_pkg_path: Option<&Path>,
-    client: &Arc<AsyncClient>,
+    client: &Arc<Mpd>,
 ) -> Result<LuaPlugin> {
     let lua = lua::create(additional_pkg_path, client, None)?;
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
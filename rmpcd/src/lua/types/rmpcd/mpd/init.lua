@@ -4,8 +4,11 @@
 ---@class Mpd
 ---@field set_song_sticker fun(uri: string, name: string, value: string): (boolean, string|nil)
 ---@field get_song_sticker fun(uri: string, name: string): (string|nil, string|nil)
+---@field inc_song_sticker fun(uri: string, name: string, value?: integer): (boolean, string|nil)
 ---@field set_consume fun(value: OnOffOneshot): (boolean, string|nil)
 ---@field set_crossfade fun(seconds: integer): (boolean, string|nil)
+---@field get_replay_gain_mode fun(): (ReplayGainMode|nil, string|nil)
+---@field set_replay_gain_mode fun(value: ReplayGainMode): (boolean, string|nil)
 ---@field set_random fun(value: boolean): (boolean, string|nil)
 ---@field set_repeat fun(value: boolean): (boolean, string|nil)
 ---@field set_single fun(value: OnOffOneshot): (boolean, string|nil)
@@ -30,6 +33,8 @@
 ---@field get_song fun(uri: string): (Song|nil, nil|string)
 ---@field get_song_by_id fun(id: number): (QueuedSong|nil, nil|string)
 ---@field get_current_song fun(): (QueuedSong|nil, nil|string)
+---@field find fun(query: string): (Song[]|nil, nil|string) Case sensitive search using a MPD filter expression
+---@field search fun(query: string): (Song[]|nil, nil|string) Case insensitive search using a MPD filter expression
 
 ---@alias ValueChange string e.g. "+5", "-10", "50"
 
@@ -24,4 +24,5 @@
 ---@field last_loaded_playlist string|nil
 
 ---@alias OnOffOneshot "on" | "off" | "oneshot"
+---@alias ReplayGainMode "off" | "track" | "album" | "auto"
 ---@alias PlaybackState "play" | "pause" | "stop"
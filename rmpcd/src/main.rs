@@ -7,10 +7,7 @@ use std::{
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use rmpc_mpd::{
-    commands::{IdleEvent, Status},
-    mpd_client::MpdClient,
-};
+use rmpc_mpd::commands::{IdleEvent, Status};
 use serde::Serialize;
 use serde_json::json;
 use tokio::sync::RwLock;
@@ -19,19 +16,19 @@ use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt};
 
 use crate::{
-    async_client::AsyncClient,
     ctx::Ctx,
     lua::{eval_config, plugin::PluginStore},
+    mpd::Mpd,
     paths::Paths,
     pkg::Lockfile,
 };
 
-mod async_client;
 mod ctx;
 mod event_loop;
 mod ext;
 mod kv_bridge;
 mod lua;
+mod mpd;
 mod mpd_ext;
 mod mpris;
 mod paths;
@@ -188,21 +185,7 @@ async fn run() -> Result<()> {
 
     let (idle_tx, idle_rx) = tokio::sync::mpsc::unbounded_channel::<AppEvent>();
 
-    let idle_tx_clone = idle_tx.clone();
-    let idle_tx_clone2 = idle_tx.clone();
-
-    let mpd = Arc::new(AsyncClient::new(
-        move |evs| {
-            if let Err(err) = idle_tx_clone.send(AppEvent::Idle(evs)) {
-                error!(err = ?err, "Failed to send idle event");
-            }
-        },
-        move || {
-            if let Err(err) = idle_tx_clone2.send(AppEvent::Reconnected) {
-                error!(err = ?err, "Failed to send reconnected event");
-            }
-        },
-    ));
+    let mpd = Arc::new(Mpd::default());
 
     let cfg_dir = Paths::config_dir();
 
@@ -242,16 +225,17 @@ async fn run() -> Result<()> {
         plugin_store.all().flat_map(|p| &p.subscribed_channels).chain(subscribed_channels.iter())
     {
         info!(channel, "Subscribing to channel");
-        let channel = channel.clone();
-        mpd.run(move |c| c.subscribe(&channel)).await?;
+        mpd.client()?.subscribe(channel);
     }
+    let idle_handle = mpd.spawn_idle(idle_tx.clone());
 
-    let status = mpd.run(|c| c.get_status()).await?;
-    let current_song = mpd.run(|c| c.get_current_song()).await?;
+    let status = mpd.run(|c| c.status()).await?;
+    let current_song = mpd.run(|c| c.current_song()).await?;
     let queue = mpd.run(|c| c.playlist_info()).await?.unwrap_or_default();
     let ctx = Arc::new(RwLock::new(Ctx {
         current_song: current_song.clone(),
         status: status.clone(),
+        queue_version: status.queue_version(),
         queue,
         album_art: None,
         last_written_album_art_song_uri: None,
@@ -264,7 +248,7 @@ async fn run() -> Result<()> {
     event_loop::init(mpd.clone(), ctx.clone(), idle_rx, shutdown_rx, idle_tx, tx, plugin_store)
         .await?;
 
-    mpd.shutdown().await;
+    idle_handle.abort();
 
     Ok(())
 }
@@ -272,7 +256,7 @@ async fn run() -> Result<()> {
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 enum AppEvent {
-    Idle(Vec<IdleEvent>),
+    Idle(IdleEvent),
     StatusUpdate(Status),
     Reconnected,
 }
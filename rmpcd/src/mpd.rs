@@ -0,0 +1,101 @@
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use rmpc_mpd::{
+    address::{MpdAddress, MpdPassword},
+    errors::MpdError,
+    tokio_client::AsyncClient,
+};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle, time::timeout};
+use tracing::{error, info, warn};
+
+use crate::{AppEvent, ext::SenderExt};
+
+/// Connection to MPD shared by the event loop, MPRIS and Lua. It is created
+/// empty because Lua needs it before the config with the address is
+/// evaluated, requests fail until [`Mpd::connect`] is called.
+#[derive(Debug, Default)]
+pub struct Mpd {
+    client: OnceLock<AsyncClient>,
+}
+
+impl Mpd {
+    pub async fn connect(
+        &self,
+        address: MpdAddress,
+        password: Option<MpdPassword>,
+        enable_keepalive: bool,
+    ) -> anyhow::Result<()> {
+        let client = AsyncClient::connect(address, password, enable_keepalive).await?;
+        self.client
+            .set(client)
+            .map_err(|_| anyhow::anyhow!("Mpd::connect called more than once"))?;
+
+        Ok(())
+    }
+
+    pub fn client(&self) -> Result<&AsyncClient, MpdError> {
+        self.client.get().ok_or_else(|| MpdError::Generic("Not connected to MPD".into()))
+    }
+
+    #[tracing::instrument(skip(self, f))]
+    pub async fn run<'a, T, F>(
+        &'a self,
+        f: impl FnOnce(&'a AsyncClient) -> F,
+    ) -> Result<T, MpdError>
+    where
+        F: Future<Output = Result<T, MpdError>> + 'a,
+    {
+        timeout(Duration::from_secs(10), f(self.client()?))
+            .await
+            .map_err(|err| MpdError::Generic(format!("Timed out waiting for response: {err}")))?
+    }
+
+    /// Forwards idle events to the event loop until the task is aborted. The
+    /// idle connection is reopened with a backoff when it is lost.
+    pub fn spawn_idle(self: &Arc<Self>, tx: UnboundedSender<AppEvent>) -> JoinHandle<()> {
+        let mpd = Arc::clone(self);
+        tokio::spawn(async move {
+            let reconnect_base_delay = Duration::from_millis(500);
+            let reconnect_max_delay = Duration::from_secs(16);
+            let mut delay = reconnect_base_delay;
+            let mut lost_connection = false;
+
+            loop {
+                let mut stream = match mpd.client() {
+                    Ok(client) => match client.idle(&[]).await {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            warn!(error = ?err, wait = ?delay, "Reconnect failed, retrying");
+                            tokio::time::sleep(delay).await;
+                            delay = (delay * 2).min(reconnect_max_delay);
+                            continue;
+                        }
+                    },
+                    Err(err) => {
+                        error!(error = ?err, "Cannot wait for MPD events");
+                        return;
+                    }
+                };
+
+                delay = reconnect_base_delay;
+                if lost_connection {
+                    info!("Reconnected to MPD");
+                    tx.send_safe(AppEvent::Reconnected);
+                }
+
+                while let Some(event) = stream.recv().await {
+                    match event {
+                        Ok(event) => tx.send_safe(AppEvent::Idle(event)),
+                        Err(err) => {
+                            warn!(error = ?err, "Lost MPD connection, attempting reconnect");
+                        }
+                    }
+                }
+                lost_connection = true;
+            }
+        })
+    }
+}
@@ -4,7 +4,7 @@ use anyhow::Result;
 use tokio::sync::{RwLock, mpsc::UnboundedSender};
 use tracing::error;
 
-use crate::{async_client::AsyncClient, ctx::Ctx};
+use crate::{ctx::Ctx, mpd::Mpd};
 
 mod metadata;
 mod notify;
@@ -18,7 +18,7 @@ pub use root::Root;
 pub use tracklist::Tracklist;
 
 pub async fn setup(
-    client: Arc<AsyncClient>,
+    client: Arc<Mpd>,
     mpd_state: Arc<RwLock<Ctx>>,
 ) -> Result<UnboundedSender<Change>> {
     let root = Root {};
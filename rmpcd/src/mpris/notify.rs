@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use anyhow::Result;
 use tokio::sync::{RwLock, mpsc::UnboundedReceiver};
@@ -10,14 +13,19 @@ use crate::{
     mpris::{Player, Tracklist, metadata::SongExt},
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Change {
     Volume,
     PlaybackState,
     LoopStatus,
     Shuffle,
     Metadata,
+    /// Whole queue has to be sent again
     Queue,
+    /// Songs with the given ids were appended to the end of the queue
+    TracksAdded(Vec<u32>),
+    /// Songs with the given ids changed in place
+    TracksMetadata(Vec<u32>),
 }
 
 pub async fn notify_consumer(
@@ -99,6 +107,64 @@ pub async fn notify_consumer(
                 emit_player_changed!(can_go_next_changed, "CanGoNext");
                 emit_player_changed!(can_go_previous_changed, "CanGoPrevious");
             }
+            Change::TracksAdded(ids) => {
+                let ids: HashSet<u32> = ids.into_iter().collect();
+                let added: Vec<_> = {
+                    let state = ctx.read().await;
+                    state
+                        .queue
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, song)| ids.contains(&song.id))
+                        .map(|(idx, song)| {
+                            let after_track = idx.checked_sub(1).map_or_else(
+                                || no_track_path.clone(),
+                                |prev| state.queue[prev].to_mpris_id(),
+                            );
+                            (song.clone(), after_track)
+                        })
+                        .collect()
+                };
+
+                for (song, after_track) in added {
+                    let mut metadata = HashMap::new();
+                    Ctx::song_to_metadata(&song, &mut metadata);
+                    if let Err(err) = Tracklist::track_added(
+                        tracklist_emmiter,
+                        metadata,
+                        after_track.into_inner(),
+                    )
+                    .await
+                    {
+                        error!(err = ?err, "Failed to emit track added signal");
+                    }
+                }
+
+                emit_player_changed!(can_play_changed, "CanPlay");
+                emit_player_changed!(can_go_next_changed, "CanGoNext");
+                emit_player_changed!(can_go_previous_changed, "CanGoPrevious");
+            }
+            Change::TracksMetadata(ids) => {
+                let ids: HashSet<u32> = ids.into_iter().collect();
+                let changed: Vec<_> = {
+                    let state = ctx.read().await;
+                    state.queue.iter().filter(|song| ids.contains(&song.id)).cloned().collect()
+                };
+
+                for song in changed {
+                    let mut metadata = HashMap::new();
+                    Ctx::song_to_metadata(&song, &mut metadata);
+                    if let Err(err) = Tracklist::track_metadata_changed(
+                        tracklist_emmiter,
+                        song.to_mpris_id().into_inner(),
+                        metadata,
+                    )
+                    .await
+                    {
+                        error!(err = ?err, "Failed to emit track metadata changed signal");
+                    }
+                }
+            }
         }
     }
 
@@ -3,8 +3,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use rmpc_mpd::{
     commands::{Song, State, status::OnOffOneshot, volume::Bound},
-    mpd_client::{MpdClient, MpdCommand, ValueChange},
-    proto_client::ProtoClient,
+    mpd_client::{MpdCommand, ValueChange},
 };
 use tokio::sync::RwLock;
 use zbus::{
@@ -15,8 +14,8 @@ use zbus::{
 };
 
 use crate::{
-    async_client::AsyncClient,
     ctx::Ctx,
+    mpd::Mpd,
     mpris::{
         metadata::SongExt,
         seek::{self, SeekPlan},
@@ -25,11 +24,11 @@ use crate::{
 
 pub struct Player {
     ctx: Arc<RwLock<Ctx>>,
-    client: Arc<AsyncClient>,
+    client: Arc<Mpd>,
 }
 
 impl Player {
-    pub fn new(ctx: Arc<RwLock<Ctx>>, client: Arc<AsyncClient>) -> Self {
+    pub fn new(ctx: Arc<RwLock<Ctx>>, client: Arc<Mpd>) -> Self {
         Self { ctx, client }
     }
 }
@@ -102,7 +101,7 @@ impl Player {
                     ValueChange::Increase(delta_secs as u32)
                 };
                 self.client
-                    .run(move |c| c.seek_current(change))
+                    .run(|c| c.seek_current(change))
                     .await
                     .map_err(|e| fdo::Error::Failed(format!("Failed to seek: {e}")))?;
                 Player::seeked(&emitter, seeked_us).await?;
@@ -132,7 +131,7 @@ impl Player {
         match seek::plan_set_position(current_id, requested_id, position, duration_us) {
             SeekPlan::Absolute { secs, seeked_us } => {
                 self.client
-                    .run(move |c| c.seek_current(ValueChange::Set(secs)))
+                    .run(|c| c.seek_current(ValueChange::Set(secs)))
                     .await
                     .map_err(|e| fdo::Error::Failed(format!("Failed to set position: {e}")))?;
                 Player::seeked(&emitter, seeked_us).await?;
@@ -156,7 +155,7 @@ impl Player {
         let mpd_volume = (volume * 100.0).round() as u32;
 
         self.client
-            .run(move |c| c.volume(ValueChange::Set(mpd_volume)))
+            .run(|c| c.volume(ValueChange::Set(mpd_volume)))
             .await
             .map_err(|e| fdo::Error::Failed(format!("Failed to set MPD volume: {e}")))?;
 
@@ -203,13 +202,13 @@ impl Player {
         };
 
         self.client
-            .run(move |c| {
-                c.send_start_cmd_list()?;
-                c.send_repeat(repeat)?;
-                c.send_single(single)?;
-                c.send_execute_cmd_list()?;
-                c.read_ok()?;
-                Ok(())
+            .run(|c| {
+                c.send_ok(|c| {
+                    c.send_start_cmd_list()?;
+                    c.send_repeat(repeat)?;
+                    c.send_single(single)?;
+                    c.send_execute_cmd_list()
+                })
             })
             .await
             .map_err(|e| fdo::Error::Failed(format!("Failed to set loop status: {e}")))?;
@@ -229,7 +228,7 @@ impl Player {
     #[zbus(property)]
     async fn set_shuffle(&self, value: bool) -> zbus::Result<()> {
         self.client
-            .run(move |c| c.random(value))
+            .run(|c| c.random(value))
             .await
             .map_err(|e| fdo::Error::Failed(format!("Failed to set shuffle: {e}")))?;
 
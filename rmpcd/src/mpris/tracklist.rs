@@ -63,4 +63,20 @@ impl Tracklist {
         current_track: ObjectPath<'_>,
     ) -> zbus::Result<()> {
     }
+
+    #[zbus(signal)]
+    pub async fn track_added(
+        ctx: &SignalEmitter<'_>,
+        metadata: HashMap<&'static str, Value<'_>>,
+        after_track: ObjectPath<'_>,
+    ) -> zbus::Result<()> {
+    }
+
+    #[zbus(signal)]
+    pub async fn track_metadata_changed(
+        ctx: &SignalEmitter<'_>,
+        track_id: ObjectPath<'_>,
+        metadata: HashMap<&'static str, Value<'_>>,
+    ) -> zbus::Result<()> {
+    }
 }